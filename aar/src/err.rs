@@ -1,5 +1,4 @@
 use common::thiserror;
-use std::process::Output;
 
 use miette::Diagnostic;
 use thiserror::Error;
@@ -10,8 +9,8 @@ pub enum PrepareAARError {
     IOError(#[from] std::io::Error),
     #[error(transparent)]
     ZipError(#[from] zip::result::ZipError),
-    #[error("dx failed: {0:?}")]
-    DXErr(Output),
+    #[error(transparent)]
+    JvmError(#[from] jvm::JvmError),
 }
 
 pub type PrepareAARResult<T> = std::result::Result<T, PrepareAARError>;
@@ -1,25 +1,51 @@
-use clap::Parser;
+use clap::{ArgEnum, Parser, Subcommand};
 
 #[derive(Parser, Debug)]
-#[clap(author, version, about, long_about = None)]
+#[clap(author, version, about, long_about = None, subcommand_negates_reqs = true)]
 pub struct Args {
-    #[clap(long)]
-    pub baksmali_path: String,
-
-    #[clap(long)]
-    pub dx_path: String,
+    #[clap(subcommand)]
+    pub command: Option<Command>,
 
     #[clap(
         long,
+        required_unless_present_any = &["mock-sources", "mock-dex"],
         help = "Path to aar file that is generated by compiling your mocks"
     )]
-    pub mock_aar: String,
+    pub mock_aar: Option<String>,
 
     #[clap(
         long,
-        help = "Path to the directory that was generated by apktool. It contains the smali files to compare against."
+        conflicts_with = "mock-aar",
+        help = "Path to the java sources of your mocks, read instead of a compiled aar"
     )]
-    pub disass: String,
+    pub mock_sources: Option<String>,
+
+    #[clap(
+        long,
+        conflicts_with_all = &["mock-aar", "mock-sources"],
+        help = "Path to a classes.dex that d8 compiled from your mocks, read instead of a compiled aar"
+    )]
+    pub mock_dex: Option<String>,
+
+    #[clap(
+        long,
+        required = true,
+        help = "Path to the directory that was generated by apktool, or a .zip, .tar or .tar.gz of it. It contains the smali files to compare against."
+    )]
+    pub disass: Option<String>,
+
+    #[clap(
+        long,
+        help = "Path to the ProGuard/R8 mapping.txt of the disassembled app, used to compare its obfuscated classes with your mocks"
+    )]
+    pub mapping: Option<String>,
+
+    #[clap(
+        long,
+        global = true,
+        help = "Parse every smali file again instead of reusing the classes cached by previous runs"
+    )]
+    pub no_cache: bool,
 
     #[clap(
         long,
@@ -29,6 +55,85 @@ pub struct Args {
 
     #[clap(long)]
     pub ignore_object_super: bool,
+
+    #[clap(
+        long,
+        arg_enum,
+        default_value = "include",
+        help = "How members generated by the compilers (lambdas, accessors, `$values`, ...) are compared: like any other member, not at all or reported separately"
+    )]
+    pub synthetic: Synthetic,
+
+    #[clap(
+        long,
+        arg_enum,
+        help = "Which side defines the API: also list the interfaces and members only the mock (mock), only the app (app) or either side (both) has"
+    )]
+    pub authority: Option<Authority>,
+}
+
+/// See `diff::Authority`
+#[derive(ArgEnum, Debug, Clone, Copy)]
+pub enum Authority {
+    Mock,
+    App,
+    Both,
+}
+
+/// See `diff::SyntheticPolicy`
+#[derive(ArgEnum, Debug, Clone, Copy)]
+pub enum Synthetic {
+    Include,
+    Ignore,
+    Group,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// List who references a class or member and what it references
+    Xref(XrefArgs),
+    /// Search the string literals of all methods, grouped by class
+    Strings(StringsArgs),
+    /// Print the super classes, subtypes and interfaces of a class as a tree
+    Hierarchy(HierarchyArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct XrefArgs {
+    #[clap(
+        long,
+        help = "Path to the directory that was generated by apktool, or a .zip, .tar or .tar.gz of it. It contains the smali files to search."
+    )]
+    pub disass: String,
+
+    #[clap(
+        help = "Class or member to look up, e.g. `tv.twitch.Foo`, `Ltv/twitch/Foo;->bar(I)V`, `tv.twitch.Foo->bar` (all overloads) or `tv.twitch.Foo->count:I`"
+    )]
+    pub target: String,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct StringsArgs {
+    #[clap(
+        long,
+        help = "Path to the directory that was generated by apktool, or a .zip, .tar or .tar.gz of it. It contains the smali files to search."
+    )]
+    pub disass: String,
+
+    #[clap(help = "Regular expression the `const-string` literals are matched against")]
+    pub pattern: String,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct HierarchyArgs {
+    #[clap(
+        long,
+        help = "Path to the directory that was generated by apktool, or a .zip, .tar or .tar.gz of it. It contains the smali files to link."
+    )]
+    pub disass: String,
+
+    #[clap(help = "Class to print, e.g. `tv.twitch.Foo` or `Ltv/twitch/Foo;`")]
+    pub class: String,
 }
 
 pub fn parse() -> Args {
@@ -0,0 +1,499 @@
+use crate::args::{Args, Authority, Synthetic};
+use diff::{
+    ClassDiff, DiffOptions, EnumDiff, Members, MethodDiff, SyntheticDiff, SyntheticPolicy,
+    ValueDiff,
+};
+use java::JavaClass;
+use smali::kotlin::{KotlinMember, KotlinMetadata};
+use smali::{
+    ClassEntry, ClassIndex, ClassPath, Direction, Mapping, Notation, SmaliAccessModifier,
+    SmaliClass, SmaliEnum, SmaliMethod, SmaliType,
+};
+use std::path::PathBuf;
+
+const OBJECT: &str = "java.lang.Object";
+
+/// A mock class, with the java file it was declared in if it was read from `--mock-sources`
+pub struct Mock {
+    pub class: SmaliClass,
+    pub source: Option<Source>,
+}
+
+/// The java file of a mock and the lines of its declarations
+pub struct Source {
+    pub file: PathBuf,
+    pub java: JavaClass,
+}
+
+impl Mock {
+    pub fn from_java(file: PathBuf, java: JavaClass) -> Self {
+        Self {
+            class: java.class.clone(),
+            source: Some(Source { file, java }),
+        }
+    }
+}
+
+impl From<SmaliClass> for Mock {
+    fn from(class: SmaliClass) -> Self {
+        Self {
+            class,
+            source: None,
+        }
+    }
+}
+
+impl Source {
+    /// ` at <file>:<line>`, empty if the line is unknown
+    fn at(&self, line: Option<usize>) -> String {
+        line.map_or_else(String::new, |line| {
+            format!(" at {}:{line}", self.file.display())
+        })
+    }
+
+    fn value_at(&self, name: &str) -> String {
+        self.at(self.java.value_line(name))
+    }
+
+    fn method_at(&self, method: &SmaliMethod) -> String {
+        self.at(self.java.method_line(method))
+    }
+}
+
+/// Compares every mock with the class of the app it mocks and prints their differences.
+/// With a mapping the classes of the app are looked up by their obfuscated name and
+/// compared with their original names, see `diff::diff_with`.
+pub fn run(args: &Args, mut mocks: Vec<Mock>, classes: &ClassIndex, mapping: Option<&Mapping>) {
+    let options = DiffOptions {
+        authority: args.authority.map(|authority| match authority {
+            Authority::Mock => diff::Authority::Mock,
+            Authority::App => diff::Authority::App,
+            Authority::Both => diff::Authority::Both,
+        }),
+        synthetic: match args.synthetic {
+            Synthetic::Include => SyntheticPolicy::Include,
+            Synthetic::Ignore => SyntheticPolicy::Ignore,
+            Synthetic::Group => SyntheticPolicy::Group,
+        },
+        mapping,
+    };
+    mocks.retain(|mock| options.synthetic.includes_class(&mock.class));
+    mocks.sort_by(|a, b| a.class.class_path.cmp(&b.class.class_path));
+    let mut missing = vec![];
+    let mut differing = 0;
+    for Mock {
+        class: mock,
+        source,
+    } in &mocks
+    {
+        let source = source.as_ref();
+        let app_path = mapping.map_or_else(
+            || mock.class_path.clone(),
+            |mapping| mapping.map_class(&mock.class_path, Direction::Obfuscate),
+        );
+        let Some(entry) = classes.get(&app_path) else {
+            missing.push((&mock.class_path, app_path));
+            continue;
+        };
+        // files that could not be parsed were logged when the app was loaded
+        let Ok(app) = entry.class() else {
+            continue;
+        };
+
+        let enum_diff = SmaliEnum::from_class(mock)
+            .zip(app_enum(entry, app, mapping))
+            .and_then(|(mock, app)| diff::diff_enum(&mock, &app));
+        let (mock, app) = prepare(args, mock, app, mapping);
+        let class_diff = diff::diff_with(&mock, &app, &options);
+        let unresolved = source.map_or(&[][..], |source| &source.java.unresolved[..]);
+        if class_diff.is_some() || enum_diff.is_some() || !unresolved.is_empty() {
+            if class_diff.is_some() || enum_diff.is_some() {
+                differing += 1;
+            }
+            // the members of the diff have the original names
+            let app = match mapping {
+                Some(mapping) => mapping.map_smali_class(&app, Direction::Deobfuscate),
+                None => app,
+            };
+            let kotlin = Kotlin::of(&mock, &app);
+            print_class_diff(
+                &mock.class_path,
+                &app_path,
+                class_diff.as_ref(),
+                kotlin.as_ref(),
+                source,
+            );
+            print_enum_diff(enum_diff.as_ref());
+            println!();
+        }
+    }
+
+    if !missing.is_empty() {
+        println!("missing in the app ({}):", missing.len());
+        for (mock_path, app_path) in &missing {
+            if *mock_path == app_path {
+                println!("  {mock_path}");
+            } else {
+                println!("  {mock_path} (looked up as {app_path})");
+            }
+        }
+        println!();
+    }
+    println!(
+        "{differing} of {} mocks differ from the app, {} are missing",
+        mocks.len(),
+        missing.len()
+    );
+}
+
+/// The constants of an enum class of the app in ordinal order, read from the `<clinit>` of its
+/// smali file, with their original names. Mocks have no smali text and are compiled by javac,
+/// their constants are in field order, see `SmaliEnum::from_class`.
+fn app_enum(entry: &ClassEntry, app: &SmaliClass, mapping: Option<&Mapping>) -> Option<SmaliEnum> {
+    let mut app_enum = match std::fs::read_to_string(&entry.file) {
+        Ok(smali) => SmaliEnum::from_smali(app, &smali)?,
+        Err(err) => {
+            debug!("could not read {}: {err}", entry.file.display());
+            SmaliEnum::from_class(app)?
+        }
+    };
+    if let Some(mapping) = mapping {
+        for constant in &mut app_enum.constants {
+            if let Some(value) = app.values.iter().find(|value| value.name == *constant) {
+                *constant = mapping.map_field(&app.class_path, value, Direction::Deobfuscate);
+            }
+        }
+        app_enum.class_path = mapping.map_class(&app.class_path, Direction::Deobfuscate);
+    }
+    Some(app_enum)
+}
+
+/// Applies the `--ignore-*` options to copies of the mock and the class of the app
+fn prepare(
+    args: &Args,
+    mock: &SmaliClass,
+    app: &SmaliClass,
+    mapping: Option<&Mapping>,
+) -> (SmaliClass, SmaliClass) {
+    let mut mock = mock.clone();
+    let mut app = app.clone();
+    if args.ignore_default_constructors {
+        let is_default_constructor = |method: &smali::SmaliMethod| {
+            method.name == "<init>" && method.parameter_types.is_empty()
+        };
+        mock.methods
+            .retain(|method| !is_default_constructor(method));
+        app.methods.retain(|method| !is_default_constructor(method));
+    }
+    if args.ignore_object_super
+        && mock
+            .super_path
+            .as_ref()
+            .is_none_or(|super_path| *super_path == OBJECT)
+    {
+        // compared after the app class is deobfuscated, so take its original name
+        mock.super_path = app.super_path.as_ref().map(|super_path| {
+            mapping.map_or_else(
+                || super_path.clone(),
+                |mapping| mapping.map_class(super_path, Direction::Deobfuscate),
+            )
+        });
+    }
+    (mock, app)
+}
+
+/// The `kotlin.Metadata` of the mock, or of the class of the app if the mock has none,
+/// to name the kotlin declarations of the diffed members
+struct Kotlin<'a> {
+    class: &'a SmaliClass,
+    metadata: KotlinMetadata,
+}
+
+impl<'a> Kotlin<'a> {
+    fn of(mock: &'a SmaliClass, app: &'a SmaliClass) -> Option<Self> {
+        [mock, app]
+            .iter()
+            .copied()
+            .find_map(|class| match class.kotlin_metadata()? {
+                Ok(metadata) => Some(Self { class, metadata }),
+                Err(err) => {
+                    debug!(
+                        "could not read the kotlin metadata of {}: {err}",
+                        class.class_path
+                    );
+                    None
+                }
+            })
+    }
+
+    fn method(&self, method: &MethodDiff) -> Option<KotlinMember<'_>> {
+        diff::kotlin::explain_method(method, self.class, &self.metadata)
+    }
+
+    fn value(&self, value: &ValueDiff) -> Option<KotlinMember<'_>> {
+        diff::kotlin::explain_value(value, self.class, &self.metadata)
+    }
+}
+
+fn print_class_diff(
+    mock_path: &ClassPath,
+    app_path: &ClassPath,
+    diff: Option<&ClassDiff>,
+    kotlin: Option<&Kotlin>,
+    source: Option<&Source>,
+) {
+    if mock_path == app_path {
+        println!("{mock_path}:");
+    } else {
+        println!("{mock_path} ({app_path} in the app):");
+    }
+    if let Some(source) = source {
+        println!("  declared{}", source.at(Some(source.java.line)));
+        if !source.java.unresolved.is_empty() {
+            println!(
+                "  unresolved in the mock: {} (in the same package or an on-demand import)",
+                source.java.unresolved.join(", ")
+            );
+        }
+    }
+    let Some(diff) = diff else {
+        return;
+    };
+    if let Some((mock, app)) = &diff.class_path {
+        println!("  class path: {mock} -> {app}");
+    }
+    if let Some((mock, app)) = &diff.access {
+        println!("  access: {} -> {}", access(*mock), access(*app));
+    }
+    if let Some((mock, app)) = &diff.is_abstract {
+        println!("  abstract: {mock} -> {app}");
+    }
+    if let Some((mock, app)) = &diff.super_path {
+        println!(
+            "  super class: {} -> {}",
+            class_or_none(mock.as_ref()),
+            class_or_none(app.as_ref())
+        );
+    }
+    if let Some(interfaces) = &diff.interfaces {
+        let interfaces: Vec<_> = interfaces.iter().map(ToString::to_string).collect();
+        println!("  missing interfaces: {}", interfaces.join(", "));
+    }
+    print_members(
+        "  ",
+        diff.values.as_deref().unwrap_or_default(),
+        diff.methods.as_deref().unwrap_or_default(),
+        kotlin,
+        source,
+    );
+    print_only_in(
+        "  ",
+        "only in the mock",
+        mock_path,
+        diff.only_in_mock.as_ref(),
+        source,
+    );
+    print_only_in(
+        "  ",
+        "only in the app",
+        mock_path,
+        diff.only_in_app.as_ref(),
+        None,
+    );
+    for group in diff.synthetic.iter().flatten() {
+        print_synthetic(mock_path, group, kotlin, source);
+    }
+}
+
+fn print_enum_diff(diff: Option<&EnumDiff>) {
+    let Some(diff) = diff else {
+        return;
+    };
+    let names = |constants: &[smali::Symbol]| {
+        constants
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    if !diff.removed.is_empty() {
+        println!(
+            "  enum constants missing in the app: {}",
+            names(&diff.removed)
+        );
+    }
+    if !diff.added.is_empty() {
+        println!("  enum constants only in the app: {}", names(&diff.added));
+    }
+    for constant in &diff.reordered {
+        let (mock, app) = constant.ordinal;
+        println!("  enum constant {}: ordinal {mock} -> {app}", constant.name);
+    }
+}
+
+fn print_synthetic(
+    mock_path: &ClassPath,
+    group: &SyntheticDiff,
+    kotlin: Option<&Kotlin>,
+    source: Option<&Source>,
+) {
+    println!("  synthetic {:?}:", group.kind);
+    print_members("    ", &group.values, &group.methods, kotlin, source);
+    print_only_in(
+        "    ",
+        "only in the mock",
+        mock_path,
+        group.only_in_mock.as_ref(),
+        source,
+    );
+    print_only_in(
+        "    ",
+        "only in the app",
+        mock_path,
+        group.only_in_app.as_ref(),
+        None,
+    );
+}
+
+/// Prints the interfaces and members only one side has, see `diff::diff_with_authority`.
+/// The members of the app have the original names, so both sides are owned by the mocked class.
+/// source is the java file of the members, if they are members of the mock.
+fn print_only_in(
+    indent: &str,
+    title: &str,
+    owner: &ClassPath,
+    members: Option<&Members>,
+    source: Option<&Source>,
+) {
+    let Some(members) = members else {
+        return;
+    };
+    println!("{indent}{title}:");
+    for interface in &members.interfaces {
+        println!("{indent}  interface {interface}");
+    }
+    for value in &members.values {
+        println!(
+            "{indent}  field {}{}",
+            value.display(Notation::Java).with_owner(owner),
+            source.map_or_else(String::new, |source| source.value_at(&value.name))
+        );
+    }
+    for method in &members.methods {
+        println!(
+            "{indent}  method {}{}",
+            method.display(Notation::Java).with_owner(owner),
+            source.map_or_else(String::new, |source| source.method_at(method))
+        );
+    }
+}
+
+fn print_members(
+    indent: &str,
+    values: &[ValueDiff],
+    methods: &[MethodDiff],
+    kotlin: Option<&Kotlin>,
+    source: Option<&Source>,
+) {
+    for value in values {
+        println!(
+            "{indent}field {}: {}{}{}",
+            value.name,
+            value_changes(value).join(", "),
+            declaration(kotlin.and_then(|kotlin| kotlin.value(value))),
+            source.map_or_else(String::new, |source| source.value_at(&value.name))
+        );
+    }
+    for method in methods {
+        // the lines are of the methods of the mock, the left side of the diff
+        let at = source
+            .and_then(|source| {
+                let method = diff::kotlin::diffed_method(method, &source.java.class)?;
+                Some(source.method_at(method))
+            })
+            .unwrap_or_default();
+        println!(
+            "{indent}method {}{}: {}{}{at}",
+            method.name,
+            method.descriptor.as_deref().unwrap_or_default(),
+            method_changes(method).join(", "),
+            declaration(kotlin.and_then(|kotlin| kotlin.method(method)))
+        );
+    }
+}
+
+fn declaration(member: Option<KotlinMember>) -> String {
+    member.map_or_else(String::new, |member| format!(" ({member})"))
+}
+
+fn value_changes(value: &ValueDiff) -> Vec<String> {
+    if value.not_found {
+        return vec!["not found".to_string()];
+    }
+    let mut changes = vec![];
+    if let Some((mock, app)) = &value.data_type {
+        changes.push(format!("type {} -> {}", java(mock), java(app)));
+    }
+    modifier_changes(&mut changes, value.access, value.is_static, value.is_final);
+    changes
+}
+
+fn method_changes(method: &MethodDiff) -> Vec<String> {
+    if method.not_found {
+        return vec!["not found".to_string()];
+    }
+    let mut changes = vec![];
+    if let Some((mock, app)) = &method.parameter_types {
+        changes.push(format!(
+            "parameters ({}) -> ({})",
+            java_list(mock),
+            java_list(app)
+        ));
+    }
+    if let Some((mock, app)) = &method.return_type {
+        changes.push(format!("return type {} -> {}", java(mock), java(app)));
+    }
+    modifier_changes(
+        &mut changes,
+        method.access,
+        method.is_static,
+        method.is_final,
+    );
+    changes
+}
+
+fn modifier_changes(
+    changes: &mut Vec<String>,
+    access_diff: Option<(SmaliAccessModifier, SmaliAccessModifier)>,
+    is_static: Option<(bool, bool)>,
+    is_final: Option<(bool, bool)>,
+) {
+    if let Some((mock, app)) = access_diff {
+        changes.push(format!("access {} -> {}", access(mock), access(app)));
+    }
+    if let Some((mock, app)) = is_static {
+        changes.push(format!("static {mock} -> {app}"));
+    }
+    if let Some((mock, app)) = is_final {
+        changes.push(format!("final {mock} -> {app}"));
+    }
+}
+
+fn access(access: SmaliAccessModifier) -> &'static str {
+    match access.keyword() {
+        "" => "package-private",
+        keyword => keyword,
+    }
+}
+
+fn class_or_none(class_path: Option<&ClassPath>) -> String {
+    class_path.map_or_else(|| "none".to_string(), ToString::to_string)
+}
+
+fn java(data_type: &SmaliType) -> String {
+    data_type.display(Notation::Java).to_string()
+}
+
+fn java_list(types: &[SmaliType]) -> String {
+    types.iter().map(java).collect::<Vec<_>>().join(", ")
+}
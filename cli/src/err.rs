@@ -6,4 +6,14 @@ use thiserror::Error;
 pub enum ApplicationError {
     #[error(transparent)]
     PrepareAARError(#[from] aar::PrepareAARError),
+    #[error(transparent)]
+    JavaError(#[from] java::JavaError),
+    #[error(transparent)]
+    DexError(#[from] dex::DexError),
+    #[error(transparent)]
+    InvalidMapping(#[from] smali::MappingError),
+    #[error(transparent)]
+    SmaliError(#[from] smali::ParserError),
+    #[error("invalid pattern: {0}")]
+    InvalidPattern(#[from] regex::Error),
 }
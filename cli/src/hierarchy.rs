@@ -0,0 +1,78 @@
+use crate::args::HierarchyArgs;
+use crate::err::ApplicationError;
+use smali::{ClassPath, Hierarchy};
+use std::collections::HashSet;
+
+/// Prints the class of `ubi hierarchy` below its super classes with all its subtypes,
+/// followed by the interfaces it implements
+pub fn run(args: &HierarchyArgs, no_cache: bool) -> Result<(), ApplicationError> {
+    let class_path = if args.class.starts_with('L') && args.class.ends_with(';') {
+        ClassPath::from_smali(&args.class)?
+    } else {
+        ClassPath::from_java(&args.class)?
+    };
+    let classes = crate::load_disass(&args.disass, no_cache)?;
+    let hierarchy = Hierarchy::from_index(&classes);
+    crate::warn_failed(&classes);
+
+    crate::print_app_header(classes.app());
+    let mut supers = hierarchy.superclasses(&class_path);
+    supers.reverse();
+    let mut indent = String::new();
+    for (i, super_path) in supers.iter().enumerate() {
+        let branch = if i == 0 { "" } else { "└── " };
+        println!("{}{}{}", indent, branch, label(&hierarchy, super_path));
+        if i > 0 {
+            indent.push_str("    ");
+        }
+    }
+    let branch = if supers.is_empty() { "" } else { "└── " };
+    println!("{}{}{}", indent, branch, label(&hierarchy, &class_path));
+    if !supers.is_empty() {
+        indent.push_str("    ");
+    }
+    let mut seen = HashSet::from([&class_path]);
+    print_subtypes(&hierarchy, &class_path, &indent, &mut seen);
+
+    let interfaces = hierarchy.interfaces(&class_path);
+    println!();
+    println!("interfaces of {} ({}):", class_path, interfaces.len());
+    for interface in interfaces {
+        println!("  {}", label(&hierarchy, interface));
+    }
+    Ok(())
+}
+
+/// Prints the subtypes of `class_path` as a tree. Every subtype is expanded once and only
+/// marked when it is reached again, so cycles in broken trees end.
+fn print_subtypes<'a>(
+    hierarchy: &'a Hierarchy,
+    class_path: &ClassPath,
+    indent: &str,
+    seen: &mut HashSet<&'a ClassPath>,
+) {
+    let subtypes = hierarchy.direct_subtypes(class_path);
+    for (i, subtype) in subtypes.iter().enumerate() {
+        let last = i + 1 == subtypes.len();
+        let (branch, next) = if last {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        if !seen.insert(subtype) {
+            println!("{indent}{branch}{subtype} (see above)");
+            continue;
+        }
+        println!("{}{}{}", indent, branch, label(hierarchy, subtype));
+        print_subtypes(hierarchy, subtype, &format!("{indent}{next}"), seen);
+    }
+}
+
+/// The class path, marked if the class is not part of the tree
+fn label(hierarchy: &Hierarchy, class_path: &ClassPath) -> String {
+    if hierarchy.class(class_path).is_some() {
+        class_path.to_string()
+    } else {
+        format!("{class_path} (not in tree)")
+    }
+}
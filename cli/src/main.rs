@@ -17,7 +17,7 @@
     clippy::rest_pat_in_fully_bound_structs,
     clippy::same_name_method,
     clippy::self_named_module_files,
-    clippy::string_to_string,
+    clippy::implicit_clone,
     clippy::todo,
     clippy::undocumented_unsafe_blocks,
     clippy::unimplemented,
@@ -30,24 +30,111 @@
 extern crate common;
 
 mod args;
+mod check;
 mod err;
+mod hierarchy;
 mod logging;
+mod strings;
+mod xref;
 
 use aar::prepare_mock_aar;
-use args::Args;
+use args::{Args, Command};
 use err::ApplicationError;
 
 fn main() -> miette::Result<()> {
     logging::setup();
     let args = args::parse();
-    if let Err(err) = run(args) {
+    if let Err(err) = run(&args) {
         return Err(miette::Report::new(err));
     }
     Ok(())
 }
 
-fn run(args: Args) -> Result<(), ApplicationError> {
-    let _mocks_dir_path = prepare_mock_aar(args.mock_aar, &args.dx_path, &args.baksmali_path)?;
-    // TODO
+fn run(args: &Args) -> Result<(), ApplicationError> {
+    match &args.command {
+        Some(Command::Xref(xref_args)) => return xref::run(xref_args, args.no_cache),
+        Some(Command::Strings(strings_args)) => return strings::run(strings_args, args.no_cache),
+        Some(Command::Hierarchy(hierarchy_args)) => {
+            return hierarchy::run(hierarchy_args, args.no_cache)
+        }
+        None => {}
+    }
+
+    let mock_classes = load_mocks(args)?;
+    let mapping = args
+        .mapping
+        .as_ref()
+        .map(smali::Mapping::from_file)
+        .transpose()?;
+    let disass = args
+        .disass
+        .as_ref()
+        .expect("clap requires disass without a subcommand");
+    let disass_classes = load_disass(disass, args.no_cache)?;
+    warn_failed(&disass_classes);
+    print_app_header(disass_classes.app());
+    check::run(args, mock_classes, &disass_classes, mapping.as_ref());
     Ok(())
 }
+
+/// Starts every report with the app build of the disass directory,
+/// so it is clear which version the mocks were checked against
+fn print_app_header(app: Option<&smali::ApktoolInfo>) {
+    if let Some(app) = app {
+        println!("app: {app}");
+    } else {
+        warn!("no {} found in the disass directory", smali::APKTOOL_YML);
+        println!("app: unknown build");
+    }
+    println!();
+}
+
+/// Logs every smali file of the disass directory that could not be read so far
+fn warn_failed(index: &smali::ClassIndex) {
+    for (path, err) in index.failed() {
+        warn!("skipped {}: {}", path.display(), err);
+    }
+}
+
+/// Loads all classes of the apktool directory, reusing the classes of unchanged files
+/// from the previous run unless the cache is disabled. The cache is kept in the cache
+/// directory of the user, see `smali::default_cache_file`.
+fn load_disass(disass: &str, no_cache: bool) -> Result<smali::ClassIndex, ApplicationError> {
+    if no_cache {
+        return Ok(smali::ClassIndex::load(disass)?);
+    }
+    let cache_file = smali::default_cache_file(disass);
+    debug!("cache file: {}", cache_file.display());
+    let (index, stats) = smali::ClassIndex::load_cached(disass, cache_file)?;
+    debug!(
+        "loaded {} classes, {} from the cache and {} parsed",
+        index.len(),
+        stats.reused,
+        stats.parsed
+    );
+    Ok(index)
+}
+
+/// Reads the mocks, those read from java sources keep their file and declaration lines and
+/// report the type names they could not resolve, see `check::Source`
+fn load_mocks(args: &Args) -> Result<Vec<check::Mock>, ApplicationError> {
+    if let Some(mock_sources) = &args.mock_sources {
+        let mut mocks = vec![];
+        for file in java::parse_source_tree(mock_sources)? {
+            for class in file.classes? {
+                mocks.push(check::Mock::from_java(file.path.clone(), class));
+            }
+        }
+        return Ok(mocks);
+    }
+    let classes = if let Some(mock_dex) = &args.mock_dex {
+        dex::parse_dex_file(mock_dex)?
+    } else {
+        let mock_aar = args
+            .mock_aar
+            .as_ref()
+            .expect("clap requires mock_aar without mock_sources and mock_dex");
+        prepare_mock_aar(mock_aar)?
+    };
+    Ok(classes.into_iter().map(check::Mock::from).collect())
+}
@@ -0,0 +1,35 @@
+use crate::args::StringsArgs;
+use crate::err::ApplicationError;
+use regex::Regex;
+use smali::{MemberRef, StringIndex};
+use std::path::Path;
+
+/// Prints the string literals matching the pattern of `ubi strings`, grouped by class
+pub fn run(args: &StringsArgs, no_cache: bool) -> Result<(), ApplicationError> {
+    let pattern = Regex::new(&args.pattern)?;
+    let classes = crate::load_disass(&args.disass, no_cache)?;
+    let index = StringIndex::from_index(&classes);
+    crate::warn_failed(&classes);
+
+    crate::print_app_header(classes.app());
+    let root = Path::new(&args.disass);
+    let classes = index.search(|value| pattern.is_match(value));
+    for (i, class) in classes.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        let path = class.path.strip_prefix(root).unwrap_or(class.path);
+        println!("{} ({}):", class.class_path, class.hits.len());
+        for hit in &class.hits {
+            let method = MemberRef::method(class.class_path, &hit.method.method);
+            println!(
+                "  {}:{}  {}  {:?}",
+                path.display(),
+                hit.string.line,
+                method,
+                hit.string.value
+            );
+        }
+    }
+    Ok(())
+}
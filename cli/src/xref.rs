@@ -0,0 +1,42 @@
+use crate::args::XrefArgs;
+use crate::err::ApplicationError;
+use smali::{XrefHit, XrefIndex, XrefQuery};
+use std::path::Path;
+
+/// Prints the callers and callees of the target of `ubi xref`
+pub fn run(args: &XrefArgs, no_cache: bool) -> Result<(), ApplicationError> {
+    let query: XrefQuery = args.target.parse()?;
+    let classes = crate::load_disass(&args.disass, no_cache)?;
+    let index = XrefIndex::from_index(&classes);
+    crate::warn_failed(&classes);
+
+    crate::print_app_header(classes.app());
+    let root = Path::new(&args.disass);
+    print_hits(
+        &format!("callers of {}", args.target),
+        &index.callers(&query),
+        root,
+    );
+    println!();
+    print_hits(
+        &format!("callees of {}", args.target),
+        &index.callees(&query),
+        root,
+    );
+    Ok(())
+}
+
+fn print_hits(title: &str, hits: &[XrefHit], root: &Path) {
+    println!("{} ({}):", title, hits.len());
+    for hit in hits {
+        let path = hit.path.strip_prefix(root).unwrap_or(hit.path);
+        println!(
+            "  {}:{}  {}  {} {}",
+            path.display(),
+            hit.xref.line,
+            hit.source(),
+            hit.xref.kind,
+            hit.xref.target
+        );
+    }
+}
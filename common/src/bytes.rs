@@ -0,0 +1,140 @@
+use thiserror::Error;
+
+/// The byte order of the integers read by a `Reader`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    /// dex files
+    Little,
+    /// JVM class files
+    Big,
+}
+
+/// Reading past the end of the data, with the offset the read started at
+#[derive(Error, Debug)]
+#[error("unexpected end of data at offset {0:#x}")]
+pub struct UnexpectedEof(pub usize);
+
+pub type ReadResult<T> = Result<T, UnexpectedEof>;
+
+/// A cursor over the binary data of dex and class files
+#[derive(Clone)]
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    endian: Endian,
+}
+
+impl<'a> Reader<'a> {
+    pub fn little_endian(bytes: &'a [u8], pos: usize) -> Self {
+        Self {
+            bytes,
+            pos,
+            endian: Endian::Little,
+        }
+    }
+
+    pub fn big_endian(bytes: &'a [u8], pos: usize) -> Self {
+        Self {
+            bytes,
+            pos,
+            endian: Endian::Big,
+        }
+    }
+
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    pub fn bytes(&mut self, len: usize) -> ReadResult<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len());
+        let end = end.ok_or(UnexpectedEof(self.pos))?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    /// skips `len` bytes
+    pub fn skip(&mut self, len: usize) -> ReadResult<()> {
+        self.bytes(len).map(|_| ())
+    }
+
+    pub fn u8(&mut self) -> ReadResult<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> ReadResult<u16> {
+        let bytes = self.bytes(2)?;
+        let bytes = [bytes[0], bytes[1]];
+        Ok(match self.endian {
+            Endian::Little => u16::from_le_bytes(bytes),
+            Endian::Big => u16::from_be_bytes(bytes),
+        })
+    }
+
+    pub fn u32(&mut self) -> ReadResult<u32> {
+        let bytes = self.bytes(4)?;
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        Ok(match self.endian {
+            Endian::Little => u32::from_le_bytes(bytes),
+            Endian::Big => u32::from_be_bytes(bytes),
+        })
+    }
+
+    pub fn u64(&mut self) -> ReadResult<u64> {
+        let (first, second) = (u64::from(self.u32()?), u64::from(self.u32()?));
+        Ok(match self.endian {
+            Endian::Little => (second << 32) | first,
+            Endian::Big => (first << 32) | second,
+        })
+    }
+
+    /// reads an unsigned LEB128 of up to 5 bytes
+    pub fn uleb128(&mut self) -> ReadResult<u32> {
+        let mut result = 0u32;
+        for i in 0..5 {
+            let byte = self.u8()?;
+            result |= u32::from(byte & 0x7f) << (i * 7);
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BYTES: [u8; 14] = [0xca, 0xfe, 0xba, 0xbe, 0x00, 0x34, 0, 0, 0, 1, 0, 0, 0, 2];
+
+    #[test]
+    fn big_endian() {
+        let mut reader = Reader::big_endian(&BYTES, 0);
+        assert_eq!(reader.u32().unwrap(), 0xcafe_babe);
+        assert_eq!(reader.u16().unwrap(), 52);
+        assert_eq!(reader.u64().unwrap(), 0x1_0000_0002);
+        assert!(matches!(reader.u8(), Err(UnexpectedEof(14))));
+    }
+
+    #[test]
+    fn little_endian() {
+        let mut reader = Reader::little_endian(&BYTES, 0);
+        assert_eq!(reader.u32().unwrap(), 0xbeba_feca);
+        assert_eq!(reader.u16().unwrap(), 0x3400);
+        assert_eq!(reader.u64().unwrap(), 0x0200_0000_0100_0000);
+    }
+
+    #[test]
+    fn uleb128() {
+        let mut reader = Reader::little_endian(&[0x00, 0x7f, 0x80, 0x7f, 0xb4, 0x07], 0);
+        assert_eq!(reader.uleb128().unwrap(), 0);
+        assert_eq!(reader.uleb128().unwrap(), 127);
+        assert_eq!(reader.uleb128().unwrap(), 16256);
+        assert_eq!(reader.uleb128().unwrap(), 948);
+        assert!(reader.uleb128().is_err());
+    }
+}
@@ -1,3 +1,6 @@
 pub extern crate thiserror;
 pub extern crate tracing;
 pub use tracing::*; // re-export macros
+
+pub mod bytes;
+pub mod mutf8;
@@ -0,0 +1,56 @@
+/// Decodes the modified UTF-8 used by dex and JVM class files, where NUL is encoded as two bytes and
+/// characters outside the BMP are encoded as two separately encoded surrogates
+pub fn decode(bytes: &[u8]) -> Option<String> {
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut iter = bytes.iter();
+
+    while let Some(&first) = iter.next() {
+        let unit = match first {
+            0x01..=0x7f => u16::from(first),
+            0xc0..=0xdf => {
+                let second = continuation(iter.next())?;
+                (u16::from(first & 0x1f) << 6) | second
+            }
+            0xe0..=0xef => {
+                let second = continuation(iter.next())?;
+                let third = continuation(iter.next())?;
+                (u16::from(first & 0x0f) << 12) | (second << 6) | third
+            }
+            _ => return None,
+        };
+        units.push(unit);
+    }
+
+    String::from_utf16(&units).ok()
+}
+
+fn continuation(byte: Option<&u8>) -> Option<u16> {
+    match byte {
+        Some(byte) if byte & 0xc0 == 0x80 => Some(u16::from(byte & 0x3f)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii() {
+        assert_eq!(decode(b"Lbttv/Test;").unwrap(), "Lbttv/Test;");
+    }
+
+    #[test]
+    fn special() {
+        // NUL, ä and U+1F600 as surrogate pair
+        let bytes = [0xc0, 0x80, 0xc3, 0xa4, 0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80];
+        assert_eq!(decode(&bytes).unwrap(), "\u{0}\u{e4}\u{1F600}");
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(decode(&[0x00]).is_none());
+        assert!(decode(&[0xc3]).is_none());
+        assert!(decode(&[0xed, 0xa0, 0xbd]).is_none());
+    }
+}
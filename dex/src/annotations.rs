@@ -0,0 +1,165 @@
+use crate::dex_file::{to_usize, DexFile};
+use crate::err::*;
+use crate::reader::{Reader, SizedInt};
+use smali::*;
+
+const VALUE_BYTE: u8 = 0x00;
+const VALUE_SHORT: u8 = 0x02;
+const VALUE_CHAR: u8 = 0x03;
+const VALUE_INT: u8 = 0x04;
+const VALUE_LONG: u8 = 0x06;
+const VALUE_FLOAT: u8 = 0x10;
+const VALUE_DOUBLE: u8 = 0x11;
+const VALUE_METHOD_TYPE: u8 = 0x15;
+const VALUE_METHOD_HANDLE: u8 = 0x16;
+const VALUE_STRING: u8 = 0x17;
+const VALUE_TYPE: u8 = 0x18;
+const VALUE_FIELD: u8 = 0x19;
+const VALUE_METHOD: u8 = 0x1a;
+const VALUE_ENUM: u8 = 0x1b;
+const VALUE_ARRAY: u8 = 0x1c;
+const VALUE_ANNOTATION: u8 = 0x1d;
+const VALUE_NULL: u8 = 0x1e;
+const VALUE_BOOLEAN: u8 = 0x1f;
+
+/// How deep arrays and annotations may be nested in an encoded value. dx and d8 never get
+/// close, the limit keeps a crafted file from overflowing the stack.
+pub const MAX_DEPTH: usize = 64;
+
+/// Reads the class level annotations of an annotations_directory_item, the field, method and
+/// parameter annotations that follow them are skipped, see `parse_dex`
+pub fn class_annotations(dex: &DexFile, directory_off: u32) -> DexResult<Vec<SmaliAnnotation>> {
+    if directory_off == 0 {
+        return Ok(vec![]);
+    }
+    let set_off = Reader::little_endian(dex.bytes(), to_usize(directory_off)).u32()?;
+    if set_off == 0 {
+        return Ok(vec![]);
+    }
+
+    let mut set = Reader::little_endian(dex.bytes(), to_usize(set_off));
+    let size = set.u32()?;
+    (0..size)
+        .map(|_| {
+            let mut item = Reader::little_endian(dex.bytes(), to_usize(set.u32()?));
+            let visibility = match item.u8()? {
+                0 => AnnotationVisibility::Build,
+                1 => AnnotationVisibility::Runtime,
+                _ => AnnotationVisibility::System,
+            };
+            encoded_annotation(dex, &mut item, visibility, 0)
+        })
+        .collect()
+}
+
+fn encoded_annotation(
+    dex: &DexFile,
+    reader: &mut Reader,
+    visibility: AnnotationVisibility,
+    depth: usize,
+) -> DexResult<SmaliAnnotation> {
+    let type_path = dex.class_path(reader.uleb128()?)?;
+    let size = reader.uleb128()?;
+    let elements = (0..size)
+        .map(|_| {
+            let name = Symbol::intern(dex.string(reader.uleb128()?)?);
+            let value = encoded_value(dex, reader, visibility, depth + 1)?;
+            Ok(AnnotationElement { name, value })
+        })
+        .collect::<DexResult<_>>()?;
+    Ok(SmaliAnnotation {
+        visibility,
+        type_path,
+        elements,
+    })
+}
+
+fn encoded_value(
+    dex: &DexFile,
+    reader: &mut Reader,
+    visibility: AnnotationVisibility,
+    depth: usize,
+) -> DexResult<AnnotationValue> {
+    if depth > MAX_DEPTH {
+        return Err(DexError::EncodedValueTooDeep(reader.pos()));
+    }
+    let header = reader.u8()?;
+    let value_type = header & 0x1f;
+    let value_arg = usize::from(header >> 5);
+    // the size in bytes of the value, for the types that have one
+    let len = value_arg + 1;
+    let index = |reader: &mut Reader| -> DexResult<u32> {
+        // indices are at most 4 bytes wide, so the truncation is lossless
+        Ok(reader.sized_int(len, 4, false)? as u32)
+    };
+
+    // sized_int sign extends to 64 bits and rejects values wider than their type,
+    // truncating keeps the two's complement value
+    let value = match value_type {
+        VALUE_BYTE => AnnotationValue::Byte(reader.sized_int(len, 1, true)? as i8),
+        VALUE_SHORT => AnnotationValue::Short(reader.sized_int(len, 2, true)? as i16),
+        VALUE_CHAR => AnnotationValue::Char(reader.sized_int(len, 2, false)? as u16),
+        VALUE_INT => AnnotationValue::Int(reader.sized_int(len, 4, true)? as i32),
+        VALUE_LONG => AnnotationValue::Long(reader.sized_int(len, 8, true)? as i64),
+        VALUE_FLOAT => {
+            let bits = reader.right_zero_extended(len, 4)? as u32;
+            AnnotationValue::Float(f32::from_bits(bits))
+        }
+        VALUE_DOUBLE => {
+            let bits = reader.right_zero_extended(len, 8)?;
+            AnnotationValue::Double(f64::from_bits(bits))
+        }
+        VALUE_METHOD_TYPE => AnnotationValue::MethodType(dex.proto_descriptor(index(reader)?)?),
+        VALUE_METHOD_HANDLE => AnnotationValue::MethodHandle(dex.method_handle(index(reader)?)?),
+        VALUE_STRING => AnnotationValue::String(dex.string(index(reader)?)?.to_string()),
+        VALUE_TYPE => AnnotationValue::Type(dex.smali_type(index(reader)?)?),
+        VALUE_FIELD => AnnotationValue::Field(dex.field_ref(index(reader)?)?),
+        VALUE_METHOD => AnnotationValue::Method(dex.method_ref(index(reader)?)?),
+        VALUE_ENUM => AnnotationValue::Enum(dex.field_ref(index(reader)?)?),
+        VALUE_ARRAY => {
+            let size = reader.uleb128()?;
+            let values = (0..size)
+                .map(|_| encoded_value(dex, reader, visibility, depth + 1))
+                .collect::<DexResult<_>>()?;
+            AnnotationValue::Array(values)
+        }
+        VALUE_ANNOTATION => {
+            AnnotationValue::Annotation(encoded_annotation(dex, reader, visibility, depth)?)
+        }
+        VALUE_NULL => AnnotationValue::Null,
+        VALUE_BOOLEAN => AnnotationValue::Boolean(value_arg != 0),
+        _ => return Err(DexError::InvalidEncodedValue(value_type)),
+    };
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nested_arrays(depth: usize) -> Vec<u8> {
+        // arrays of one element each, the innermost holds null
+        let mut bytes = [VALUE_ARRAY, 1].repeat(depth);
+        bytes.push(VALUE_NULL);
+        bytes
+    }
+
+    #[test]
+    fn nesting_depth() {
+        let dex = DexFile::open(include_bytes!("../tests/files/test.dex")).unwrap();
+        let read = |bytes: &[u8]| {
+            encoded_value(
+                &dex,
+                &mut Reader::little_endian(bytes, 0),
+                AnnotationVisibility::Runtime,
+                0,
+            )
+        };
+
+        assert!(read(&nested_arrays(MAX_DEPTH)).is_ok());
+        assert!(matches!(
+            read(&nested_arrays(MAX_DEPTH + 1)),
+            Err(DexError::EncodedValueTooDeep(offset)) if offset == 2 * (MAX_DEPTH + 1)
+        ));
+    }
+}
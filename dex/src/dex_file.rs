@@ -0,0 +1,369 @@
+use crate::err::*;
+use crate::reader::Reader;
+use common::mutf8;
+use smali::*;
+use std::str::FromStr;
+
+const MAGIC: &[u8] = b"dex\n";
+const ENDIAN_CONSTANT: u32 = 0x1234_5678;
+const NO_INDEX: u32 = 0xffff_ffff;
+const TYPE_METHOD_HANDLE_ITEM: u16 = 0x0008;
+
+pub const ACC_PUBLIC: u32 = 0x1;
+pub const ACC_PRIVATE: u32 = 0x2;
+pub const ACC_PROTECTED: u32 = 0x4;
+pub const ACC_STATIC: u32 = 0x8;
+pub const ACC_FINAL: u32 = 0x10;
+pub const ACC_INTERFACE: u32 = 0x200;
+pub const ACC_ABSTRACT: u32 = 0x400;
+pub const ACC_SYNTHETIC: u32 = 0x1000;
+pub const ACC_ANNOTATION: u32 = 0x2000;
+pub const ACC_ENUM: u32 = 0x4000;
+
+struct Proto {
+    return_type: u32,
+    parameters: Vec<u32>,
+}
+
+struct MemberId {
+    class: u32,
+    // the type of a field or the proto of a method
+    descriptor: u32,
+    name: u32,
+}
+
+/// The id tables of a dex file, with all strings decoded
+pub struct DexFile<'a> {
+    bytes: &'a [u8],
+    strings: Vec<String>,
+    types: Vec<u32>,
+    protos: Vec<Proto>,
+    fields: Vec<MemberId>,
+    methods: Vec<MemberId>,
+    method_handles: Vec<(u16, u32)>,
+    class_defs_size: u32,
+    class_defs_off: u32,
+}
+
+impl<'a> DexFile<'a> {
+    pub fn open(bytes: &'a [u8]) -> DexResult<Self> {
+        let mut header = Reader::little_endian(bytes, 0);
+        if header.bytes(8)?.get(..4) != Some(MAGIC) {
+            return Err(DexError::InvalidMagic());
+        }
+        let checksum = header.u32()?;
+        let found = adler32(&bytes[12..]);
+        if checksum != found {
+            return Err(DexError::ChecksumMismatch {
+                expected: checksum,
+                found,
+            });
+        }
+
+        let mut header = Reader::little_endian(bytes, 40);
+        let endian_tag = header.u32()?;
+        if endian_tag != ENDIAN_CONSTANT {
+            return Err(DexError::UnsupportedEndianness(endian_tag));
+        }
+        let _link = (header.u32()?, header.u32()?);
+        let map_off = header.u32()?;
+        let (string_ids_size, string_ids_off) = (header.u32()?, header.u32()?);
+        let (type_ids_size, type_ids_off) = (header.u32()?, header.u32()?);
+        let (proto_ids_size, proto_ids_off) = (header.u32()?, header.u32()?);
+        let (field_ids_size, field_ids_off) = (header.u32()?, header.u32()?);
+        let (method_ids_size, method_ids_off) = (header.u32()?, header.u32()?);
+        let (class_defs_size, class_defs_off) = (header.u32()?, header.u32()?);
+
+        let mut reader = Reader::little_endian(bytes, to_usize(string_ids_off));
+        let strings = (0..string_ids_size)
+            .map(|_| read_string(bytes, reader.u32()?))
+            .collect::<DexResult<_>>()?;
+
+        let mut reader = Reader::little_endian(bytes, to_usize(type_ids_off));
+        let types = (0..type_ids_size)
+            .map(|_| Ok(reader.u32()?))
+            .collect::<DexResult<_>>()?;
+
+        let mut reader = Reader::little_endian(bytes, to_usize(proto_ids_off));
+        let protos = (0..proto_ids_size)
+            .map(|_| {
+                let _shorty = reader.u32()?;
+                let return_type = reader.u32()?;
+                let parameters = read_type_list(bytes, reader.u32()?)?;
+                Ok(Proto {
+                    return_type,
+                    parameters,
+                })
+            })
+            .collect::<DexResult<_>>()?;
+
+        let mut reader = Reader::little_endian(bytes, to_usize(field_ids_off));
+        let fields = (0..field_ids_size)
+            .map(|_| {
+                Ok(MemberId {
+                    class: u32::from(reader.u16()?),
+                    descriptor: u32::from(reader.u16()?),
+                    name: reader.u32()?,
+                })
+            })
+            .collect::<DexResult<_>>()?;
+
+        let mut reader = Reader::little_endian(bytes, to_usize(method_ids_off));
+        let methods = (0..method_ids_size)
+            .map(|_| {
+                Ok(MemberId {
+                    class: u32::from(reader.u16()?),
+                    descriptor: u32::from(reader.u16()?),
+                    name: reader.u32()?,
+                })
+            })
+            .collect::<DexResult<_>>()?;
+
+        Ok(Self {
+            bytes,
+            strings,
+            types,
+            protos,
+            fields,
+            methods,
+            method_handles: read_method_handles(bytes, map_off)?,
+            class_defs_size,
+            class_defs_off,
+        })
+    }
+
+    pub fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    pub fn string(&self, index: u32) -> DexResult<&str> {
+        get(&self.strings, "string", index).map(String::as_str)
+    }
+
+    pub fn type_descriptor(&self, index: u32) -> DexResult<&str> {
+        self.string(*get(&self.types, "type", index)?)
+    }
+
+    pub fn smali_type(&self, index: u32) -> DexResult<SmaliType> {
+        Ok(SmaliType::from_str(self.type_descriptor(index)?)?)
+    }
+
+    pub fn class_path(&self, index: u32) -> DexResult<ClassPath> {
+        Ok(ClassPath::from_smali(self.type_descriptor(index)?)?)
+    }
+
+    /// the descriptor of a proto, `(I[J)V`
+    pub fn proto_descriptor(&self, index: u32) -> DexResult<String> {
+        let proto = get(&self.protos, "proto", index)?;
+        let mut descriptor = String::from("(");
+        for param in &proto.parameters {
+            descriptor.push_str(self.type_descriptor(*param)?);
+        }
+        descriptor.push(')');
+        descriptor.push_str(self.type_descriptor(proto.return_type)?);
+        Ok(descriptor)
+    }
+
+    /// a field reference in smali notation, `Lbttv/Test;->COUNT:I`
+    pub fn field_ref(&self, index: u32) -> DexResult<String> {
+        let field = get(&self.fields, "field", index)?;
+        Ok(format!(
+            "{}->{}:{}",
+            self.type_descriptor(field.class)?,
+            self.string(field.name)?,
+            self.type_descriptor(field.descriptor)?
+        ))
+    }
+
+    /// a method reference in smali notation, `Lbttv/Test;->run()V`
+    pub fn method_ref(&self, index: u32) -> DexResult<String> {
+        let method = get(&self.methods, "method", index)?;
+        Ok(format!(
+            "{}->{}{}",
+            self.type_descriptor(method.class)?,
+            self.string(method.name)?,
+            self.proto_descriptor(method.descriptor)?
+        ))
+    }
+
+    /// a method handle in smali notation, `invoke-static@Lbttv/Test;->run()V`
+    pub fn method_handle(&self, index: u32) -> DexResult<String> {
+        let (kind, member) = *get(&self.method_handles, "method handle", index)?;
+        let (kind, is_field) = match kind {
+            0 => ("static-put", true),
+            1 => ("static-get", true),
+            2 => ("instance-put", true),
+            3 => ("instance-get", true),
+            4 => ("invoke-static", false),
+            5 => ("invoke-instance", false),
+            6 => ("invoke-constructor", false),
+            7 => ("invoke-direct", false),
+            _ => ("invoke-interface", false),
+        };
+        let member = if is_field {
+            self.field_ref(member)?
+        } else {
+            self.method_ref(member)?
+        };
+        Ok(format!("{}@{}", kind, member))
+    }
+
+    pub fn field_value(&self, index: u32, access_flags: u32) -> DexResult<SmaliValue> {
+        let field = get(&self.fields, "field", index)?;
+        Ok(SmaliValue {
+            name: Symbol::intern(self.string(field.name)?),
+            data_type: self.smali_type(field.descriptor)?,
+            access: access_modifier(access_flags),
+            is_static: access_flags & ACC_STATIC != 0,
+            is_final: access_flags & ACC_FINAL != 0,
+            is_synthetic: access_flags & ACC_SYNTHETIC != 0,
+            is_enum: access_flags & ACC_ENUM != 0,
+        })
+    }
+
+    pub fn method(&self, index: u32, access_flags: u32) -> DexResult<SmaliMethod> {
+        let method = get(&self.methods, "method", index)?;
+        let proto = get(&self.protos, "proto", method.descriptor)?;
+        Ok(SmaliMethod {
+            name: Symbol::intern(self.string(method.name)?),
+            access: access_modifier(access_flags),
+            parameter_types: proto
+                .parameters
+                .iter()
+                .map(|param| self.smali_type(*param))
+                .collect::<DexResult<_>>()?,
+            return_type: self.smali_type(proto.return_type)?,
+            is_static: access_flags & ACC_STATIC != 0,
+            is_final: access_flags & ACC_FINAL != 0,
+            is_synthetic: access_flags & ACC_SYNTHETIC != 0,
+        })
+    }
+
+    pub fn class_defs_size(&self) -> u32 {
+        self.class_defs_size
+    }
+
+    pub fn class_def_reader(&self, index: u32) -> Reader<'a> {
+        Reader::little_endian(
+            self.bytes,
+            to_usize(self.class_defs_off) + to_usize(index) * 32,
+        )
+    }
+
+    pub fn type_list(&self, off: u32) -> DexResult<Vec<u32>> {
+        read_type_list(self.bytes, off)
+    }
+}
+
+pub fn class_kind(access_flags: u32) -> ClassKind {
+    if access_flags & ACC_ANNOTATION != 0 {
+        ClassKind::Annotation
+    } else if access_flags & ACC_INTERFACE != 0 {
+        ClassKind::Interface
+    } else if access_flags & ACC_ENUM != 0 {
+        ClassKind::Enum
+    } else {
+        ClassKind::Class
+    }
+}
+
+pub fn access_modifier(access_flags: u32) -> SmaliAccessModifier {
+    if access_flags & ACC_PUBLIC != 0 {
+        SmaliAccessModifier::Public
+    } else if access_flags & ACC_PRIVATE != 0 {
+        SmaliAccessModifier::Private
+    } else if access_flags & ACC_PROTECTED != 0 {
+        SmaliAccessModifier::Protected
+    } else {
+        SmaliAccessModifier::Package
+    }
+}
+
+pub fn is_no_index(index: u32) -> bool {
+    index == NO_INDEX
+}
+
+pub fn to_usize(value: u32) -> usize {
+    // dex offsets are u32, usize is at least as wide on every supported target
+    value as usize
+}
+
+fn get<'t, T>(table: &'t [T], name: &'static str, index: u32) -> DexResult<&'t T> {
+    table
+        .get(to_usize(index))
+        .ok_or(DexError::IndexOutOfBounds { table: name, index })
+}
+
+fn read_string(bytes: &[u8], off: u32) -> DexResult<String> {
+    let mut reader = Reader::little_endian(bytes, to_usize(off));
+    let _utf16_size = reader.uleb128()?;
+    let start = reader.pos();
+    let len = bytes
+        .get(start..)
+        .and_then(|rest| rest.iter().position(|byte| *byte == 0))
+        .ok_or(DexError::UnexpectedEof(start))?;
+    mutf8::decode(&bytes[start..start + len]).ok_or(DexError::InvalidString(start))
+}
+
+fn read_type_list(bytes: &[u8], off: u32) -> DexResult<Vec<u32>> {
+    if off == 0 {
+        return Ok(vec![]);
+    }
+    let mut reader = Reader::little_endian(bytes, to_usize(off));
+    let size = reader.u32()?;
+    (0..size).map(|_| Ok(u32::from(reader.u16()?))).collect()
+}
+
+fn read_method_handles(bytes: &[u8], map_off: u32) -> DexResult<Vec<(u16, u32)>> {
+    if map_off == 0 {
+        return Ok(vec![]);
+    }
+    let mut map = Reader::little_endian(bytes, to_usize(map_off));
+    let size = map.u32()?;
+    for _ in 0..size {
+        let item_type = map.u16()?;
+        let _unused = map.u16()?;
+        let item_size = map.u32()?;
+        let item_off = map.u32()?;
+        if item_type != TYPE_METHOD_HANDLE_ITEM {
+            continue;
+        }
+
+        let mut reader = Reader::little_endian(bytes, to_usize(item_off));
+        return (0..item_size)
+            .map(|_| {
+                let kind = reader.u16()?;
+                let _unused = reader.u16()?;
+                let member = u32::from(reader.u16()?);
+                let _unused = reader.u16()?;
+                Ok((kind, member))
+            })
+            .collect();
+    }
+    Ok(vec![])
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 is the largest n for which the sums can not overflow before the modulo
+    for chunk in bytes.chunks(5552) {
+        for byte in chunk {
+            a += u32::from(*byte);
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adler32_wikipedia() {
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+}
@@ -0,0 +1,40 @@
+use common::thiserror;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum DexError {
+    #[error("IOError")]
+    IOError(#[from] std::io::Error),
+    #[error("not a dex file")]
+    InvalidMagic(),
+    #[error("unsupported endian tag {0:#x}")]
+    UnsupportedEndianness(u32),
+    #[error("checksum mismatch: header says {expected:#x}, file has {found:#x}")]
+    ChecksumMismatch { expected: u32, found: u32 },
+    #[error("unexpected end of file at offset {0:#x}")]
+    UnexpectedEof(usize),
+    #[error("invalid MUTF-8 string at offset {0:#x}")]
+    InvalidString(usize),
+    #[error("{table} index {index} is out of bounds")]
+    IndexOutOfBounds { table: &'static str, index: u32 },
+    #[error("value of {len} bytes at offset {offset:#x} does not fit into {width} bytes")]
+    InvalidValueSize {
+        offset: usize,
+        len: usize,
+        width: usize,
+    },
+    #[error("invalid encoded value type {0:#x}")]
+    InvalidEncodedValue(u8),
+    #[error("encoded value at offset {0:#x} is nested deeper than {max} levels", max = crate::annotations::MAX_DEPTH)]
+    EncodedValueTooDeep(usize),
+    #[error(transparent)]
+    InvalidDescriptor(#[from] smali::ParserError),
+}
+
+impl From<common::bytes::UnexpectedEof> for DexError {
+    fn from(err: common::bytes::UnexpectedEof) -> Self {
+        Self::UnexpectedEof(err.0)
+    }
+}
+
+pub type DexResult<T> = Result<T, DexError>;
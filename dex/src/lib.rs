@@ -0,0 +1,94 @@
+//! dex is a crate that reads the classes of compiled dex files into the smali model,
+//! without disassembling them with baksmali first, like the mocks passed to `ubi --mock-dex`
+//!
+//! Only the class level annotations are read, the smali model has no place for field, method
+//! and parameter annotations, see `parse_dex`.
+
+extern crate common;
+
+mod annotations;
+mod dex_file;
+mod err;
+mod reader;
+
+pub use err::*;
+
+use dex_file::*;
+use smali::SmaliClass;
+
+/// Reads the dex file at file_path, see `parse_dex`
+pub fn parse_dex_file(file_path: impl AsRef<std::path::Path>) -> DexResult<Vec<SmaliClass>> {
+    let bytes = std::fs::read(file_path.as_ref())?;
+    parse_dex(&bytes)
+}
+
+/// Parses all classes defined in a dex file, in class_def order
+///
+/// The classes are equal to what `smali::parse_file` returns for the baksmali output of the
+/// same file, except that class level annotations are filled in. Field, method and parameter
+/// annotations of the annotations_directory_item are not read: the smali model has no place
+/// for them and `smali::parse_file` skips them as well.
+pub fn parse_dex(bytes: &[u8]) -> DexResult<Vec<SmaliClass>> {
+    let dex = DexFile::open(bytes)?;
+    (0..dex.class_defs_size())
+        .map(|index| parse_class_def(&dex, index))
+        .collect()
+}
+
+fn parse_class_def(dex: &DexFile, index: u32) -> DexResult<SmaliClass> {
+    let mut def = dex.class_def_reader(index);
+    let class_idx = def.u32()?;
+    let access_flags = def.u32()?;
+    let superclass_idx = def.u32()?;
+    let interfaces_off = def.u32()?;
+    let _source_file_idx = def.u32()?;
+    let annotations_off = def.u32()?;
+    let class_data_off = def.u32()?;
+
+    let mut class = SmaliClass::new(
+        dex.class_path(class_idx)?,
+        access_modifier(access_flags),
+        access_flags & ACC_ABSTRACT != 0,
+    );
+    class.is_synthetic = access_flags & ACC_SYNTHETIC != 0;
+    class.kind = class_kind(access_flags);
+    if !is_no_index(superclass_idx) {
+        class.super_path = Some(dex.class_path(superclass_idx)?);
+    }
+    class.interfaces = dex
+        .type_list(interfaces_off)?
+        .into_iter()
+        .map(|interface| dex.class_path(interface))
+        .collect::<DexResult<_>>()?;
+    class.annotations = annotations::class_annotations(dex, annotations_off)?;
+
+    if class_data_off == 0 {
+        return Ok(class);
+    }
+    let mut data = reader::Reader::little_endian(dex.bytes(), to_usize(class_data_off));
+    let static_fields_size = data.uleb128()?;
+    let instance_fields_size = data.uleb128()?;
+    let direct_methods_size = data.uleb128()?;
+    let virtual_methods_size = data.uleb128()?;
+
+    // member indices are stored as the difference to the previous member of the same list
+    for size in [static_fields_size, instance_fields_size] {
+        let mut index = 0u32;
+        for _ in 0..size {
+            index = index.wrapping_add(data.uleb128()?);
+            let access_flags = data.uleb128()?;
+            class.values.push(dex.field_value(index, access_flags)?);
+        }
+    }
+    for size in [direct_methods_size, virtual_methods_size] {
+        let mut index = 0u32;
+        for _ in 0..size {
+            index = index.wrapping_add(data.uleb128()?);
+            let access_flags = data.uleb128()?;
+            let _code_off = data.uleb128()?;
+            class.methods.push(dex.method(index, access_flags)?);
+        }
+    }
+
+    Ok(class)
+}
@@ -0,0 +1,73 @@
+use crate::err::*;
+pub use common::bytes::Reader;
+
+/// Reads the variable sized integers of encoded values
+pub trait SizedInt {
+    /// reads `len` bytes as little endian integer of a type that is `width` bytes wide,
+    /// sign extending it if `signed` is set
+    fn sized_int(&mut self, len: usize, width: usize, signed: bool) -> DexResult<u64>;
+
+    /// reads `len` bytes as the most significant bytes of a `width` bytes long value
+    fn right_zero_extended(&mut self, len: usize, width: usize) -> DexResult<u64>;
+}
+
+impl SizedInt for Reader<'_> {
+    fn sized_int(&mut self, len: usize, width: usize, signed: bool) -> DexResult<u64> {
+        check_size(self, len, width)?;
+        let bytes = self.bytes(len)?;
+        let mut value = 0u64;
+        for (i, byte) in bytes.iter().enumerate() {
+            value |= u64::from(*byte) << (i * 8);
+        }
+        let bits = len * 8;
+        if signed && bits < 64 && value & (1 << (bits - 1)) != 0 {
+            value |= u64::MAX << bits;
+        }
+        Ok(value)
+    }
+
+    fn right_zero_extended(&mut self, len: usize, width: usize) -> DexResult<u64> {
+        check_size(self, len, width)?;
+        Ok(self.sized_int(len, width, false)? << ((width - len) * 8))
+    }
+}
+
+fn check_size(reader: &Reader, len: usize, width: usize) -> DexResult<()> {
+    if len == 0 || len > width || width > 8 {
+        return Err(DexError::InvalidValueSize {
+            offset: reader.pos(),
+            len,
+            width,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sized_int() {
+        let mut reader = Reader::little_endian(&[0xfe, 0x2c, 0x01, 0x00, 0x3f], 0);
+        assert_eq!(reader.sized_int(1, 1, true).unwrap() as i64, -2);
+        assert_eq!(reader.sized_int(2, 4, true).unwrap(), 300);
+        assert_eq!(reader.right_zero_extended(2, 4).unwrap(), 0x3f00_0000);
+    }
+
+    #[test]
+    fn oversized_int() {
+        let mut reader = Reader::little_endian(&[0; 16], 0);
+        assert!(matches!(
+            reader.right_zero_extended(8, 4),
+            Err(DexError::InvalidValueSize {
+                offset: 0,
+                len: 8,
+                width: 4
+            })
+        ));
+        assert!(reader.sized_int(3, 2, true).is_err());
+        assert!(reader.sized_int(0, 4, false).is_err());
+        assert_eq!(reader.pos(), 0);
+    }
+}
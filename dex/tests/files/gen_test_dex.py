@@ -0,0 +1,430 @@
+#!/usr/bin/env python3
+"""Generates test.dex, the fixture for the dex reader tests.
+
+There is no dex compiler in the test environment, so the file is assembled by hand.
+The classes match Test.smali and Test$Iface.smali in this directory.
+
+Usage: python3 gen_test_dex.py  (writes test.dex next to this script)
+"""
+
+import hashlib
+import os
+import struct
+import zlib
+
+NO_INDEX = 0xFFFFFFFF
+
+
+def uleb128(value):
+    out = bytearray()
+    while True:
+        byte = value & 0x7F
+        value >>= 7
+        if value:
+            out.append(byte | 0x80)
+        else:
+            out.append(byte)
+            return bytes(out)
+
+
+def mutf8(string):
+    out = bytearray()
+    for unit in struct.unpack("<%dH" % (len(string.encode("utf-16-le")) // 2), string.encode("utf-16-le")):
+        if unit != 0 and unit < 0x80:
+            out.append(unit)
+        elif unit < 0x800:
+            out += bytes([0xC0 | (unit >> 6), 0x80 | (unit & 0x3F)])
+        else:
+            out += bytes([0xE0 | (unit >> 12), 0x80 | ((unit >> 6) & 0x3F), 0x80 | (unit & 0x3F)])
+    return bytes(out)
+
+
+def utf16_len(string):
+    return len(string.encode("utf-16-le")) // 2
+
+
+def utf16_key(string):
+    return string.encode("utf-16-be")
+
+
+# --- classes -----------------------------------------------------------------
+
+ACC_PUBLIC, ACC_PRIVATE, ACC_PROTECTED, ACC_STATIC, ACC_FINAL = 0x1, 0x2, 0x4, 0x8, 0x10
+ACC_BRIDGE, ACC_NATIVE, ACC_INTERFACE, ACC_ABSTRACT = 0x40, 0x100, 0x200, 0x400
+ACC_SYNTHETIC, ACC_CONSTRUCTOR = 0x1000, 0x10000
+
+TEST = "Lbttv/Test;"
+IFACE = "Lbttv/Test$Iface;"
+OBJECT = "Ljava/lang/Object;"
+
+# (descriptor, access, super, interfaces, static fields, instance fields, direct methods, virtual methods, annotations)
+# fields: (name, type, access), methods: (name, (params, return), access, has_code)
+CLASSES = [
+    (
+        TEST,
+        ACC_PUBLIC | ACC_FINAL,
+        OBJECT,
+        ["Ljava/lang/Runnable;"],
+        [("COUNT", "I", ACC_PRIVATE | ACC_STATIC | ACC_FINAL), ("names", "[[Ljava/lang/String;", ACC_PUBLIC | ACC_STATIC)],
+        [("flag", "Z", ACC_PROTECTED), ("value", "J", 0)],
+        [
+            ("<init>", ([], "V"), ACC_PUBLIC | ACC_CONSTRUCTOR, True),
+            ("foo", (["B", "C", "S", "[[I"], "J"), ACC_PRIVATE | ACC_STATIC | ACC_NATIVE, False),
+        ],
+        [
+            ("run", ([], "V"), ACC_PUBLIC, True),
+            ("get", ([OBJECT], OBJECT), ACC_PUBLIC | ACC_FINAL | ACC_SYNTHETIC | ACC_BRIDGE, True),
+        ],
+        [
+            (1, "Lkotlin/Metadata;", [
+                ("k", ("int", 1)),
+                ("mv", ("array", [("int", 1), ("int", 6), ("int", 0)])),
+                ("d1", ("array", [("string", "\u0000\u0006😀")])),
+                ("d2", ("array", [("string", TEST), ("string", "run")])),
+            ]),
+            (2, "Ldalvik/annotation/Signature;", [
+                ("value", ("array", [("string", OBJECT)])),
+            ]),
+            (0, "Lbttv/Ann;", [
+                ("b", ("boolean", True)),
+                ("by", ("byte", -1)),
+                ("c", ("char", ord("x"))),
+                ("d", ("double", -2.25)),
+                ("e", ("enum", ("Lbttv/E;", "A", "Lbttv/E;"))),
+                ("f", ("float", 1.5)),
+                ("l", ("long", -5)),
+                ("m", ("method", (TEST, "run", ([], "V")))),
+                ("n", ("null", None)),
+                ("s", ("short", -2)),
+                ("sub", ("annotation", ("Lbttv/Sub;", [("x", ("int", 300))]))),
+                ("t", ("type", "[I")),
+            ]),
+        ],
+    ),
+    (
+        IFACE,
+        ACC_PUBLIC | ACC_INTERFACE | ACC_ABSTRACT,
+        OBJECT,
+        [],
+        [],
+        [],
+        [],
+        [("call", (["I"], "V"), ACC_PUBLIC | ACC_ABSTRACT, False)],
+        [],
+    ),
+]
+
+# --- collect ids -------------------------------------------------------------
+
+strings, types, protos, fields, methods = set(), set(), set(), set(), set()
+
+
+def shorty(proto):
+    params, ret = proto
+    return "".join("L" if t[0] in "L[" else t for t in [ret] + params)
+
+
+def add_type(t):
+    types.add(t)
+    strings.add(t)
+
+
+def add_proto(proto):
+    params, ret = proto
+    protos.add((tuple(params), ret))
+    strings.add(shorty(proto))
+    add_type(ret)
+    for p in params:
+        add_type(p)
+
+
+def add_field(cls, name, t):
+    fields.add((cls, name, t))
+    add_type(cls)
+    add_type(t)
+    strings.add(name)
+
+
+def add_method(cls, name, proto):
+    methods.add((cls, name, (tuple(proto[0]), proto[1])))
+    add_type(cls)
+    add_proto(proto)
+    strings.add(name)
+
+
+def add_value(value):
+    kind, data = value
+    if kind == "string":
+        strings.add(data)
+    elif kind == "type":
+        add_type(data)
+    elif kind == "enum":
+        add_field(*data)
+    elif kind == "method":
+        add_method(*data)
+    elif kind == "array":
+        for item in data:
+            add_value(item)
+    elif kind == "annotation":
+        add_type(data[0])
+        for name, item in data[1]:
+            strings.add(name)
+            add_value(item)
+
+
+for cls, _, sup, ifaces, sfields, ifields, dmethods, vmethods, annotations in CLASSES:
+    add_type(cls)
+    add_type(sup)
+    for iface in ifaces:
+        add_type(iface)
+    for name, t, _ in sfields + ifields:
+        add_field(cls, name, t)
+    for name, proto, _, _ in dmethods + vmethods:
+        add_method(cls, name, proto)
+    for _, t, elements in annotations:
+        add_type(t)
+        for name, value in elements:
+            strings.add(name)
+            add_value(value)
+
+strings = sorted(strings, key=utf16_key)
+string_idx = {s: i for i, s in enumerate(strings)}
+types = sorted(types, key=lambda t: string_idx[t])
+type_idx = {t: i for i, t in enumerate(types)}
+protos = sorted(protos, key=lambda p: (type_idx[p[1]], [type_idx[t] for t in p[0]]))
+proto_idx = {p: i for i, p in enumerate(protos)}
+fields = sorted(fields, key=lambda f: (type_idx[f[0]], string_idx[f[1]], type_idx[f[2]]))
+field_idx = {f: i for i, f in enumerate(fields)}
+methods = sorted(methods, key=lambda m: (type_idx[m[0]], string_idx[m[1]], proto_idx[m[2]]))
+method_idx = {m: i for i, m in enumerate(methods)}
+
+# --- layout ------------------------------------------------------------------
+
+HEADER_SIZE = 0x70
+string_ids_off = HEADER_SIZE
+type_ids_off = string_ids_off + 4 * len(strings)
+proto_ids_off = type_ids_off + 4 * len(types)
+field_ids_off = proto_ids_off + 12 * len(protos)
+method_ids_off = field_ids_off + 8 * len(fields)
+class_defs_off = method_ids_off + 8 * len(methods)
+data_off = class_defs_off + 32 * len(CLASSES)
+
+data = bytearray()
+map_items = []
+
+
+def align(n=4):
+    while (data_off + len(data)) % n:
+        data.append(0)
+
+
+def here():
+    return data_off + len(data)
+
+
+# type lists
+type_list_offs = {}
+type_lists = set(tuple(p[0]) for p in protos if p[0]) | set(tuple(c[3]) for c in CLASSES if c[3])
+for tl in sorted(type_lists):
+    align()
+    type_list_offs[tl] = here()
+    data += struct.pack("<I", len(tl)) + b"".join(struct.pack("<H", type_idx[t]) for t in tl)
+if type_lists:
+    map_items.append((0x1001, len(type_lists), min(type_list_offs.values())))
+
+# string data
+string_data_offs = []
+first = here()
+for s in strings:
+    string_data_offs.append(here())
+    data += uleb128(utf16_len(s)) + mutf8(s) + b"\0"
+map_items.append((0x2002, len(strings), first))
+
+
+# annotations
+def encode_value(value):
+    kind, d = value
+
+    def sized(type_id, raw, signed):
+        n = len(raw)
+        while n > 1:
+            top, nxt = raw[n - 1], raw[n - 2]
+            if signed and ((top == 0 and nxt < 0x80) or (top == 0xFF and nxt >= 0x80)):
+                n -= 1
+            elif not signed and top == 0:
+                n -= 1
+            else:
+                break
+        return bytes([((n - 1) << 5) | type_id]) + raw[:n]
+
+    def right_sized(type_id, raw):
+        n = len(raw)
+        start = 0
+        while n - start > 1 and raw[start] == 0:
+            start += 1
+        trimmed = raw[start:]
+        return bytes([((len(trimmed) - 1) << 5) | type_id]) + trimmed
+
+    if kind == "byte":
+        return bytes([0x00]) + struct.pack("<b", d)
+    if kind == "short":
+        return sized(0x02, struct.pack("<h", d), True)
+    if kind == "char":
+        return sized(0x03, struct.pack("<H", d), False)
+    if kind == "int":
+        return sized(0x04, struct.pack("<i", d), True)
+    if kind == "long":
+        return sized(0x06, struct.pack("<q", d), True)
+    if kind == "float":
+        return right_sized(0x10, struct.pack("<f", d))
+    if kind == "double":
+        return right_sized(0x11, struct.pack("<d", d))
+    if kind == "string":
+        return sized(0x17, struct.pack("<I", string_idx[d]), False)
+    if kind == "type":
+        return sized(0x18, struct.pack("<I", type_idx[d]), False)
+    if kind == "method":
+        cls, name, proto = d
+        return sized(0x1A, struct.pack("<I", method_idx[(cls, name, (tuple(proto[0]), proto[1]))]), False)
+    if kind == "enum":
+        return sized(0x1B, struct.pack("<I", field_idx[d]), False)
+    if kind == "array":
+        return bytes([0x1C]) + uleb128(len(d)) + b"".join(encode_value(v) for v in d)
+    if kind == "annotation":
+        return bytes([0x1D]) + encode_annotation(*d)
+    if kind == "null":
+        return bytes([0x1E])
+    if kind == "boolean":
+        return bytes([(int(d) << 5) | 0x1F])
+    raise ValueError(kind)
+
+
+def encode_annotation(t, elements):
+    elements = sorted(elements, key=lambda e: string_idx[e[0]])
+    out = uleb128(type_idx[t]) + uleb128(len(elements))
+    for name, value in elements:
+        out += uleb128(string_idx[name]) + encode_value(value)
+    return out
+
+
+annotation_dir_offs = {}
+annotation_item_count = annotation_set_count = annotation_dir_count = 0
+first_item = first_set = first_dir = None
+for cls, *_, annotations in CLASSES:
+    if not annotations:
+        continue
+    item_offs = []
+    for visibility, t, elements in sorted(annotations, key=lambda a: type_idx[a[1]]):
+        item_offs.append(here())
+        first_item = first_item or here()
+        data += bytes([visibility]) + encode_annotation(t, elements)
+        annotation_item_count += 1
+    align()
+    set_off = here()
+    first_set = first_set or set_off
+    data += struct.pack("<I", len(item_offs)) + b"".join(struct.pack("<I", o) for o in item_offs)
+    annotation_set_count += 1
+    align()
+    annotation_dir_offs[cls] = here()
+    first_dir = first_dir or here()
+    data += struct.pack("<IIII", set_off, 0, 0, 0)
+    annotation_dir_count += 1
+if annotation_item_count:
+    map_items += [(0x2004, annotation_item_count, first_item), (0x1003, annotation_set_count, first_set),
+                  (0x2006, annotation_dir_count, first_dir)]
+
+# code items: `return-void` or `return-object p1` bodies, just enough to be well formed
+code_offs = {}
+first_code = None
+code_count = 0
+for cls, _, _, _, _, _, dmethods, vmethods, _ in CLASSES:
+    for name, proto, access, has_code in dmethods + vmethods:
+        if not has_code:
+            continue
+        align()
+        first_code = first_code or here()
+        code_offs[(cls, name)] = here()
+        ins = len(proto[0]) + (0 if access & ACC_STATIC else 1)
+        insn = 0x000E if proto[1] == "V" else 0x0011 | ((ins - 1) << 8)
+        data += struct.pack("<HHHHIIH", ins, ins, 0, 0, 0, 1, insn)
+        code_count += 1
+if code_count:
+    map_items.append((0x2001, code_count, first_code))
+
+# class data
+class_data_offs = {}
+first_class_data = here()
+for cls, _, _, _, sfields, ifields, dmethods, vmethods, _ in CLASSES:
+    class_data_offs[cls] = here()
+    data += uleb128(len(sfields)) + uleb128(len(ifields)) + uleb128(len(dmethods)) + uleb128(len(vmethods))
+    for group in (sfields, ifields):
+        prev = 0
+        for name, t, access in sorted(group, key=lambda f: field_idx[(cls, f[0], f[1])]):
+            idx = field_idx[(cls, name, t)]
+            data += uleb128(idx - prev) + uleb128(access)
+            prev = idx
+    for group in (dmethods, vmethods):
+        prev = 0
+        for name, proto, access, _ in sorted(group, key=lambda m: method_idx[(cls, m[0], (tuple(m[1][0]), m[1][1]))]):
+            idx = method_idx[(cls, name, (tuple(proto[0]), proto[1]))]
+            data += uleb128(idx - prev) + uleb128(access) + uleb128(code_offs.get((cls, name), 0))
+            prev = idx
+map_items.append((0x2000, len(CLASSES), first_class_data))
+
+# map list
+align()
+map_off = here()
+map_items = [
+    (0x0000, 1, 0),
+    (0x0001, len(strings), string_ids_off),
+    (0x0002, len(types), type_ids_off),
+    (0x0003, len(protos), proto_ids_off),
+    (0x0004, len(fields), field_ids_off),
+    (0x0005, len(methods), method_ids_off),
+    (0x0006, len(CLASSES), class_defs_off),
+] + sorted(map_items, key=lambda m: m[2]) + [(0x1000, 1, map_off)]
+data += struct.pack("<I", len(map_items)) + b"".join(struct.pack("<HHII", t, 0, n, o) for t, n, o in map_items)
+
+# --- ids and header ----------------------------------------------------------
+
+ids = bytearray()
+ids += b"".join(struct.pack("<I", o) for o in string_data_offs)
+ids += b"".join(struct.pack("<I", string_idx[t]) for t in types)
+for params, ret in protos:
+    ids += struct.pack("<III", string_idx[shorty((list(params), ret))], type_idx[ret],
+                       type_list_offs[params] if params else 0)
+for cls, name, t in fields:
+    ids += struct.pack("<HHI", type_idx[cls], type_idx[t], string_idx[name])
+for cls, name, proto in methods:
+    ids += struct.pack("<HHI", type_idx[cls], proto_idx[proto], string_idx[name])
+for cls, access, sup, ifaces, *_ in CLASSES:
+    ids += struct.pack(
+        "<IIIIIIII",
+        type_idx[cls],
+        access,
+        type_idx[sup],
+        type_list_offs[tuple(ifaces)] if ifaces else 0,
+        NO_INDEX,
+        annotation_dir_offs.get(cls, 0),
+        class_data_offs[cls],
+        0,
+    )
+assert len(ids) == data_off - HEADER_SIZE
+
+file_size = data_off + len(data)
+header = bytearray(b"dex\n035\0" + bytes(24))
+header += struct.pack(
+    "<" + "I" * 20,
+    file_size, HEADER_SIZE, 0x12345678, 0, 0, map_off,
+    len(strings), string_ids_off, len(types), type_ids_off, len(protos), proto_ids_off,
+    len(fields), field_ids_off, len(methods), method_ids_off, len(CLASSES), class_defs_off,
+    len(data), data_off,
+)
+assert len(header) == HEADER_SIZE
+dex = bytearray(header + ids + data)
+assert len(dex) == file_size
+dex[12:32] = hashlib.sha1(dex[32:]).digest()
+dex[8:12] = struct.pack("<I", zlib.adler32(bytes(dex[12:])))
+
+with open(os.path.join(os.path.dirname(os.path.abspath(__file__)), "test.dex"), "wb") as f:
+    f.write(dex)
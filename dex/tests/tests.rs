@@ -0,0 +1,195 @@
+use dex::*;
+use smali::*;
+use std::path::PathBuf;
+
+fn files_path() -> PathBuf {
+    std::env::current_dir().unwrap().join("tests/files")
+}
+
+fn test_classes() -> Vec<SmaliClass> {
+    parse_dex_file(files_path().join("test.dex")).unwrap()
+}
+
+#[test]
+fn equals_baksmali_output() {
+    let mut classes = test_classes();
+    assert_eq!(classes.len(), 2);
+    for class in &mut classes {
+        class.annotations.clear();
+    }
+
+    assert_eq!(
+        classes[0],
+        parse_file(files_path().join("Test.smali")).unwrap()
+    );
+    assert_eq!(
+        classes[1],
+        parse_file(files_path().join("Test$Iface.smali")).unwrap()
+    );
+}
+
+#[test]
+fn members() {
+    let classes = test_classes();
+    let class = &classes[0];
+    assert_eq!(class.class_path, "bttv.Test");
+    assert_eq!(class.access, SmaliAccessModifier::Public);
+    assert!(!class.is_abstract);
+    assert!(classes[1].is_abstract);
+
+    assert_eq!(
+        class.values[1],
+        SmaliValue {
+            name: Symbol::intern("names"),
+            data_type: SmaliType::Arr(Box::new(SmaliType::Arr(Box::new(SmaliType::Class(
+                ClassPath::from_java("java.lang.String").unwrap()
+            ))))),
+            access: SmaliAccessModifier::Public,
+            is_static: true,
+            is_final: false,
+            is_synthetic: false,
+            is_enum: false,
+        }
+    );
+    assert_eq!(
+        class.methods[1],
+        SmaliMethod {
+            name: Symbol::intern("foo"),
+            access: SmaliAccessModifier::Private,
+            parameter_types: vec![
+                SmaliType::Byte,
+                SmaliType::Char,
+                SmaliType::Short,
+                SmaliType::Arr(Box::new(SmaliType::Arr(Box::new(SmaliType::Int)))),
+            ],
+            return_type: SmaliType::Long,
+            is_static: true,
+            is_final: false,
+            is_synthetic: false,
+        }
+    );
+}
+
+#[test]
+fn annotations() {
+    let classes = test_classes();
+    let annotations = &classes[0].annotations;
+    assert_eq!(annotations.len(), 3);
+    assert!(classes[1].annotations.is_empty());
+
+    let metadata = annotations
+        .iter()
+        .find(|annotation| annotation.type_path == "kotlin.Metadata")
+        .unwrap();
+    assert_eq!(metadata.visibility, AnnotationVisibility::Runtime);
+    assert_eq!(metadata.element("k"), Some(&AnnotationValue::Int(1)));
+    assert_eq!(
+        metadata.element("mv"),
+        Some(&AnnotationValue::Array(vec![
+            AnnotationValue::Int(1),
+            AnnotationValue::Int(6),
+            AnnotationValue::Int(0),
+        ]))
+    );
+    assert_eq!(
+        metadata.element("d1"),
+        Some(&AnnotationValue::Array(vec![AnnotationValue::String(
+            "\0\u{6}😀".to_string()
+        )]))
+    );
+
+    let signature = annotations
+        .iter()
+        .find(|annotation| annotation.type_path == "dalvik.annotation.Signature")
+        .unwrap();
+    assert_eq!(signature.visibility, AnnotationVisibility::System);
+
+    let ann = annotations
+        .iter()
+        .find(|annotation| annotation.type_path == "bttv.Ann")
+        .unwrap();
+    assert_eq!(ann.visibility, AnnotationVisibility::Build);
+    let expected = [
+        ("b", AnnotationValue::Boolean(true)),
+        ("by", AnnotationValue::Byte(-1)),
+        ("c", AnnotationValue::Char(u16::from(b'x'))),
+        ("d", AnnotationValue::Double(-2.25)),
+        (
+            "e",
+            AnnotationValue::Enum("Lbttv/E;->A:Lbttv/E;".to_string()),
+        ),
+        ("f", AnnotationValue::Float(1.5)),
+        ("l", AnnotationValue::Long(-5)),
+        (
+            "m",
+            AnnotationValue::Method("Lbttv/Test;->run()V".to_string()),
+        ),
+        ("n", AnnotationValue::Null),
+        ("s", AnnotationValue::Short(-2)),
+        (
+            "t",
+            AnnotationValue::Type(SmaliType::Arr(Box::new(SmaliType::Int))),
+        ),
+    ];
+    for (name, value) in expected {
+        assert_eq!(ann.element(name), Some(&value), "element {}", name);
+    }
+
+    match ann.element("sub") {
+        Some(AnnotationValue::Annotation(sub)) => {
+            assert_eq!(sub.type_path, "bttv.Sub");
+            assert_eq!(sub.visibility, AnnotationVisibility::Build);
+            assert_eq!(sub.element("x"), Some(&AnnotationValue::Int(300)));
+        }
+        other => panic!("unexpected sub element {:?}", other),
+    }
+}
+
+#[test]
+fn invalid_files() {
+    assert!(matches!(
+        parse_dex(b"not a dex file at all"),
+        Err(DexError::InvalidMagic())
+    ));
+
+    let mut bytes = std::fs::read(files_path().join("test.dex")).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xff;
+    assert!(matches!(
+        parse_dex(&bytes),
+        Err(DexError::ChecksumMismatch { .. })
+    ));
+}
+
+/// Updates the adler32 checksum in the header after the file was modified
+fn fix_checksum(bytes: &mut [u8]) {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in &bytes[12..] {
+        a = (a + u32::from(*byte)) % 65521;
+        b = (b + a) % 65521;
+    }
+    bytes[8..12].copy_from_slice(&((b << 16) | a).to_le_bytes());
+}
+
+#[test]
+fn oversized_values() {
+    let bytes = std::fs::read(files_path().join("test.dex")).unwrap();
+    // the float 1.5 of bttv.Ann: VALUE_FLOAT with 2 bytes, followed by its upper bytes
+    let float = bytes
+        .windows(3)
+        .position(|window| window == [0x30, 0xc0, 0x3f])
+        .unwrap();
+
+    let mut bytes = bytes;
+    // claims 8 bytes for the 4 byte float
+    bytes[float] = (7 << 5) | 0x10;
+    fix_checksum(&mut bytes);
+    assert!(matches!(
+        parse_dex(&bytes),
+        Err(DexError::InvalidValueSize {
+            len: 8,
+            width: 4,
+            ..
+        })
+    ));
+}
@@ -1,14 +1,14 @@
 use crate::{MethodDiff, ValueDiff};
-use smali::{SmaliMethod, SmaliValue};
+use smali::{ClassPath, SmaliMethod, SmaliType, SmaliValue};
 
 /// returns Some with all items that are in orig and not in cmp or None if empty
-pub fn diff_string_vec<'a, 'b>(orig: &'a [String], cmp: &'b [String]) -> Option<Vec<&'a String>> {
+pub fn diff_class_path_vec(orig: &[ClassPath], cmp: &[ClassPath]) -> Option<Vec<ClassPath>> {
     let mut vec = vec![];
 
     // perf: runs in O(n*m) maybe fix later
     for item in orig {
         if !cmp.contains(item) {
-            vec.push(item);
+            vec.push(item.clone());
         }
     }
 
@@ -19,11 +19,26 @@ pub fn diff_string_vec<'a, 'b>(orig: &'a [String], cmp: &'b [String]) -> Option<
     }
 }
 
+/// returns all values of orig without a value of the same name in cmp
+pub fn missing_values(orig: &[SmaliValue], cmp: &[SmaliValue]) -> Vec<SmaliValue> {
+    orig.iter()
+        .filter(|item| !cmp.iter().any(|other| other.name == item.name))
+        .cloned()
+        .collect()
+}
+
+/// returns all methods of orig without a counterpart in cmp, overloads are paired like
+/// in `diff_method_vec`, so an overload of orig is missing even if cmp has other overloads
+pub fn missing_methods(orig: &[SmaliMethod], cmp: &[SmaliMethod]) -> Vec<SmaliMethod> {
+    orig.iter()
+        .zip(pair_methods(orig, cmp))
+        .filter(|(_, pair)| pair.is_none())
+        .map(|(item, _)| item.clone())
+        .collect()
+}
+
 /// returns Some with all items that are in orig and not in cmp or None if empty
-pub fn diff_value_vec<'a, 'b>(
-    orig: &'a [SmaliValue],
-    cmp: &'b [SmaliValue],
-) -> Option<Vec<ValueDiff<'a, 'b>>> {
+pub fn diff_value_vec(orig: &[SmaliValue], cmp: &[SmaliValue]) -> Option<Vec<ValueDiff>> {
     let mut diffs = vec![];
 
     // perf: runs in O(n*m) maybe fix later
@@ -37,7 +52,7 @@ pub fn diff_value_vec<'a, 'b>(
             found = true;
 
             let mut any_changes_found = false;
-            let mut diff = ValueDiff::new(&item.name);
+            let mut diff = ValueDiff::new(item.name.clone());
             if item.is_final != other.is_final {
                 any_changes_found = true;
                 diff.is_final = Some((item.is_final, other.is_final));
@@ -48,11 +63,11 @@ pub fn diff_value_vec<'a, 'b>(
             }
             if item.access != other.access {
                 any_changes_found = true;
-                diff.access = Some((&item.access, &other.access));
+                diff.access = Some((item.access, other.access));
             }
             if item.data_type != other.data_type {
                 any_changes_found = true;
-                diff.data_type = Some((&item.data_type, &other.data_type));
+                diff.data_type = Some((item.data_type.clone(), other.data_type.clone()));
             }
             if any_changes_found {
                 diffs.push(diff);
@@ -61,7 +76,7 @@ pub fn diff_value_vec<'a, 'b>(
             }
         }
         if !found {
-            diffs.push(ValueDiff::not_found(&item.name));
+            diffs.push(ValueDiff::not_found(item.name.clone()));
         }
     }
 
@@ -72,101 +87,190 @@ pub fn diff_value_vec<'a, 'b>(
     }
 }
 
-/// returns Some with all items that are in orig and not in cmp or None if empty
-pub fn diff_method_vec<'a, 'b>(
-    orig: &'a [SmaliMethod],
-    cmp: &'b [SmaliMethod],
-) -> Option<Vec<MethodDiff<'a, 'b>>> {
+/// returns Some with the diffs of all methods of orig that are not in cmp or changed, or None if empty
+///
+/// Methods are paired with `pair_methods`, so every overload of orig is compared with its own
+/// counterpart
+pub fn diff_method_vec(orig: &[SmaliMethod], cmp: &[SmaliMethod]) -> Option<Vec<MethodDiff>> {
+    let pairs = pair_methods(orig, cmp);
     let mut diffs = vec![];
+    for (item, pair) in orig.iter().zip(pairs) {
+        let overloads = |methods: &[SmaliMethod]| {
+            methods
+                .iter()
+                .filter(|other| other.name == item.name)
+                .count()
+        };
+        let overloaded = overloads(orig) > 1 || overloads(cmp) > 1;
+        let mut diff = match pair {
+            Some(j) => match diff_method(item, &cmp[j]) {
+                Some(diff) => diff,
+                None => continue,
+            },
+            None => MethodDiff::not_found(item.name.clone()),
+        };
+        if overloaded {
+            diff.descriptor = Some(descriptor(item));
+        }
+        diffs.push(diff);
+    }
 
-    // perf: runs in O(n*m) maybe fix later
-    for item in orig {
-        let mut found = false;
-        for other in cmp {
-            if item.name != other.name {
-                continue;
-            }
+    if diffs.is_empty() {
+        None
+    } else {
+        Some(diffs)
+    }
+}
 
-            found = true;
+/// returns the index of the method of cmp paired with each method of orig
+///
+/// Methods are paired by their full signature first, the remaining methods of the same name
+/// are paired by the similarity of their parameters, see `similarity`. Each method of cmp is
+/// paired at most once.
+fn pair_methods(orig: &[SmaliMethod], cmp: &[SmaliMethod]) -> Vec<Option<usize>> {
+    let mut pairs: Vec<Option<usize>> = vec![None; orig.len()];
+    let mut paired = vec![false; cmp.len()];
 
-            let mut any_changes_found = false;
-            let mut diff = MethodDiff::new(&item.name);
-            if item.is_final != other.is_final {
-                any_changes_found = true;
-                diff.is_final = Some((item.is_final, other.is_final));
-            }
-            if item.is_static != other.is_static {
-                any_changes_found = true;
-                diff.is_static = Some((item.is_static, other.is_static));
-            }
-            if item.access != other.access {
-                any_changes_found = true;
-                diff.access = Some((&item.access, &other.access));
-            }
-            if item.return_type != other.return_type {
-                any_changes_found = true;
-                diff.return_type = Some((&item.return_type, &other.return_type));
-            }
-            if item.parameter_types != other.parameter_types {
-                any_changes_found = true;
-                diff.parameter_types = Some((&item.parameter_types, &other.parameter_types));
-            }
-            if any_changes_found {
-                diffs.push(diff);
-            } else {
-                break;
+    for (i, item) in orig.iter().enumerate() {
+        let exact = (0..cmp.len()).find(|j| {
+            let other = &cmp[*j];
+            !paired[*j]
+                && item.name == other.name
+                && item.parameter_types == other.parameter_types
+                && item.return_type == other.return_type
+        });
+        if let Some(j) = exact {
+            pairs[i] = Some(j);
+            paired[j] = true;
+        }
+    }
+
+    // perf: runs in O(n*m) maybe fix later
+    let mut candidates = vec![];
+    for (i, item) in orig.iter().enumerate() {
+        if pairs[i].is_some() {
+            continue;
+        }
+        for (j, other) in cmp.iter().enumerate() {
+            if !paired[j] && item.name == other.name {
+                candidates.push((similarity(item, other), i, j));
             }
         }
-        if !found {
-            diffs.push(MethodDiff::not_found(&item.name));
+    }
+    // the most similar pairs first, ties in the order of orig and cmp
+    candidates.sort_by(|a, b| b.0.cmp(&a.0).then((a.1, a.2).cmp(&(b.1, b.2))));
+    for (_, i, j) in candidates {
+        if pairs[i].is_none() && !paired[j] {
+            pairs[i] = Some(j);
+            paired[j] = true;
         }
     }
+    pairs
+}
 
-    if diffs.is_empty() {
-        None
+fn diff_method(item: &SmaliMethod, other: &SmaliMethod) -> Option<MethodDiff> {
+    let mut any_changes_found = false;
+    let mut diff = MethodDiff::new(item.name.clone());
+    if item.is_final != other.is_final {
+        any_changes_found = true;
+        diff.is_final = Some((item.is_final, other.is_final));
+    }
+    if item.is_static != other.is_static {
+        any_changes_found = true;
+        diff.is_static = Some((item.is_static, other.is_static));
+    }
+    if item.access != other.access {
+        any_changes_found = true;
+        diff.access = Some((item.access, other.access));
+    }
+    if item.return_type != other.return_type {
+        any_changes_found = true;
+        diff.return_type = Some((item.return_type.clone(), other.return_type.clone()));
+    }
+    if item.parameter_types != other.parameter_types {
+        any_changes_found = true;
+        diff.parameter_types = Some((item.parameter_types.clone(), other.parameter_types.clone()));
+    }
+    if any_changes_found {
+        Some(diff)
     } else {
-        Some(diffs)
+        None
     }
 }
 
+/// How alike the signatures of two methods of the same name are: parameters of the same type
+/// at the same position count the most, then the parameter count and the return type
+fn similarity(item: &SmaliMethod, other: &SmaliMethod) -> usize {
+    let same_parameters = item
+        .parameter_types
+        .iter()
+        .zip(&other.parameter_types)
+        .filter(|(a, b)| a == b)
+        .count();
+    let same_count = item.parameter_types.len() == other.parameter_types.len();
+    let same_return = item.return_type == other.return_type;
+    same_parameters * 4 + usize::from(same_count) * 2 + usize::from(same_return)
+}
+
+/// The descriptor of a method in smali notation, e.g. `(I[J)V`
+pub(crate) fn descriptor(method: &SmaliMethod) -> String {
+    let parameters: String = method
+        .parameter_types
+        .iter()
+        .map(SmaliType::to_smali)
+        .collect();
+    format!("({}){}", parameters, method.return_type.to_smali())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use smali::{SmaliAccessModifier, SmaliType};
+    use smali::{SmaliAccessModifier, Symbol};
+
+    fn paths(java_paths: &[&str]) -> Vec<ClassPath> {
+        java_paths
+            .iter()
+            .map(|path| ClassPath::from_java(path).unwrap())
+            .collect()
+    }
 
     #[test]
-    fn test_diff_string_vec_none() {
-        let a = ["a".to_string(), "b".to_string(), "c".to_string()];
-        let b = ["b".to_string(), "c".to_string(), "a".to_string()];
-        assert!(diff_string_vec(&a, &b).is_none());
+    fn test_diff_class_path_vec_none() {
+        let a = paths(&["a", "b", "c"]);
+        let b = paths(&["b", "c", "a"]);
+        assert!(diff_class_path_vec(&a, &b).is_none());
     }
 
     #[test]
-    fn test_diff_string_vec_some() {
-        let a = ["a".to_string(), "b".to_string(), "c".to_string()];
-        let b = ["b".to_string(), "d".to_string()];
-        let diff = diff_string_vec(&a, &b);
+    fn test_diff_class_path_vec_some() {
+        let a = paths(&["a", "b", "c"]);
+        let b = paths(&["b", "d"]);
+        let diff = diff_class_path_vec(&a, &b);
         assert!(diff.is_some());
         let diff = diff.unwrap();
-        assert!(diff.contains(&&"a".to_string()));
-        assert!(diff.contains(&&"c".to_string()));
+        assert!(diff.contains(&a[0]));
+        assert!(diff.contains(&a[2]));
         assert_eq!(diff.len(), 2);
     }
 
     #[test]
     fn test_diff_value_vec_none() {
         let v1 = SmaliValue {
-            name: "height".to_string(),
+            name: Symbol::intern("height"),
             access: SmaliAccessModifier::Package,
             data_type: SmaliType::Double,
             is_final: false,
+            is_synthetic: false,
+            is_enum: false,
             is_static: true,
         };
         let v2 = SmaliValue {
-            name: "age".to_string(),
+            name: Symbol::intern("age"),
             access: SmaliAccessModifier::Public,
             data_type: SmaliType::Int,
             is_final: false,
+            is_synthetic: false,
+            is_enum: false,
             is_static: false,
         };
         let a = [v2.clone(), v1.clone()];
@@ -178,22 +282,26 @@ mod tests {
     #[test]
     fn test_diff_value_vec_some() {
         let mut v1 = SmaliValue {
-            name: "height".to_string(),
+            name: Symbol::intern("height"),
             access: SmaliAccessModifier::Package,
             data_type: SmaliType::Double,
             is_final: false,
+            is_synthetic: false,
+            is_enum: false,
             is_static: true,
         };
         let v2 = SmaliValue {
-            name: "age".to_string(),
+            name: Symbol::intern("age"),
             access: SmaliAccessModifier::Public,
             data_type: SmaliType::Int,
             is_final: false,
+            is_synthetic: false,
+            is_enum: false,
             is_static: false,
         };
         let a = [v2, v1.clone()];
         v1.is_static = false;
-        v1.data_type = SmaliType::Class("java.lang.Double".to_string());
+        v1.data_type = SmaliType::Class(ClassPath::from_java("java.lang.Double").unwrap());
 
         let b = [v1];
         let diff = diff_value_vec(&a, &b);
@@ -201,17 +309,115 @@ mod tests {
         let diff = diff.unwrap();
 
         assert_eq!(diff.len(), 2);
-        assert!(diff.contains(&ValueDiff::not_found(&"age".to_string())));
+        assert!(diff.contains(&ValueDiff::not_found(Symbol::intern("age"))));
         assert!(diff.contains(&ValueDiff {
             not_found: false,
-            name: &"height".to_string(),
+            name: Symbol::intern("height"),
             access: None,
             is_final: None,
             is_static: Some((true, false)),
             data_type: Some((
-                &SmaliType::Double,
-                &SmaliType::Class("java.lang.Double".to_string())
+                SmaliType::Double,
+                SmaliType::Class(ClassPath::from_java("java.lang.Double").unwrap())
             )),
         }));
     }
+
+    fn method(name: &str, parameter_types: Vec<SmaliType>, return_type: SmaliType) -> SmaliMethod {
+        SmaliMethod {
+            name: Symbol::intern(name),
+            access: SmaliAccessModifier::Public,
+            parameter_types,
+            return_type,
+            is_static: false,
+            is_final: false,
+            is_synthetic: false,
+        }
+    }
+
+    fn class(java: &str) -> SmaliType {
+        SmaliType::Class(ClassPath::from_java(java).unwrap())
+    }
+
+    #[test]
+    fn test_diff_method_vec_overloads() {
+        let view = class("android.view.View");
+        let a = [
+            method("<init>", vec![], SmaliType::Void),
+            method("<init>", vec![SmaliType::Int], SmaliType::Void),
+            method("onClick", vec![view.clone()], SmaliType::Void),
+            method(
+                "onClick",
+                vec![view.clone(), SmaliType::Int],
+                SmaliType::Void,
+            ),
+        ];
+        // the same overloads in another order
+        let mut b = a.clone();
+        b.reverse();
+        assert!(diff_method_vec(&a, &b).is_none());
+
+        // only the second overload changed, the first one is no bogus mismatch
+        b[0].is_final = true;
+        b[0].parameter_types[1] = SmaliType::Long;
+        let mut expected = MethodDiff::new(Symbol::intern("onClick"));
+        expected.descriptor = Some("(Landroid/view/View;I)V".to_string());
+        expected.is_final = Some((false, true));
+        expected.parameter_types = Some((
+            vec![view.clone(), SmaliType::Int],
+            vec![view.clone(), SmaliType::Long],
+        ));
+        assert_eq!(diff_method_vec(&a, &b), Some(vec![expected]));
+
+        // an overload without counterpart is missing even if another overload matches
+        let b = [a[0].clone(), a[2].clone(), a[3].clone()];
+        let mut expected = MethodDiff::not_found(Symbol::intern("<init>"));
+        expected.descriptor = Some("(I)V".to_string());
+        assert_eq!(diff_method_vec(&a, &b), Some(vec![expected]));
+    }
+
+    #[test]
+    fn test_diff_method_vec_similarity() {
+        let a = [
+            method(
+                "load",
+                vec![SmaliType::Int, SmaliType::Boolean],
+                SmaliType::Void,
+            ),
+            method("load", vec![class("java.lang.String")], SmaliType::Void),
+        ];
+        let b = [
+            method(
+                "load",
+                vec![class("java.lang.String"), SmaliType::Long],
+                SmaliType::Void,
+            ),
+            method(
+                "load",
+                vec![SmaliType::Int, SmaliType::Boolean],
+                SmaliType::Boolean,
+            ),
+        ];
+        let diffs = diff_method_vec(&a, &b).unwrap();
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[0].descriptor.as_deref(), Some("(IZ)V"));
+        assert_eq!(
+            diffs[0].return_type,
+            Some((SmaliType::Void, SmaliType::Boolean))
+        );
+        assert_eq!(diffs[0].parameter_types, None);
+        assert_eq!(
+            diffs[1].descriptor.as_deref(),
+            Some("(Ljava/lang/String;)V")
+        );
+        assert_eq!(
+            diffs[1].parameter_types.as_ref().unwrap().1,
+            [class("java.lang.String"), SmaliType::Long]
+        );
+
+        // methods that are not overloaded have no descriptor
+        let b = [method("load", vec![], SmaliType::Void)];
+        let diffs = diff_method_vec(&a[..1], &b).unwrap();
+        assert_eq!(diffs[0].descriptor, None);
+    }
 }
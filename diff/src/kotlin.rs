@@ -0,0 +1,52 @@
+//! The kotlin declarations behind the members of a diff
+//!
+//! Diffs name the JVM members, which for kotlin classes are often accessors or synthetic
+//! methods. The `kotlin.Metadata` of either side of a diff tells what they were compiled from.
+
+use crate::diff_vec::descriptor;
+use crate::{MethodDiff, ValueDiff};
+use smali::kotlin::{KotlinMember, KotlinMetadata};
+use smali::{SmaliClass, SmaliMethod, SmaliValue};
+
+/// The method of class a `MethodDiff` is about, class is either side of the diff.
+/// Overloads are told apart by the descriptor of the diff if it has one.
+pub fn diffed_method<'a>(
+    method_diff: &MethodDiff,
+    class: &'a SmaliClass,
+) -> Option<&'a SmaliMethod> {
+    class.methods.iter().find(|method| {
+        method.name == method_diff.name
+            && method_diff
+                .descriptor
+                .as_ref()
+                .is_none_or(|expected| *expected == descriptor(method))
+    })
+}
+
+/// The field of class a `ValueDiff` is about, class is either side of the diff
+pub fn diffed_value<'a>(value_diff: &ValueDiff, class: &'a SmaliClass) -> Option<&'a SmaliValue> {
+    class
+        .values
+        .iter()
+        .find(|value| value.name == value_diff.name)
+}
+
+/// The kotlin declaration the method of a `MethodDiff` was compiled from, metadata is the
+/// `kotlin.Metadata` of class, see `KotlinMetadata::explain_method`
+pub fn explain_method<'a>(
+    method_diff: &MethodDiff,
+    class: &SmaliClass,
+    metadata: &'a KotlinMetadata,
+) -> Option<KotlinMember<'a>> {
+    metadata.explain_method(diffed_method(method_diff, class)?)
+}
+
+/// The kotlin property or companion object of the field of a `ValueDiff`, metadata is the
+/// `kotlin.Metadata` of class, see `KotlinMetadata::explain_value`
+pub fn explain_value<'a>(
+    value_diff: &ValueDiff,
+    class: &SmaliClass,
+    metadata: &'a KotlinMetadata,
+) -> Option<KotlinMember<'a>> {
+    metadata.explain_value(diffed_value(value_diff, class)?)
+}
@@ -1,20 +1,31 @@
 mod diff_vec;
+pub mod kotlin;
+mod synthetic;
+
+pub use crate::synthetic::*;
 
 use crate::diff_vec::*;
 use smali::*;
-#[derive(Debug, PartialEq)]
-pub struct ClassDiff<'orig, 'cmp> {
-    pub class_path: Option<(&'orig String, &'cmp String)>,
-    pub access: Option<(&'orig SmaliAccessModifier, &'cmp SmaliAccessModifier)>,
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClassDiff {
+    pub class_path: Option<(ClassPath, ClassPath)>,
+    pub access: Option<(SmaliAccessModifier, SmaliAccessModifier)>,
     pub is_abstract: Option<(bool, bool)>,
-    pub super_path: Option<(&'orig Option<String>, &'cmp Option<String>)>,
-    pub interfaces: Option<Vec<&'orig String>>,
-    pub values: Option<Vec<ValueDiff<'orig, 'cmp>>>,
-    pub methods: Option<Vec<MethodDiff<'orig, 'cmp>>>,
+    pub super_path: Option<(Option<ClassPath>, Option<ClassPath>)>,
+    pub interfaces: Option<Vec<ClassPath>>,
+    pub values: Option<Vec<ValueDiff>>,
+    pub methods: Option<Vec<MethodDiff>>,
+    /// diffs of synthetic members grouped by kind, see `SyntheticPolicy::Group`
+    pub synthetic: Option<Vec<SyntheticDiff>>,
+    /// interfaces and members of the mock that the app class lacks, see `diff_with_authority`
+    pub only_in_mock: Option<Members>,
+    /// interfaces and members of the app class that the mock lacks, see `diff_with_authority`
+    pub only_in_app: Option<Members>,
 }
 
-impl<'a, 'b> ClassDiff<'a, 'b> {
-    fn new() -> Self {
+impl ClassDiff {
+    pub(crate) fn new() -> Self {
         Self {
             class_path: None,
             access: None,
@@ -23,23 +34,68 @@ impl<'a, 'b> ClassDiff<'a, 'b> {
             interfaces: None,
             values: None,
             methods: None,
+            synthetic: None,
+            only_in_mock: None,
+            only_in_app: None,
+        }
+    }
+}
+
+/// The interfaces and members that only one side of a `ClassDiff` has
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Members {
+    pub interfaces: Vec<ClassPath>,
+    pub values: Vec<SmaliValue>,
+    pub methods: Vec<SmaliMethod>,
+}
+
+impl Members {
+    /// The interfaces and members of class that other lacks, None if there are none
+    fn missing_from(class: &SmaliClass, other: &SmaliClass) -> Option<Self> {
+        let members = Self {
+            interfaces: diff_class_path_vec(&class.interfaces, &other.interfaces)
+                .unwrap_or_default(),
+            values: missing_values(&class.values, &other.values),
+            methods: missing_methods(&class.methods, &other.methods),
+        };
+        if members.is_empty() {
+            None
+        } else {
+            Some(members)
         }
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.interfaces.is_empty() && self.values.is_empty() && self.methods.is_empty()
+    }
 }
 
-#[derive(Debug, PartialEq)]
-pub struct ValueDiff<'orig, 'cmp> {
-    pub name: &'orig String,
+/// Which side of a diff defines the API, decides which missing members `diff_with_authority` reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Authority {
+    /// the mock defines the API, report what the app lacks in `ClassDiff::only_in_mock`
+    Mock,
+    /// the app defines the API, report what the mock lacks in `ClassDiff::only_in_app`,
+    /// e.g. to find the API the app added to the mocked classes
+    App,
+    /// neither side does, report what is missing on either side
+    Both,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ValueDiff {
+    pub name: Symbol,
     pub not_found: bool,
-    pub data_type: Option<(&'orig SmaliType, &'cmp SmaliType)>,
-    pub access: Option<(&'orig SmaliAccessModifier, &'cmp SmaliAccessModifier)>,
+    pub data_type: Option<(SmaliType, SmaliType)>,
+    pub access: Option<(SmaliAccessModifier, SmaliAccessModifier)>,
     pub is_static: Option<(bool, bool)>,
     pub is_final: Option<(bool, bool)>,
 }
 
-#[allow(clippy::ptr_arg)]
-impl<'orig, 'cmp> ValueDiff<'orig, 'cmp> {
-    fn new(name: &'orig String) -> Self {
+impl ValueDiff {
+    pub fn new(name: Symbol) -> Self {
         Self {
             name,
             not_found: false,
@@ -50,27 +106,29 @@ impl<'orig, 'cmp> ValueDiff<'orig, 'cmp> {
         }
     }
 
-    fn not_found(name: &'orig String) -> Self {
+    pub fn not_found(name: Symbol) -> Self {
         let mut inst = Self::new(name);
         inst.not_found = true;
         inst
     }
 }
 
-#[derive(Debug, PartialEq)]
-pub struct MethodDiff<'orig, 'cmp> {
-    pub name: &'orig String,
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MethodDiff {
+    pub name: Symbol,
     pub not_found: bool,
-    pub return_type: Option<(&'orig SmaliType, &'cmp SmaliType)>,
-    pub access: Option<(&'orig SmaliAccessModifier, &'cmp SmaliAccessModifier)>,
+    pub return_type: Option<(SmaliType, SmaliType)>,
+    pub access: Option<(SmaliAccessModifier, SmaliAccessModifier)>,
     pub is_static: Option<(bool, bool)>,
     pub is_final: Option<(bool, bool)>,
-    pub parameter_types: Option<(&'orig Vec<SmaliType>, &'cmp Vec<SmaliType>)>,
+    pub parameter_types: Option<(Vec<SmaliType>, Vec<SmaliType>)>,
+    /// the descriptor of the overload of orig (`(I)V`), only set if the name is overloaded
+    pub descriptor: Option<String>,
 }
 
-#[allow(clippy::ptr_arg)]
-impl<'orig, 'cmp> MethodDiff<'orig, 'cmp> {
-    pub fn new(name: &'orig String) -> Self {
+impl MethodDiff {
+    pub fn new(name: Symbol) -> Self {
         Self {
             name,
             not_found: false,
@@ -79,29 +137,52 @@ impl<'orig, 'cmp> MethodDiff<'orig, 'cmp> {
             is_static: None,
             is_final: None,
             parameter_types: None,
+            descriptor: None,
         }
     }
 
-    pub fn not_found(name: &'orig String) -> Self {
+    pub fn not_found(name: Symbol) -> Self {
         let mut inst = Self::new(name);
         inst.not_found = true;
         inst
     }
 }
 
-pub fn diff<'a, 'b>(orig: &'a SmaliClass, cmp: &'b SmaliClass) -> Option<ClassDiff<'a, 'b>> {
+/// The changes of the constants of an enum, ordinals of constants that are in both enums
+/// change when constants are added, removed or moved
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnumDiff {
+    pub class_path: ClassPath,
+    /// constants in cmp that are not in orig
+    pub added: Vec<Symbol>,
+    /// constants in orig that are not in cmp
+    pub removed: Vec<Symbol>,
+    pub reordered: Vec<OrdinalDiff>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OrdinalDiff {
+    pub name: Symbol,
+    pub ordinal: (usize, usize),
+}
+
+/// Compares orig with cmp, interfaces and members of orig that cmp lacks are reported in
+/// `ClassDiff::interfaces` and as `not_found`. See `diff_with_authority` for both directions.
+pub fn diff(orig: &SmaliClass, cmp: &SmaliClass) -> Option<ClassDiff> {
     let mut any_diff_found = false;
 
     let mut diff = ClassDiff::new();
 
     if orig.class_path != cmp.class_path {
         any_diff_found = true;
-        diff.class_path = Some((&orig.class_path, &cmp.class_path));
+        diff.class_path = Some((orig.class_path.clone(), cmp.class_path.clone()));
     }
 
     if orig.access != cmp.access {
         any_diff_found = true;
-        diff.access = Some((&orig.access, &cmp.access));
+        diff.access = Some((orig.access, cmp.access));
     }
 
     if orig.is_abstract != cmp.is_abstract {
@@ -111,10 +192,10 @@ pub fn diff<'a, 'b>(orig: &'a SmaliClass, cmp: &'b SmaliClass) -> Option<ClassDi
 
     if orig.super_path != cmp.super_path {
         any_diff_found = true;
-        diff.super_path = Some((&orig.super_path, &cmp.super_path));
+        diff.super_path = Some((orig.super_path.clone(), cmp.super_path.clone()));
     }
 
-    if let Some(vec_diff) = diff_string_vec(&orig.interfaces, &cmp.interfaces) {
+    if let Some(vec_diff) = diff_class_path_vec(&orig.interfaces, &cmp.interfaces) {
         any_diff_found = true;
         diff.interfaces = Some(vec_diff);
     }
@@ -135,3 +216,126 @@ pub fn diff<'a, 'b>(orig: &'a SmaliClass, cmp: &'b SmaliClass) -> Option<ClassDi
 
     Some(diff)
 }
+
+/// Compares a mock with the class of the app and reports missing interfaces and members in
+/// `ClassDiff::only_in_mock` and `ClassDiff::only_in_app` as the authority says. Unlike `diff`,
+/// `interfaces` is always None and `values` and `methods` only contain members of both sides.
+pub fn diff_with_authority(
+    mock: &SmaliClass,
+    app: &SmaliClass,
+    authority: Authority,
+) -> Option<ClassDiff> {
+    let mut diff = diff(mock, app).unwrap_or_else(ClassDiff::new);
+    diff.interfaces = None;
+    if let Some(values) = &mut diff.values {
+        values.retain(|value| !value.not_found);
+    }
+    diff.values = diff.values.filter(|values| !values.is_empty());
+    if let Some(methods) = &mut diff.methods {
+        methods.retain(|method| !method.not_found);
+    }
+    diff.methods = diff.methods.filter(|methods| !methods.is_empty());
+
+    if authority != Authority::App {
+        diff.only_in_mock = Members::missing_from(mock, app);
+    }
+    if authority != Authority::Mock {
+        diff.only_in_app = Members::missing_from(app, mock);
+    }
+
+    if diff == ClassDiff::new() {
+        return None;
+    }
+    Some(diff)
+}
+
+/// Like `diff`, but cmp is obfuscated (e.g. disassembled from a release build) and translated
+/// to the original names of the mapping before comparing it with orig
+pub fn diff_with_mapping(
+    orig: &SmaliClass,
+    cmp: &SmaliClass,
+    mapping: &Mapping,
+) -> Option<ClassDiff> {
+    let options = DiffOptions {
+        mapping: Some(mapping),
+        ..DiffOptions::default()
+    };
+    diff_with(orig, cmp, &options)
+}
+
+/// How `diff_with` compares a mock with the class of the app
+#[derive(Debug, Clone, Copy)]
+pub struct DiffOptions<'a> {
+    /// None reports like `diff`, otherwise members only one side has are reported like
+    /// `diff_with_authority` does
+    pub authority: Option<Authority>,
+    pub synthetic: SyntheticPolicy,
+    /// translates the obfuscated class of the app like `diff_with_mapping`
+    pub mapping: Option<&'a Mapping>,
+}
+
+impl Default for DiffOptions<'_> {
+    fn default() -> Self {
+        Self {
+            authority: None,
+            synthetic: SyntheticPolicy::Include,
+            mapping: None,
+        }
+    }
+}
+
+/// Compares a mock with the class of the app: the app class is translated with the mapping
+/// first, then synthetic members of both sides are treated as the policy says and members
+/// only one side has are reported as the authority says
+pub fn diff_with(mock: &SmaliClass, app: &SmaliClass, options: &DiffOptions) -> Option<ClassDiff> {
+    let mapped;
+    let app = match options.mapping {
+        Some(mapping) => {
+            mapped = mapping.map_smali_class(app, Direction::Deobfuscate);
+            &mapped
+        }
+        None => app,
+    };
+    let compare = |mock: &SmaliClass, app: &SmaliClass| match options.authority {
+        Some(authority) => diff_with_authority(mock, app, authority),
+        None => diff(mock, app),
+    };
+    if options.synthetic == SyntheticPolicy::Include {
+        return compare(mock, app);
+    }
+    synthetic::diff_synthetic(mock, app, options.synthetic, compare)
+}
+
+/// Compares the constants of two enums, see `SmaliEnum::from_smali` for reading their order
+pub fn diff_enum(orig: &SmaliEnum, cmp: &SmaliEnum) -> Option<EnumDiff> {
+    let added: Vec<_> = cmp
+        .constants
+        .iter()
+        .filter(|constant| !orig.constants.contains(constant))
+        .cloned()
+        .collect();
+
+    let mut removed = vec![];
+    let mut reordered = vec![];
+    for (orig_ordinal, constant) in orig.constants.iter().enumerate() {
+        match cmp.ordinal(constant) {
+            None => removed.push(constant.clone()),
+            Some(cmp_ordinal) if cmp_ordinal != orig_ordinal => reordered.push(OrdinalDiff {
+                name: constant.clone(),
+                ordinal: (orig_ordinal, cmp_ordinal),
+            }),
+            Some(_) => {}
+        }
+    }
+
+    if added.is_empty() && removed.is_empty() && reordered.is_empty() {
+        return None;
+    }
+
+    Some(EnumDiff {
+        class_path: orig.class_path.clone(),
+        added,
+        removed,
+        reordered,
+    })
+}
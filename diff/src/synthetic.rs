@@ -0,0 +1,115 @@
+use crate::{diff_with, ClassDiff, DiffOptions, Members, MethodDiff, ValueDiff};
+use smali::{SmaliClass, SyntheticKind};
+
+/// How members generated by the compilers are treated when diffing, see `SyntheticKind`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntheticPolicy {
+    /// diff synthetic members like any other member
+    Include,
+    /// leave synthetic members out of the diff
+    Ignore,
+    /// report diffs of synthetic members separately in `ClassDiff::synthetic`
+    Group,
+}
+
+/// The diffs of the synthetic members of one kind
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SyntheticDiff {
+    pub kind: SyntheticKind,
+    pub values: Vec<ValueDiff>,
+    pub methods: Vec<MethodDiff>,
+    /// members of this kind only the mock has, see `DiffOptions::authority`
+    pub only_in_mock: Option<Members>,
+    /// members of this kind only the app has, see `DiffOptions::authority`
+    pub only_in_app: Option<Members>,
+}
+
+impl SyntheticPolicy {
+    /// Whether a class should be diffed at all, only `Include` diffs synthetic classes
+    pub fn includes_class(&self, class: &SmaliClass) -> bool {
+        *self == Self::Include || class.synthetic_kind().is_none()
+    }
+}
+
+/// Like `diff`, but synthetic members of both sides are treated as the policy says,
+/// see `diff_with` to combine the policy with an authority and a mapping
+pub fn diff_with_policy(
+    orig: &SmaliClass,
+    cmp: &SmaliClass,
+    policy: SyntheticPolicy,
+) -> Option<ClassDiff> {
+    let options = DiffOptions {
+        synthetic: policy,
+        ..DiffOptions::default()
+    };
+    diff_with(orig, cmp, &options)
+}
+
+/// Diffs the declared members of both sides with compare and, for `SyntheticPolicy::Group`,
+/// the synthetic members of each kind separately
+pub(crate) fn diff_synthetic(
+    mock: &SmaliClass,
+    app: &SmaliClass,
+    policy: SyntheticPolicy,
+    compare: impl Fn(&SmaliClass, &SmaliClass) -> Option<ClassDiff>,
+) -> Option<ClassDiff> {
+    let class_diff = compare(
+        &with_members(mock, |kind| kind.is_none()),
+        &with_members(app, |kind| kind.is_none()),
+    );
+    if policy != SyntheticPolicy::Group {
+        return class_diff;
+    }
+
+    let mut kinds: Vec<_> = [mock, app]
+        .iter()
+        .flat_map(|class| {
+            let values = class.values.iter().map(|value| value.synthetic_kind());
+            values.chain(class.methods.iter().map(|method| method.synthetic_kind()))
+        })
+        .flatten()
+        .collect();
+    kinds.sort_unstable();
+    kinds.dedup();
+
+    let mut groups = vec![];
+    for kind in kinds {
+        let group = compare(
+            &with_members(mock, |member| member == Some(kind)),
+            &with_members(app, |member| member == Some(kind)),
+        );
+        let Some(group) = group else {
+            continue;
+        };
+        let group = SyntheticDiff {
+            kind,
+            values: group.values.unwrap_or_default(),
+            methods: group.methods.unwrap_or_default(),
+            only_in_mock: group.only_in_mock,
+            only_in_app: group.only_in_app,
+        };
+        if !group.values.is_empty()
+            || !group.methods.is_empty()
+            || group.only_in_mock.is_some()
+            || group.only_in_app.is_some()
+        {
+            groups.push(group);
+        }
+    }
+
+    if groups.is_empty() {
+        return class_diff;
+    }
+    let mut class_diff = class_diff.unwrap_or_else(ClassDiff::new);
+    class_diff.synthetic = Some(groups);
+    Some(class_diff)
+}
+
+/// A copy of class with only the fields and methods whose synthetic kind is accepted by keep
+fn with_members(class: &SmaliClass, keep: impl Fn(Option<SyntheticKind>) -> bool) -> SmaliClass {
+    let mut class = class.clone();
+    class.values.retain(|value| keep(value.synthetic_kind()));
+    class.methods.retain(|method| keep(method.synthetic_kind()));
+    class
+}
@@ -1,5 +1,8 @@
-use diff::{ClassDiff, MethodDiff};
-use smali::SmaliType;
+use diff::{
+    Authority, ClassDiff, DiffOptions, EnumDiff, Members, MethodDiff, OrdinalDiff, SyntheticDiff,
+    SyntheticPolicy, ValueDiff,
+};
+use smali::{ClassPath, EnumOrder, Mapping, SmaliEnum, SmaliType, Symbol, SyntheticKind};
 use std::path::PathBuf;
 
 fn files_paths(dir: &'static str) -> (PathBuf, PathBuf) {
@@ -20,17 +23,17 @@ fn one() {
         SmaliType::Arr(Box::new(SmaliType::Int)),
     ];
     let init_method_diff = MethodDiff {
-        name: &"<init>".to_string(),
+        name: Symbol::intern("<init>"),
         not_found: false,
         return_type: None,
         access: None,
         is_static: None,
         is_final: None,
-        parameter_types: Some((&init_params_before, &init_params_after)),
+        parameter_types: Some((init_params_before, init_params_after)),
+        descriptor: None,
     };
 
-    let name = "onClick".to_string();
-    let on_click_method_diff = MethodDiff::not_found(&name);
+    let on_click_method_diff = MethodDiff::not_found(Symbol::intern("onClick"));
 
     let expected = ClassDiff {
         class_path: None,
@@ -40,7 +43,327 @@ fn one() {
         interfaces: None,
         values: None,
         methods: Some(vec![init_method_diff, on_click_method_diff]),
+        synthetic: None,
+        only_in_mock: None,
+        only_in_app: None,
     };
     dbg!(&expected, &class_diff);
     assert_eq!(expected, class_diff);
 }
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trip() {
+    use smali::serialization::Versioned;
+
+    let (orig_path, cmp_path) = files_paths("1/");
+    let orig = smali::parse_file(orig_path).unwrap();
+    let cmp = smali::parse_file(cmp_path).unwrap();
+    let class_diff = diff::diff(&orig, &cmp).unwrap();
+
+    let json = serde_json::to_string(&Versioned::new(&class_diff)).unwrap();
+    let parsed: Versioned<ClassDiff> = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed.data, class_diff);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn representation() {
+    use smali::serialization::Versioned;
+
+    let mut method_diff = MethodDiff::not_found(Symbol::intern("onClick"));
+    method_diff.descriptor = Some("(I)V".to_string());
+    let class_diff = ClassDiff {
+        class_path: None,
+        access: None,
+        is_abstract: Some((true, false)),
+        super_path: None,
+        interfaces: None,
+        values: None,
+        methods: Some(vec![method_diff]),
+        synthetic: None,
+        only_in_mock: None,
+        only_in_app: Some(Members {
+            interfaces: vec![ClassPath::from_java("java.lang.Runnable").unwrap()],
+            values: vec![],
+            methods: vec![],
+        }),
+    };
+    let json = serde_json::to_string(&Versioned::new(&class_diff)).unwrap();
+    assert_eq!(
+        json,
+        concat!(
+            r#"{"format_version":8,"data":{"class_path":null,"access":null,"#,
+            r#""is_abstract":[true,false],"super_path":null,"interfaces":null,"values":null,"#,
+            r#""methods":[{"name":"onClick","not_found":true,"return_type":null,"access":null,"#,
+            r#""is_static":null,"is_final":null,"parameter_types":null,"descriptor":"(I)V"}],"#,
+            r#""synthetic":null,"only_in_mock":null,"#,
+            r#""only_in_app":{"interfaces":["java.lang.Runnable"],"values":[],"methods":[]}}}"#
+        )
+    );
+}
+
+#[test]
+fn obfuscated() {
+    let (orig_path, cmp_path) = files_paths("2/");
+    let orig = smali::parse_file(orig_path).unwrap();
+    let cmp = smali::parse_file(cmp_path).unwrap();
+    let mapping = Mapping::from_file("tests/2/mapping.txt").unwrap();
+
+    let class_diff = diff::diff(&orig, &cmp).unwrap();
+    assert!(class_diff.class_path.is_some());
+    assert!(class_diff.super_path.is_some());
+
+    let class_diff = diff::diff_with_mapping(&orig, &cmp, &mapping).unwrap();
+    let mut load_method_diff = MethodDiff::new(Symbol::intern("load"));
+    load_method_diff.is_final = Some((false, true));
+    let expected = ClassDiff {
+        class_path: None,
+        access: None,
+        is_abstract: None,
+        super_path: None,
+        interfaces: None,
+        values: None,
+        methods: Some(vec![load_method_diff]),
+        synthetic: None,
+        only_in_mock: None,
+        only_in_app: None,
+    };
+    assert_eq!(expected, class_diff);
+}
+
+#[test]
+fn enum_constants() {
+    let (orig_path, cmp_path) = files_paths("3/");
+    let orig = SmaliEnum::from_class(&smali::parse_file(orig_path).unwrap()).unwrap();
+    let cmp = smali::parse_enum_file(cmp_path).unwrap().unwrap();
+    assert_eq!(cmp.order, EnumOrder::Ordinals);
+
+    let expected = EnumDiff {
+        class_path: orig.class_path.clone(),
+        added: vec![Symbol::intern("PAST_PREMIERE")],
+        removed: vec![Symbol::intern("CLIP")],
+        reordered: vec![
+            OrdinalDiff {
+                name: Symbol::intern("HIGHLIGHT"),
+                ordinal: (1, 2),
+            },
+            OrdinalDiff {
+                name: Symbol::intern("UPLOAD"),
+                ordinal: (2, 3),
+            },
+        ],
+    };
+    assert_eq!(diff::diff_enum(&orig, &cmp), Some(expected));
+    assert_eq!(diff::diff_enum(&cmp, &cmp), None);
+}
+
+#[test]
+fn synthetic_members() {
+    let (orig_path, cmp_path) = files_paths("4/");
+    let orig = smali::parse_file(orig_path).unwrap();
+    let cmp = smali::parse_file(cmp_path).unwrap();
+
+    let class_diff = diff::diff_with_policy(&orig, &cmp, SyntheticPolicy::Include).unwrap();
+    assert_eq!(class_diff.values.unwrap().len(), 1);
+    assert_eq!(class_diff.methods.unwrap().len(), 2);
+    assert_eq!(class_diff.synthetic, None);
+
+    assert_eq!(
+        diff::diff_with_policy(&orig, &cmp, SyntheticPolicy::Ignore),
+        None
+    );
+
+    let class_diff = diff::diff_with_policy(&orig, &cmp, SyntheticPolicy::Group).unwrap();
+    assert_eq!(class_diff.values, None);
+    assert_eq!(class_diff.methods, None);
+    let expected = vec![
+        SyntheticDiff {
+            kind: SyntheticKind::Lambda,
+            values: vec![],
+            methods: vec![MethodDiff::not_found(Symbol::intern("lambda$onStart$0"))],
+            only_in_mock: None,
+            only_in_app: None,
+        },
+        SyntheticDiff {
+            kind: SyntheticKind::Accessor,
+            values: vec![],
+            methods: vec![MethodDiff::not_found(Symbol::intern("access$000"))],
+            only_in_mock: None,
+            only_in_app: None,
+        },
+        SyntheticDiff {
+            kind: SyntheticKind::Captured,
+            values: vec![ValueDiff::not_found(Symbol::intern("this$0"))],
+            methods: vec![],
+            only_in_mock: None,
+            only_in_app: None,
+        },
+    ];
+    assert_eq!(class_diff.synthetic, Some(expected));
+
+    assert!(SyntheticPolicy::Ignore.includes_class(&orig));
+}
+
+#[test]
+fn synthetic_only_in_app() {
+    let (mock_path, app_path) = files_paths("4/");
+    let mock = smali::parse_file(mock_path).unwrap();
+    let app = smali::parse_file(app_path).unwrap();
+    let options = |authority, synthetic| DiffOptions {
+        authority: Some(authority),
+        synthetic,
+        mapping: None,
+    };
+    let names = |members: &Option<Members>| {
+        let members = members.as_ref().unwrap();
+        let values = members.values.iter().map(|value| value.name.to_string());
+        let methods = members.methods.iter().map(|method| method.name.to_string());
+        values.chain(methods).collect::<Vec<_>>()
+    };
+
+    // lambda$onStart$1 is only in the app
+    let class_diff = diff::diff_with(
+        &mock,
+        &app,
+        &options(Authority::App, SyntheticPolicy::Include),
+    )
+    .unwrap();
+    assert_eq!(names(&class_diff.only_in_app), ["lambda$onStart$1"]);
+
+    assert_eq!(
+        diff::diff_with(
+            &mock,
+            &app,
+            &options(Authority::Both, SyntheticPolicy::Ignore)
+        ),
+        None
+    );
+
+    let class_diff = diff::diff_with(
+        &mock,
+        &app,
+        &options(Authority::Both, SyntheticPolicy::Group),
+    )
+    .unwrap();
+    assert_eq!(class_diff.only_in_mock, None);
+    assert_eq!(class_diff.only_in_app, None);
+    let groups = class_diff.synthetic.unwrap();
+    assert_eq!(groups[0].kind, SyntheticKind::Lambda);
+    assert!(groups[0].methods.is_empty());
+    assert_eq!(names(&groups[0].only_in_mock), ["lambda$onStart$0"]);
+    assert_eq!(names(&groups[0].only_in_app), ["lambda$onStart$1"]);
+    assert_eq!(groups.len(), 3);
+}
+
+#[test]
+fn authority() {
+    let (mock_path, app_path) = files_paths("5/");
+    let mock = smali::parse_file(mock_path).unwrap();
+    let app = smali::parse_file(app_path).unwrap();
+    let names = |members: &Members| {
+        let values = members.values.iter().map(|value| value.name.to_string());
+        let methods = members.methods.iter().map(|method| method.name.to_string());
+        values.chain(methods).collect::<Vec<_>>()
+    };
+
+    let class_diff = diff::diff_with_authority(&mock, &app, Authority::Both).unwrap();
+    let mut play_method_diff = MethodDiff::new(Symbol::intern("play"));
+    play_method_diff.is_final = Some((false, true));
+    assert_eq!(class_diff.interfaces, None);
+    assert_eq!(class_diff.values, None);
+    assert_eq!(class_diff.methods, Some(vec![play_method_diff]));
+    let only_in_mock = class_diff.only_in_mock.unwrap();
+    assert!(only_in_mock.interfaces.is_empty());
+    assert_eq!(names(&only_in_mock), ["legacyMode", "enableLegacyMode"]);
+    let only_in_app = class_diff.only_in_app.unwrap();
+    assert_eq!(
+        only_in_app.interfaces,
+        [ClassPath::from_java("java.lang.Runnable").unwrap()]
+    );
+    assert_eq!(names(&only_in_app), ["latency", "run"]);
+
+    let class_diff = diff::diff_with_authority(&mock, &app, Authority::Mock).unwrap();
+    assert_eq!(class_diff.only_in_mock, Some(only_in_mock));
+    assert_eq!(class_diff.only_in_app, None);
+    let class_diff = diff::diff_with_authority(&mock, &app, Authority::App).unwrap();
+    assert_eq!(class_diff.only_in_mock, None);
+    assert_eq!(class_diff.only_in_app, Some(only_in_app));
+
+    assert_eq!(diff::diff_with_authority(&app, &app, Authority::Both), None);
+    // the mock lacks nothing the app has if it is a superset of the app
+    let mut superset = app.clone();
+    superset.values.extend(mock.values.iter().cloned());
+    assert!(diff::diff_with_authority(&superset, &app, Authority::App).is_none());
+}
+
+#[test]
+fn authority_overloads() {
+    let mock = smali::parse_class(
+        ".class public Ltv/twitch/Button;
+.super Ljava/lang/Object;
+.method public onClick(Landroid/view/View;)V
+.end method
+.method public onClick(Landroid/view/View;I)V
+.end method",
+    )
+    .unwrap();
+    let mut app = mock.clone();
+    app.methods.remove(1);
+
+    let class_diff = diff::diff_with_authority(&mock, &app, Authority::Both).unwrap();
+    assert_eq!(class_diff.methods, None);
+    assert_eq!(class_diff.only_in_app, None);
+    let only_in_mock = class_diff.only_in_mock.unwrap();
+    assert_eq!(only_in_mock.methods, [mock.methods[1].clone()]);
+
+    let class_diff = diff::diff_with_authority(&app, &mock, Authority::App).unwrap();
+    assert_eq!(class_diff.only_in_mock, None);
+    assert_eq!(
+        class_diff.only_in_app.unwrap().methods,
+        [mock.methods[1].clone()]
+    );
+}
+
+#[test]
+fn kotlin_members() {
+    let (orig_path, cmp_path) = files_paths("6/");
+    let orig = smali::parse_file(orig_path).unwrap();
+    let cmp = smali::parse_file(cmp_path).unwrap();
+    let metadata = cmp.kotlin_metadata().unwrap().unwrap();
+    assert!(orig.kotlin_metadata().is_none());
+    let class_diff = diff::diff(&orig, &cmp).unwrap();
+
+    let methods: Vec<_> = class_diff
+        .methods
+        .unwrap()
+        .iter()
+        .map(|method| {
+            let member = diff::kotlin::explain_method(method, &cmp, &metadata);
+            (
+                method.name.to_string(),
+                member.map(|member| member.to_string()),
+            )
+        })
+        .collect();
+    assert_eq!(
+        methods,
+        [
+            (
+                "getVolume".to_string(),
+                Some("getter of var volume: Float?".to_string())
+            ),
+            (
+                "setVolume".to_string(),
+                Some("setter of var volume: Float?".to_string())
+            ),
+            ("stop".to_string(), None),
+        ]
+    );
+
+    let values = class_diff.values.unwrap();
+    assert_eq!(
+        diff::kotlin::explain_value(&values[0], &cmp, &metadata).map(|member| member.to_string()),
+        Some("backing field of var volume: Float?".to_string())
+    );
+}
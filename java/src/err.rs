@@ -0,0 +1,22 @@
+use common::thiserror;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum JavaError {
+    #[error("IOError")]
+    IOError(#[from] std::io::Error),
+    #[error("line {line}: unterminated {what}")]
+    Unterminated { line: usize, what: &'static str },
+    #[error("line {line}: expected {expected}, found `{found}`")]
+    UnexpectedToken {
+        line: usize,
+        expected: &'static str,
+        found: String,
+    },
+    #[error("unexpected end of file, expected {0}")]
+    UnexpectedEof(&'static str),
+    #[error(transparent)]
+    InvalidClassPath(#[from] smali::ParserError),
+}
+
+pub type JavaResult<T> = Result<T, JavaError>;
@@ -0,0 +1,61 @@
+use smali::{SmaliClass, SmaliMethod, SmaliValue};
+
+/// A class declared in java source together with the lines of its declarations
+///
+/// Members that javac generates implicitly (default constructors, enum and record methods,
+/// `this$0`, `<clinit>`) have the line of the type declaration
+#[derive(Debug, Clone, PartialEq)]
+pub struct JavaClass {
+    pub class: SmaliClass,
+    /// line of the type declaration, starting with its modifiers
+    pub line: usize,
+    /// lines of `class.values`, in the same order
+    pub value_lines: Vec<usize>,
+    /// lines of `class.methods`, in the same order
+    pub method_lines: Vec<usize>,
+    /// simple names of types that could be in the same package or in a package imported on
+    /// demand, sorted. They are left unqualified in `class`.
+    pub unresolved: Vec<String>,
+}
+
+impl JavaClass {
+    pub(crate) fn new(class: SmaliClass, line: usize) -> Self {
+        Self {
+            class,
+            line,
+            value_lines: vec![],
+            method_lines: vec![],
+            unresolved: vec![],
+        }
+    }
+
+    pub(crate) fn push_value(&mut self, value: SmaliValue, line: usize) {
+        self.class.values.push(value);
+        self.value_lines.push(line);
+    }
+
+    pub(crate) fn push_method(&mut self, method: SmaliMethod, line: usize) {
+        self.class.methods.push(method);
+        self.method_lines.push(line);
+    }
+
+    /// The line of the field called name
+    pub fn value_line(&self, name: &str) -> Option<usize> {
+        let index = self
+            .class
+            .values
+            .iter()
+            .position(|value| value.name == name)?;
+        Some(self.value_lines[index])
+    }
+
+    /// The line of a method, matched by name and signature
+    pub fn method_line(&self, method: &SmaliMethod) -> Option<usize> {
+        let index = self.class.methods.iter().position(|own| {
+            own.name == method.name
+                && own.parameter_types == method.parameter_types
+                && own.return_type == method.return_type
+        })?;
+        Some(self.method_lines[index])
+    }
+}
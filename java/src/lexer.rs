@@ -0,0 +1,189 @@
+use crate::err::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// identifiers and keywords
+    Ident,
+    /// string, char and number literals, their content is not needed
+    Literal,
+    /// a single punctuation char, except for `...`, `::` and `->`
+    Punct,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub text: String,
+    pub line: usize,
+}
+
+/// Splits java source into tokens, skipping whitespace and comments
+///
+/// Operators are split into single chars (`>>` is two `>` tokens), which keeps closing
+/// nested type arguments simple, expressions are only skipped and never evaluated
+pub fn tokenize(source: &str) -> JavaResult<Vec<Token>> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = vec![];
+    let mut line = 1;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        let start_line = line;
+
+        if c == '\n' {
+            line += 1;
+            i += 1;
+            continue;
+        }
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+        if c == '/' && chars.get(i + 1) == Some(&'*') {
+            i += 2;
+            loop {
+                match chars.get(i) {
+                    None => {
+                        return Err(JavaError::Unterminated {
+                            line: start_line,
+                            what: "comment",
+                        })
+                    }
+                    Some('*') if chars.get(i + 1) == Some(&'/') => break,
+                    Some('\n') => line += 1,
+                    _ => {}
+                }
+                i += 1;
+            }
+            i += 2;
+            continue;
+        }
+
+        let kind = if c.is_alphabetic() || c == '_' || c == '$' {
+            while i < chars.len()
+                && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '$')
+            {
+                i += 1;
+            }
+            TokenKind::Ident
+        } else if c.is_ascii_digit()
+            || (c == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit))
+        {
+            while i < chars.len()
+                && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.')
+            {
+                i += 1;
+            }
+            TokenKind::Literal
+        } else if c == '"' && chars.get(i + 1) == Some(&'"') && chars.get(i + 2) == Some(&'"') {
+            i += 3;
+            loop {
+                match chars.get(i) {
+                    None => {
+                        return Err(JavaError::Unterminated {
+                            line: start_line,
+                            what: "text block",
+                        })
+                    }
+                    Some('\\') => i += 1,
+                    Some('"')
+                        if chars.get(i + 1) == Some(&'"') && chars.get(i + 2) == Some(&'"') =>
+                    {
+                        break
+                    }
+                    Some('\n') => line += 1,
+                    _ => {}
+                }
+                i += 1;
+            }
+            i += 3;
+            TokenKind::Literal
+        } else if c == '"' || c == '\'' {
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None | Some('\n') => {
+                        return Err(JavaError::Unterminated {
+                            line: start_line,
+                            what: "literal",
+                        })
+                    }
+                    Some('\\') => i += 1,
+                    Some(quote) if *quote == c => break,
+                    _ => {}
+                }
+                i += 1;
+            }
+            i += 1;
+            TokenKind::Literal
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 3)].iter().collect();
+            i += if rest.starts_with("...") {
+                3
+            } else if rest.starts_with("::") || rest.starts_with("->") {
+                2
+            } else {
+                1
+            };
+            TokenKind::Punct
+        };
+
+        tokens.push(Token {
+            kind,
+            text: chars[start..i].iter().collect(),
+            line: start_line,
+        });
+    }
+
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(source: &str) -> Vec<String> {
+        tokenize(source)
+            .unwrap()
+            .into_iter()
+            .map(|token| token.text)
+            .collect()
+    }
+
+    #[test]
+    fn tokens() {
+        assert_eq!(
+            texts("Map<String, List<T>> m = f(\"a\\\"b\", 'c', 1.5e3f); // x").join(" "),
+            "Map < String , List < T > > m = f ( \"a\\\"b\" , 'c' , 1.5e3f ) ;"
+        );
+        assert_eq!(
+            texts("String... a -> b::c"),
+            vec!["String", "...", "a", "->", "b", "::", "c"]
+        );
+    }
+
+    #[test]
+    fn lines() {
+        let tokens = tokenize("a /* b\n c */ d\n\"\"\"\ne\n\"\"\" f").unwrap();
+        let lines: Vec<_> = tokens.iter().map(|token| token.line).collect();
+        assert_eq!(lines, vec![1, 2, 3, 5]);
+    }
+
+    #[test]
+    fn unterminated() {
+        assert!(matches!(
+            tokenize("a \"b\nc"),
+            Err(JavaError::Unterminated { line: 1, .. })
+        ));
+        assert!(tokenize("/* a").is_err());
+    }
+}
@@ -0,0 +1,116 @@
+//! java is a crate that reads the declarations of java source files into the smali model,
+//! so mocks can be checked without compiling them
+//!
+//! Only declarations are parsed, method bodies and initializers are skipped. Members that
+//! javac generates implicitly are added the way javac does, see `JavaClass`.
+
+extern crate common;
+
+mod err;
+mod java_class;
+mod lexer;
+mod resolve;
+mod syntax;
+
+pub use err::*;
+pub use java_class::JavaClass;
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use syntax::CompilationUnit;
+
+/// A java file of a source tree together with the result of parsing it
+#[derive(Debug)]
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub classes: JavaResult<Vec<JavaClass>>,
+}
+
+/// Parses the content of a single java file, types of other files of the same
+/// package can not be told apart from types of on-demand imports, see `parse_source_tree`
+/// and `JavaClass::unresolved`
+pub fn parse_source(source: &str) -> JavaResult<Vec<JavaClass>> {
+    let unit = parse_unit(source)?;
+    resolve::Resolver::new(&unit, &HashSet::new(), false).classes()
+}
+
+/// Reads and parses a single java file, see `parse_source`
+pub fn parse_source_file(file_path: impl AsRef<Path>) -> JavaResult<Vec<JavaClass>> {
+    parse_source(&std::fs::read_to_string(file_path.as_ref())?)
+}
+
+/// Parses every `.java` file below dir, sorted by path
+///
+/// Type names are resolved against all types declared in the tree, which makes on-demand
+/// imports and types of the same package resolve exactly
+pub fn parse_source_tree(dir: impl AsRef<Path>) -> JavaResult<Vec<SourceFile>> {
+    let units: Vec<_> = find_java_files(dir)?
+        .into_iter()
+        .map(|path| {
+            let unit = std::fs::read_to_string(&path)
+                .map_err(JavaError::from)
+                .and_then(|source| parse_unit(&source));
+            (path, unit)
+        })
+        .collect();
+
+    let mut known = HashSet::new();
+    for unit in units.iter().filter_map(|(_, unit)| unit.as_ref().ok()) {
+        let prefix = unit.package.as_ref().map(|package| format!("{}.", package));
+        for decl in &unit.types {
+            collect_names(&mut known, prefix.as_deref().unwrap_or(""), '.', decl);
+        }
+    }
+
+    Ok(units
+        .into_iter()
+        .map(|(path, unit)| SourceFile {
+            path,
+            classes: unit.and_then(|unit| resolve::Resolver::new(&unit, &known, true).classes()),
+        })
+        .collect())
+}
+
+fn parse_unit(source: &str) -> JavaResult<CompilationUnit> {
+    let tokens = lexer::tokenize(source)?;
+    syntax::Parser::new(&tokens).compilation_unit()
+}
+
+fn collect_names(
+    known: &mut HashSet<String>,
+    prefix: &str,
+    separator: char,
+    decl: &syntax::TypeDecl,
+) {
+    let name = if prefix.is_empty() {
+        decl.name.clone()
+    } else if separator == '.' {
+        format!("{}{}", prefix, decl.name)
+    } else {
+        format!("{}{}{}", prefix, separator, decl.name)
+    };
+    for nested in &decl.types {
+        collect_names(known, &name, '$', nested);
+    }
+    known.insert(name);
+}
+
+fn find_java_files(dir: impl AsRef<Path>) -> JavaResult<Vec<PathBuf>> {
+    let mut files = vec![];
+    let mut dirs = vec![dir.as_ref().to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "java") {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
@@ -0,0 +1,732 @@
+use crate::err::*;
+use crate::java_class::JavaClass;
+use crate::syntax::*;
+use smali::*;
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// simple names of `java.lang`, which is imported implicitly
+const JAVA_LANG: &[&str] = &[
+    "AbstractMethodError",
+    "Appendable",
+    "ArithmeticException",
+    "ArrayIndexOutOfBoundsException",
+    "AssertionError",
+    "AutoCloseable",
+    "Boolean",
+    "Byte",
+    "CharSequence",
+    "Character",
+    "Class",
+    "ClassCastException",
+    "ClassLoader",
+    "ClassNotFoundException",
+    "CloneNotSupportedException",
+    "Cloneable",
+    "Comparable",
+    "Deprecated",
+    "Double",
+    "Enum",
+    "Error",
+    "Exception",
+    "Float",
+    "FunctionalInterface",
+    "IllegalAccessException",
+    "IllegalArgumentException",
+    "IllegalStateException",
+    "IndexOutOfBoundsException",
+    "Integer",
+    "InterruptedException",
+    "Iterable",
+    "Long",
+    "Math",
+    "NoSuchFieldException",
+    "NoSuchMethodException",
+    "NullPointerException",
+    "Number",
+    "NumberFormatException",
+    "Object",
+    "OutOfMemoryError",
+    "Override",
+    "Process",
+    "Readable",
+    "Record",
+    "ReflectiveOperationException",
+    "Runnable",
+    "Runtime",
+    "RuntimeException",
+    "SafeVarargs",
+    "SecurityException",
+    "Short",
+    "StackOverflowError",
+    "StackTraceElement",
+    "String",
+    "StringBuffer",
+    "StringBuilder",
+    "SuppressWarnings",
+    "System",
+    "Thread",
+    "ThreadLocal",
+    "Throwable",
+    "UnsupportedOperationException",
+    "Void",
+];
+
+/// Resolves the type names of one compilation unit to fully qualified class paths
+///
+/// A simple name is looked up in this order: type variables, member types of the enclosing
+/// declarations, single-type imports, types of the same file, known types of the same package,
+/// known types of on-demand imports and `java.lang`. When the whole source tree is known, the
+/// type can only be in a package imported on demand without known types, which is used if
+/// there is exactly one. Anything else is in the same package if no on-demand import could
+/// contain it, otherwise it is left as the bare name (`Missing`, `Missing$Inner`) and reported
+/// in `JavaClass::unresolved`.
+/// Known types are all types declared in the parsed source tree.
+pub struct Resolver<'a> {
+    unit: &'a CompilationUnit,
+    known: &'a HashSet<String>,
+    /// whether known holds every type of the source tree, including the other files
+    /// of the package of unit
+    whole_tree: bool,
+    single_imports: HashMap<&'a str, &'a str>,
+    /// the unresolved names of the class that is currently built
+    unresolved: RefCell<BTreeSet<String>>,
+}
+
+/// A declaration that encloses the current member, innermost last
+struct Scope<'a> {
+    decl: &'a TypeDecl,
+    java_path: String,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(unit: &'a CompilationUnit, known: &'a HashSet<String>, whole_tree: bool) -> Self {
+        let single_imports = unit
+            .imports
+            .iter()
+            .filter(|import| !import.is_static && !import.on_demand)
+            .map(|import| {
+                let simple = import.path.rsplit('.').next().unwrap_or(&import.path);
+                (simple, import.path.as_str())
+            })
+            .collect();
+        Self {
+            unit,
+            known,
+            whole_tree,
+            single_imports,
+            unresolved: RefCell::default(),
+        }
+    }
+
+    /// Builds the classes of all declarations in the compilation unit, nested classes follow
+    /// their enclosing class
+    pub fn classes(&self) -> JavaResult<Vec<JavaClass>> {
+        let mut classes = vec![];
+        for decl in &self.unit.types {
+            let java_path = match &self.unit.package {
+                Some(package) => format!("{}.{}", package, decl.name),
+                None => decl.name.clone(),
+            };
+            self.collect(&mut vec![], decl, java_path, &mut classes)?;
+        }
+        Ok(classes)
+    }
+
+    fn collect(
+        &self,
+        scopes: &mut Vec<Scope<'a>>,
+        decl: &'a TypeDecl,
+        java_path: String,
+        classes: &mut Vec<JavaClass>,
+    ) -> JavaResult<()> {
+        scopes.push(Scope { decl, java_path });
+        classes.push(self.class(scopes)?);
+        for nested in &decl.types {
+            let nested_path = format!("{}${}", scopes[scopes.len() - 1].java_path, nested.name);
+            self.collect(scopes, nested, nested_path, classes)?;
+        }
+        scopes.pop();
+        Ok(())
+    }
+
+    fn class(&self, scopes: &[Scope]) -> JavaResult<JavaClass> {
+        let scope = &scopes[scopes.len() - 1];
+        let decl = scope.decl;
+        let this_path = ClassPath::from_java(&scope.java_path)?;
+        let this_type = SmaliType::Class(this_path.clone());
+        let is_nested = scopes.len() > 1;
+        let is_interface = matches!(decl.kind, TypeKind::Interface | TypeKind::Annotation);
+        let outer_is_interface = is_nested
+            && matches!(
+                scopes[scopes.len() - 2].decl.kind,
+                TypeKind::Interface | TypeKind::Annotation
+            );
+        let is_inner = is_nested
+            && decl.kind == TypeKind::Class
+            && !decl.modifiers.is_static
+            && !outer_is_interface;
+
+        // nested classes can not be private or protected in a class file
+        let access = match decl.modifiers.access {
+            SmaliAccessModifier::Protected => SmaliAccessModifier::Public,
+            SmaliAccessModifier::Private => SmaliAccessModifier::Package,
+            access if outer_is_interface && access == SmaliAccessModifier::Package => {
+                SmaliAccessModifier::Public
+            }
+            access => access,
+        };
+        let has_abstract_method = decl
+            .methods
+            .iter()
+            .any(|method| method.modifiers.is_abstract);
+        let is_abstract = is_interface
+            || decl.modifiers.is_abstract
+            || (decl.kind == TypeKind::Enum && has_abstract_method);
+
+        let mut class = JavaClass::new(SmaliClass::new(this_path, access, is_abstract), decl.line);
+        class.class.kind = match decl.kind {
+            TypeKind::Interface => ClassKind::Interface,
+            TypeKind::Annotation => ClassKind::Annotation,
+            TypeKind::Enum => ClassKind::Enum,
+            TypeKind::Class | TypeKind::Record => ClassKind::Class,
+        };
+
+        class.class.super_path = Some(match (decl.kind, decl.extends.first()) {
+            (TypeKind::Class, Some(extends)) => self.class_path(scopes, &[], extends)?,
+            (TypeKind::Enum, _) => ClassPath::from_java("java.lang.Enum")?,
+            (TypeKind::Record, _) => ClassPath::from_java("java.lang.Record")?,
+            _ => ClassPath::from_java("java.lang.Object")?,
+        });
+        let interfaces = if is_interface {
+            &decl.extends
+        } else {
+            &decl.implements
+        };
+        for interface in interfaces {
+            let interface = self.class_path(scopes, &[], interface)?;
+            class.class.interfaces.push(interface);
+        }
+        if decl.kind == TypeKind::Annotation {
+            let annotation = ClassPath::from_java("java.lang.annotation.Annotation")?;
+            class.class.interfaces.push(annotation);
+        }
+
+        // fields
+        for (name, line) in &decl.enum_constants {
+            let mut constant = value(
+                name,
+                this_type.clone(),
+                SmaliAccessModifier::Public,
+                true,
+                true,
+            );
+            constant.is_enum = true;
+            class.push_value(constant, *line);
+        }
+        for field in &decl.fields {
+            let data_type = self.smali_type(scopes, &[], &field.field_type)?;
+            let mut modifiers = field.modifiers;
+            if is_interface {
+                modifiers.access = SmaliAccessModifier::Public;
+                modifiers.is_static = true;
+                modifiers.is_final = true;
+            }
+            class.push_value(
+                value(
+                    &field.name,
+                    data_type,
+                    modifiers.access,
+                    modifiers.is_static,
+                    modifiers.is_final,
+                ),
+                field.line,
+            );
+        }
+        for component in &decl.record_components {
+            let data_type = self.smali_type(scopes, &[], &component.param_type)?;
+            class.push_value(
+                value(
+                    &component.name,
+                    data_type,
+                    SmaliAccessModifier::Private,
+                    false,
+                    true,
+                ),
+                decl.line,
+            );
+        }
+        if decl.kind == TypeKind::Enum {
+            let values_type = SmaliType::Arr(Box::new(this_type.clone()));
+            let mut values = value(
+                "$VALUES",
+                values_type,
+                SmaliAccessModifier::Private,
+                true,
+                true,
+            );
+            values.is_synthetic = true;
+            class.push_value(values, decl.line);
+        }
+        let outer_type = if is_inner {
+            let outer = ClassPath::from_java(&scopes[scopes.len() - 2].java_path)?;
+            let outer_type = SmaliType::Class(outer);
+            let mut this = value(
+                "this$0",
+                outer_type.clone(),
+                SmaliAccessModifier::Package,
+                false,
+                true,
+            );
+            this.is_synthetic = true;
+            class.push_value(this, decl.line);
+            Some(outer_type)
+        } else {
+            None
+        };
+
+        // methods
+        let mut has_constructor = false;
+        for method in &decl.methods {
+            let mut parameter_types = method
+                .params
+                .iter()
+                .map(|param| self.smali_type(scopes, &method.type_params, &param.param_type))
+                .collect::<JavaResult<Vec<_>>>()?;
+            let mut modifiers = method.modifiers;
+
+            let return_type = match &method.return_type {
+                Some(return_type) => self.smali_type(scopes, &method.type_params, return_type)?,
+                None => {
+                    has_constructor = true;
+                    if decl.kind == TypeKind::Enum {
+                        modifiers.access = SmaliAccessModifier::Private;
+                        parameter_types.splice(0..0, enum_constructor_params());
+                    }
+                    if let Some(outer_type) = &outer_type {
+                        parameter_types.insert(0, outer_type.clone());
+                    }
+                    SmaliType::Void
+                }
+            };
+            if is_interface && modifiers.access != SmaliAccessModifier::Private {
+                modifiers.access = SmaliAccessModifier::Public;
+            }
+
+            class.push_method(
+                SmaliMethod {
+                    name: Symbol::intern(&method.name),
+                    access: modifiers.access,
+                    parameter_types,
+                    return_type,
+                    is_static: modifiers.is_static,
+                    is_final: modifiers.is_final,
+                    is_synthetic: false,
+                },
+                method.line,
+            );
+        }
+
+        if !has_constructor && !is_interface {
+            let mut parameter_types = vec![];
+            let mut access = decl.modifiers.access;
+            match decl.kind {
+                TypeKind::Enum => {
+                    access = SmaliAccessModifier::Private;
+                    parameter_types.extend(enum_constructor_params());
+                }
+                TypeKind::Record => {
+                    for component in &decl.record_components {
+                        parameter_types.push(self.smali_type(
+                            scopes,
+                            &[],
+                            &component.param_type,
+                        )?);
+                    }
+                }
+                _ => {}
+            }
+            if let Some(outer_type) = &outer_type {
+                parameter_types.insert(0, outer_type.clone());
+            }
+            class.push_method(
+                method(
+                    "<init>",
+                    access,
+                    parameter_types,
+                    SmaliType::Void,
+                    false,
+                    false,
+                ),
+                decl.line,
+            );
+        }
+
+        if decl.kind == TypeKind::Record {
+            self.record_methods(scopes, &mut class)?;
+        }
+        if decl.kind == TypeKind::Enum {
+            let values_type = SmaliType::Arr(Box::new(this_type.clone()));
+            let string_type = SmaliType::Class(ClassPath::from_java("java.lang.String")?);
+            let public = SmaliAccessModifier::Public;
+            let private = SmaliAccessModifier::Private;
+            class.push_method(
+                method("values", public, vec![], values_type.clone(), true, false),
+                decl.line,
+            );
+            class.push_method(
+                method("valueOf", public, vec![string_type], this_type, true, false),
+                decl.line,
+            );
+            let mut values = method("$values", private, vec![], values_type, true, false);
+            values.is_synthetic = true;
+            class.push_method(values, decl.line);
+        }
+
+        // javac inlines constants, every other static initializer runs in `<clinit>`
+        let needs_static_init = decl.has_static_init
+            || decl.kind == TypeKind::Enum
+            || decl.fields.iter().any(|field| {
+                let is_static = field.modifiers.is_static || is_interface;
+                let is_constant = (field.modifiers.is_final || is_interface)
+                    && is_constant_type(&field.field_type)
+                    && !field.has_code_initializer;
+                is_static && field.has_initializer && !is_constant
+            });
+        if needs_static_init {
+            class.push_method(
+                method(
+                    "<clinit>",
+                    SmaliAccessModifier::Package,
+                    vec![],
+                    SmaliType::Void,
+                    true,
+                    false,
+                ),
+                decl.line,
+            );
+        }
+
+        class.unresolved = self.unresolved.take().into_iter().collect();
+        Ok(class)
+    }
+
+    fn record_methods(&self, scopes: &[Scope], class: &mut JavaClass) -> JavaResult<()> {
+        let decl = scopes[scopes.len() - 1].decl;
+        let declared = |name: &str, params: usize| {
+            decl.methods
+                .iter()
+                .any(|method| method.name == name && method.params.len() == params)
+        };
+        let public = SmaliAccessModifier::Public;
+
+        for component in &decl.record_components {
+            if !declared(&component.name, 0) {
+                let return_type = self.smali_type(scopes, &[], &component.param_type)?;
+                class.push_method(
+                    method(&component.name, public, vec![], return_type, false, false),
+                    decl.line,
+                );
+            }
+        }
+        if !declared("toString", 0) {
+            let string_type = SmaliType::Class(ClassPath::from_java("java.lang.String")?);
+            class.push_method(
+                method("toString", public, vec![], string_type, false, true),
+                decl.line,
+            );
+        }
+        if !declared("hashCode", 0) {
+            class.push_method(
+                method("hashCode", public, vec![], SmaliType::Int, false, true),
+                decl.line,
+            );
+        }
+        if !declared("equals", 1) {
+            let object_type = SmaliType::Class(ClassPath::from_java("java.lang.Object")?);
+            class.push_method(
+                method(
+                    "equals",
+                    public,
+                    vec![object_type],
+                    SmaliType::Boolean,
+                    false,
+                    true,
+                ),
+                decl.line,
+            );
+        }
+        Ok(())
+    }
+
+    fn smali_type(
+        &self,
+        scopes: &[Scope],
+        method_type_params: &[TypeParam],
+        type_ref: &TypeRef,
+    ) -> JavaResult<SmaliType> {
+        let base = match type_ref.name.as_str() {
+            "void" => SmaliType::Void,
+            "boolean" => SmaliType::Boolean,
+            "byte" => SmaliType::Byte,
+            "char" => SmaliType::Char,
+            "short" => SmaliType::Short,
+            "int" => SmaliType::Int,
+            "long" => SmaliType::Long,
+            "float" => SmaliType::Float,
+            "double" => SmaliType::Double,
+            _ => {
+                let erased = self.erase(scopes, method_type_params, &type_ref.name, 0)?;
+                match erased {
+                    Some(erased) => {
+                        // `T[]` erases to an array of the bound of T
+                        let mut erased = erased;
+                        for _ in 0..type_ref.dims {
+                            erased = SmaliType::Arr(Box::new(erased));
+                        }
+                        return Ok(erased);
+                    }
+                    None => {
+                        SmaliType::Class(self.class_path(scopes, method_type_params, type_ref)?)
+                    }
+                }
+            }
+        };
+        Ok((0..type_ref.dims).fold(base, |inner, _| SmaliType::Arr(Box::new(inner))))
+    }
+
+    /// The erasure of a type variable, None if name is not a type variable in scope
+    fn erase(
+        &self,
+        scopes: &[Scope],
+        method_type_params: &[TypeParam],
+        name: &str,
+        depth: usize,
+    ) -> JavaResult<Option<SmaliType>> {
+        let class_params = scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| &scope.decl.type_params);
+        let param = method_type_params
+            .iter()
+            .chain(class_params)
+            .find(|param| param.name == name);
+        let param = match param {
+            Some(param) => param,
+            None => return Ok(None),
+        };
+        match &param.bound {
+            // the depth limit stops cycles like `<A extends B, B extends A>`
+            Some(bound) if depth < 8 => {
+                if let Some(erased) =
+                    self.erase(scopes, method_type_params, &bound.name, depth + 1)?
+                {
+                    return Ok(Some(erased));
+                }
+                Ok(Some(self.smali_type(scopes, method_type_params, bound)?))
+            }
+            _ => Ok(Some(SmaliType::Class(ClassPath::from_java(
+                "java.lang.Object",
+            )?))),
+        }
+    }
+
+    fn class_path(
+        &self,
+        scopes: &[Scope],
+        method_type_params: &[TypeParam],
+        type_ref: &TypeRef,
+    ) -> JavaResult<ClassPath> {
+        if let Some(SmaliType::Class(erased)) =
+            self.erase(scopes, method_type_params, &type_ref.name, 0)?
+        {
+            return Ok(erased);
+        }
+        Ok(ClassPath::from_java(&self.resolve(scopes, &type_ref.name))?)
+    }
+
+    /// The binary java name (`a.B$C`) of a possibly qualified type name
+    fn resolve(&self, scopes: &[Scope], name: &str) -> String {
+        let segments: Vec<&str> = name.split('.').collect();
+        if let Some(base) = self.resolve_simple(scopes, segments[0]) {
+            return std::iter::once(base.as_str())
+                .chain(segments[1..].iter().copied())
+                .collect::<Vec<_>>()
+                .join("$");
+        }
+
+        // either in the same package or in one of the packages imported on demand
+        let candidates = self.unknown_on_demand_imports().len() + usize::from(!self.whole_tree);
+        if segments[0].starts_with(char::is_uppercase) && candidates > 1 {
+            self.unresolved.borrow_mut().insert(segments[0].to_string());
+            return segments.join("$");
+        }
+        if segments.len() == 1 {
+            return match &self.unit.package {
+                Some(package) => format!("{}.{}", package, name),
+                None => name.to_string(),
+            };
+        }
+
+        self.resolve_qualified(name)
+    }
+
+    fn resolve_simple(&self, scopes: &[Scope], name: &str) -> Option<String> {
+        for scope in scopes.iter().rev() {
+            if scope.decl.types.iter().any(|nested| nested.name == name) {
+                return Some(format!("{}${}", scope.java_path, name));
+            }
+            if scope.decl.name == name {
+                return Some(scope.java_path.clone());
+            }
+        }
+
+        if let Some(import) = self.single_imports.get(name) {
+            return Some(self.resolve_qualified(import));
+        }
+
+        let same_package = match &self.unit.package {
+            Some(package) => format!("{}.{}", package, name),
+            None => name.to_string(),
+        };
+        if self.unit.types.iter().any(|decl| decl.name == name)
+            || self.known.contains(&same_package)
+        {
+            return Some(same_package);
+        }
+
+        let on_demand: Vec<_> = self
+            .unit
+            .imports
+            .iter()
+            .filter(|import| import.on_demand && !import.is_static)
+            .collect();
+        for import in &on_demand {
+            for candidate in [
+                format!("{}.{}", import.path, name),
+                format!("{}${}", self.resolve_qualified(&import.path), name),
+            ] {
+                if self.known.contains(&candidate) {
+                    return Some(candidate);
+                }
+            }
+        }
+
+        if JAVA_LANG.contains(&name) {
+            return Some(format!("java.lang.{}", name));
+        }
+
+        if self.whole_tree {
+            if let [import] = self.unknown_on_demand_imports().as_slice() {
+                return Some(format!("{}.{}", import.path, name));
+            }
+        }
+
+        None
+    }
+
+    /// The on-demand imports of packages without known types, which could contain any
+    /// simple name that is not found elsewhere
+    fn unknown_on_demand_imports(&self) -> Vec<&Import> {
+        self.unit
+            .imports
+            .iter()
+            .filter(|import| import.on_demand && !import.is_static)
+            .filter(|import| {
+                let prefix = format!("{}.", import.path);
+                !self.known.iter().any(|known| known.starts_with(&prefix))
+            })
+            .collect()
+    }
+
+    /// The binary name of a fully qualified name like `a.b.C.D`,
+    /// packages are lower case by convention if the type is not known
+    fn resolve_qualified(&self, name: &str) -> String {
+        let segments: Vec<&str> = name.split('.').collect();
+        for split in (1..segments.len()).rev() {
+            let candidate = format!(
+                "{}.{}",
+                segments[..split].join("."),
+                segments[split..].join("$")
+            );
+            if self.known.contains(&candidate) {
+                return candidate;
+            }
+        }
+        let split = segments[1..]
+            .iter()
+            .position(|segment| segment.starts_with(char::is_uppercase))
+            .map_or(segments.len() - 1, |position| position + 1);
+        if split == 0 {
+            return name.to_string();
+        }
+        format!(
+            "{}.{}",
+            segments[..split].join("."),
+            segments[split..].join("$")
+        )
+    }
+}
+
+fn value(
+    name: &str,
+    data_type: SmaliType,
+    access: SmaliAccessModifier,
+    is_static: bool,
+    is_final: bool,
+) -> SmaliValue {
+    SmaliValue {
+        name: Symbol::intern(name),
+        data_type,
+        access,
+        is_static,
+        is_final,
+        is_synthetic: false,
+        is_enum: false,
+    }
+}
+
+fn method(
+    name: &str,
+    access: SmaliAccessModifier,
+    parameter_types: Vec<SmaliType>,
+    return_type: SmaliType,
+    is_static: bool,
+    is_final: bool,
+) -> SmaliMethod {
+    SmaliMethod {
+        name: Symbol::intern(name),
+        access,
+        parameter_types,
+        return_type,
+        is_static,
+        is_final,
+        is_synthetic: false,
+    }
+}
+
+/// javac passes the name and ordinal to every enum constructor
+fn enum_constructor_params() -> Vec<SmaliType> {
+    vec![
+        SmaliType::Class(ClassPath::from_java("java.lang.String").expect("valid class path")),
+        SmaliType::Int,
+    ]
+}
+
+/// primitives and strings can be compile time constants
+fn is_constant_type(field_type: &TypeRef) -> bool {
+    field_type.dims == 0
+        && matches!(
+            field_type.name.as_str(),
+            "boolean"
+                | "byte"
+                | "char"
+                | "short"
+                | "int"
+                | "long"
+                | "float"
+                | "double"
+                | "String"
+                | "java.lang.String"
+        )
+}
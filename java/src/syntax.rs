@@ -0,0 +1,718 @@
+use crate::err::*;
+use crate::lexer::{Token, TokenKind};
+use smali::SmaliAccessModifier;
+
+/// The declarations of a java file, type names are kept as written in the source
+#[derive(Debug, Default)]
+pub struct CompilationUnit {
+    pub package: Option<String>,
+    pub imports: Vec<Import>,
+    pub types: Vec<TypeDecl>,
+}
+
+#[derive(Debug)]
+pub struct Import {
+    /// the dotted name without a trailing `.*`
+    pub path: String,
+    pub is_static: bool,
+    pub on_demand: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeKind {
+    Class,
+    Interface,
+    Enum,
+    Annotation,
+    Record,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Modifiers {
+    pub access: SmaliAccessModifier,
+    pub is_static: bool,
+    pub is_final: bool,
+    pub is_abstract: bool,
+}
+
+impl Default for Modifiers {
+    fn default() -> Self {
+        Self {
+            access: SmaliAccessModifier::Package,
+            is_static: false,
+            is_final: false,
+            is_abstract: false,
+        }
+    }
+}
+
+/// A type as written in the source, without type arguments
+#[derive(Debug, Clone)]
+pub struct TypeRef {
+    /// a primitive keyword or a (possibly qualified) type name, `Map.Entry`
+    pub name: String,
+    pub dims: usize,
+}
+
+#[derive(Debug)]
+pub struct TypeParam {
+    pub name: String,
+    /// the first bound, which a type variable erases to
+    pub bound: Option<TypeRef>,
+}
+
+#[derive(Debug)]
+pub struct TypeDecl {
+    pub kind: TypeKind,
+    pub name: String,
+    pub modifiers: Modifiers,
+    pub line: usize,
+    pub type_params: Vec<TypeParam>,
+    pub extends: Vec<TypeRef>,
+    pub implements: Vec<TypeRef>,
+    pub record_components: Vec<Param>,
+    pub enum_constants: Vec<(String, usize)>,
+    pub fields: Vec<FieldDecl>,
+    pub methods: Vec<MethodDecl>,
+    pub types: Vec<TypeDecl>,
+    pub has_static_init: bool,
+}
+
+#[derive(Debug)]
+pub struct FieldDecl {
+    pub name: String,
+    pub modifiers: Modifiers,
+    pub field_type: TypeRef,
+    pub line: usize,
+    pub has_initializer: bool,
+    /// whether the initializer contains more than literals, names and operators,
+    /// which javac can not inline as constant
+    pub has_code_initializer: bool,
+}
+
+#[derive(Debug)]
+pub struct MethodDecl {
+    /// the declared name, `<init>` for constructors
+    pub name: String,
+    pub modifiers: Modifiers,
+    pub type_params: Vec<TypeParam>,
+    /// None for constructors
+    pub return_type: Option<TypeRef>,
+    pub params: Vec<Param>,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Param {
+    pub param_type: TypeRef,
+    pub name: String,
+}
+
+pub struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(tokens: &'a [Token]) -> Self {
+        Self { tokens, pos: 0 }
+    }
+
+    pub fn compilation_unit(mut self) -> JavaResult<CompilationUnit> {
+        let mut unit = CompilationUnit::default();
+
+        // annotations of package-info.java
+        self.annotations()?;
+        if self.eat("package") {
+            unit.package = Some(self.qualified_name()?);
+            self.expect(";")?;
+        }
+
+        while self.eat("import") {
+            let is_static = self.eat("static");
+            let mut path = self.ident()?;
+            let mut on_demand = false;
+            while self.eat(".") {
+                if self.eat("*") {
+                    on_demand = true;
+                    break;
+                }
+                path.push('.');
+                path.push_str(&self.ident()?);
+            }
+            self.expect(";")?;
+            unit.imports.push(Import {
+                path,
+                is_static,
+                on_demand,
+            });
+        }
+
+        while self.peek().is_some() {
+            if self.eat(";") {
+                continue;
+            }
+            let line = self.line();
+            let modifiers = self.modifiers()?;
+            unit.types.push(self.type_decl(modifiers, line)?);
+        }
+
+        Ok(unit)
+    }
+
+    fn type_decl(&mut self, modifiers: Modifiers, line: usize) -> JavaResult<TypeDecl> {
+        let kind = if self.eat("class") {
+            TypeKind::Class
+        } else if self.eat("interface") {
+            TypeKind::Interface
+        } else if self.eat("enum") {
+            TypeKind::Enum
+        } else if self.eat("record") {
+            TypeKind::Record
+        } else if self.peek_text(0) == Some("@") && self.peek_text(1) == Some("interface") {
+            self.pos += 2;
+            TypeKind::Annotation
+        } else {
+            return Err(self.unexpected("a type declaration"));
+        };
+
+        let mut decl = TypeDecl {
+            kind,
+            name: self.ident()?,
+            modifiers,
+            line,
+            type_params: self.type_params()?,
+            extends: vec![],
+            implements: vec![],
+            record_components: vec![],
+            enum_constants: vec![],
+            fields: vec![],
+            methods: vec![],
+            types: vec![],
+            has_static_init: false,
+        };
+
+        if kind == TypeKind::Record {
+            decl.record_components = self.params()?;
+        }
+        if self.eat("extends") {
+            decl.extends = self.type_list()?;
+        }
+        if self.eat("implements") {
+            decl.implements = self.type_list()?;
+        }
+        if self.eat("permits") {
+            self.type_list()?;
+        }
+
+        self.expect("{")?;
+        if kind == TypeKind::Enum {
+            self.enum_constants(&mut decl)?;
+        }
+        self.members(&mut decl)?;
+        Ok(decl)
+    }
+
+    fn enum_constants(&mut self, decl: &mut TypeDecl) -> JavaResult<()> {
+        loop {
+            self.annotations()?;
+            if self.peek_kind() != Some(TokenKind::Ident) {
+                break;
+            }
+            let line = self.line();
+            decl.enum_constants.push((self.ident()?, line));
+            if self.peek_text(0) == Some("(") {
+                self.skip_balanced("(", ")")?;
+            }
+            if self.peek_text(0) == Some("{") {
+                self.skip_balanced("{", "}")?;
+            }
+            if !self.eat(",") {
+                break;
+            }
+        }
+        // without members the constants may be followed by `}` directly
+        self.eat(";");
+        Ok(())
+    }
+
+    fn members(&mut self, decl: &mut TypeDecl) -> JavaResult<()> {
+        while !self.eat("}") {
+            if self.peek().is_none() {
+                return Err(JavaError::UnexpectedEof("`}`"));
+            }
+            if self.eat(";") {
+                continue;
+            }
+            if self.peek_text(0) == Some("{") {
+                self.skip_balanced("{", "}")?;
+                continue;
+            }
+            if self.peek_text(0) == Some("static") && self.peek_text(1) == Some("{") {
+                self.pos += 1;
+                self.skip_balanced("{", "}")?;
+                decl.has_static_init = true;
+                continue;
+            }
+
+            let line = self.line();
+            let modifiers = self.modifiers()?;
+            if self.at_type_decl() {
+                decl.types.push(self.type_decl(modifiers, line)?);
+                continue;
+            }
+
+            let type_params = self.type_params()?;
+            let is_constructor = self.peek_text(0) == Some(decl.name.as_str());
+            if is_constructor && self.peek_text(1) == Some("(") {
+                self.pos += 1;
+                let params = self.params()?;
+                self.method_rest(decl, modifiers, type_params, "<init>", None, params, line)?;
+                continue;
+            }
+            if is_constructor && self.peek_text(1) == Some("{") && decl.kind == TypeKind::Record {
+                self.pos += 1;
+                self.skip_balanced("{", "}")?;
+                decl.methods.push(MethodDecl {
+                    name: "<init>".to_string(),
+                    modifiers,
+                    type_params,
+                    return_type: None,
+                    params: decl.record_components.clone(),
+                    line,
+                });
+                continue;
+            }
+
+            let member_type = self.type_ref()?;
+            let name = self.ident()?;
+            if self.peek_text(0) == Some("(") {
+                let params = self.params()?;
+                self.method_rest(
+                    decl,
+                    modifiers,
+                    type_params,
+                    &name,
+                    Some(member_type),
+                    params,
+                    line,
+                )?;
+            } else {
+                self.fields(decl, modifiers, member_type, name, line)?;
+            }
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn method_rest(
+        &mut self,
+        decl: &mut TypeDecl,
+        modifiers: Modifiers,
+        type_params: Vec<TypeParam>,
+        name: &str,
+        mut return_type: Option<TypeRef>,
+        params: Vec<Param>,
+        line: usize,
+    ) -> JavaResult<()> {
+        // `int matrix()[]` is an old style array return type
+        let dims = self.dims()?;
+        if let Some(return_type) = &mut return_type {
+            return_type.dims += dims;
+        }
+        if self.eat("throws") {
+            self.type_list()?;
+        }
+
+        if self.peek_text(0) == Some("{") {
+            self.skip_balanced("{", "}")?;
+        } else {
+            // annotation elements may have a default value
+            if self.eat("default") {
+                self.skip_expression()?;
+            }
+            self.expect(";")?;
+        }
+
+        decl.methods.push(MethodDecl {
+            name: name.to_string(),
+            modifiers,
+            type_params,
+            return_type,
+            params,
+            line,
+        });
+        Ok(())
+    }
+
+    fn fields(
+        &mut self,
+        decl: &mut TypeDecl,
+        modifiers: Modifiers,
+        base_type: TypeRef,
+        mut name: String,
+        mut line: usize,
+    ) -> JavaResult<()> {
+        loop {
+            let mut field_type = base_type.clone();
+            field_type.dims += self.dims()?;
+            let has_initializer = self.eat("=");
+            let has_code_initializer = has_initializer && self.skip_expression()?;
+            decl.fields.push(FieldDecl {
+                name,
+                modifiers,
+                field_type,
+                line,
+                has_initializer,
+                has_code_initializer,
+            });
+
+            if !self.eat(",") {
+                break;
+            }
+            line = self.line();
+            name = self.ident()?;
+        }
+        self.expect(";")
+    }
+
+    /// Skips an initializer up to the next `;` or the `,` that starts the next declarator,
+    /// returns whether it contains more than literals, names and operators
+    fn skip_expression(&mut self) -> JavaResult<bool> {
+        let mut has_code = false;
+        let mut depth = 0usize;
+        loop {
+            let token = self.peek().ok_or(JavaError::UnexpectedEof("`;`"))?;
+            match token.text.as_str() {
+                ";" if depth == 0 => return Ok(has_code),
+                // a comma in `new HashMap<A, B>()` is not followed by a declarator
+                "," if depth == 0 && self.at_declarator(1) => return Ok(has_code),
+                "(" | "{" | "[" => {
+                    has_code |= token.text != "[";
+                    depth += 1;
+                }
+                ")" | "}" | "]" => depth = depth.saturating_sub(1),
+                "new" | "->" | "::" => has_code = true,
+                _ => {}
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn at_declarator(&self, offset: usize) -> bool {
+        self.peek_kind_at(offset) == Some(TokenKind::Ident)
+            && matches!(self.peek_text(offset + 1), Some("=" | "," | ";" | "["))
+    }
+
+    fn at_type_decl(&self) -> bool {
+        match self.peek_text(0) {
+            Some("class" | "interface" | "enum") => true,
+            Some("@") => self.peek_text(1) == Some("interface"),
+            Some("record") => {
+                self.peek_kind_at(1) == Some(TokenKind::Ident)
+                    && matches!(self.peek_text(2), Some("(" | "<"))
+            }
+            _ => false,
+        }
+    }
+
+    fn modifiers(&mut self) -> JavaResult<Modifiers> {
+        let mut modifiers = Modifiers::default();
+        loop {
+            if self.peek_text(0) == Some("@") && self.peek_text(1) != Some("interface") {
+                self.annotation()?;
+                continue;
+            }
+            match self.peek_text(0) {
+                Some("public") => modifiers.access = SmaliAccessModifier::Public,
+                Some("private") => modifiers.access = SmaliAccessModifier::Private,
+                Some("protected") => modifiers.access = SmaliAccessModifier::Protected,
+                Some("static") => modifiers.is_static = true,
+                Some("final") => modifiers.is_final = true,
+                Some("abstract") => modifiers.is_abstract = true,
+                Some(
+                    "default" | "native" | "synchronized" | "transient" | "volatile" | "strictfp"
+                    | "sealed",
+                ) => {}
+                Some("non") if self.peek_text(1) == Some("-") => self.pos += 2,
+                _ => return Ok(modifiers),
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn annotations(&mut self) -> JavaResult<()> {
+        while self.peek_text(0) == Some("@") && self.peek_text(1) != Some("interface") {
+            self.annotation()?;
+        }
+        Ok(())
+    }
+
+    fn annotation(&mut self) -> JavaResult<()> {
+        self.expect("@")?;
+        self.qualified_name()?;
+        if self.peek_text(0) == Some("(") {
+            self.skip_balanced("(", ")")?;
+        }
+        Ok(())
+    }
+
+    fn type_params(&mut self) -> JavaResult<Vec<TypeParam>> {
+        let mut params = vec![];
+        if !self.eat("<") {
+            return Ok(params);
+        }
+        loop {
+            self.annotations()?;
+            let name = self.ident()?;
+            let bound = if self.eat("extends") {
+                let bound = self.type_ref()?;
+                while self.eat("&") {
+                    self.type_ref()?;
+                }
+                Some(bound)
+            } else {
+                None
+            };
+            params.push(TypeParam { name, bound });
+            if !self.eat(",") {
+                break;
+            }
+        }
+        self.expect(">")?;
+        Ok(params)
+    }
+
+    fn params(&mut self) -> JavaResult<Vec<Param>> {
+        self.expect("(")?;
+        let mut params = vec![];
+        if self.eat(")") {
+            return Ok(params);
+        }
+        loop {
+            self.modifiers()?;
+            let mut param_type = self.type_ref()?;
+            self.annotations()?;
+            if self.eat("...") {
+                param_type.dims += 1;
+            }
+            // the receiver parameter `Outer.this` is not a real parameter
+            let name = self.ident()?;
+            if self.eat(".") {
+                self.expect("this")?;
+            } else if name != "this" {
+                param_type.dims += self.dims()?;
+                params.push(Param { param_type, name });
+            }
+            if !self.eat(",") {
+                break;
+            }
+        }
+        self.expect(")")?;
+        Ok(params)
+    }
+
+    fn type_list(&mut self) -> JavaResult<Vec<TypeRef>> {
+        let mut types = vec![self.type_ref()?];
+        while self.eat(",") {
+            types.push(self.type_ref()?);
+        }
+        Ok(types)
+    }
+
+    fn type_ref(&mut self) -> JavaResult<TypeRef> {
+        self.annotations()?;
+        let mut name = self.ident()?;
+        self.skip_type_args()?;
+        while self.peek_text(0) == Some(".") && self.peek_kind_at(1) == Some(TokenKind::Ident) {
+            self.pos += 1;
+            self.annotations()?;
+            name.push('.');
+            name.push_str(&self.ident()?);
+            self.skip_type_args()?;
+        }
+        self.annotations()?;
+        let dims = self.dims()?;
+        Ok(TypeRef { name, dims })
+    }
+
+    fn skip_type_args(&mut self) -> JavaResult<()> {
+        if self.peek_text(0) == Some("<") {
+            self.skip_balanced("<", ">")?;
+        }
+        Ok(())
+    }
+
+    fn dims(&mut self) -> JavaResult<usize> {
+        let mut dims = 0;
+        while self.peek_text(0) == Some("[") && self.peek_text(1) == Some("]") {
+            self.pos += 2;
+            dims += 1;
+        }
+        Ok(dims)
+    }
+
+    fn qualified_name(&mut self) -> JavaResult<String> {
+        let mut name = self.ident()?;
+        while self.peek_text(0) == Some(".") && self.peek_kind_at(1) == Some(TokenKind::Ident) {
+            self.pos += 1;
+            name.push('.');
+            name.push_str(&self.ident()?);
+        }
+        Ok(name)
+    }
+
+    /// skips from an `open` token to its matching `close` token
+    fn skip_balanced(&mut self, open: &'static str, close: &str) -> JavaResult<()> {
+        self.expect(open)?;
+        let mut depth = 1;
+        while depth > 0 {
+            let token = self
+                .next()
+                .ok_or(JavaError::UnexpectedEof("a closing bracket"))?;
+            if token.text == open {
+                depth += 1;
+            } else if token.text == close {
+                depth -= 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn ident(&mut self) -> JavaResult<String> {
+        match self.peek() {
+            Some(token) if token.kind == TokenKind::Ident => {
+                self.pos += 1;
+                Ok(token.text.clone())
+            }
+            _ => Err(self.unexpected("an identifier")),
+        }
+    }
+
+    fn expect(&mut self, text: &'static str) -> JavaResult<()> {
+        if self.eat(text) {
+            Ok(())
+        } else {
+            Err(self.unexpected(text))
+        }
+    }
+
+    fn eat(&mut self, text: &str) -> bool {
+        let matches = self.peek_text(0) == Some(text);
+        if matches {
+            self.pos += 1;
+        }
+        matches
+    }
+
+    fn next(&mut self) -> Option<&'a Token> {
+        let token = self.tokens.get(self.pos);
+        self.pos += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_text(&self, offset: usize) -> Option<&'a str> {
+        self.tokens
+            .get(self.pos + offset)
+            .filter(|token| token.kind != TokenKind::Literal)
+            .map(|token| token.text.as_str())
+    }
+
+    fn peek_kind(&self) -> Option<TokenKind> {
+        self.peek_kind_at(0)
+    }
+
+    fn peek_kind_at(&self, offset: usize) -> Option<TokenKind> {
+        self.tokens.get(self.pos + offset).map(|token| token.kind)
+    }
+
+    fn line(&self) -> usize {
+        self.peek().map_or(0, |token| token.line)
+    }
+
+    fn unexpected(&self, expected: &'static str) -> JavaError {
+        match self.peek() {
+            Some(token) => JavaError::UnexpectedToken {
+                line: token.line,
+                expected,
+                found: token.text.clone(),
+            },
+            None => JavaError::UnexpectedEof(expected),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::tokenize;
+
+    fn parse(source: &str) -> CompilationUnit {
+        let tokens = tokenize(source).unwrap();
+        Parser::new(&tokens).compilation_unit().unwrap()
+    }
+
+    #[test]
+    fn fields() {
+        let unit = parse(
+            "class A {
+                Map<String, List<T>> a = new HashMap<String, List<T>>(), b;
+                static final int C = 1 + 2, d[] = {1, 2};
+            }",
+        );
+        let fields = &unit.types[0].fields;
+        let names: Vec<_> = fields.iter().map(|field| field.name.as_str()).collect();
+        assert_eq!(names, vec!["a", "b", "C", "d"]);
+        assert!(fields[0].has_code_initializer);
+        assert!(!fields[2].has_code_initializer);
+        assert_eq!(fields[3].field_type.dims, 1);
+        assert_eq!(fields[3].line, 3);
+    }
+
+    #[test]
+    fn methods() {
+        let unit = parse(
+            "@interface A { String value() default \"\"; }
+            class B<T extends Number> {
+                B(@Deprecated final int... a) { }
+                public <R> R[] map(B<T> this, java.util.function.Function<? super T, R> f)[] throws E { return null; }
+            }",
+        );
+        assert_eq!(unit.types[0].kind, TypeKind::Annotation);
+        assert_eq!(unit.types[0].methods[0].name, "value");
+
+        let class = &unit.types[1];
+        assert_eq!(class.type_params[0].bound.as_ref().unwrap().name, "Number");
+        assert_eq!(class.methods[0].name, "<init>");
+        assert_eq!(class.methods[0].params[0].param_type.dims, 1);
+
+        let map = &class.methods[1];
+        assert_eq!(map.params.len(), 1);
+        assert_eq!(map.params[0].param_type.name, "java.util.function.Function");
+        assert_eq!(map.return_type.as_ref().unwrap().dims, 2);
+    }
+
+    #[test]
+    fn enums_and_records() {
+        let unit = parse(
+            "enum E { A(1) { void f() {} }, @Deprecated B, ; E(int i) {} }
+            record R(int x, String y) { R { } static { } }",
+        );
+        let constants: Vec<_> = unit.types[0]
+            .enum_constants
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(constants, vec!["A", "B"]);
+        assert_eq!(unit.types[0].methods.len(), 1);
+
+        let record = &unit.types[1];
+        assert_eq!(record.record_components.len(), 2);
+        assert_eq!(record.methods[0].params.len(), 2);
+        assert!(record.has_static_init);
+    }
+}
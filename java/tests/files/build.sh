@@ -0,0 +1,9 @@
+#!/bin/sh
+# Compiles the fixtures in src/ into classes.jar, the reference javac output for the source reader tests.
+# Usage: ./build.sh  (needs JDK 17, writes classes.jar next to this script)
+set -e
+cd "$(dirname "$0")"
+out="$(mktemp -d)"
+javac --release 17 -d "$out" $(find src -name '*.java')
+jar --create --file classes.jar --date=2022-01-01T00:00:00Z -C "$out" .
+rm -r "$out"
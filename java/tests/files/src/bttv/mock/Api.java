@@ -0,0 +1,11 @@
+package bttv.mock;
+
+import java.lang.annotation.Retention;
+import java.lang.annotation.RetentionPolicy;
+
+@Retention(RetentionPolicy.RUNTIME)
+public @interface Api {
+    String value() default "";
+
+    int since();
+}
@@ -0,0 +1,5 @@
+package bttv.mock;
+
+public class Base {
+    protected Object tag;
+}
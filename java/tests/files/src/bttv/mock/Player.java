@@ -0,0 +1,87 @@
+package bttv.mock;
+
+import bttv.mock.model.*;
+import bttv.mock.util.Helper;
+import java.io.IOException;
+import java.util.*;
+import java.util.function.Function;
+
+/** A mock with a bit of everything the reader has to handle */
+@Deprecated
+public abstract class Player<T extends CharSequence & Comparable<T>> extends Base
+        implements Runnable, Helper.Listener {
+    public static final int MAX = 10;
+    public static final String NAME = "player" + MAX;
+    private static List<String> cache = new ArrayList<>();
+    protected Map<String, List<T>> byName = new HashMap<String, List<T>>(), other;
+    int counts[], grid[][];
+    private final Quality quality;
+    private Helper helper;
+
+    static {
+        cache.add("x");
+    }
+
+    public Player(Quality quality) {
+        this.quality = quality;
+    }
+
+    protected Player() {
+        this(Quality.AUTO);
+    }
+
+    @Override
+    public void run() {
+        if (quality == null) {
+            return;
+        }
+    }
+
+    public abstract <R> R map(Function<? super T, ? extends R> mapper, T... values)
+            throws IOException;
+
+    public final T first(List<? extends T> list) {
+        return list.get(0);
+    }
+
+    static native long nativeTime(byte[] data, char c, short s, float f, double d, boolean b);
+
+    public int[] matrix()[] {
+        return null;
+    }
+
+    @Override
+    public synchronized void onEvent(final Helper.Event event, @Deprecated String... tags) {}
+
+    public class Inner {
+        private String label;
+
+        public Inner(String label) {
+            this.label = label;
+        }
+    }
+
+    protected static class Nested implements Cloneable {
+        Point origin = Point.origin();
+    }
+
+    interface Callback {
+        int CODE = 1;
+
+        void call(Quality quality);
+
+        default String name() {
+            return "cb";
+        }
+
+        static Callback empty() {
+            return null;
+        }
+    }
+
+    enum State {
+        IDLE,
+        PLAYING,
+        DONE;
+    }
+}
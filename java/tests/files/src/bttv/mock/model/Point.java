@@ -0,0 +1,13 @@
+package bttv.mock.model;
+
+public record Point(int x, int y) {
+    public Point {
+        if (x < 0) {
+            throw new IllegalArgumentException();
+        }
+    }
+
+    public static Point origin() {
+        return new Point(0, 0);
+    }
+}
@@ -0,0 +1,16 @@
+package bttv.mock.model;
+
+public enum Quality {
+    AUTO("auto"),
+    HD("720p");
+
+    private final String label;
+
+    Quality(String label) {
+        this.label = label;
+    }
+
+    public String getLabel() {
+        return label;
+    }
+}
@@ -0,0 +1,17 @@
+package bttv.mock.util;
+
+public final class Helper {
+    private Helper() {}
+
+    public interface Listener {
+        void onEvent(Event event, String... tags);
+    }
+
+    public static class Event {
+        public final long time = 0L;
+    }
+
+    public static String join(String separator, Object[] parts) {
+        return null;
+    }
+}
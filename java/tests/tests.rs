@@ -0,0 +1,106 @@
+use java::*;
+use smali::{Notation, SmaliClass, SmaliType};
+use std::path::PathBuf;
+
+fn files_path() -> PathBuf {
+    std::env::current_dir().unwrap().join("tests/files")
+}
+
+fn source_classes() -> Vec<JavaClass> {
+    parse_source_tree(files_path().join("src"))
+        .unwrap()
+        .into_iter()
+        .flat_map(|file| file.classes.unwrap())
+        .collect()
+}
+
+/// member order differs between javac and the source reader, annotations are not read from source
+fn normalize(mut class: SmaliClass) -> SmaliClass {
+    class.annotations.clear();
+    class.values.sort_by(|a, b| a.name.cmp(&b.name));
+    class
+        .methods
+        .sort_by_key(|method| method.display(Notation::Smali).to_string());
+    class
+}
+
+#[test]
+fn equals_javac_output() {
+    let classes = source_classes();
+    // the whole tree is known, so the only unknown on-demand import is exact
+    assert!(classes.iter().all(|class| class.unresolved.is_empty()));
+    let mut sources: Vec<_> = classes
+        .into_iter()
+        .map(|class| normalize(class.class))
+        .collect();
+    let mut compiled: Vec<_> = jvm::parse_jar(files_path().join("classes.jar"))
+        .unwrap()
+        .into_iter()
+        .map(normalize)
+        .collect();
+    sources.sort_by(|a, b| a.class_path.cmp(&b.class_path));
+    compiled.sort_by(|a, b| a.class_path.cmp(&b.class_path));
+
+    assert_eq!(sources.len(), compiled.len());
+    for (source, compiled) in sources.iter().zip(&compiled) {
+        assert_eq!(source, compiled);
+    }
+}
+
+#[test]
+fn lines() {
+    let classes = source_classes();
+    let player = classes
+        .iter()
+        .find(|class| class.class.class_path == "bttv.mock.Player")
+        .unwrap();
+    assert_eq!(player.line, 10);
+    assert_eq!(player.value_line("grid"), Some(17));
+    assert_eq!(player.value_line("missing"), None);
+
+    let map = player
+        .class
+        .methods
+        .iter()
+        .find(|method| method.name == "map")
+        .unwrap();
+    assert_eq!(player.method_line(map), Some(40));
+    assert_eq!(
+        map.display(Notation::Smali).to_string(),
+        "map(Ljava/util/function/Function;[Ljava/lang/CharSequence;)Ljava/lang/Object;"
+    );
+}
+
+#[test]
+fn single_file() {
+    let classes = parse_source(
+        "package a;
+        import java.util.List;
+        import java.util.*;
+        class A { List<B> list; Missing missing; }",
+    )
+    .unwrap();
+    let types: Vec<_> = classes[0]
+        .class
+        .values
+        .iter()
+        .map(|value| value.data_type.clone())
+        .collect();
+    assert_eq!(
+        types,
+        vec![
+            SmaliType::Class("java.util.List".parse().unwrap()),
+            SmaliType::Class("Missing".parse().unwrap()),
+        ]
+    );
+    // Missing could be in a or in java.util
+    assert_eq!(classes[0].unresolved, ["Missing"]);
+}
+
+#[test]
+fn syntax_error() {
+    assert!(matches!(
+        parse_source("class A { int }"),
+        Err(JavaError::UnexpectedToken { line: 1, .. })
+    ));
+}
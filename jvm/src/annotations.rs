@@ -0,0 +1,75 @@
+use crate::constant_pool::ConstantPool;
+use crate::err::*;
+use common::bytes::Reader;
+use smali::*;
+use std::str::FromStr;
+
+/// Reads the content of a `Runtime(In)VisibleAnnotations` attribute
+pub fn read_annotations(
+    pool: &ConstantPool,
+    reader: &mut Reader,
+    visibility: AnnotationVisibility,
+) -> JvmResult<Vec<SmaliAnnotation>> {
+    let count = reader.u16()?;
+    (0..count)
+        .map(|_| read_annotation(pool, reader, visibility))
+        .collect()
+}
+
+fn read_annotation(
+    pool: &ConstantPool,
+    reader: &mut Reader,
+    visibility: AnnotationVisibility,
+) -> JvmResult<SmaliAnnotation> {
+    let type_path = ClassPath::from_smali(pool.utf8(reader.u16()?)?)?;
+    let count = reader.u16()?;
+    let elements = (0..count)
+        .map(|_| {
+            let name = Symbol::intern(pool.utf8(reader.u16()?)?);
+            let value = read_element_value(pool, reader, visibility)?;
+            Ok(AnnotationElement { name, value })
+        })
+        .collect::<JvmResult<_>>()?;
+    Ok(SmaliAnnotation {
+        visibility,
+        type_path,
+        elements,
+    })
+}
+
+fn read_element_value(
+    pool: &ConstantPool,
+    reader: &mut Reader,
+    visibility: AnnotationVisibility,
+) -> JvmResult<AnnotationValue> {
+    let tag = char::from(reader.u8()?);
+    // byte, char, short and boolean constants are stored as CONSTANT_Integer,
+    // the truncation restores the declared type
+    let value = match tag {
+        'B' => AnnotationValue::Byte(pool.integer(reader.u16()?)? as i8),
+        'C' => AnnotationValue::Char(pool.integer(reader.u16()?)? as u16),
+        'S' => AnnotationValue::Short(pool.integer(reader.u16()?)? as i16),
+        'Z' => AnnotationValue::Boolean(pool.integer(reader.u16()?)? != 0),
+        'I' => AnnotationValue::Int(pool.integer(reader.u16()?)?),
+        'J' => AnnotationValue::Long(pool.long(reader.u16()?)?),
+        'F' => AnnotationValue::Float(pool.float(reader.u16()?)?),
+        'D' => AnnotationValue::Double(pool.double(reader.u16()?)?),
+        's' => AnnotationValue::String(pool.utf8(reader.u16()?)?.to_string()),
+        'e' => {
+            let type_name = pool.utf8(reader.u16()?)?;
+            let const_name = pool.utf8(reader.u16()?)?;
+            AnnotationValue::Enum(format!("{}->{}:{}", type_name, const_name, type_name))
+        }
+        'c' => AnnotationValue::Type(SmaliType::from_str(pool.utf8(reader.u16()?)?)?),
+        '@' => AnnotationValue::Annotation(read_annotation(pool, reader, visibility)?),
+        '[' => {
+            let count = reader.u16()?;
+            let values = (0..count)
+                .map(|_| read_element_value(pool, reader, visibility))
+                .collect::<JvmResult<_>>()?;
+            AnnotationValue::Array(values)
+        }
+        _ => return Err(JvmError::InvalidElementValue(tag)),
+    };
+    Ok(value)
+}
@@ -0,0 +1,411 @@
+use crate::annotations;
+use crate::constant_pool::ConstantPool;
+use crate::err::*;
+use common::bytes::Reader;
+use smali::*;
+use std::str::FromStr;
+
+const MAGIC: u32 = 0xcafe_babe;
+
+pub const ACC_PUBLIC: u16 = 0x1;
+pub const ACC_PRIVATE: u16 = 0x2;
+pub const ACC_PROTECTED: u16 = 0x4;
+pub const ACC_STATIC: u16 = 0x8;
+pub const ACC_FINAL: u16 = 0x10;
+pub const ACC_INTERFACE: u16 = 0x200;
+pub const ACC_ABSTRACT: u16 = 0x400;
+pub const ACC_SYNTHETIC: u16 = 0x1000;
+pub const ACC_ANNOTATION: u16 = 0x2000;
+pub const ACC_ENUM: u16 = 0x4000;
+
+/// The metadata of a JVM class file
+///
+/// Members are kept in class file order, generic signatures are the raw `Signature` attributes
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassFile {
+    pub class_path: ClassPath,
+    pub access_flags: u16,
+    pub super_path: Option<ClassPath>,
+    pub interfaces: Vec<ClassPath>,
+    pub fields: Vec<FieldInfo>,
+    pub methods: Vec<MethodInfo>,
+    pub signature: Option<String>,
+    pub inner_classes: Vec<InnerClass>,
+    /// `RuntimeVisibleAnnotations` as runtime and `RuntimeInvisibleAnnotations` as build annotations
+    pub annotations: Vec<SmaliAnnotation>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldInfo {
+    pub value: SmaliValue,
+    pub access_flags: u16,
+    pub signature: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodInfo {
+    pub method: SmaliMethod,
+    pub access_flags: u16,
+    pub signature: Option<String>,
+    /// the checked exceptions of the `Exceptions` attribute
+    pub exceptions: Vec<ClassPath>,
+}
+
+/// An entry of the `InnerClasses` attribute
+#[derive(Debug, Clone, PartialEq)]
+pub struct InnerClass {
+    pub inner: ClassPath,
+    /// None for local and anonymous classes
+    pub outer: Option<ClassPath>,
+    /// the simple name, None for anonymous classes
+    pub name: Option<String>,
+    pub access_flags: u16,
+}
+
+impl ClassFile {
+    pub fn parse(bytes: &[u8]) -> JvmResult<Self> {
+        let mut reader = Reader::big_endian(bytes, 0);
+        if reader.u32()? != MAGIC {
+            return Err(JvmError::InvalidMagic());
+        }
+        let _version = (reader.u16()?, reader.u16()?);
+        let pool = ConstantPool::read(&mut reader)?;
+
+        let access_flags = reader.u16()?;
+        let class_path = class_path(&pool, reader.u16()?)?;
+        let super_class = reader.u16()?;
+        let super_path = if super_class == 0 {
+            None
+        } else {
+            Some(self::class_path(&pool, super_class)?)
+        };
+        let interfaces_count = reader.u16()?;
+        let interfaces = (0..interfaces_count)
+            .map(|_| self::class_path(&pool, reader.u16()?))
+            .collect::<JvmResult<_>>()?;
+
+        let fields_count = reader.u16()?;
+        let fields = (0..fields_count)
+            .map(|_| read_field(&pool, &mut reader))
+            .collect::<JvmResult<_>>()?;
+        let methods_count = reader.u16()?;
+        let methods = (0..methods_count)
+            .map(|_| read_method(&pool, &mut reader))
+            .collect::<JvmResult<_>>()?;
+
+        let mut class = Self {
+            class_path,
+            access_flags,
+            super_path,
+            interfaces,
+            fields,
+            methods,
+            signature: None,
+            inner_classes: vec![],
+            annotations: vec![],
+        };
+
+        read_attributes(&pool, &mut reader, |name, attribute| {
+            match name {
+                "Signature" => class.signature = Some(pool.utf8(attribute.u16()?)?.to_string()),
+                "InnerClasses" => class.inner_classes = read_inner_classes(&pool, attribute)?,
+                "RuntimeVisibleAnnotations" => class.annotations.extend(
+                    annotations::read_annotations(&pool, attribute, AnnotationVisibility::Runtime)?,
+                ),
+                "RuntimeInvisibleAnnotations" => class.annotations.extend(
+                    annotations::read_annotations(&pool, attribute, AnnotationVisibility::Build)?,
+                ),
+                _ => {}
+            }
+            Ok(())
+        })?;
+
+        Ok(class)
+    }
+
+    /// Maps the class file into the smali model, the way dx would compile it
+    ///
+    /// `Signature` and `InnerClasses` become the `dalvik.annotation` system annotations,
+    /// see `system_annotations`
+    pub fn into_smali_class(self) -> SmaliClass {
+        let system_annotations = self.system_annotations();
+
+        let mut class = SmaliClass::new(
+            self.class_path,
+            access_modifier(self.access_flags),
+            self.access_flags & ACC_ABSTRACT != 0,
+        );
+        class.is_synthetic = self.access_flags & ACC_SYNTHETIC != 0;
+        class.kind = class_kind(self.access_flags);
+        class.super_path = self.super_path;
+        class.interfaces = self.interfaces;
+        class.values = self.fields.into_iter().map(|field| field.value).collect();
+        class.methods = self
+            .methods
+            .into_iter()
+            .map(|method| method.method)
+            .collect();
+        class.annotations = self.annotations;
+        class.annotations.extend(system_annotations);
+        class
+    }
+
+    /// The class level system annotations dx generates for the `Signature` and `InnerClasses`
+    /// attributes: `Signature`, `InnerClass`, `EnclosingClass` and `MemberClasses`
+    pub fn system_annotations(&self) -> Vec<SmaliAnnotation> {
+        let mut annotations = vec![];
+
+        if let Some(signature) = &self.signature {
+            let pieces = split_signature(signature)
+                .into_iter()
+                .map(|piece| AnnotationValue::String(piece.to_string()))
+                .collect();
+            annotations.push(system_annotation(
+                "dalvik.annotation.Signature",
+                "value",
+                AnnotationValue::Array(pieces),
+            ));
+        }
+
+        let own_entry = self
+            .inner_classes
+            .iter()
+            .find(|entry| entry.inner == self.class_path);
+        if let Some(entry) = own_entry {
+            if let Some(outer) = &entry.outer {
+                annotations.push(system_annotation(
+                    "dalvik.annotation.EnclosingClass",
+                    "value",
+                    AnnotationValue::Type(SmaliType::Class(outer.clone())),
+                ));
+            }
+            let mut inner_class = system_annotation(
+                "dalvik.annotation.InnerClass",
+                "accessFlags",
+                AnnotationValue::Int(i32::from(entry.access_flags)),
+            );
+            inner_class.elements.push(AnnotationElement {
+                name: Symbol::intern("name"),
+                value: match &entry.name {
+                    Some(name) => AnnotationValue::String(name.clone()),
+                    None => AnnotationValue::Null,
+                },
+            });
+            annotations.push(inner_class);
+        }
+
+        let members: Vec<_> = self
+            .inner_classes
+            .iter()
+            .filter(|entry| entry.outer.as_ref() == Some(&self.class_path))
+            .map(|entry| AnnotationValue::Type(SmaliType::Class(entry.inner.clone())))
+            .collect();
+        if !members.is_empty() {
+            annotations.push(system_annotation(
+                "dalvik.annotation.MemberClasses",
+                "value",
+                AnnotationValue::Array(members),
+            ));
+        }
+
+        annotations
+    }
+}
+
+pub fn class_path(pool: &ConstantPool, index: u16) -> JvmResult<ClassPath> {
+    Ok(ClassPath::from_java(
+        &pool.class_name(index)?.replace('/', "."),
+    )?)
+}
+
+/// Calls `on_attribute` with the name and a reader over the content of each attribute
+pub fn read_attributes(
+    pool: &ConstantPool,
+    reader: &mut Reader,
+    mut on_attribute: impl FnMut(&str, &mut Reader) -> JvmResult<()>,
+) -> JvmResult<()> {
+    let count = reader.u16()?;
+    for _ in 0..count {
+        let name = pool.utf8(reader.u16()?)?;
+        let len = reader.u32()?;
+        // attribute lengths are u32, usize is at least as wide on every supported target
+        let content = reader.bytes(len as usize)?;
+        on_attribute(name, &mut Reader::big_endian(content, 0))?;
+    }
+    Ok(())
+}
+
+fn read_field(pool: &ConstantPool, reader: &mut Reader) -> JvmResult<FieldInfo> {
+    let access_flags = reader.u16()?;
+    let name = pool.utf8(reader.u16()?)?;
+    let descriptor = pool.utf8(reader.u16()?)?;
+
+    let mut signature = None;
+    read_attributes(pool, reader, |name, attribute| {
+        if name == "Signature" {
+            signature = Some(pool.utf8(attribute.u16()?)?.to_string());
+        }
+        Ok(())
+    })?;
+
+    Ok(FieldInfo {
+        value: SmaliValue {
+            name: Symbol::intern(name),
+            data_type: SmaliType::from_str(descriptor)?,
+            access: access_modifier(access_flags),
+            is_static: access_flags & ACC_STATIC != 0,
+            is_final: access_flags & ACC_FINAL != 0,
+            is_synthetic: access_flags & ACC_SYNTHETIC != 0,
+            is_enum: access_flags & ACC_ENUM != 0,
+        },
+        access_flags,
+        signature,
+    })
+}
+
+fn read_method(pool: &ConstantPool, reader: &mut Reader) -> JvmResult<MethodInfo> {
+    let access_flags = reader.u16()?;
+    let name = pool.utf8(reader.u16()?)?;
+    let (parameter_types, return_type) = parse_method_descriptor(pool.utf8(reader.u16()?)?)?;
+
+    let mut signature = None;
+    let mut exceptions = vec![];
+    read_attributes(pool, reader, |name, attribute| {
+        match name {
+            "Signature" => signature = Some(pool.utf8(attribute.u16()?)?.to_string()),
+            "Exceptions" => {
+                let count = attribute.u16()?;
+                exceptions = (0..count)
+                    .map(|_| class_path(pool, attribute.u16()?))
+                    .collect::<JvmResult<_>>()?;
+            }
+            _ => {}
+        }
+        Ok(())
+    })?;
+
+    Ok(MethodInfo {
+        method: SmaliMethod {
+            name: Symbol::intern(name),
+            access: access_modifier(access_flags),
+            parameter_types,
+            return_type,
+            is_static: access_flags & ACC_STATIC != 0,
+            is_final: access_flags & ACC_FINAL != 0,
+            is_synthetic: access_flags & ACC_SYNTHETIC != 0,
+        },
+        access_flags,
+        signature,
+        exceptions,
+    })
+}
+
+fn read_inner_classes(pool: &ConstantPool, reader: &mut Reader) -> JvmResult<Vec<InnerClass>> {
+    let count = reader.u16()?;
+    (0..count)
+        .map(|_| {
+            let inner = class_path(pool, reader.u16()?)?;
+            let outer = match reader.u16()? {
+                0 => None,
+                index => Some(class_path(pool, index)?),
+            };
+            let name = match reader.u16()? {
+                0 => None,
+                index => Some(pool.utf8(index)?.to_string()),
+            };
+            Ok(InnerClass {
+                inner,
+                outer,
+                name,
+                access_flags: reader.u16()?,
+            })
+        })
+        .collect()
+}
+
+fn class_kind(access_flags: u16) -> ClassKind {
+    if access_flags & ACC_ANNOTATION != 0 {
+        ClassKind::Annotation
+    } else if access_flags & ACC_INTERFACE != 0 {
+        ClassKind::Interface
+    } else if access_flags & ACC_ENUM != 0 {
+        ClassKind::Enum
+    } else {
+        ClassKind::Class
+    }
+}
+
+fn access_modifier(access_flags: u16) -> SmaliAccessModifier {
+    if access_flags & ACC_PUBLIC != 0 {
+        SmaliAccessModifier::Public
+    } else if access_flags & ACC_PRIVATE != 0 {
+        SmaliAccessModifier::Private
+    } else if access_flags & ACC_PROTECTED != 0 {
+        SmaliAccessModifier::Protected
+    } else {
+        SmaliAccessModifier::Package
+    }
+}
+
+fn system_annotation(type_path: &str, name: &str, value: AnnotationValue) -> SmaliAnnotation {
+    SmaliAnnotation {
+        visibility: AnnotationVisibility::System,
+        type_path: ClassPath::from_java(type_path).expect("valid system annotation path"),
+        elements: vec![AnnotationElement {
+            name: Symbol::intern(name),
+            value,
+        }],
+    }
+}
+
+/// Splits a generic signature into the pieces dx stores in `dalvik.annotation.Signature`,
+/// class names up to and including their `;` or `<` and everything in between
+fn split_signature(signature: &str) -> Vec<&str> {
+    let bytes = signature.as_bytes();
+    let mut pieces = vec![];
+    let mut start = 0;
+
+    while start < bytes.len() {
+        let mut end = start + 1;
+        if bytes[start] == b'L' {
+            while end < bytes.len() {
+                match bytes[end] {
+                    b';' | b'<' => {
+                        end += 1;
+                        break;
+                    }
+                    _ => end += 1,
+                }
+            }
+        } else {
+            while end < bytes.len() && bytes[end] != b'L' {
+                end += 1;
+            }
+        }
+        // all split points are ASCII, so they are char boundaries
+        pieces.push(&signature[start..end]);
+        start = end;
+    }
+
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_pieces() {
+        assert_eq!(
+            split_signature("<T:Ljava/lang/Number;>Ljava/lang/Object;Ljava/util/List<TT;>;"),
+            vec![
+                "<T:",
+                "Ljava/lang/Number;",
+                ">",
+                "Ljava/lang/Object;",
+                "Ljava/util/List<",
+                "TT;>;"
+            ]
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use crate::err::*;
+use common::bytes::Reader;
+use common::mutf8;
+
+const CONSTANT_UTF8: u8 = 1;
+const CONSTANT_INTEGER: u8 = 3;
+const CONSTANT_FLOAT: u8 = 4;
+const CONSTANT_LONG: u8 = 5;
+const CONSTANT_DOUBLE: u8 = 6;
+const CONSTANT_CLASS: u8 = 7;
+const CONSTANT_STRING: u8 = 8;
+const CONSTANT_FIELDREF: u8 = 9;
+const CONSTANT_METHODREF: u8 = 10;
+const CONSTANT_INTERFACE_METHODREF: u8 = 11;
+const CONSTANT_NAME_AND_TYPE: u8 = 12;
+const CONSTANT_METHOD_HANDLE: u8 = 15;
+const CONSTANT_METHOD_TYPE: u8 = 16;
+const CONSTANT_DYNAMIC: u8 = 17;
+const CONSTANT_INVOKE_DYNAMIC: u8 = 18;
+const CONSTANT_MODULE: u8 = 19;
+const CONSTANT_PACKAGE: u8 = 20;
+
+/// The constants the class reader needs, references between entries are not resolved
+enum Constant {
+    Utf8(String),
+    Integer(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    Class(u16),
+    // entries that are not needed to read class metadata and the unusable slot after longs and doubles
+    Other,
+}
+
+pub struct ConstantPool(Vec<Constant>);
+
+impl ConstantPool {
+    pub fn read(reader: &mut Reader) -> JvmResult<Self> {
+        let count = reader.u16()?;
+        // index 0 is never valid
+        let mut constants = vec![Constant::Other];
+
+        while constants.len() < usize::from(count) {
+            let tag = reader.u8()?;
+            let constant = match tag {
+                CONSTANT_UTF8 => {
+                    let len = reader.u16()?;
+                    let start = reader.pos();
+                    let bytes = reader.bytes(usize::from(len))?;
+                    Constant::Utf8(mutf8::decode(bytes).ok_or(JvmError::InvalidString(start))?)
+                }
+                CONSTANT_INTEGER => {
+                    Constant::Integer(i32::from_be_bytes(reader.u32()?.to_be_bytes()))
+                }
+                CONSTANT_FLOAT => Constant::Float(f32::from_bits(reader.u32()?)),
+                CONSTANT_LONG => Constant::Long(i64::from_be_bytes(reader.u64()?.to_be_bytes())),
+                CONSTANT_DOUBLE => Constant::Double(f64::from_bits(reader.u64()?)),
+                CONSTANT_CLASS => Constant::Class(reader.u16()?),
+                CONSTANT_STRING | CONSTANT_METHOD_TYPE | CONSTANT_MODULE | CONSTANT_PACKAGE => {
+                    reader.skip(2)?;
+                    Constant::Other
+                }
+                CONSTANT_METHOD_HANDLE => {
+                    reader.skip(3)?;
+                    Constant::Other
+                }
+                CONSTANT_FIELDREF
+                | CONSTANT_METHODREF
+                | CONSTANT_INTERFACE_METHODREF
+                | CONSTANT_NAME_AND_TYPE
+                | CONSTANT_DYNAMIC
+                | CONSTANT_INVOKE_DYNAMIC => {
+                    reader.skip(4)?;
+                    Constant::Other
+                }
+                _ => return Err(JvmError::UnknownConstantTag(tag)),
+            };
+            let is_wide = matches!(constant, Constant::Long(_) | Constant::Double(_));
+            constants.push(constant);
+            if is_wide {
+                constants.push(Constant::Other);
+            }
+        }
+
+        Ok(Self(constants))
+    }
+
+    fn get(&self, index: u16) -> JvmResult<&Constant> {
+        self.0
+            .get(usize::from(index))
+            .ok_or(JvmError::InvalidConstant(index))
+    }
+
+    pub fn utf8(&self, index: u16) -> JvmResult<&str> {
+        match self.get(index)? {
+            Constant::Utf8(string) => Ok(string),
+            _ => Err(JvmError::InvalidConstant(index)),
+        }
+    }
+
+    /// The internal name of a class constant, `java/lang/String` or `[I` for arrays
+    pub fn class_name(&self, index: u16) -> JvmResult<&str> {
+        match self.get(index)? {
+            Constant::Class(name) => self.utf8(*name),
+            _ => Err(JvmError::InvalidConstant(index)),
+        }
+    }
+
+    pub fn integer(&self, index: u16) -> JvmResult<i32> {
+        match self.get(index)? {
+            Constant::Integer(value) => Ok(*value),
+            _ => Err(JvmError::InvalidConstant(index)),
+        }
+    }
+
+    pub fn float(&self, index: u16) -> JvmResult<f32> {
+        match self.get(index)? {
+            Constant::Float(value) => Ok(*value),
+            _ => Err(JvmError::InvalidConstant(index)),
+        }
+    }
+
+    pub fn long(&self, index: u16) -> JvmResult<i64> {
+        match self.get(index)? {
+            Constant::Long(value) => Ok(*value),
+            _ => Err(JvmError::InvalidConstant(index)),
+        }
+    }
+
+    pub fn double(&self, index: u16) -> JvmResult<f64> {
+        match self.get(index)? {
+            Constant::Double(value) => Ok(*value),
+            _ => Err(JvmError::InvalidConstant(index)),
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use common::thiserror;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum JvmError {
+    #[error("IOError")]
+    IOError(#[from] std::io::Error),
+    #[error(transparent)]
+    ZipError(#[from] zip::result::ZipError),
+    #[error("not a class file")]
+    InvalidMagic(),
+    #[error("unexpected end of file at offset {0:#x}")]
+    UnexpectedEof(usize),
+    #[error("invalid modified UTF-8 string at offset {0:#x}")]
+    InvalidString(usize),
+    #[error("unknown constant pool tag {0}")]
+    UnknownConstantTag(u8),
+    #[error("constant pool entry {0} is missing or has an unexpected type")]
+    InvalidConstant(u16),
+    #[error("invalid annotation element tag {0:?}")]
+    InvalidElementValue(char),
+    #[error("{path}: {source}")]
+    InvalidEntry {
+        path: String,
+        #[source]
+        source: Box<JvmError>,
+    },
+    #[error(transparent)]
+    InvalidDescriptor(#[from] smali::ParserError),
+}
+
+impl From<common::bytes::UnexpectedEof> for JvmError {
+    fn from(err: common::bytes::UnexpectedEof) -> Self {
+        Self::UnexpectedEof(err.0)
+    }
+}
+
+pub type JvmResult<T> = Result<T, JvmError>;
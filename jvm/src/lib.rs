@@ -0,0 +1,68 @@
+//! jvm is a crate that reads the metadata of JVM class files and jars into the smali model,
+//! without compiling them to dex first
+
+extern crate common;
+
+mod annotations;
+mod class_file;
+mod constant_pool;
+mod err;
+
+pub use class_file::{
+    ClassFile, FieldInfo, InnerClass, MethodInfo, ACC_ABSTRACT, ACC_FINAL, ACC_PRIVATE,
+    ACC_PROTECTED, ACC_PUBLIC, ACC_STATIC, ACC_SYNTHETIC,
+};
+pub use err::*;
+
+use smali::SmaliClass;
+use std::io::{Read, Seek};
+use zip::ZipArchive;
+
+/// Parses the content of a class file
+pub fn parse_class(bytes: &[u8]) -> JvmResult<ClassFile> {
+    ClassFile::parse(bytes)
+}
+
+/// Reads and parses the class file at file_path
+pub fn parse_class_file(file_path: impl AsRef<std::path::Path>) -> JvmResult<ClassFile> {
+    let bytes = std::fs::read(file_path.as_ref())?;
+    parse_class(&bytes)
+}
+
+/// Reads all classes of the jar at file_path into the smali model, see `read_jar`
+pub fn parse_jar(file_path: impl AsRef<std::path::Path>) -> JvmResult<Vec<SmaliClass>> {
+    let file = std::fs::File::open(file_path.as_ref())?;
+    Ok(read_jar(file)?
+        .into_iter()
+        .map(ClassFile::into_smali_class)
+        .collect())
+}
+
+/// Parses every `.class` entry of a jar in archive order, `module-info.class` is skipped.
+/// Errors of an entry are wrapped in `JvmError::InvalidEntry` with the entry's path
+pub fn read_jar(jar: impl Read + Seek) -> JvmResult<Vec<ClassFile>> {
+    let mut archive = ZipArchive::new(jar)?;
+    let mut classes = vec![];
+    let mut bytes = vec![];
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        let path = entry.name().to_string();
+        if !path.ends_with(".class") || path.rsplit('/').next() == Some("module-info.class") {
+            continue;
+        }
+
+        bytes.clear();
+        let class = entry
+            .read_to_end(&mut bytes)
+            .map_err(JvmError::from)
+            .and_then(|_| parse_class(&bytes))
+            .map_err(|source| JvmError::InvalidEntry {
+                path,
+                source: Box::new(source),
+            })?;
+        classes.push(class);
+    }
+
+    Ok(classes)
+}
@@ -0,0 +1,9 @@
+#!/bin/sh
+# Compiles the fixtures in src/ into classes.jar, the fixture for the class file reader tests.
+# Usage: ./build.sh  (needs a JDK, writes classes.jar next to this script)
+set -e
+cd "$(dirname "$0")"
+out="$(mktemp -d)"
+javac --release 8 -d "$out" src/bttv/*.java
+jar --create --file classes.jar --date=2022-01-01T00:00:00Z -C "$out" .
+rm -r "$out"
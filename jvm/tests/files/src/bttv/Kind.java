@@ -0,0 +1,6 @@
+package bttv;
+
+public enum Kind {
+    A,
+    B
+}
@@ -0,0 +1,5 @@
+package bttv;
+
+public @interface Mock {
+    String value();
+}
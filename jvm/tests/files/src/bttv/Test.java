@@ -0,0 +1,37 @@
+package bttv;
+
+import java.io.IOException;
+import java.util.List;
+import java.util.function.Supplier;
+
+@Mock("test")
+@Visible(names = {"a", "b"}, kind = Kind.B, type = int[].class, nested = @Mock("n"), level = -3, ratio = 0.5)
+public abstract class Test<T extends Number> implements Supplier<List<T>>, Runnable {
+    public static final int COUNT = 1;
+    protected static String[][] names;
+    private long value;
+    volatile char c;
+    T generic;
+
+    public Test(long value) {
+        this.value = value;
+    }
+
+    public abstract void run();
+
+    public List<T> get() {
+        return null;
+    }
+
+    static native byte nativeMethod(short s, double[] d);
+
+    protected final void io() throws IOException, InterruptedException {}
+
+    public static class Nested {}
+
+    private class Inner {}
+
+    public interface Iface {
+        void call(int x);
+    }
+}
@@ -0,0 +1,19 @@
+package bttv;
+
+import java.lang.annotation.Retention;
+import java.lang.annotation.RetentionPolicy;
+
+@Retention(RetentionPolicy.RUNTIME)
+public @interface Visible {
+    String[] names();
+
+    Kind kind();
+
+    Class<?> type();
+
+    Mock nested();
+
+    int level();
+
+    double ratio();
+}
@@ -0,0 +1,201 @@
+use jvm::*;
+use smali::{
+    parse_file, AnnotationValue, AnnotationVisibility, ClassPath, Notation, SmaliAccessModifier,
+    SmaliType,
+};
+use std::path::PathBuf;
+
+fn files_path() -> PathBuf {
+    std::env::current_dir().unwrap().join("tests/files")
+}
+
+fn read_fixture_jar() -> Vec<ClassFile> {
+    let jar = std::fs::File::open(files_path().join("classes.jar")).unwrap();
+    read_jar(jar).unwrap()
+}
+
+fn test_class() -> ClassFile {
+    read_fixture_jar()
+        .into_iter()
+        .find(|class| class.class_path == "bttv.Test")
+        .unwrap()
+}
+
+#[test]
+fn equals_smali() {
+    let mut class = test_class().into_smali_class();
+    class.annotations.clear();
+    assert_eq!(class, parse_file(files_path().join("Test.smali")).unwrap());
+}
+
+#[test]
+fn jar() {
+    let classes = parse_jar(files_path().join("classes.jar")).unwrap();
+    let class_paths: Vec<_> = classes
+        .iter()
+        .map(|class| class.class_path.as_java())
+        .collect();
+    assert_eq!(
+        class_paths,
+        vec![
+            "bttv.Kind",
+            "bttv.Mock",
+            "bttv.Test$Iface",
+            "bttv.Test$Inner",
+            "bttv.Test$Nested",
+            "bttv.Test",
+            "bttv.Visible"
+        ]
+    );
+
+    let iface = &classes[2];
+    assert!(iface.is_abstract);
+    assert_eq!(iface.access, SmaliAccessModifier::Public);
+    assert_eq!(
+        iface.methods[0].display(Notation::Smali).to_string(),
+        "call(I)V"
+    );
+}
+
+#[test]
+fn attributes() {
+    let class = test_class();
+    assert_eq!(
+        class.signature.as_deref(),
+        Some("<T:Ljava/lang/Number;>Ljava/lang/Object;Ljava/util/function/Supplier<Ljava/util/List<TT;>;>;Ljava/lang/Runnable;")
+    );
+    assert_eq!(class.fields[4].signature.as_deref(), Some("TT;"));
+
+    let io = class
+        .methods
+        .iter()
+        .find(|method| method.method.name == "io")
+        .unwrap();
+    assert_eq!(
+        io.exceptions,
+        vec![
+            ClassPath::from_java("java.io.IOException").unwrap(),
+            ClassPath::from_java("java.lang.InterruptedException").unwrap()
+        ]
+    );
+    assert_eq!(io.access_flags, ACC_PROTECTED | ACC_FINAL);
+
+    assert_eq!(class.inner_classes.len(), 3);
+    assert_eq!(
+        class.inner_classes[1],
+        InnerClass {
+            inner: ClassPath::from_java("bttv.Test$Inner").unwrap(),
+            outer: Some(ClassPath::from_java("bttv.Test").unwrap()),
+            name: Some("Inner".to_string()),
+            access_flags: ACC_PRIVATE,
+        }
+    );
+}
+
+#[test]
+fn annotations() {
+    let class = test_class().into_smali_class();
+    let find = |java_path: &str| {
+        class
+            .annotations
+            .iter()
+            .find(|annotation| annotation.type_path == java_path)
+            .unwrap()
+    };
+
+    let mock = find("bttv.Mock");
+    assert_eq!(mock.visibility, AnnotationVisibility::Build);
+    assert_eq!(
+        mock.element("value"),
+        Some(&AnnotationValue::String("test".to_string()))
+    );
+
+    let visible = find("bttv.Visible");
+    assert_eq!(visible.visibility, AnnotationVisibility::Runtime);
+    assert_eq!(
+        visible.element("names"),
+        Some(&AnnotationValue::Array(vec![
+            AnnotationValue::String("a".to_string()),
+            AnnotationValue::String("b".to_string())
+        ]))
+    );
+    assert_eq!(
+        visible.element("kind"),
+        Some(&AnnotationValue::Enum(
+            "Lbttv/Kind;->B:Lbttv/Kind;".to_string()
+        ))
+    );
+    assert_eq!(
+        visible.element("type"),
+        Some(&AnnotationValue::Type(SmaliType::Arr(Box::new(
+            SmaliType::Int
+        ))))
+    );
+    assert_eq!(visible.element("level"), Some(&AnnotationValue::Int(-3)));
+    assert_eq!(
+        visible.element("ratio"),
+        Some(&AnnotationValue::Double(0.5))
+    );
+    match visible.element("nested") {
+        Some(AnnotationValue::Annotation(nested)) => {
+            assert_eq!(nested.type_path, "bttv.Mock");
+            assert_eq!(
+                nested.element("value"),
+                Some(&AnnotationValue::String("n".to_string()))
+            );
+        }
+        other => panic!("unexpected nested element {:?}", other),
+    }
+
+    let signature = find("dalvik.annotation.Signature");
+    assert_eq!(signature.visibility, AnnotationVisibility::System);
+    match signature.element("value") {
+        Some(AnnotationValue::Array(pieces)) => assert_eq!(pieces.len(), 8),
+        other => panic!("unexpected signature {:?}", other),
+    }
+
+    let members = find("dalvik.annotation.MemberClasses");
+    match members.element("value") {
+        Some(AnnotationValue::Array(members)) => assert_eq!(members.len(), 3),
+        other => panic!("unexpected member classes {:?}", other),
+    }
+}
+
+#[test]
+fn inner_class_annotations() {
+    let inner = read_fixture_jar()
+        .into_iter()
+        .find(|class| class.class_path == "bttv.Test$Inner")
+        .unwrap()
+        .into_smali_class();
+
+    let inner_class = inner
+        .annotations
+        .iter()
+        .find(|annotation| annotation.type_path == "dalvik.annotation.InnerClass")
+        .unwrap();
+    assert_eq!(
+        inner_class.element("accessFlags"),
+        Some(&AnnotationValue::Int(i32::from(ACC_PRIVATE)))
+    );
+    assert_eq!(
+        inner_class.element("name"),
+        Some(&AnnotationValue::String("Inner".to_string()))
+    );
+    assert!(inner
+        .annotations
+        .iter()
+        .any(|annotation| annotation.type_path == "dalvik.annotation.EnclosingClass"));
+}
+
+#[test]
+fn invalid_class() {
+    assert!(matches!(
+        parse_class(b"not a class file"),
+        Err(JvmError::InvalidMagic())
+    ));
+    assert!(matches!(
+        parse_class(&[0xca, 0xfe, 0xba, 0xbe, 0, 0]),
+        Err(JvmError::UnexpectedEof(_))
+    ));
+}
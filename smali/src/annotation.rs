@@ -0,0 +1,74 @@
+use crate::class_path::ClassPath;
+use crate::smali_class::SmaliType;
+use crate::symbol::Symbol;
+
+/// When an annotation is visible, `.annotation <visibility> ...` in smali
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AnnotationVisibility {
+    Build,
+    Runtime,
+    System,
+}
+
+impl std::str::FromStr for AnnotationVisibility {
+    type Err = ();
+    fn from_str(token: &str) -> Result<Self, Self::Err> {
+        match token {
+            "build" => Ok(Self::Build),
+            "runtime" => Ok(Self::Runtime),
+            "system" => Ok(Self::System),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SmaliAnnotation {
+    pub visibility: AnnotationVisibility,
+    pub type_path: ClassPath,
+    pub elements: Vec<AnnotationElement>,
+}
+
+impl SmaliAnnotation {
+    /// Returns the value of the element called name
+    pub fn element(&self, name: &str) -> Option<&AnnotationValue> {
+        self.elements
+            .iter()
+            .find(|element| element.name == name)
+            .map(|element| &element.value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AnnotationElement {
+    pub name: Symbol,
+    pub value: AnnotationValue,
+}
+
+/// A constant value of an annotation element,
+/// references to members are kept in smali notation (`Lbttv/Util;->run()V`)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AnnotationValue {
+    Byte(i8),
+    Short(i16),
+    Char(u16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    Boolean(bool),
+    Null,
+    String(String),
+    Type(SmaliType),
+    Field(String),
+    Method(String),
+    MethodType(String),
+    MethodHandle(String),
+    Enum(String),
+    Array(Vec<AnnotationValue>),
+    Annotation(SmaliAnnotation),
+}
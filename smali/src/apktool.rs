@@ -0,0 +1,241 @@
+//! The metadata apktool writes to `apktool.yml` next to the smali roots
+//!
+//! Only the handful of keys needed to identify an app build are read, with a line based reader
+//! for the plain YAML apktool writes:
+//!
+//! ```text
+//! !!brut.androlib.meta.MetaInfo
+//! apkFileName: twitch.apk
+//! sdkInfo:
+//!   minSdkVersion: '21'
+//!   targetSdkVersion: '33'
+//! version: 2.7.0
+//! versionInfo:
+//!   versionCode: '1502000'
+//!   versionName: 15.2.0
+//! ```
+
+use crate::err::*;
+use std::fmt;
+use std::path::Path;
+
+/// The name of the file apktool writes its metadata to
+pub const APKTOOL_YML: &str = "apktool.yml";
+
+/// The app build an apktool directory was disassembled from
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ApktoolInfo {
+    pub apk_file_name: Option<String>,
+    /// the version of apktool that disassembled the app
+    pub apktool_version: Option<String>,
+    /// None if apktool.yml has no valid version code
+    pub version_code: Option<u64>,
+    pub version_name: Option<String>,
+    /// the SDK versions as written by apktool, an API level (`33`) or the codename of a
+    /// preview SDK (`Tiramisu`)
+    pub min_sdk_version: Option<String>,
+    pub target_sdk_version: Option<String>,
+    pub max_sdk_version: Option<String>,
+    /// the dex files of the app in load order (`classes.dex`, `classes2.dex`, ...),
+    /// taken from the smali roots since apktool.yml does not list them
+    pub dex_files: Vec<String>,
+}
+
+impl ApktoolInfo {
+    /// Reads the keys of `ApktoolInfo` from the contents of an apktool.yml, values that can not
+    /// be read are logged and left out
+    pub fn parse(yaml: &str) -> Self {
+        let mut info = Self::default();
+        // the keys of the maps the current line is nested in with their indentation
+        let mut parents: Vec<(usize, &str)> = vec![];
+
+        for (i, line) in yaml.lines().enumerate() {
+            let content = line.trim_start();
+            if content.is_empty() || content.starts_with('#') || content.starts_with("!!") {
+                continue;
+            }
+            let indent = line.len() - content.len();
+            while parents.last().is_some_and(|(parent, _)| *parent >= indent) {
+                parents.pop();
+            }
+            if content.starts_with('-') {
+                // list items, like the files in doNotCompress
+                continue;
+            }
+            let Some((key, value)) = content.split_once(':') else {
+                continue;
+            };
+            let value = unquote(value.trim());
+            if value.is_empty() {
+                parents.push((indent, key));
+                continue;
+            }
+
+            let parent = parents.last().map(|(_, parent)| *parent);
+            match (parent, key) {
+                (None, "apkFileName") => info.apk_file_name = not_null(value),
+                (None, "version") => info.apktool_version = not_null(value),
+                (Some("versionInfo"), "versionCode") => {
+                    info.version_code = not_null(value).and_then(|code| match code.parse() {
+                        Ok(code) => Some(code),
+                        Err(_) => {
+                            common::warn!("invalid versionCode in line {} of apktool.yml", i + 1);
+                            None
+                        }
+                    })
+                }
+                (Some("versionInfo"), "versionName") => info.version_name = not_null(value),
+                (Some("sdkInfo"), "minSdkVersion") => info.min_sdk_version = not_null(value),
+                (Some("sdkInfo"), "targetSdkVersion") => info.target_sdk_version = not_null(value),
+                (Some("sdkInfo"), "maxSdkVersion") => info.max_sdk_version = not_null(value),
+                _ => {}
+            }
+        }
+        info
+    }
+
+    /// Reads the apktool.yml of an apktool directory, None if there is none
+    pub fn from_dir(apktool_dir: impl AsRef<Path>) -> ParserResult<Option<Self>> {
+        let apktool_dir = apktool_dir.as_ref();
+        let yaml = match std::fs::read_to_string(apktool_dir.join(APKTOOL_YML)) {
+            Ok(yaml) => yaml,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let mut info = Self::parse(&yaml);
+        info.set_dex_files(&crate::smali_roots(apktool_dir)?);
+        Ok(Some(info))
+    }
+
+    /// Derives `dex_files` from the smali roots of the apktool directory
+    pub(crate) fn set_dex_files(&mut self, roots: &[impl AsRef<Path>]) {
+        self.dex_files = roots
+            .iter()
+            .filter_map(|root| root.as_ref().file_name()?.to_str())
+            .filter_map(crate::tree::dex_number)
+            .map(|number| match number {
+                1 => "classes.dex".to_string(),
+                number => format!("classes{}.dex", number),
+            })
+            .collect();
+    }
+}
+
+/// The app build in one line, like `twitch.apk 15.2.0 (1502000), minSdk 21, targetSdk 33, 3 dex files`
+impl fmt::Display for ApktoolInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = vec![];
+        if let Some(name) = &self.apk_file_name {
+            parts.push(name.clone());
+        }
+        parts.push(
+            self.version_name
+                .clone()
+                .unwrap_or_else(|| "unknown version".to_string()),
+        );
+        if let Some(code) = self.version_code {
+            parts.push(format!("({})", code));
+        }
+        let mut line = parts.join(" ");
+
+        let details = [
+            ("minSdk", self.min_sdk_version.as_deref()),
+            ("targetSdk", self.target_sdk_version.as_deref()),
+            ("maxSdk", self.max_sdk_version.as_deref()),
+        ];
+        for (name, level) in details {
+            if let Some(level) = level {
+                line.push_str(&format!(", {} {}", name, level));
+            }
+        }
+        match self.dex_files.len() {
+            0 => {}
+            1 => line.push_str(", 1 dex file"),
+            count => line.push_str(&format!(", {} dex files", count)),
+        }
+        f.write_str(&line)
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    let quoted = |quote| value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote);
+    if quoted('\'') || quoted('"') {
+        &value[1..value.len() - 1]
+    } else {
+        value
+    }
+}
+
+fn not_null(value: &str) -> Option<String> {
+    (value != "null" && value != "~").then(|| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const APKTOOL_YML: &str = "!!brut.androlib.meta.MetaInfo
+apkFileName: twitch.apk
+compressionType: false
+doNotCompress:
+- resources.arsc
+- png
+isFrameworkApk: false
+packageInfo:
+  forcedPackageId: '127'
+  renameManifestPackage: null
+sdkInfo:
+  minSdkVersion: '21'
+  targetSdkVersion: '33'
+sharedLibrary: false
+usesFramework:
+  ids:
+  - 1
+  tag: null
+version: 2.7.0
+versionInfo:
+  versionCode: '1502000'
+  versionName: 15.2.0
+";
+
+    #[test]
+    fn parse() {
+        let mut info = ApktoolInfo::parse(APKTOOL_YML);
+        assert_eq!(
+            info,
+            ApktoolInfo {
+                apk_file_name: Some("twitch.apk".to_string()),
+                apktool_version: Some("2.7.0".to_string()),
+                version_code: Some(1502000),
+                version_name: Some("15.2.0".to_string()),
+                min_sdk_version: Some("21".to_string()),
+                target_sdk_version: Some("33".to_string()),
+                max_sdk_version: None,
+                dex_files: vec![],
+            }
+        );
+        assert_eq!(
+            info.to_string(),
+            "twitch.apk 15.2.0 (1502000), minSdk 21, targetSdk 33"
+        );
+
+        info.set_dex_files(&["smali", "smali_classes2", "original"]);
+        assert_eq!(info.dex_files, ["classes.dex", "classes2.dex"]);
+        assert!(info.to_string().ends_with(", 2 dex files"));
+    }
+
+    #[test]
+    fn invalid() {
+        let yaml = "sdkInfo:\n  targetSdkVersion: 'Tiramisu'\nversionInfo:\n  versionCode: abc\n";
+        let info = ApktoolInfo::parse(yaml);
+        assert_eq!(info.target_sdk_version.as_deref(), Some("Tiramisu"));
+        assert_eq!(info.version_code, None);
+        assert_eq!(info.to_string(), "unknown version, targetSdk Tiramisu");
+
+        // keys outside of their maps are ignored
+        let info = ApktoolInfo::parse("versionCode: abc\nversionName: null");
+        assert_eq!(info, ApktoolInfo::default());
+        assert_eq!(info.to_string(), "unknown version");
+    }
+}
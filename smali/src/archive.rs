@@ -0,0 +1,204 @@
+//! apktool output directories packed into a single archive, enabled by the `archive` cargo feature
+//!
+//! `.zip`, `.tar`, `.tar.gz` and `.tgz` archives are supported. The smali files are read one
+//! entry at a time straight into memory, nothing is extracted to disk. Whether the archive
+//! contains the smali roots at its top level or inside a directory does not matter.
+
+use crate::err::*;
+use flate2::read::GzDecoder;
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use zip::ZipArchive;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveKind {
+    fn of(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else {
+            None
+        }
+    }
+}
+
+/// Whether path is an archive file that `ClassIndex::open` reads instead of a directory
+pub fn is_archive(path: impl AsRef<Path>) -> bool {
+    let path = path.as_ref();
+    ArchiveKind::of(path).is_some() && path.is_file()
+}
+
+/// Calls visit with the path inside the archive (`apk/smali/tv/twitch/Foo.smali`) and the
+/// contents of every `.smali` file and `apktool.yml` in archive order
+pub(crate) fn read_apktool_files(
+    archive: &Path,
+    mut visit: impl FnMut(String, Vec<u8>),
+) -> ParserResult<()> {
+    let kind = ArchiveKind::of(archive)
+        .ok_or_else(|| ParserError::MissingSmaliRoot(archive.display().to_string()))?;
+    let file = BufReader::new(File::open(archive)?);
+    match kind {
+        ArchiveKind::Zip => {
+            let mut zip = ZipArchive::new(file)?;
+            for i in 0..zip.len() {
+                let mut entry = zip.by_index(i)?;
+                if !entry.is_file() || !is_apktool_file(entry.name()) {
+                    continue;
+                }
+                let name = entry.name().to_string();
+                let mut bytes = Vec::with_capacity(usize::try_from(entry.size()).unwrap_or(0));
+                entry.read_to_end(&mut bytes)?;
+                visit(name, bytes);
+            }
+        }
+        ArchiveKind::Tar => read_tar(file, visit)?,
+        ArchiveKind::TarGz => read_tar(GzDecoder::new(file), visit)?,
+    }
+    Ok(())
+}
+
+/// Whether name is the path of a file `ClassIndex` reads from an archive
+fn is_apktool_file(name: &str) -> bool {
+    name.ends_with(".smali") || is_apktool_yml(name)
+}
+
+pub(crate) fn is_apktool_yml(name: &str) -> bool {
+    name.rsplit('/').next() == Some(crate::APKTOOL_YML)
+}
+
+fn read_tar(reader: impl Read, mut visit: impl FnMut(String, Vec<u8>)) -> ParserResult<()> {
+    let mut tar = tar::Archive::new(reader);
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = match entry.path()?.to_str() {
+            Some(name) if is_apktool_file(name) => name.to_string(),
+            _ => continue,
+        };
+        let mut bytes = Vec::with_capacity(usize::try_from(entry.size()).unwrap_or(0));
+        entry.read_to_end(&mut bytes)?;
+        visit(name, bytes);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::class_index::ClassIndex;
+    use crate::class_path::ClassPath;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    /// The multidex test files below a `twitch/` directory as (name, contents)
+    fn multidex_files() -> Vec<(String, Vec<u8>)> {
+        let source = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/files/multidex");
+        let mut files = crate::find_smali_files(&source).unwrap();
+        files.push(source.join(crate::APKTOOL_YML));
+        files
+            .into_iter()
+            .map(|file| {
+                let relative = file.strip_prefix(&source).unwrap().to_str().unwrap();
+                (
+                    format!("twitch/{}", relative),
+                    std::fs::read(&file).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    fn write_archive(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("ubi-{}-{}", std::process::id(), name));
+        let file = File::create(&path).unwrap();
+        match ArchiveKind::of(&path).unwrap() {
+            ArchiveKind::Zip => {
+                let mut zip = zip::ZipWriter::new(file);
+                zip.add_directory("twitch/", Default::default()).unwrap();
+                for (name, bytes) in multidex_files() {
+                    zip.start_file(name, Default::default()).unwrap();
+                    zip.write_all(&bytes).unwrap();
+                }
+                zip.finish().unwrap();
+            }
+            ArchiveKind::Tar => write_tar(file),
+            ArchiveKind::TarGz => {
+                write_tar(GzEncoder::new(file, flate2::Compression::fast()));
+            }
+        }
+        path
+    }
+
+    fn write_tar(writer: impl Write) {
+        let mut tar = tar::Builder::new(writer);
+        for (name, bytes) in multidex_files() {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(bytes.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            tar.append_data(&mut header, name, &bytes[..]).unwrap();
+        }
+        tar.into_inner().unwrap().flush().unwrap();
+    }
+
+    #[test]
+    fn formats() {
+        let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/files/multidex");
+        let expected = ClassIndex::load(&directory).unwrap();
+        let player = ClassPath::from_java("tv.twitch.Player").unwrap();
+
+        for name in ["multidex.zip", "multidex.tar", "multidex.tar.gz"] {
+            let archive = write_archive(name);
+            assert!(is_archive(&archive));
+            let index = ClassIndex::open(&archive).unwrap();
+            assert_eq!(
+                index.roots(),
+                ["smali", "smali_classes2", "smali_classes10"]
+                    .map(|root| archive.join("twitch").join(root))
+            );
+            assert_eq!(index.len(), expected.len());
+            assert_eq!(index.app(), expected.app());
+            assert_eq!(index.duplicates(), [&player]);
+            assert!(index.failed().is_empty());
+            for entry in expected.entries() {
+                let class = index.class(&entry.class_path).unwrap().unwrap();
+                assert_eq!(class, entry.class().unwrap());
+                let bodies = index.get(&entry.class_path).unwrap().bodies();
+                assert_eq!(bodies.unwrap(), entry.bodies().unwrap());
+            }
+            assert_eq!(
+                index.get(&player).unwrap().file,
+                archive.join("twitch/smali/tv/twitch/Player.smali")
+            );
+            std::fs::remove_file(archive).unwrap();
+        }
+        assert!(!is_archive(&directory));
+    }
+
+    #[cfg(feature = "cache")]
+    #[test]
+    fn cached() {
+        let archive = write_archive("cached.tgz");
+        let cache_file = archive.with_extension("cache");
+        let (_, first) = ClassIndex::load_cached(&archive, &cache_file).unwrap();
+        let (index, second) = ClassIndex::load_cached(&archive, &cache_file).unwrap();
+        assert_eq!((first.parsed, second.reused, second.parsed), (5, 5, 0));
+        assert_eq!(index.len(), 4);
+        std::fs::remove_file(archive).unwrap();
+        std::fs::remove_file(cache_file).unwrap();
+    }
+}
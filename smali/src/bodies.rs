@@ -0,0 +1,140 @@
+//! The method bodies of smali classes
+//!
+//! `walk_bodies` is the one pass over the instructions that cross references (`xref`) and
+//! string literals (`strings`) are read with. `ClassBodies` holds both, read together for the
+//! classes of a `ClassIndex` and stored in its cache.
+
+use crate::class_path::ClassPath;
+use crate::err::*;
+use crate::parser::{class, method};
+use crate::smali_class::SmaliMethod;
+use crate::strings::{self, ClassStrings, ConstString, MethodStrings};
+use crate::xref::{self, ClassXrefs, MethodXrefs, Xref};
+
+/// The cross references and string literals of all method bodies of a class
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClassBodies {
+    pub xrefs: ClassXrefs,
+    pub strings: ClassStrings,
+}
+
+/// A method with what was read from the instructions of its body
+pub(crate) struct Body<T> {
+    pub method: SmaliMethod,
+    /// the line of the `.method` directive
+    pub line: usize,
+    pub items: Vec<T>,
+}
+
+/// Walks the method bodies of a smali class, instruction gets every trimmed line inside a method
+/// with its line number (starting at 1) and returns what it read from it
+pub(crate) fn walk_bodies<T>(
+    lines: impl Iterator<Item = ParserResult<impl AsRef<str>>>,
+    mut instruction: impl FnMut(&str, usize) -> ParserResult<Option<T>>,
+) -> ParserResult<(ClassPath, Vec<Body<T>>)> {
+    let mut class_path = None;
+    let mut methods: Vec<Body<T>> = vec![];
+    let mut in_method = false;
+
+    for (i, line) in lines.enumerate() {
+        let line = line?;
+        let line = line.as_ref().trim();
+        if line.starts_with(".class") {
+            class_path = Some(class::parse_line(line)?.class_path);
+        } else if line.starts_with(".method") {
+            methods.push(Body {
+                method: method::parse_line(line)?,
+                line: i + 1,
+                items: vec![],
+            });
+            in_method = true;
+        } else if line.starts_with(".end method") {
+            in_method = false;
+        } else if in_method {
+            if let Some(item) = instruction(line, i + 1)? {
+                if let Some(method) = methods.last_mut() {
+                    method.items.push(item);
+                }
+            }
+        }
+    }
+
+    Ok((class_path.ok_or(ParserError::MissingClass())?, methods))
+}
+
+enum Item {
+    Xref(Xref),
+    String(ConstString),
+}
+
+/// Reads the cross references and string literals of all method bodies of a smali class
+pub fn read_bodies(
+    lines: impl Iterator<Item = ParserResult<impl AsRef<str>>>,
+) -> ParserResult<ClassBodies> {
+    let (class_path, methods) = walk_bodies(lines, |line, line_number| {
+        if let Some(xref) = xref::parse_instruction(line, line_number)? {
+            return Ok(Some(Item::Xref(xref)));
+        }
+        Ok(strings::parse_instruction(line, line_number)?.map(Item::String))
+    })?;
+
+    let mut xrefs = vec![];
+    let mut strings = vec![];
+    for body in methods {
+        let mut method_xrefs = vec![];
+        let mut method_strings = vec![];
+        for item in body.items {
+            match item {
+                Item::Xref(xref) => method_xrefs.push(xref),
+                Item::String(string) => method_strings.push(string),
+            }
+        }
+        xrefs.push(MethodXrefs {
+            method: body.method.clone(),
+            line: body.line,
+            xrefs: method_xrefs,
+        });
+        strings.push(MethodStrings {
+            method: body.method,
+            line: body.line,
+            strings: method_strings,
+        });
+    }
+
+    Ok(ClassBodies {
+        xrefs: ClassXrefs {
+            class_path: class_path.clone(),
+            methods: xrefs,
+        },
+        strings: ClassStrings {
+            class_path,
+            methods: strings,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_both() {
+        let smali = ".class La/B;
+.method public run()V
+    const-string v0, \"Player\"
+    invoke-static {v0}, La/Log;->d(Ljava/lang/String;)V
+.end method";
+        let bodies = read_bodies(smali.lines().map(Ok)).unwrap();
+        assert_eq!(
+            bodies.xrefs,
+            xref::read_xrefs(smali.lines().map(Ok)).unwrap()
+        );
+        assert_eq!(
+            bodies.strings,
+            strings::read_strings(smali.lines().map(Ok)).unwrap()
+        );
+        assert_eq!(bodies.xrefs.methods[0].xrefs[0].line, 4);
+        assert_eq!(bodies.strings.methods[0].strings[0].line, 3);
+    }
+}
@@ -0,0 +1,284 @@
+//! An on-disk cache of the classes of a `ClassIndex`, enabled by the `cache` cargo feature
+//!
+//! Every parsed class is stored with its method bodies and the xxh3 hash of its smali file. The next load only parses
+//! files whose hash changed and takes all other classes from the cache. The cache is written
+//! with `bincode` and wrapped in `Versioned`, so a new `FORMAT_VERSION` or `PARSER_VERSION`
+//! (or another version of this crate) discards it as a whole. The cache is only an optimization,
+//! so a cache that can not be written is logged and does not fail the load.
+
+use crate::bodies::ClassBodies;
+use crate::class_index::ClassIndex;
+use crate::err::*;
+use crate::serialization::Versioned;
+use crate::smali_class::SmaliClass;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use xxhash_rust::xxh3::xxh3_64;
+
+/// The version of the parser output, increment it whenever the same smali file
+/// is parsed into a different `SmaliClass` or `ClassBodies`
+///
+/// 1. initial
+/// 2. method bodies
+pub const PARSER_VERSION: u32 = 2;
+
+/// How the classes of a `ClassIndex::load_cached` call were read
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// classes taken from the cache
+    pub reused: usize,
+    /// classes parsed because they were new, changed or not cached
+    pub parsed: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheData {
+    parser_version: u32,
+    crate_version: String,
+    files: Vec<CachedFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedFile {
+    /// the path of the smali file relative to the apktool directory
+    file: PathBuf,
+    hash: u64,
+    class: SmaliClass,
+    bodies: ClassBodies,
+}
+
+impl ClassIndex {
+    /// Like `ClassIndex::load`, but reuses the classes of unchanged files from the cache at
+    /// cache_file and writes the updated cache back. A missing, outdated or broken cache is
+    /// rebuilt from scratch.
+    pub fn load_cached(
+        apktool_dir: impl AsRef<Path>,
+        cache_file: impl AsRef<Path>,
+    ) -> ParserResult<(Self, CacheStats)> {
+        let apktool_dir = apktool_dir.as_ref();
+        let cache_file = cache_file.as_ref();
+        let cached = read_cache(cache_file);
+        // the hash of every read file and whether its class was taken from the cache
+        let hashes: Mutex<HashMap<PathBuf, (u64, bool)>> = Mutex::default();
+        let load = |relative: &Path, bytes: &[u8]| {
+            let hash = xxh3_64(bytes);
+            let reused = cached.get(relative).filter(|file| file.hash == hash);
+            hashes
+                .lock()
+                .insert(relative.to_path_buf(), (hash, reused.is_some()));
+            match reused {
+                Some(file) => (Ok(file.class.clone()), Ok(file.bodies.clone())),
+                None => crate::read_bytes(bytes),
+            }
+        };
+        let relative = |file: &Path| file.strip_prefix(apktool_dir).unwrap_or(file).to_path_buf();
+
+        let index = Self::read_with(apktool_dir, load)?;
+
+        let hashes = hashes.into_inner();
+        let mut stats = CacheStats::default();
+        let mut files = vec![];
+        for entry in index.all_entries() {
+            let file = relative(&entry.file);
+            let Some((hash, reused)) = hashes.get(&file) else {
+                continue;
+            };
+            if *reused {
+                stats.reused += 1;
+            } else {
+                stats.parsed += 1;
+            }
+            if let (Ok(class), Ok(bodies)) = (entry.class(), entry.bodies()) {
+                files.push(CachedFile {
+                    file,
+                    hash: *hash,
+                    class: class.clone(),
+                    bodies: bodies.clone(),
+                });
+            }
+        }
+        if let Err(err) = write_cache(cache_file, files) {
+            common::warn!("could not write cache {}: {}", cache_file.display(), err);
+        }
+        Ok((index, stats))
+    }
+}
+
+/// A cache file for apktool_dir in the cache directory of the user (`$XDG_CACHE_HOME/ubi`,
+/// `~/.cache/ubi` or `%LOCALAPPDATA%\ubi`, the temporary directory if none of them is set),
+/// so read-only inputs can be cached too. Every directory or archive gets its own file,
+/// named after it and the hash of its canonical path.
+pub fn default_cache_file(apktool_dir: impl AsRef<Path>) -> PathBuf {
+    let apktool_dir = apktool_dir.as_ref();
+    let canonical = std::fs::canonicalize(apktool_dir).unwrap_or_else(|_| apktool_dir.into());
+    let hash = xxh3_64(canonical.to_string_lossy().as_bytes());
+    let name = canonical
+        .file_name()
+        .map_or_else(|| "root".into(), |name| name.to_string_lossy());
+    user_cache_dir()
+        .join("ubi")
+        .join(format!("{}-{:016x}", name, hash))
+}
+
+fn user_cache_dir() -> PathBuf {
+    let var = |name| std::env::var_os(name).filter(|value| !value.is_empty());
+    if let Some(dir) = var("XDG_CACHE_HOME") {
+        return dir.into();
+    }
+    if let Some(home) = var("HOME") {
+        return Path::new(&home).join(".cache");
+    }
+    var("LOCALAPPDATA").map_or_else(std::env::temp_dir, PathBuf::from)
+}
+
+/// The cached files by path, empty if the cache can not be used
+fn read_cache(cache_file: &Path) -> HashMap<PathBuf, CachedFile> {
+    let Ok(bytes) = std::fs::read(cache_file) else {
+        return HashMap::new();
+    };
+    let data: CacheData = match bincode::deserialize::<Versioned<CacheData>>(&bytes) {
+        Ok(versioned) => versioned.data,
+        Err(err) => {
+            common::debug!("discarding cache {}: {}", cache_file.display(), err);
+            return HashMap::new();
+        }
+    };
+    if data.parser_version != PARSER_VERSION || data.crate_version != env!("CARGO_PKG_VERSION") {
+        common::debug!(
+            "discarding cache {} of another version",
+            cache_file.display()
+        );
+        return HashMap::new();
+    }
+    data.files
+        .into_iter()
+        .map(|file| (file.file.clone(), file))
+        .collect()
+}
+
+/// Writes to a temporary file first, so readers never see a partially written cache.
+/// The temporary file is named after the process, so concurrent runs do not write into
+/// the same file and the last rename wins.
+fn write_cache(cache_file: &Path, files: Vec<CachedFile>) -> ParserResult<()> {
+    let data = Versioned::new(CacheData {
+        parser_version: PARSER_VERSION,
+        crate_version: env!("CARGO_PKG_VERSION").to_string(),
+        files,
+    });
+    let bytes = bincode::serialize(&data).map_err(std::io::Error::other)?;
+    if let Some(dir) = cache_file.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut temporary = cache_file.as_os_str().to_owned();
+    temporary.push(format!(".{}.tmp", std::process::id()));
+    std::fs::write(&temporary, bytes)?;
+    if let Err(err) = std::fs::rename(&temporary, cache_file) {
+        let _ = std::fs::remove_file(&temporary);
+        return Err(err.into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::class_path::ClassPath;
+
+    /// A copy of the multidex test files that can be modified
+    fn apktool_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ubi-cache-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let source = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/files/multidex");
+        for file in crate::find_smali_files(&source).unwrap() {
+            let target = dir.join(file.strip_prefix(&source).unwrap());
+            std::fs::create_dir_all(target.parent().unwrap()).unwrap();
+            std::fs::copy(&file, target).unwrap();
+        }
+        dir
+    }
+
+    fn stats(reused: usize, parsed: usize) -> CacheStats {
+        CacheStats { reused, parsed }
+    }
+
+    #[test]
+    fn reuses_unchanged_files() {
+        let dir = apktool_dir("reuse");
+        let cache_file = dir.join("cache/index");
+
+        let (index, first) = ClassIndex::load_cached(&dir, &cache_file).unwrap();
+        assert_eq!(first, stats(0, 5));
+        let player = ClassPath::from_java("tv.twitch.Player").unwrap();
+        let class = index.class(&player).unwrap().unwrap().clone();
+
+        let (index, second) = ClassIndex::load_cached(&dir, &cache_file).unwrap();
+        assert_eq!(second, stats(5, 0));
+        assert_eq!(index.class(&player).unwrap().unwrap(), &class);
+        let entry = index.get(&player).unwrap();
+        assert_eq!(
+            entry.bodies().unwrap(),
+            &crate::bodies_file(&entry.file).unwrap()
+        );
+
+        let file = dir.join("smali/tv/twitch/Player.smali");
+        let smali = std::fs::read_to_string(&file).unwrap();
+        std::fs::write(&file, smali.replace("play()V", "pause()V")).unwrap();
+        let (index, third) = ClassIndex::load_cached(&dir, &cache_file).unwrap();
+        assert_eq!(third, stats(4, 1));
+        let class = index.class(&player).unwrap().unwrap();
+        assert_eq!(class.methods[0].name, "pause");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn discards_broken_cache() {
+        let dir = apktool_dir("broken");
+        let cache_file = dir.join("index.cache");
+        ClassIndex::load_cached(&dir, &cache_file).unwrap();
+
+        let mut bytes = std::fs::read(&cache_file).unwrap();
+        // the format version is the first field
+        bytes[0] = bytes[0].wrapping_add(1);
+        std::fs::write(&cache_file, &bytes).unwrap();
+        let (_, stats_after) = ClassIndex::load_cached(&dir, &cache_file).unwrap();
+        assert_eq!(stats_after, stats(0, 5));
+
+        std::fs::write(&cache_file, b"garbage").unwrap();
+        let (_, stats_after) = ClassIndex::load_cached(&dir, &cache_file).unwrap();
+        assert_eq!(stats_after, stats(0, 5));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unwritable_cache() {
+        let dir = apktool_dir("unwritable");
+        // a directory of the cache file is a smali file
+        let cache_file = dir.join("smali/tv/twitch/Player.smali/index");
+        let (index, first) = ClassIndex::load_cached(&dir, &cache_file).unwrap();
+        assert_eq!(index.len(), 4);
+        let (_, second) = ClassIndex::load_cached(&dir, &cache_file).unwrap();
+        assert_eq!((first, second), (stats(0, 5), stats(0, 5)));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn default_cache_files() {
+        let one = apktool_dir("default-one");
+        let two = apktool_dir("default-two");
+        let file = default_cache_file(&one);
+        assert_eq!(file, default_cache_file(one.join("smali/..")));
+        assert_ne!(file, default_cache_file(&two));
+        assert!(!file.starts_with(&one));
+        let name = file.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("ubi-cache-default-one-"));
+        assert_eq!(file.parent().unwrap().file_name().unwrap(), "ubi");
+
+        std::fs::remove_dir_all(one).unwrap();
+        std::fs::remove_dir_all(two).unwrap();
+    }
+}
@@ -0,0 +1,362 @@
+//! An index of all classes in an apktool output directory
+//!
+//! The classes are discovered from the file names in all smali roots without reading the files.
+//! They are parsed on first access or all at once in parallel with `ClassIndex::parse_all`, the
+//! same goes for their method bodies and `ClassIndex::read_all_bodies`. Classes of archives are
+//! read completely when the archive is opened, since their files can not be read again later.
+//! Apps may define a class in more than one dex file, the runtime then loads the one of the
+//! first dex file. The index does the same and keeps the other definitions as duplicates.
+
+use crate::apktool::{ApktoolInfo, APKTOOL_YML};
+use crate::bodies::ClassBodies;
+use crate::class_path::ClassPath;
+use crate::err::*;
+use crate::smali_class::SmaliClass;
+#[cfg(feature = "archive")]
+use crate::tree::dex_number;
+use crate::tree::{find_smali_files, smali_roots};
+#[cfg(feature = "archive")]
+use parking_lot::Mutex;
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// A smali file of a `ClassIndex`
+#[derive(Debug)]
+pub struct ClassEntry {
+    /// the class path derived from the path of the file in its smali root
+    pub class_path: ClassPath,
+    pub file: PathBuf,
+    /// the smali root containing the file, see `ClassIndex::roots`
+    pub root: usize,
+    pub(crate) class: OnceLock<ParserResult<SmaliClass>>,
+    pub(crate) bodies: OnceLock<ParserResult<ClassBodies>>,
+}
+
+/// The class and method bodies read from the contents of a smali file
+#[cfg(any(feature = "cache", feature = "archive"))]
+pub(crate) type ReadFile = (ParserResult<SmaliClass>, ParserResult<ClassBodies>);
+
+impl ClassEntry {
+    /// The parsed class, the file is parsed on the first call
+    pub fn class(&self) -> Result<&SmaliClass, &ParserError> {
+        self.class
+            .get_or_init(|| crate::parse_file(&self.file))
+            .as_ref()
+    }
+
+    /// Whether the file was parsed already
+    pub fn is_parsed(&self) -> bool {
+        self.class.get().is_some()
+    }
+
+    /// The cross references and string literals of the method bodies,
+    /// the file is read on the first call
+    pub fn bodies(&self) -> Result<&ClassBodies, &ParserError> {
+        self.bodies
+            .get_or_init(|| crate::bodies_file(&self.file))
+            .as_ref()
+    }
+
+    #[cfg(any(feature = "cache", feature = "archive"))]
+    fn set(&self, (class, bodies): ReadFile) {
+        let _ = self.class.set(class);
+        let _ = self.bodies.set(bodies);
+    }
+}
+
+/// The classes of an apktool output directory by class path, package and simple name
+#[derive(Debug)]
+pub struct ClassIndex {
+    roots: Vec<PathBuf>,
+    entries: Vec<ClassEntry>,
+    // all definitions of a class in root order, the first one is the one used
+    by_class_path: BTreeMap<ClassPath, Vec<usize>>,
+    by_simple_name: HashMap<String, Vec<usize>>,
+    // files whose path is no valid class path
+    invalid: Vec<(PathBuf, ParserError)>,
+    app: Option<ApktoolInfo>,
+}
+
+impl ClassIndex {
+    /// Discovers all classes in the smali roots of apktool_dir, nothing is parsed yet.
+    /// Archives of apktool directories (see `is_archive`) are read and parsed right away.
+    pub fn open(apktool_dir: impl AsRef<Path>) -> ParserResult<Self> {
+        let apktool_dir = apktool_dir.as_ref();
+        #[cfg(feature = "archive")]
+        if crate::archive::is_archive(apktool_dir) {
+            return Self::from_archive(apktool_dir, |_, bytes| crate::read_bytes(bytes));
+        }
+
+        let roots = smali_roots(apktool_dir)?;
+        if roots.is_empty() {
+            return Err(ParserError::MissingSmaliRoot(
+                apktool_dir.display().to_string(),
+            ));
+        }
+
+        let mut index = Self::empty();
+        for (root, root_dir) in roots.iter().enumerate() {
+            for file in find_smali_files(root_dir)? {
+                let relative = file.strip_prefix(root_dir).unwrap_or(&file);
+                match ClassPath::from_file_path(relative) {
+                    Ok(class_path) => index.push(class_path, file, root),
+                    Err(err) => index.invalid.push((file, err)),
+                }
+            }
+        }
+        // the app build is only informational, so an unreadable apktool.yml is no reason to fail
+        index.app = ApktoolInfo::from_dir(apktool_dir).unwrap_or_else(|err| {
+            common::warn!("could not read {}: {}", APKTOOL_YML, err);
+            None
+        });
+        index.roots = roots;
+        Ok(index)
+    }
+
+    /// Reads every smali file of a directory or archive right away with read,
+    /// which gets the path of the file relative to apktool_dir and its contents
+    #[cfg(feature = "cache")]
+    pub(crate) fn read_with<F>(apktool_dir: &Path, read: F) -> ParserResult<Self>
+    where
+        F: Fn(&Path, &[u8]) -> ReadFile + Sync,
+    {
+        #[cfg(feature = "archive")]
+        if crate::archive::is_archive(apktool_dir) {
+            return Self::from_archive(apktool_dir, read);
+        }
+
+        let index = Self::open(apktool_dir)?;
+        index.entries.par_iter().for_each(|entry| {
+            let relative = entry.file.strip_prefix(apktool_dir).unwrap_or(&entry.file);
+            match std::fs::read(&entry.file) {
+                Ok(bytes) => entry.set(read(relative, &bytes)),
+                Err(err) => {
+                    let message = err.to_string();
+                    entry.set((Err(err.into()), Err(std::io::Error::other(message).into())));
+                }
+            }
+        });
+        Ok(index)
+    }
+
+    /// Reads every smali file of an archive with read, in parallel while the archive is read.
+    /// The smali roots are the directories named like them.
+    #[cfg(feature = "archive")]
+    fn from_archive<F>(archive: &Path, read: F) -> ParserResult<Self>
+    where
+        F: Fn(&Path, &[u8]) -> ReadFile + Sync,
+    {
+        let parsed = Mutex::new(vec![]);
+        // the apktool.yml closest to the top of the archive
+        let mut apktool_yml: Option<(String, Vec<u8>)> = None;
+        let mut result = Ok(());
+        rayon::scope(|scope| {
+            let (read, parsed) = (&read, &parsed);
+            result = crate::archive::read_apktool_files(archive, |name, bytes| {
+                if crate::archive::is_apktool_yml(&name) {
+                    let depth = |name: &str| name.matches('/').count();
+                    if apktool_yml
+                        .as_ref()
+                        .is_none_or(|(yml, _)| depth(&name) < depth(yml))
+                    {
+                        apktool_yml = Some((name, bytes));
+                    }
+                    return;
+                }
+                scope.spawn(move |_| {
+                    let file = read(Path::new(&name), &bytes);
+                    parsed.lock().push((name, file));
+                });
+            });
+        });
+        result?;
+
+        // (dex number, root) and the path of the file in the root of every smali file
+        let mut files = vec![];
+        for (name, file) in parsed.into_inner() {
+            let segments: Vec<_> = name.split('/').collect();
+            let Some((position, number)) = segments
+                .iter()
+                .enumerate()
+                .find_map(|(i, segment)| Some((i, dex_number(segment)?)))
+            else {
+                continue;
+            };
+            let root = segments[..=position].join("/");
+            let relative = segments[position + 1..].join("/");
+            files.push(((number, root), relative, name, file));
+        }
+        files.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+
+        let mut index = Self::empty();
+        for ((_, root), relative, name, read_file) in files {
+            if index.roots.last() != Some(&archive.join(&root)) {
+                index.roots.push(archive.join(&root));
+            }
+            let file = archive.join(&name);
+            match ClassPath::from_file_path(&relative) {
+                Ok(class_path) => {
+                    index.push(class_path, file, index.roots.len() - 1);
+                    if let Some(entry) = index.entries.last() {
+                        entry.set(read_file);
+                    }
+                }
+                Err(err) => index.invalid.push((file, err)),
+            }
+        }
+        if index.roots.is_empty() {
+            return Err(ParserError::MissingSmaliRoot(archive.display().to_string()));
+        }
+        if let Some((_, bytes)) = apktool_yml {
+            let mut app = ApktoolInfo::parse(&String::from_utf8_lossy(&bytes));
+            app.set_dex_files(&index.roots);
+            index.app = Some(app);
+        }
+        Ok(index)
+    }
+
+    fn empty() -> Self {
+        Self {
+            roots: vec![],
+            entries: vec![],
+            by_class_path: BTreeMap::new(),
+            by_simple_name: HashMap::new(),
+            invalid: vec![],
+            app: None,
+        }
+    }
+
+    /// Like `open`, but parses all classes right away in parallel
+    pub fn load(apktool_dir: impl AsRef<Path>) -> ParserResult<Self> {
+        let index = Self::open(apktool_dir)?;
+        index.parse_all();
+        Ok(index)
+    }
+
+    fn push(&mut self, class_path: ClassPath, file: PathBuf, root: usize) {
+        let entry = self.entries.len();
+        let definitions = self.by_class_path.entry(class_path.clone()).or_default();
+        if definitions.is_empty() {
+            self.by_simple_name
+                .entry(class_path.simple_name().to_string())
+                .or_default()
+                .push(entry);
+        }
+        definitions.push(entry);
+        self.entries.push(ClassEntry {
+            class_path,
+            file,
+            root,
+            class: OnceLock::new(),
+            bodies: OnceLock::new(),
+        });
+    }
+
+    /// Parses every class that was not parsed yet, in parallel on the rayon thread pool
+    pub fn parse_all(&self) {
+        self.entries.par_iter().for_each(|entry| {
+            let _ = entry.class();
+        });
+    }
+
+    /// Reads the method bodies of every class that were not read yet, in parallel
+    /// on the rayon thread pool
+    pub fn read_all_bodies(&self) {
+        self.entries.par_iter().for_each(|entry| {
+            let _ = entry.bodies();
+        });
+    }
+
+    /// All definitions of all classes, including duplicates
+    #[cfg(feature = "cache")]
+    pub(crate) fn all_entries(&self) -> &[ClassEntry] {
+        &self.entries
+    }
+
+    /// The smali roots in the order of their dex files
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// The app build from the apktool.yml of the directory or archive, None if it has none
+    pub fn app(&self) -> Option<&ApktoolInfo> {
+        self.app.as_ref()
+    }
+
+    /// The number of distinct classes
+    pub fn len(&self) -> usize {
+        self.by_class_path.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_class_path.is_empty()
+    }
+
+    /// The used definition of every class, sorted by class path
+    pub fn entries(&self) -> impl Iterator<Item = &ClassEntry> {
+        self.by_class_path
+            .values()
+            .map(move |definitions| &self.entries[definitions[0]])
+    }
+
+    /// The used definition of a class
+    pub fn get(&self, class_path: &ClassPath) -> Option<&ClassEntry> {
+        let definitions = self.by_class_path.get(class_path)?;
+        Some(&self.entries[definitions[0]])
+    }
+
+    /// The parsed class of a class path, see `ClassEntry::class`
+    pub fn class(&self, class_path: &ClassPath) -> Option<Result<&SmaliClass, &ParserError>> {
+        self.get(class_path).map(ClassEntry::class)
+    }
+
+    /// All definitions of a class in root order, more than one for duplicates
+    pub fn definitions(&self, class_path: &ClassPath) -> Vec<&ClassEntry> {
+        self.by_class_path
+            .get(class_path)
+            .map(|definitions| definitions.iter().map(|i| &self.entries[*i]).collect())
+            .unwrap_or_default()
+    }
+
+    /// The classes defined in more than one smali root, sorted
+    pub fn duplicates(&self) -> Vec<&ClassPath> {
+        self.by_class_path
+            .iter()
+            .filter(|(_, definitions)| definitions.len() > 1)
+            .map(|(class_path, _)| class_path)
+            .collect()
+    }
+
+    /// The classes in a package and its subpackages (`tv.twitch`), sorted by class path.
+    /// An empty package matches every class.
+    pub fn in_package(&self, package: &str) -> Vec<&ClassEntry> {
+        let prefix = format!("{}.", package);
+        self.entries()
+            .filter(|entry| package.is_empty() || entry.class_path.as_java().starts_with(&prefix))
+            .collect()
+    }
+
+    /// The classes with a simple name (`Bar` for `tv.twitch.Foo$Bar`), in root and path order
+    pub fn by_simple_name(&self, simple_name: &str) -> Vec<&ClassEntry> {
+        self.by_simple_name
+            .get(simple_name)
+            .map(|entries| entries.iter().map(|i| &self.entries[*i]).collect())
+            .unwrap_or_default()
+    }
+
+    /// The files that are no valid class path or whose class or method bodies could not be read
+    /// so far, with their errors
+    pub fn failed(&self) -> Vec<(&Path, &ParserError)> {
+        let invalid = self.invalid.iter().map(|(file, err)| (file.as_path(), err));
+        let unparsable =
+            self.entries
+                .iter()
+                .filter_map(|entry| match (entry.class.get(), entry.bodies.get()) {
+                    (Some(Err(err)), _) | (_, Some(Err(err))) => Some((entry.file.as_path(), err)),
+                    _ => None,
+                });
+        invalid.chain(unparsable).collect()
+    }
+}
@@ -0,0 +1,247 @@
+use crate::err::*;
+use crate::parser::util::{escape, smali_to_java_path};
+use crate::symbol::Symbol;
+use crate::tree::smali_roots;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// A fully qualified class name, stored interned in java notation (`tv.twitch.Foo$Bar`)
+///
+/// Provides access to the parts of the name and conversions into the smali descriptor
+/// (`Ltv/twitch/Foo$Bar;`) and file path (`tv/twitch/Foo$Bar.smali`) forms
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ClassPath(Symbol);
+
+impl ClassPath {
+    /// Parses a class path in java notation (`tv.twitch.Foo$Bar`)
+    pub fn from_java(java_path: &str) -> ParserResult<Self> {
+        let valid = !java_path.is_empty()
+            && !java_path.contains(['/', ';', '['])
+            && java_path.split('.').all(|segment| !segment.is_empty());
+
+        if !valid {
+            return Err(ParserError::InvalidClassPath(java_path.to_string()));
+        }
+        Ok(Self(Symbol::intern(java_path)))
+    }
+
+    /// Parses a smali class descriptor (`Ltv/twitch/Foo$Bar;`)
+    pub fn from_smali(descriptor: &str) -> ParserResult<Self> {
+        let java_path = smali_to_java_path(descriptor)?;
+        Self::from_java(&java_path)
+            .map_err(|_| ParserError::InvalidClassPath(descriptor.to_string()))
+    }
+
+    /// Parses a path of a smali file relative to its smali root (`tv/twitch/Foo$Bar.smali`)
+    pub fn from_file_path(file_path: impl AsRef<Path>) -> ParserResult<Self> {
+        let file_path = file_path.as_ref();
+        let error = || ParserError::InvalidClassPath(file_path.display().to_string());
+
+        let file_path = file_path.with_extension("");
+        let segments = file_path
+            .iter()
+            .map(|segment| segment.to_str().ok_or_else(error))
+            .collect::<ParserResult<Vec<_>>>()?;
+
+        Self::from_java(&segments.join(".")).map_err(|_| error())
+    }
+
+    /// The class path in java notation (`tv.twitch.Foo$Bar`)
+    pub fn as_java(&self) -> &str {
+        &self.0
+    }
+
+    /// The interned java notation of the class path
+    pub fn as_symbol(&self) -> &Symbol {
+        &self.0
+    }
+
+    /// The class path as smali descriptor (`Ltv/twitch/Foo$Bar;`)
+    pub fn to_smali(&self) -> String {
+        format!("L{};", escape(&self.0).replace('.', "/"))
+    }
+
+    /// The path of the smali file relative to a smali root (`tv/twitch/Foo$Bar.smali`)
+    pub fn to_file_path(&self) -> PathBuf {
+        let mut path: PathBuf = self.0.split('.').collect();
+        path.set_extension("smali");
+        path
+    }
+
+    /// Looks for the smali file of this class in all smali roots (`smali`, `smali_classes2`, ...)
+    /// of an apktool output directory. Roots are searched by their dex number, so
+    /// `smali_classes2` comes before `smali_classes10` like at runtime, see `smali_roots`.
+    pub fn find_file(&self, apktool_dir: impl AsRef<Path>) -> Option<PathBuf> {
+        let relative = self.to_file_path();
+        smali_roots(apktool_dir)
+            .ok()?
+            .into_iter()
+            .map(|root| root.join(&relative))
+            .find(|file| file.is_file())
+    }
+
+    /// The package of the class (`tv.twitch`) or None for the default package
+    pub fn package(&self) -> Option<&str> {
+        self.0.rsplit_once('.').map(|(package, _)| package)
+    }
+
+    /// The class name without the package (`Foo$Bar`)
+    pub fn binary_name(&self) -> &str {
+        match self.0.rsplit_once('.') {
+            Some((_, name)) => name,
+            None => &self.0,
+        }
+    }
+
+    /// The classes this class is nested in, outermost first (`["Foo"]` for `Foo$Bar`)
+    pub fn outer_classes(&self) -> Vec<&str> {
+        match self.split_binary_name() {
+            Some((outer, _)) => outer.split('$').collect(),
+            None => vec![],
+        }
+    }
+
+    /// The name of the class without package and outer classes (`Bar` for `Foo$Bar`)
+    pub fn simple_name(&self) -> &str {
+        match self.split_binary_name() {
+            Some((_, name)) => name,
+            None => self.binary_name(),
+        }
+    }
+
+    /// The class path of the directly enclosing class, if this class is nested
+    pub fn outer_class(&self) -> Option<Self> {
+        let (outer, _) = self.split_binary_name()?;
+        match self.package() {
+            Some(package) => Some(Self(Symbol::from(format!("{}.{}", package, outer)))),
+            None => Some(Self(Symbol::intern(outer))),
+        }
+    }
+
+    /// Whether this class is nested in another class
+    pub fn is_nested(&self) -> bool {
+        self.split_binary_name().is_some()
+    }
+
+    /// Whether this is an anonymous class generated by javac (`Foo$1`)
+    pub fn is_anonymous(&self) -> bool {
+        self.is_nested() && self.simple_name().chars().all(|ch| ch.is_ascii_digit())
+    }
+
+    /// Whether this is a class generated by d8/r8 or retrolambda for a lambda
+    pub fn is_lambda(&self) -> bool {
+        let name = self.binary_name();
+        name.starts_with("-$$Lambda$")
+            || name.contains("$$Lambda$")
+            || name.contains("$$ExternalSyntheticLambda")
+            || name.contains("$r8$lambda$")
+    }
+
+    /// splits the binary name into the outer class chain and the simple name,
+    /// empty segments (like in `-$$Lambda$Foo`) are not treated as nesting
+    fn split_binary_name(&self) -> Option<(&str, &str)> {
+        let (outer, name) = self.binary_name().rsplit_once('$')?;
+        if outer.is_empty() || name.is_empty() || outer.split('$').any(str::is_empty) {
+            return None;
+        }
+        Some((outer, name))
+    }
+}
+
+impl FromStr for ClassPath {
+    type Err = ParserError;
+    fn from_str(java_path: &str) -> ParserResult<Self> {
+        Self::from_java(java_path)
+    }
+}
+
+impl fmt::Display for ClassPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for ClassPath {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<str> for ClassPath {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<&str> for ClassPath {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversions() {
+        let path = ClassPath::from_smali("Ltv/twitch/Foo$Bar;").unwrap();
+        assert_eq!(path.as_java(), "tv.twitch.Foo$Bar");
+        assert_eq!(path.to_smali(), "Ltv/twitch/Foo$Bar;");
+        assert_eq!(
+            path.to_file_path(),
+            ["tv", "twitch", "Foo$Bar.smali"]
+                .iter()
+                .collect::<PathBuf>()
+        );
+        assert_eq!(
+            ClassPath::from_file_path(path.to_file_path()).unwrap(),
+            path
+        );
+        assert_eq!(ClassPath::from_java("tv.twitch.Foo$Bar").unwrap(), path);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(ClassPath::from_java("").is_err());
+        assert!(ClassPath::from_java("tv..Foo").is_err());
+        assert!(ClassPath::from_java("tv/Foo").is_err());
+        assert!(ClassPath::from_smali("tv/Foo").is_err());
+        assert!(ClassPath::from_smali("L/tv/Foo;").is_err());
+    }
+
+    #[test]
+    fn parts() {
+        let path = ClassPath::from_java("tv.twitch.Foo$Bar$Baz").unwrap();
+        assert_eq!(path.package(), Some("tv.twitch"));
+        assert_eq!(path.binary_name(), "Foo$Bar$Baz");
+        assert_eq!(path.outer_classes(), vec!["Foo", "Bar"]);
+        assert_eq!(path.simple_name(), "Baz");
+        assert_eq!(path.outer_class().unwrap(), "tv.twitch.Foo$Bar");
+        assert!(path.is_nested());
+        assert!(!path.is_anonymous());
+
+        let path = ClassPath::from_java("Foo").unwrap();
+        assert_eq!(path.package(), None);
+        assert_eq!(path.simple_name(), "Foo");
+        assert!(path.outer_classes().is_empty());
+        assert!(path.outer_class().is_none());
+        assert!(!path.is_nested());
+    }
+
+    #[test]
+    fn synthetic() {
+        let path = ClassPath::from_java("bttv.SleepTimer$2").unwrap();
+        assert!(path.is_anonymous());
+        assert!(!path.is_lambda());
+
+        let path = ClassPath::from_java("tv.twitch.-$$Lambda$Foo$abc").unwrap();
+        assert!(path.is_lambda());
+        assert!(!path.is_nested());
+        assert_eq!(path.simple_name(), "-$$Lambda$Foo$abc");
+
+        let path = ClassPath::from_java("tv.twitch.Foo$$ExternalSyntheticLambda0").unwrap();
+        assert!(path.is_lambda());
+    }
+}
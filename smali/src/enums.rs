@@ -0,0 +1,321 @@
+//! Enum classes and their constants in ordinal order
+//!
+//! Dex files sort static fields by name, so the field order of disassembled enums says nothing
+//! about the ordinals. The order is recovered from the static initializer instead, which calls
+//! `<init>(Ljava/lang/String;I)V` with the ordinal of every constant:
+//!
+//! ```text
+//! new-instance v0, Ltv/twitch/VideoType;
+//! const-string v1, "CLIP"
+//! const/4 v2, 0x1
+//! invoke-direct {v0, v1, v2}, Ltv/twitch/VideoType;-><init>(Ljava/lang/String;I)V
+//! sput-object v0, Ltv/twitch/VideoType;->CLIP:Ltv/twitch/VideoType;
+//! ```
+
+use crate::class_path::ClassPath;
+use crate::parser::util::unescape;
+use crate::smali_class::{SmaliClass, SmaliType, SmaliValue};
+use crate::symbol::Symbol;
+use std::collections::HashMap;
+
+const ENUM: &str = "java.lang.Enum";
+const CLINIT: &str = ".method static constructor <clinit>()V";
+const ENUM_INIT: &str = "-><init>(Ljava/lang/String;I";
+
+/// Where the order of the constants of a `SmaliEnum` comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EnumOrder {
+    /// the ordinals passed to the constructors in `<clinit>`
+    Ordinals,
+    /// the order the constants are assigned in `<clinit>`
+    Initialization,
+    /// the order of the field declarations, only reliable for classes compiled by javac
+    Fields,
+}
+
+/// An enum class with the names of its constants in ordinal order
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SmaliEnum {
+    pub class_path: ClassPath,
+    pub constants: Vec<Symbol>,
+    pub order: EnumOrder,
+}
+
+impl SmaliClass {
+    /// Whether this is an enum class, constants with a body are subclasses and no enums
+    pub fn is_enum(&self) -> bool {
+        self.super_path.as_ref().is_some_and(|path| *path == ENUM)
+    }
+
+    /// The fields holding the constants of an enum class in declaration order,
+    /// `$VALUES` and other static fields of the enum type are not included
+    pub fn enum_constants(&self) -> Vec<&SmaliValue> {
+        if !self.is_enum() {
+            return vec![];
+        }
+        self.values
+            .iter()
+            .filter(|value| {
+                value.is_enum
+                    && matches!(&value.data_type, SmaliType::Class(path) if *path == self.class_path)
+            })
+            .collect()
+    }
+}
+
+impl SmaliEnum {
+    /// The constants of an enum class in field order, see `EnumOrder::Fields`
+    pub fn from_class(class: &SmaliClass) -> Option<Self> {
+        if !class.is_enum() {
+            return None;
+        }
+        Some(Self {
+            class_path: class.class_path.clone(),
+            constants: field_names(class),
+            order: EnumOrder::Fields,
+        })
+    }
+
+    /// The constants of an enum class ordered by the `<clinit>` found in the smali text of class,
+    /// falls back to field order if not every constant is initialized there
+    pub fn from_smali(class: &SmaliClass, smali: &str) -> Option<Self> {
+        let mut result = Self::from_class(class)?;
+        let assigned = read_clinit(&class.class_path, smali);
+        let position = |constant: &Symbol| assigned.iter().position(|(name, _)| name == constant);
+        if !result
+            .constants
+            .iter()
+            .all(|constant| position(constant).is_some())
+        {
+            return Some(result);
+        }
+
+        let ordinal = |constant: &Symbol| assigned[position(constant)?].1;
+        let mut ordinals: Vec<_> = result.constants.iter().map(ordinal).collect();
+        ordinals.sort_unstable();
+        ordinals.dedup();
+        if ordinals.len() == result.constants.len() && ordinals.iter().all(Option::is_some) {
+            result.constants.sort_by_key(ordinal);
+            result.order = EnumOrder::Ordinals;
+        } else {
+            result.constants.sort_by_key(position);
+            result.order = EnumOrder::Initialization;
+        }
+        Some(result)
+    }
+
+    /// The ordinal of a constant
+    pub fn ordinal(&self, constant: &str) -> Option<usize> {
+        self.constants.iter().position(|name| *name == constant)
+    }
+}
+
+fn field_names(class: &SmaliClass) -> Vec<Symbol> {
+    class
+        .enum_constants()
+        .into_iter()
+        .map(|value| value.name.clone())
+        .collect()
+}
+
+/// The fields of class assigned in `<clinit>` in assignment order, together with the ordinal
+/// passed to the constructor of the assigned instance if it could be followed
+fn read_clinit(class_path: &ClassPath, smali: &str) -> Vec<(Symbol, Option<i64>)> {
+    let descriptor = class_path.to_smali();
+    // the literals loaded into registers and the ordinals of constructed instances by register
+    let mut literals: HashMap<String, i64> = HashMap::new();
+    let mut ordinals: HashMap<String, i64> = HashMap::new();
+    let mut assigned = vec![];
+
+    let lines = smali
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| !line.starts_with(CLINIT))
+        .skip(1)
+        .take_while(|line| *line != ".end method");
+
+    for line in lines {
+        let Some((opcode, operands)) = line.split_once(char::is_whitespace) else {
+            continue;
+        };
+        let operands = operands.trim();
+
+        match opcode {
+            "const/4" | "const/16" | "const" => {
+                if let Some((register, literal)) = operands.split_once(',') {
+                    match parse_literal(literal.trim()) {
+                        Some(literal) => literals.insert(register.trim().to_string(), literal),
+                        None => literals.remove(register.trim()),
+                    };
+                }
+            }
+            "invoke-direct" | "invoke-direct/range" if operands.contains(ENUM_INIT) => {
+                let registers = registers(operands);
+                if let (Some(instance), Some(ordinal)) = (registers.first(), registers.get(2)) {
+                    if let Some(ordinal) = literals.get(ordinal) {
+                        ordinals.insert(instance.clone(), *ordinal);
+                    }
+                }
+            }
+            "sput-object" => {
+                let Some((register, field)) = operands.split_once(',') else {
+                    continue;
+                };
+                let Some((owner, field)) = field.trim().split_once("->") else {
+                    continue;
+                };
+                let Some(name) = field.split_once(':').and_then(|(name, _)| unescape(name)) else {
+                    continue;
+                };
+                if owner == descriptor {
+                    let ordinal = ordinals.get(register.trim()).copied();
+                    assigned.push((Symbol::intern(&name), ordinal));
+                }
+            }
+            _ => {
+                // most other instructions write their first register, it no longer holds a literal
+                if let Some((register, _)) = operands.split_once(',') {
+                    literals.remove(register.trim());
+                    ordinals.remove(register.trim());
+                }
+            }
+        }
+    }
+
+    assigned
+}
+
+/// The registers of an invoke, `{v0, v1, v2}` or `{v0 .. v2}`
+fn registers(operands: &str) -> Vec<String> {
+    let Some(list) = operands
+        .strip_prefix('{')
+        .and_then(|rest| rest.split_once('}'))
+        .map(|(list, _)| list)
+    else {
+        return vec![];
+    };
+
+    if let Some((first, last)) = list.split_once("..") {
+        let (first, last) = (first.trim(), last.trim());
+        let (Some(prefix), Some(start), Some(end)) =
+            (first.get(..1), first.get(1..), last.get(1..))
+        else {
+            return vec![];
+        };
+        let (Ok(start), Ok(end)) = (start.parse::<u32>(), end.parse::<u32>()) else {
+            return vec![];
+        };
+        return (start..=end)
+            .map(|index| format!("{}{}", prefix, index))
+            .collect();
+    }
+
+    list.split(',')
+        .map(|register| register.trim().to_string())
+        .collect()
+}
+
+/// Parses the literal of a const instruction, like `0x1` or `-0x10`
+fn parse_literal(literal: &str) -> Option<i64> {
+    let (negative, literal) = match literal.strip_prefix('-') {
+        Some(literal) => (true, literal),
+        None => (false, literal),
+    };
+    let value = match literal.strip_prefix("0x") {
+        Some(hex) => i64::from_str_radix(hex, 16).ok()?,
+        None => literal.parse().ok()?,
+    };
+    Some(if negative { -value } else { value })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VIDEO_TYPE: &str = ".class public final enum Ltv/twitch/VideoType;
+.super Ljava/lang/Enum;
+
+.field private static final synthetic $VALUES:[Ltv/twitch/VideoType;
+.field public static final enum ARCHIVE:Ltv/twitch/VideoType;
+.field public static final enum CLIP:Ltv/twitch/VideoType;
+.field public static final enum HIGHLIGHT:Ltv/twitch/VideoType;
+.field public static final DEFAULT:Ltv/twitch/VideoType;
+.field private final value:Ljava/lang/String;
+
+.method static constructor <clinit>()V
+    .registers 5
+
+    new-instance v0, Ltv/twitch/VideoType;
+    const-string v1, \"HIGHLIGHT\"
+    const/4 v2, 0x0
+    invoke-direct {v0, v1, v2}, Ltv/twitch/VideoType;-><init>(Ljava/lang/String;I)V
+    sput-object v0, Ltv/twitch/VideoType;->HIGHLIGHT:Ltv/twitch/VideoType;
+
+    new-instance v0, Ltv/twitch/VideoType$1;
+    const-string v1, \"ARCHIVE\"
+    const/4 v3, 0x2
+    invoke-direct {v0, v1, v3}, Ltv/twitch/VideoType$1;-><init>(Ljava/lang/String;I)V
+    sput-object v0, Ltv/twitch/VideoType;->ARCHIVE:Ltv/twitch/VideoType;
+
+    new-instance v0, Ltv/twitch/VideoType;
+    const-string v1, \"CLIP\"
+    const/4 v2, 0x1
+    invoke-direct/range {v0 .. v2}, Ltv/twitch/VideoType;-><init>(Ljava/lang/String;I)V
+    sput-object v0, Ltv/twitch/VideoType;->CLIP:Ltv/twitch/VideoType;
+
+    return-void
+.end method";
+
+    fn names(enum_class: &SmaliEnum) -> Vec<&str> {
+        enum_class.constants.iter().map(Symbol::as_str).collect()
+    }
+
+    #[test]
+    fn ordinals() {
+        let class = crate::parse_class(VIDEO_TYPE).unwrap();
+        assert!(class.is_enum());
+        assert_eq!(class.enum_constants().len(), 3);
+
+        let from_fields = SmaliEnum::from_class(&class).unwrap();
+        assert_eq!(names(&from_fields), ["ARCHIVE", "CLIP", "HIGHLIGHT"]);
+        assert_eq!(from_fields.order, EnumOrder::Fields);
+
+        let from_clinit = SmaliEnum::from_smali(&class, VIDEO_TYPE).unwrap();
+        assert_eq!(names(&from_clinit), ["HIGHLIGHT", "CLIP", "ARCHIVE"]);
+        assert_eq!(from_clinit.order, EnumOrder::Ordinals);
+        assert_eq!(from_clinit.ordinal("ARCHIVE"), Some(2));
+    }
+
+    #[test]
+    fn initialization_order() {
+        // the ordinal of CLIP is not a constant
+        let smali = VIDEO_TYPE.replace("const/4 v2, 0x1", "move v2, v4");
+        let class = crate::parse_class(&smali).unwrap();
+        let enum_class = SmaliEnum::from_smali(&class, &smali).unwrap();
+        assert_eq!(names(&enum_class), ["HIGHLIGHT", "ARCHIVE", "CLIP"]);
+        assert_eq!(enum_class.order, EnumOrder::Initialization);
+
+        // HIGHLIGHT is not initialized at all
+        let smali = VIDEO_TYPE.replace("sput-object v0, Ltv/twitch/VideoType;->HIGHLIGHT", "");
+        let enum_class = SmaliEnum::from_smali(&class, &smali).unwrap();
+        assert_eq!(enum_class.order, EnumOrder::Fields);
+    }
+
+    #[test]
+    fn not_enum() {
+        let class = crate::parse_class(".class public Ltv/twitch/VideoType;").unwrap();
+        assert!(!class.is_enum());
+        assert!(SmaliEnum::from_class(&class).is_none());
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(parse_literal("0x1f"), Some(31));
+        assert_eq!(parse_literal("-0x2"), Some(-2));
+        assert_eq!(parse_literal("x"), None);
+        assert_eq!(registers("{p0 .. p2}, La;->b()V"), ["p0", "p1", "p2"]);
+        assert!(registers("{ .. v2}, La;->b()V").is_empty());
+    }
+}
@@ -5,6 +5,8 @@ use thiserror::Error;
 pub enum ParserError {
     #[error("IOError")]
     IOError(#[from] std::io::Error),
+    #[error("line {0} is not valid UTF-8")]
+    InvalidUtf8(usize),
     #[error("class with missing class path found in this line: {0}")]
     MissingClassPath(String),
     #[error("invalid class path found: {0}")]
@@ -23,6 +25,49 @@ pub enum ParserError {
     InvalidField(),
     #[error("InvalidMethod")]
     InvalidMethod(),
+    #[error("invalid annotation found near: {0}")]
+    InvalidAnnotation(String),
+    #[error("invalid reference in line {0}: {1}")]
+    InvalidReference(usize, String),
+    #[error("invalid class or member to look up: {0}")]
+    InvalidXrefQuery(String),
+    #[error("invalid string literal in line {0}: {1}")]
+    InvalidString(usize, String),
+    #[error("no smali directories found in {0}")]
+    MissingSmaliRoot(String),
+    #[cfg(feature = "archive")]
+    #[error("invalid zip archive")]
+    InvalidArchive(#[from] zip::result::ZipError),
 }
 
 pub type ParserResult<T> = Result<T, ParserError>;
+
+#[derive(Error, Debug)]
+pub enum KotlinError {
+    #[error("invalid {0} element in kotlin metadata")]
+    InvalidElement(&'static str),
+    #[error("unknown kotlin metadata kind {0}")]
+    UnknownKind(i32),
+    #[error("d1 of the kotlin metadata is not a valid byte encoding")]
+    InvalidEncoding(),
+    #[error("invalid protobuf in kotlin metadata: {0}")]
+    InvalidProtobuf(&'static str),
+    #[error("string {0} is missing in the kotlin metadata string table")]
+    InvalidString(i32),
+    #[error("kotlin metadata {0} is missing a required field")]
+    MissingField(&'static str),
+}
+
+pub type KotlinResult<T> = Result<T, KotlinError>;
+
+#[derive(Error, Debug)]
+pub enum MappingError {
+    #[error("IOError")]
+    IOError(#[from] std::io::Error),
+    #[error("invalid mapping in line {0}: {1}")]
+    InvalidLine(usize, String),
+    #[error("member mapping in line {0} is not part of a class mapping")]
+    MemberOutsideClass(usize),
+}
+
+pub type MappingResult<T> = Result<T, MappingError>;
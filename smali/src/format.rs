@@ -0,0 +1,453 @@
+use crate::class_path::ClassPath;
+use crate::parser::util::escape;
+use crate::smali_class::*;
+use std::fmt;
+
+/// The notation used to render parts of the smali model
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Notation {
+    /// java source notation: `java.lang.String[]`, `public void run(int)`, `a.Outer.Inner`,
+    /// constructors are named after their class if the owner is known
+    Java,
+    /// smali descriptors and signatures: `[Ljava/lang/String;`, `run(I)V`
+    Smali,
+    /// JVM-style member references: `Lbttv/Util;->run(I)V`, falls back to `Smali` for
+    /// types, classes and members without a known owner
+    MemberRef,
+}
+
+/// A part of the smali model together with the notation it is displayed in,
+/// created by the `display` methods
+pub struct Formatted<'a, T> {
+    item: &'a T,
+    notation: Notation,
+    owner: Option<&'a ClassPath>,
+}
+
+impl<'a, T> Formatted<'a, T> {
+    fn new(item: &'a T, notation: Notation) -> Self {
+        Self {
+            item,
+            notation,
+            owner: None,
+        }
+    }
+
+    /// Sets the class that declares the displayed member, used by `Notation::MemberRef` and
+    /// to name constructors in `Notation::Java`
+    pub fn with_owner(mut self, owner: &'a ClassPath) -> Self {
+        self.owner = Some(owner);
+        self
+    }
+}
+
+impl SmaliType {
+    pub fn display(&self, notation: Notation) -> Formatted<'_, Self> {
+        Formatted::new(self, notation)
+    }
+}
+
+impl SmaliValue {
+    pub fn display(&self, notation: Notation) -> Formatted<'_, Self> {
+        Formatted::new(self, notation)
+    }
+}
+
+impl SmaliMethod {
+    pub fn display(&self, notation: Notation) -> Formatted<'_, Self> {
+        Formatted::new(self, notation)
+    }
+}
+
+impl SmaliClass {
+    pub fn display(&self, notation: Notation) -> Formatted<'_, Self> {
+        Formatted::new(self, notation)
+    }
+}
+
+impl SmaliAccessModifier {
+    /// The java keyword of the modifier, empty for package-private
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            Self::Public => "public",
+            Self::Private => "private",
+            Self::Protected => "protected",
+            Self::Package => "",
+        }
+    }
+}
+
+impl fmt::Display for Formatted<'_, SmaliType> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.notation {
+            Notation::Smali | Notation::MemberRef => f.write_str(&self.item.to_smali()),
+            Notation::Java => write_java_type(f, self.item),
+        }
+    }
+}
+
+impl fmt::Display for Formatted<'_, SmaliValue> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self.item;
+        match self.notation {
+            Notation::Java => {
+                write_modifiers(f, &value.access, value.is_static, value.is_final)?;
+                write_java_type(f, &value.data_type)?;
+                write!(f, " {}", value.name)
+            }
+            Notation::Smali | Notation::MemberRef => {
+                write_owner(f, self.notation, self.owner)?;
+                write!(f, "{}:{}", escape(&value.name), value.data_type.to_smali())
+            }
+        }
+    }
+}
+
+impl fmt::Display for Formatted<'_, SmaliMethod> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let method = self.item;
+        match self.notation {
+            Notation::Java => {
+                if method.name == "<clinit>" {
+                    return f.write_str("static {}");
+                }
+                write_modifiers(f, &method.access, method.is_static, method.is_final)?;
+                if method.name == "<init>" {
+                    // without the owner the name of the constructor is unknown
+                    let name = self.owner.map_or("<init>", ClassPath::simple_name);
+                    write!(f, "{}(", name)?;
+                } else {
+                    write_java_type(f, &method.return_type)?;
+                    write!(f, " {}(", method.name)?;
+                }
+                for (i, param) in method.parameter_types.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(", ")?;
+                    }
+                    write_java_type(f, param)?;
+                }
+                f.write_str(")")
+            }
+            Notation::Smali | Notation::MemberRef => {
+                write_owner(f, self.notation, self.owner)?;
+                write!(f, "{}(", escape(&method.name))?;
+                for param in &method.parameter_types {
+                    f.write_str(&param.to_smali())?;
+                }
+                write!(f, "){}", method.return_type.to_smali())
+            }
+        }
+    }
+}
+
+impl fmt::Display for Formatted<'_, SmaliClass> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let class = self.item;
+        match self.notation {
+            Notation::Java => {
+                write_modifiers(f, &class.access, false, false)?;
+                // interfaces are always abstract, so are enums with constant bodies
+                if class.is_abstract && class.kind == ClassKind::Class {
+                    f.write_str("abstract ")?;
+                }
+                f.write_str(match class.kind {
+                    ClassKind::Class => "class ",
+                    ClassKind::Interface => "interface ",
+                    ClassKind::Enum => "enum ",
+                    ClassKind::Annotation => "@interface ",
+                })?;
+                write_java_class(f, &class.class_path)?;
+                let (super_path, interfaces, keyword) = match class.kind {
+                    ClassKind::Class => (
+                        class.super_path.as_ref(),
+                        &class.interfaces[..],
+                        " implements ",
+                    ),
+                    ClassKind::Enum => (None, &class.interfaces[..], " implements "),
+                    ClassKind::Interface => (None, &class.interfaces[..], " extends "),
+                    // every annotation type implements `java.lang.annotation.Annotation`
+                    ClassKind::Annotation => (None, &[][..], ""),
+                };
+                if let Some(super_path) = super_path {
+                    f.write_str(" extends ")?;
+                    write_java_class(f, super_path)?;
+                }
+                for (i, interface) in interfaces.iter().enumerate() {
+                    f.write_str(if i == 0 { keyword } else { ", " })?;
+                    write_java_class(f, interface)?;
+                }
+                Ok(())
+            }
+            Notation::Smali => {
+                f.write_str(".class ")?;
+                write_modifiers(f, &class.access, false, false)?;
+                if class.is_abstract {
+                    f.write_str("abstract ")?;
+                }
+                f.write_str(&class.class_path.to_smali())
+            }
+            Notation::MemberRef => f.write_str(&class.class_path.to_smali()),
+        }
+    }
+}
+
+fn write_java_type(f: &mut fmt::Formatter<'_>, typ: &SmaliType) -> fmt::Result {
+    match typ {
+        SmaliType::Void => f.write_str("void"),
+        SmaliType::Boolean => f.write_str("boolean"),
+        SmaliType::Byte => f.write_str("byte"),
+        SmaliType::Char => f.write_str("char"),
+        SmaliType::Short => f.write_str("short"),
+        SmaliType::Float => f.write_str("float"),
+        SmaliType::Double => f.write_str("double"),
+        SmaliType::Int => f.write_str("int"),
+        SmaliType::Long => f.write_str("long"),
+        SmaliType::Arr(inner) => {
+            write_java_type(f, inner)?;
+            f.write_str("[]")
+        }
+        SmaliType::Class(class_path) => write_java_class(f, class_path),
+    }
+}
+
+/// Writes the canonical name of the class, nested classes are separated by `.` (`a.Outer.Inner`)
+fn write_java_class(f: &mut fmt::Formatter<'_>, class_path: &ClassPath) -> fmt::Result {
+    if let Some(package) = class_path.package() {
+        write!(f, "{}.", package)?;
+    }
+    for outer in class_path.outer_classes() {
+        write!(f, "{}.", outer)?;
+    }
+    f.write_str(class_path.simple_name())
+}
+
+fn write_modifiers(
+    f: &mut fmt::Formatter<'_>,
+    access: &SmaliAccessModifier,
+    is_static: bool,
+    is_final: bool,
+) -> fmt::Result {
+    if *access != SmaliAccessModifier::Package {
+        write!(f, "{} ", access.keyword())?;
+    }
+    if is_static {
+        f.write_str("static ")?;
+    }
+    if is_final {
+        f.write_str("final ")?;
+    }
+    Ok(())
+}
+
+fn write_owner(
+    f: &mut fmt::Formatter<'_>,
+    notation: Notation,
+    owner: Option<&ClassPath>,
+) -> fmt::Result {
+    match (notation, owner) {
+        (Notation::MemberRef, Some(owner)) => write!(f, "{}->", owner.to_smali()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_class, Symbol};
+
+    fn class_type(java_path: &str) -> SmaliType {
+        SmaliType::Class(ClassPath::from_java(java_path).unwrap())
+    }
+
+    #[test]
+    fn types() {
+        let typ = SmaliType::Arr(Box::new(class_type("a.B")));
+        assert_eq!(typ.display(Notation::Java).to_string(), "a.B[]");
+        assert_eq!(typ.display(Notation::Smali).to_string(), "[La/B;");
+        assert_eq!(typ.display(Notation::MemberRef).to_string(), "[La/B;");
+    }
+
+    #[test]
+    fn nested_types() {
+        let typ = class_type("a.Outer$Inner$Deepest");
+        assert_eq!(
+            typ.display(Notation::Java).to_string(),
+            "a.Outer.Inner.Deepest"
+        );
+        assert_eq!(
+            typ.display(Notation::Smali).to_string(),
+            "La/Outer$Inner$Deepest;"
+        );
+        assert_eq!(
+            class_type("Outer$Inner")
+                .display(Notation::Java)
+                .to_string(),
+            "Outer.Inner"
+        );
+    }
+
+    #[test]
+    fn values() {
+        let value = SmaliValue {
+            name: Symbol::intern("names"),
+            data_type: SmaliType::Arr(Box::new(class_type("java.lang.String"))),
+            access: SmaliAccessModifier::Private,
+            is_static: true,
+            is_final: true,
+            is_synthetic: false,
+            is_enum: false,
+        };
+        let owner = ClassPath::from_java("bttv.Util").unwrap();
+
+        assert_eq!(
+            value.display(Notation::Java).to_string(),
+            "private static final java.lang.String[] names"
+        );
+        assert_eq!(
+            value.display(Notation::Smali).to_string(),
+            "names:[Ljava/lang/String;"
+        );
+        assert_eq!(
+            value
+                .display(Notation::MemberRef)
+                .with_owner(&owner)
+                .to_string(),
+            "Lbttv/Util;->names:[Ljava/lang/String;"
+        );
+    }
+
+    #[test]
+    fn methods() {
+        let method = SmaliMethod {
+            name: Symbol::intern("run"),
+            access: SmaliAccessModifier::Package,
+            parameter_types: vec![SmaliType::Int, class_type("a.B")],
+            return_type: SmaliType::Void,
+            is_static: false,
+            is_final: false,
+            is_synthetic: false,
+        };
+        let owner = ClassPath::from_java("bttv.Util").unwrap();
+
+        assert_eq!(
+            method.display(Notation::Java).to_string(),
+            "void run(int, a.B)"
+        );
+        assert_eq!(method.display(Notation::Smali).to_string(), "run(ILa/B;)V");
+        assert_eq!(
+            method
+                .display(Notation::MemberRef)
+                .with_owner(&owner)
+                .to_string(),
+            "Lbttv/Util;->run(ILa/B;)V"
+        );
+    }
+
+    #[test]
+    fn constructors() {
+        let constructor = |name: &str, is_static| SmaliMethod {
+            name: Symbol::intern(name),
+            access: SmaliAccessModifier::Public,
+            parameter_types: vec![SmaliType::Int],
+            return_type: SmaliType::Void,
+            is_static,
+            is_final: false,
+            is_synthetic: false,
+        };
+        let owner = ClassPath::from_java("a.Outer$Inner").unwrap();
+
+        assert_eq!(
+            constructor("<init>", false)
+                .display(Notation::Java)
+                .with_owner(&owner)
+                .to_string(),
+            "public Inner(int)"
+        );
+        assert_eq!(
+            constructor("<init>", false)
+                .display(Notation::Java)
+                .to_string(),
+            "public <init>(int)"
+        );
+        assert_eq!(
+            constructor("<clinit>", true)
+                .display(Notation::Java)
+                .with_owner(&owner)
+                .to_string(),
+            "static {}"
+        );
+        assert_eq!(
+            constructor("<init>", false)
+                .display(Notation::MemberRef)
+                .with_owner(&owner)
+                .to_string(),
+            "La/Outer$Inner;-><init>(I)V"
+        );
+    }
+
+    #[test]
+    fn classes() {
+        let class = parse_class(
+            ".class public abstract Lbttv/Util;
+.super Ljava/lang/Object;
+.implements La/B;
+.implements La/C;",
+        )
+        .unwrap();
+
+        assert_eq!(
+            class.display(Notation::Java).to_string(),
+            "public abstract class bttv.Util extends java.lang.Object implements a.B, a.C"
+        );
+        assert_eq!(
+            class.display(Notation::Smali).to_string(),
+            ".class public abstract Lbttv/Util;"
+        );
+        assert_eq!(
+            class.display(Notation::MemberRef).to_string(),
+            "Lbttv/Util;"
+        );
+    }
+
+    #[test]
+    fn class_kinds() {
+        let java = |smali: &str| {
+            parse_class(smali)
+                .unwrap()
+                .display(Notation::Java)
+                .to_string()
+        };
+
+        assert_eq!(
+            java(
+                ".class public interface abstract La/Outer$Listener;
+.super Ljava/lang/Object;
+.implements La/B;"
+            ),
+            "public interface a.Outer.Listener extends a.B"
+        );
+        assert_eq!(
+            java(
+                ".class public final enum La/Color;
+.super Ljava/lang/Enum;
+.implements La/B;"
+            ),
+            "public enum a.Color implements a.B"
+        );
+        assert_eq!(
+            java(
+                ".class public interface abstract annotation La/Keep;
+.super Ljava/lang/Object;
+.implements Ljava/lang/annotation/Annotation;"
+            ),
+            "public @interface a.Keep"
+        );
+        assert_eq!(
+            java(
+                ".class La/Outer$Inner;
+.super La/Outer$Base;"
+            ),
+            "class a.Outer.Inner extends a.Outer.Base"
+        );
+    }
+}
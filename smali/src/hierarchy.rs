@@ -0,0 +1,397 @@
+//! The class hierarchy of a tree of smali classes
+//!
+//! Every class only names its direct super class and interfaces. A `Hierarchy` links them over a
+//! whole tree to answer subtypes, super class chains and implemented interfaces transitively.
+//! Classes that are referenced but not part of the tree (mostly the android framework and the
+//! java runtime) become stubs: they are known to exist, but not what they extend or declare.
+
+use crate::class_index::ClassIndex;
+use crate::class_path::ClassPath;
+use crate::err::*;
+use crate::smali_class::{SmaliAccessModifier, SmaliClass, SmaliMethod, SmaliType, SmaliValue};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Where a member visible on a class is declared
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MemberOwner<'a, T> {
+    /// declared by a class of the tree
+    Declared {
+        class_path: &'a ClassPath,
+        member: &'a T,
+    },
+    /// not declared in the tree, this is the first stub on the way up that may declare it
+    Outside(&'a ClassPath),
+}
+
+/// The classes of a tree linked to their super classes, interfaces and subtypes
+#[derive(Debug, Default)]
+pub struct Hierarchy {
+    classes: HashMap<ClassPath, SmaliClass>,
+    stubs: BTreeSet<ClassPath>,
+    // the classes directly extending or implementing a class, sorted
+    subtypes: HashMap<ClassPath, Vec<ClassPath>>,
+    failed: Vec<(PathBuf, ParserError)>,
+}
+
+impl Hierarchy {
+    /// Links the given classes, of classes with the same path only the first one is used
+    pub fn new(classes: impl IntoIterator<Item = SmaliClass>) -> Self {
+        let mut hierarchy = Self::default();
+        for class in classes {
+            if !hierarchy.classes.contains_key(&class.class_path) {
+                hierarchy.classes.insert(class.class_path.clone(), class);
+            }
+        }
+
+        for class in hierarchy.classes.values() {
+            for parent in class.super_path.iter().chain(&class.interfaces) {
+                hierarchy
+                    .subtypes
+                    .entry(parent.clone())
+                    .or_default()
+                    .push(class.class_path.clone());
+                if !hierarchy.classes.contains_key(parent) {
+                    hierarchy.stubs.insert(parent.clone());
+                }
+            }
+        }
+        for subtypes in hierarchy.subtypes.values_mut() {
+            subtypes.sort();
+            subtypes.dedup();
+        }
+        hierarchy
+    }
+
+    /// Scans the headers of every `.smali` file below dir and links them, see `scan_tree`
+    pub fn from_tree(dir: impl AsRef<Path>) -> ParserResult<Self> {
+        let mut classes = vec![];
+        let mut failed = vec![];
+        for file in crate::scan_tree(dir)? {
+            match file.class {
+                Ok(class) => classes.push(class),
+                Err(err) => failed.push((file.path, err)),
+            }
+        }
+        let mut hierarchy = Self::new(classes);
+        hierarchy.failed = failed;
+        Ok(hierarchy)
+    }
+
+    /// Links the classes used by a `ClassIndex`, classes that were not parsed or taken from its
+    /// cache yet are parsed in parallel. Files that could not be parsed are reported by
+    /// `ClassIndex::failed`.
+    pub fn from_index(index: &ClassIndex) -> Self {
+        index.parse_all();
+        Self::new(
+            index
+                .entries()
+                .filter_map(|entry| entry.class().ok().cloned()),
+        )
+    }
+
+    /// The files that could not be read with their errors
+    pub fn failed(&self) -> &[(PathBuf, ParserError)] {
+        &self.failed
+    }
+
+    /// A class of the tree, None for stubs and unknown classes
+    pub fn class(&self, class_path: &ClassPath) -> Option<&SmaliClass> {
+        self.classes.get(class_path)
+    }
+
+    /// Whether a class is referenced by the tree without being part of it
+    pub fn is_stub(&self, class_path: &ClassPath) -> bool {
+        self.stubs.contains(class_path)
+    }
+
+    /// All referenced classes that are not part of the tree, sorted
+    pub fn stubs(&self) -> impl Iterator<Item = &ClassPath> {
+        self.stubs.iter()
+    }
+
+    /// The super classes of a class from its direct super class up to the root or the first stub
+    pub fn superclasses(&self, class_path: &ClassPath) -> Vec<&ClassPath> {
+        let mut chain: Vec<&ClassPath> = vec![];
+        let mut current = self.class(class_path);
+        while let Some(super_path) = current.and_then(|class| class.super_path.as_ref()) {
+            // broken trees may contain cycles
+            if super_path == class_path || chain.contains(&super_path) {
+                break;
+            }
+            chain.push(super_path);
+            current = self.class(super_path);
+        }
+        chain
+    }
+
+    /// All interfaces a class implements, including those of its super classes and the
+    /// interfaces these extend. Ordered breadth first, starting with the direct interfaces.
+    pub fn interfaces(&self, class_path: &ClassPath) -> Vec<&ClassPath> {
+        let mut interfaces: Vec<&ClassPath> = vec![];
+        let mut seen = HashSet::new();
+        let classes = std::iter::once(class_path).chain(self.superclasses(class_path));
+        for class in classes.filter_map(|class_path| self.class(class_path)) {
+            let start = interfaces.len();
+            for interface in &class.interfaces {
+                if seen.insert(interface) {
+                    interfaces.push(interface);
+                }
+            }
+            // the interfaces extended by the interfaces added above
+            let mut i = start;
+            while i < interfaces.len() {
+                if let Some(interface) = self.class(interfaces[i]) {
+                    for parent in &interface.interfaces {
+                        if seen.insert(parent) {
+                            interfaces.push(parent);
+                        }
+                    }
+                }
+                i += 1;
+            }
+        }
+        interfaces
+    }
+
+    /// The classes directly extending a class or implementing an interface, sorted
+    pub fn direct_subtypes(&self, class_path: &ClassPath) -> &[ClassPath] {
+        self.subtypes.get(class_path).map_or(&[], Vec::as_slice)
+    }
+
+    /// All classes extending a class or implementing an interface, directly or indirectly,
+    /// in depth first order
+    pub fn subtypes(&self, class_path: &ClassPath) -> Vec<&ClassPath> {
+        let mut subtypes = vec![];
+        let mut seen = HashSet::new();
+        let mut stack: Vec<&ClassPath> = self.direct_subtypes(class_path).iter().rev().collect();
+        while let Some(subtype) = stack.pop() {
+            if !seen.insert(subtype) {
+                continue;
+            }
+            subtypes.push(subtype);
+            stack.extend(self.direct_subtypes(subtype).iter().rev());
+        }
+        subtypes
+    }
+
+    /// Finds the class declaring the method that a virtual call on class_path resolves to.
+    ///
+    /// The class itself and its super classes are searched first, then its interfaces.
+    /// Private methods of super types are not visible and constructors are never inherited.
+    /// Like overload resolution in java the return type is not compared.
+    pub fn resolve_method(
+        &self,
+        class_path: &ClassPath,
+        name: &str,
+        parameter_types: &[SmaliType],
+    ) -> Option<MemberOwner<'_, SmaliMethod>> {
+        let inherited = name != "<init>" && name != "<clinit>";
+        self.resolve(class_path, inherited, |class, is_super| {
+            class.methods.iter().find(|method| {
+                method.name == name
+                    && method.parameter_types == parameter_types
+                    && is_visible(method.access, is_super)
+            })
+        })
+    }
+
+    /// Finds the class declaring the field that an access on class_path resolves to,
+    /// searched in the same order as `resolve_method`
+    pub fn resolve_field(
+        &self,
+        class_path: &ClassPath,
+        name: &str,
+    ) -> Option<MemberOwner<'_, SmaliValue>> {
+        self.resolve(class_path, true, |class, is_super| {
+            class
+                .values
+                .iter()
+                .find(|value| value.name == name && is_visible(value.access, is_super))
+        })
+    }
+
+    /// Looks for a member with find in the class, then in its super types if inherited.
+    /// Classes outside of the tree are unknown, the first one passed is returned instead.
+    fn resolve<'a, T>(
+        &'a self,
+        class_path: &ClassPath,
+        inherited: bool,
+        find: impl Fn(&'a SmaliClass, bool) -> Option<&'a T>,
+    ) -> Option<MemberOwner<'a, T>> {
+        let Some(start) = self.class(class_path) else {
+            // unknown classes are not even referenced by the tree
+            return self.stubs.get(class_path).map(MemberOwner::Outside);
+        };
+        if let Some(member) = find(start, false) {
+            return Some(MemberOwner::Declared {
+                class_path: &start.class_path,
+                member,
+            });
+        }
+        if !inherited {
+            return None;
+        }
+
+        let mut outside = None;
+        let supers = self.superclasses(class_path);
+        let interfaces = self.interfaces(class_path);
+        for super_path in supers.into_iter().chain(interfaces) {
+            match self.class(super_path) {
+                Some(class) => {
+                    if let Some(member) = find(class, true) {
+                        return Some(MemberOwner::Declared {
+                            class_path: &class.class_path,
+                            member,
+                        });
+                    }
+                }
+                None => {
+                    outside.get_or_insert(super_path);
+                }
+            }
+        }
+        outside.map(MemberOwner::Outside)
+    }
+}
+
+/// Private members are only visible on the class declaring them
+fn is_visible(access: SmaliAccessModifier, inherited: bool) -> bool {
+    !inherited || access != SmaliAccessModifier::Private
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hierarchy(classes: &[&str]) -> Hierarchy {
+        Hierarchy::new(
+            classes
+                .iter()
+                .map(|smali| crate::parse_class(smali).unwrap()),
+        )
+    }
+
+    fn path(java: &str) -> ClassPath {
+        ClassPath::from_java(java).unwrap()
+    }
+
+    fn players() -> Hierarchy {
+        hierarchy(&[
+            ".class public abstract Ltv/twitch/Player;
+.super Ljava/lang/Object;
+.implements Ltv/twitch/Playable;
+.field protected name:Ljava/lang/String;
+.field private state:I
+.method public constructor <init>()V
+.end method
+.method public play(I)V
+.end method
+.method private reset()V
+.end method",
+            ".class public interface abstract Ltv/twitch/Playable;
+.super Ljava/lang/Object;
+.implements Ljava/io/Closeable;
+.method public abstract stop()V
+.end method",
+            ".class public Ltv/twitch/LivePlayer;
+.super Ltv/twitch/Player;
+.implements Ljava/lang/Runnable;
+.method public play(I)V
+.end method",
+            ".class public final Ltv/twitch/LivePlayer$Low;
+.super Ltv/twitch/LivePlayer;",
+            ".class public final Ltv/twitch/VodPlayer;
+.super Ltv/twitch/Player;",
+        ])
+    }
+
+    fn declared<T>(owner: Option<MemberOwner<'_, T>>) -> String {
+        match owner {
+            Some(MemberOwner::Declared { class_path, .. }) => class_path.to_string(),
+            Some(MemberOwner::Outside(class_path)) => format!("outside {}", class_path),
+            None => "none".to_string(),
+        }
+    }
+
+    #[test]
+    fn types() {
+        let hierarchy = players();
+        let low = path("tv.twitch.LivePlayer$Low");
+        assert_eq!(
+            hierarchy.superclasses(&low),
+            [
+                "tv.twitch.LivePlayer",
+                "tv.twitch.Player",
+                "java.lang.Object"
+            ]
+        );
+        assert_eq!(
+            hierarchy.interfaces(&low),
+            [
+                "java.lang.Runnable",
+                "tv.twitch.Playable",
+                "java.io.Closeable"
+            ]
+        );
+        assert_eq!(
+            hierarchy.subtypes(&path("tv.twitch.Player")),
+            [
+                "tv.twitch.LivePlayer",
+                "tv.twitch.LivePlayer$Low",
+                "tv.twitch.VodPlayer"
+            ]
+        );
+        assert_eq!(
+            hierarchy.direct_subtypes(&path("tv.twitch.Playable")),
+            ["tv.twitch.Player"]
+        );
+        assert_eq!(
+            hierarchy.stubs().collect::<Vec<_>>(),
+            [
+                "java.io.Closeable",
+                "java.lang.Object",
+                "java.lang.Runnable"
+            ]
+        );
+        assert!(hierarchy.is_stub(&path("java.lang.Object")));
+        assert!(hierarchy.class(&path("java.lang.Object")).is_none());
+    }
+
+    #[test]
+    fn members() {
+        let hierarchy = players();
+        let low = path("tv.twitch.LivePlayer$Low");
+        let vod = path("tv.twitch.VodPlayer");
+        let method = |class_path: &ClassPath, name: &str, parameter_types: &[SmaliType]| {
+            declared(hierarchy.resolve_method(class_path, name, parameter_types))
+        };
+        assert_eq!(
+            method(&low, "play", &[SmaliType::Int]),
+            "tv.twitch.LivePlayer"
+        );
+        assert_eq!(method(&vod, "play", &[SmaliType::Int]), "tv.twitch.Player");
+        assert_eq!(method(&vod, "stop", &[]), "tv.twitch.Playable");
+        // private and constructors are not inherited
+        assert_eq!(method(&vod, "reset", &[]), "outside java.lang.Object");
+        assert_eq!(method(&vod, "<init>", &[]), "none");
+        assert_eq!(
+            method(&path("tv.twitch.Player"), "<init>", &[]),
+            "tv.twitch.Player"
+        );
+        assert_eq!(
+            method(&path("java.lang.Object"), "hashCode", &[]),
+            "outside java.lang.Object"
+        );
+        assert_eq!(method(&path("tv.twitch.Missing"), "play", &[]), "none");
+
+        assert_eq!(
+            declared(hierarchy.resolve_field(&low, "name")),
+            "tv.twitch.Player"
+        );
+        assert_eq!(
+            declared(hierarchy.resolve_field(&low, "state")),
+            "outside java.lang.Object"
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use super::model::*;
+use crate::smali_class::{SmaliMethod, SmaliType, SmaliValue};
+use std::fmt;
+
+const DEFAULT_CONSTRUCTOR_MARKER: &str = "Lkotlin/jvm/internal/DefaultConstructorMarker;";
+const OBJECT: &str = "Ljava/lang/Object;";
+
+/// The kotlin declaration a JVM member was compiled from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KotlinMember<'a> {
+    Constructor(&'a KotlinConstructor),
+    /// the synthetic constructor with bit masks and a `DefaultConstructorMarker` that fills in
+    /// default arguments
+    ConstructorDefaults(&'a KotlinConstructor),
+    Function(&'a KotlinFunction),
+    /// the static `name$default` method with bit masks that fills in default arguments
+    FunctionDefaults(&'a KotlinFunction),
+    Getter(&'a KotlinProperty),
+    Setter(&'a KotlinProperty),
+    Field(&'a KotlinProperty),
+    /// the static field holding the companion object, with its simple name
+    Companion(&'a str),
+}
+
+impl fmt::Display for KotlinMember<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Constructor(constructor) => write!(f, "{}", constructor),
+            Self::ConstructorDefaults(constructor) => {
+                write!(f, "default arguments of {}", constructor)
+            }
+            Self::Function(function) => write!(f, "{}", function),
+            Self::FunctionDefaults(function) => write!(f, "default arguments of {}", function),
+            Self::Getter(property) => write!(f, "getter of {}", property),
+            Self::Setter(property) => write!(f, "setter of {}", property),
+            Self::Field(property) => write!(f, "backing field of {}", property),
+            Self::Companion(name) => write!(f, "companion object {}", name),
+        }
+    }
+}
+
+impl KotlinMetadata {
+    fn members(&self) -> (&[KotlinConstructor], &[KotlinFunction], &[KotlinProperty]) {
+        match &self.declaration {
+            KotlinDeclaration::Class(class) => {
+                (&class.constructors, &class.functions, &class.properties)
+            }
+            KotlinDeclaration::Package(package) => (&[], &package.functions, &package.properties),
+            _ => (&[], &[], &[]),
+        }
+    }
+
+    /// Finds the declaration a method was compiled from, including accessors of properties
+    /// and the synthetic methods that fill in default arguments
+    pub fn explain_method(&self, method: &SmaliMethod) -> Option<KotlinMember<'_>> {
+        let (constructors, functions, properties) = self.members();
+        let descriptor = method_descriptor(&method.parameter_types, &method.return_type);
+
+        if method.name == "<init>" {
+            for constructor in constructors {
+                let Some(signature) = &constructor.signature else {
+                    continue;
+                };
+                if signature.descriptor == descriptor {
+                    return Some(KotlinMember::Constructor(constructor));
+                }
+                let with_defaults = defaults_descriptor(
+                    &signature.descriptor,
+                    constructor.parameters.len(),
+                    DEFAULT_CONSTRUCTOR_MARKER,
+                );
+                if with_defaults.as_deref() == Some(descriptor.as_str()) {
+                    return Some(KotlinMember::ConstructorDefaults(constructor));
+                }
+            }
+            return None;
+        }
+
+        for function in functions {
+            let Some(signature) = &function.signature else {
+                continue;
+            };
+            if method.name == signature.name.as_str() && signature.descriptor == descriptor {
+                return Some(KotlinMember::Function(function));
+            }
+            if method.is_static
+                && method.name.strip_suffix("$default") == Some(signature.name.as_str())
+            {
+                let with_defaults =
+                    defaults_descriptor(&signature.descriptor, function.parameters.len(), OBJECT);
+                // instance functions get the receiver as first parameter
+                let without_receiver = method_descriptor(
+                    method.parameter_types.get(1..).unwrap_or_default(),
+                    &method.return_type,
+                );
+                if let Some(with_defaults) = with_defaults {
+                    if with_defaults == descriptor || with_defaults == without_receiver {
+                        return Some(KotlinMember::FunctionDefaults(function));
+                    }
+                }
+            }
+        }
+
+        for property in properties {
+            let matches = |accessor: &Option<JvmSignature>| {
+                accessor.as_ref().is_some_and(|accessor| {
+                    method.name == accessor.name.as_str() && accessor.descriptor == descriptor
+                })
+            };
+            if matches(&property.getter) {
+                return Some(KotlinMember::Getter(property));
+            }
+            if matches(&property.setter) {
+                return Some(KotlinMember::Setter(property));
+            }
+        }
+
+        None
+    }
+
+    /// Finds the property a field backs or the companion object it holds
+    pub fn explain_value(&self, value: &SmaliValue) -> Option<KotlinMember<'_>> {
+        if let KotlinDeclaration::Class(class) = &self.declaration {
+            if let Some(companion) = &class.companion_object {
+                if value.is_static && value.name == companion.as_str() {
+                    return Some(KotlinMember::Companion(companion));
+                }
+            }
+        }
+
+        let (_, _, properties) = self.members();
+        let descriptor = value.data_type.to_smali();
+        properties
+            .iter()
+            .find(|property| {
+                property.field.as_ref().is_some_and(|field| {
+                    value.name == field.name.as_str() && field.descriptor == descriptor
+                })
+            })
+            .map(KotlinMember::Field)
+    }
+}
+
+fn method_descriptor(parameter_types: &[SmaliType], return_type: &SmaliType) -> String {
+    let parameters: String = parameter_types.iter().map(SmaliType::to_smali).collect();
+    format!("({}){}", parameters, return_type.to_smali())
+}
+
+/// Appends one int mask per 32 parameters and the trailing marker parameter to a descriptor
+fn defaults_descriptor(descriptor: &str, parameter_count: usize, marker: &str) -> Option<String> {
+    let (parameters, return_type) = descriptor.strip_prefix('(')?.split_once(')')?;
+    let masks = "I".repeat(parameter_count.div_ceil(32).max(1));
+    Some(format!(
+        "({}{}{}){}",
+        parameters, masks, marker, return_type
+    ))
+}
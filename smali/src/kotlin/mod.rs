@@ -0,0 +1,87 @@
+//! Decoding of the `kotlin.Metadata` annotation
+//!
+//! kotlinc stores the kotlin declarations of every class as protobuf in `d1`, encoded as
+//! strings, with the names it refers to in `d2`. They recover what the JVM members lost:
+//! nullability, `suspend`, properties behind getters and setters and default arguments.
+
+mod member;
+mod model;
+mod names;
+mod proto;
+mod read;
+
+pub use member::KotlinMember;
+pub use model::*;
+
+use crate::annotation::{AnnotationValue, SmaliAnnotation};
+use crate::err::*;
+use names::NameResolver;
+use proto::Reader;
+
+pub const METADATA_ANNOTATION: &str = "kotlin.Metadata";
+
+const KIND_CLASS: i32 = 1;
+const KIND_FILE: i32 = 2;
+const KIND_SYNTHETIC_CLASS: i32 = 3;
+const KIND_MULTIFILE_CLASS: i32 = 4;
+const KIND_MULTIFILE_CLASS_PART: i32 = 5;
+
+impl KotlinMetadata {
+    /// Decodes a `kotlin.Metadata` annotation, elements with default values may be missing
+    pub fn from_annotation(annotation: &SmaliAnnotation) -> KotlinResult<Self> {
+        let kind = match annotation.element("k") {
+            None => KIND_CLASS,
+            Some(AnnotationValue::Int(kind)) => *kind,
+            Some(_) => return Err(KotlinError::InvalidElement("k")),
+        };
+        let version = match annotation.element("mv") {
+            None => vec![],
+            Some(AnnotationValue::Array(values)) => values
+                .iter()
+                .map(|value| match value {
+                    AnnotationValue::Int(part) => Ok(*part),
+                    _ => Err(KotlinError::InvalidElement("mv")),
+                })
+                .collect::<KotlinResult<_>>()?,
+            Some(_) => return Err(KotlinError::InvalidElement("mv")),
+        };
+        let d1 = strings(annotation, "d1")?;
+        let d2 = strings(annotation, "d2")?;
+
+        let declaration = match kind {
+            KIND_CLASS | KIND_FILE | KIND_MULTIFILE_CLASS_PART => {
+                let bytes = names::decode_bytes(&d1)?;
+                let mut reader = Reader::new(&bytes);
+                let names = NameResolver::read(&mut reader, &d2)?;
+                let message = reader.rest();
+                if kind == KIND_CLASS {
+                    KotlinDeclaration::Class(read::read_class(message, &names)?)
+                } else {
+                    KotlinDeclaration::Package(read::read_package(message, &names)?)
+                }
+            }
+            KIND_SYNTHETIC_CLASS => KotlinDeclaration::Synthetic,
+            KIND_MULTIFILE_CLASS => KotlinDeclaration::MultiFileFacade(d1),
+            _ => return Err(KotlinError::UnknownKind(kind)),
+        };
+
+        Ok(Self {
+            version,
+            declaration,
+        })
+    }
+}
+
+fn strings(annotation: &SmaliAnnotation, name: &'static str) -> KotlinResult<Vec<String>> {
+    match annotation.element(name) {
+        None => Ok(vec![]),
+        Some(AnnotationValue::Array(values)) => values
+            .iter()
+            .map(|value| match value {
+                AnnotationValue::String(string) => Ok(string.clone()),
+                _ => Err(KotlinError::InvalidElement(name)),
+            })
+            .collect(),
+        Some(_) => Err(KotlinError::InvalidElement(name)),
+    }
+}
@@ -0,0 +1,436 @@
+use crate::class_path::ClassPath;
+use std::fmt;
+
+/// The declarations the kotlin compiler keeps in the `kotlin.Metadata` annotation
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KotlinMetadata {
+    /// the metadata version `mv`
+    pub version: Vec<i32>,
+    pub declaration: KotlinDeclaration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KotlinDeclaration {
+    Class(KotlinClass),
+    /// top level declarations of a file facade (`UtilKt`) or a part of a multi-file class
+    Package(KotlinPackage),
+    /// a facade of `@JvmMultifileClass` files, lists the internal names of its parts
+    MultiFileFacade(Vec<String>),
+    /// lambdas, `DefaultImpls`, `WhenMappings` and other compiler generated classes
+    Synthetic,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KotlinClass {
+    /// the class id, packages are separated by `/` and nested classes by `.` (`bttv/Outer.Inner`)
+    pub name: String,
+    pub kind: KotlinClassKind,
+    pub visibility: KotlinVisibility,
+    pub modality: KotlinModality,
+    pub is_inner: bool,
+    pub is_data: bool,
+    pub is_value: bool,
+    pub is_fun: bool,
+    pub type_parameters: Vec<KotlinTypeParameter>,
+    pub supertypes: Vec<KotlinType>,
+    /// the simple name of the companion object, usually `Companion`
+    pub companion_object: Option<String>,
+    /// simple names of the nested classes
+    pub nested_classes: Vec<String>,
+    /// the enum constants in declaration order
+    pub enum_entries: Vec<String>,
+    /// class ids of the direct subclasses of a sealed class
+    pub sealed_subclasses: Vec<String>,
+    pub constructors: Vec<KotlinConstructor>,
+    pub functions: Vec<KotlinFunction>,
+    pub properties: Vec<KotlinProperty>,
+}
+
+impl KotlinClass {
+    /// The JVM class path of the class id
+    pub fn class_path(&self) -> Option<ClassPath> {
+        class_id_to_path(&self.name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KotlinPackage {
+    pub functions: Vec<KotlinFunction>,
+    pub properties: Vec<KotlinProperty>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KotlinClassKind {
+    Class,
+    Interface,
+    EnumClass,
+    EnumEntry,
+    AnnotationClass,
+    Object,
+    CompanionObject,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KotlinVisibility {
+    Internal,
+    Private,
+    Protected,
+    Public,
+    PrivateToThis,
+    Local,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KotlinModality {
+    Final,
+    Open,
+    Abstract,
+    Sealed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KotlinVariance {
+    Invariant,
+    In,
+    Out,
+}
+
+/// The name and descriptor of the JVM member a declaration is compiled to,
+/// descriptors are `(I)V` for methods and `I` for fields
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct JvmSignature {
+    pub name: String,
+    pub descriptor: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KotlinConstructor {
+    pub visibility: KotlinVisibility,
+    pub is_secondary: bool,
+    pub parameters: Vec<KotlinParameter>,
+    pub signature: Option<JvmSignature>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KotlinFunction {
+    pub name: String,
+    pub visibility: KotlinVisibility,
+    pub modality: KotlinModality,
+    /// compiled with a trailing `Continuation` parameter and an `Object` return type
+    pub is_suspend: bool,
+    pub is_inline: bool,
+    pub is_operator: bool,
+    pub is_infix: bool,
+    pub type_parameters: Vec<KotlinTypeParameter>,
+    pub receiver: Option<KotlinType>,
+    pub parameters: Vec<KotlinParameter>,
+    pub return_type: KotlinType,
+    pub signature: Option<JvmSignature>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KotlinProperty {
+    pub name: String,
+    pub visibility: KotlinVisibility,
+    pub modality: KotlinModality,
+    pub is_var: bool,
+    pub is_const: bool,
+    pub is_lateinit: bool,
+    pub is_delegated: bool,
+    pub type_parameters: Vec<KotlinTypeParameter>,
+    pub receiver: Option<KotlinType>,
+    pub data_type: KotlinType,
+    /// the backing field, for companion properties it is a static field of the outer class
+    pub field: Option<JvmSignature>,
+    pub getter: Option<JvmSignature>,
+    pub setter: Option<JvmSignature>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KotlinParameter {
+    pub name: String,
+    pub data_type: KotlinType,
+    /// the element type of a `vararg` parameter, `data_type` is the array then
+    pub vararg_element_type: Option<KotlinType>,
+    pub has_default: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KotlinTypeParameter {
+    pub name: String,
+    pub variance: KotlinVariance,
+    pub is_reified: bool,
+    pub upper_bounds: Vec<KotlinType>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KotlinType {
+    pub classifier: KotlinClassifier,
+    pub arguments: Vec<KotlinTypeArgument>,
+    pub is_nullable: bool,
+    /// a suspend function type like `suspend () -> Unit`
+    pub is_suspend: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KotlinClassifier {
+    /// a class id like `kotlin/collections/List`
+    Class(String),
+    TypeParameter(String),
+    TypeAlias(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KotlinTypeArgument {
+    Star,
+    Projection(KotlinVariance, KotlinType),
+}
+
+/// Converts a class id like `bttv/Outer.Inner` into the class path `bttv.Outer$Inner`
+pub fn class_id_to_path(class_id: &str) -> Option<ClassPath> {
+    let (package, names) = class_id.rsplit_once('/').unwrap_or(("", class_id));
+    let names = names.replace('.', "$");
+    let java = if package.is_empty() {
+        names
+    } else {
+        format!("{}.{}", package.replace('/', "."), names)
+    };
+    ClassPath::from_java(&java).ok()
+}
+
+/// The name of a class id without its package, nested classes keep their outer names
+fn simple_name(class_id: &str) -> &str {
+    class_id.rsplit_once('/').map_or(class_id, |(_, name)| name)
+}
+
+fn visibility_prefix(visibility: KotlinVisibility) -> &'static str {
+    match visibility {
+        KotlinVisibility::Public => "",
+        KotlinVisibility::Internal => "internal ",
+        KotlinVisibility::Private | KotlinVisibility::PrivateToThis => "private ",
+        KotlinVisibility::Protected => "protected ",
+        KotlinVisibility::Local => "local ",
+    }
+}
+
+fn write_list<T: fmt::Display>(f: &mut fmt::Formatter, items: &[T]) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        write!(f, "{}", item)?;
+    }
+    Ok(())
+}
+
+fn write_type_parameters(
+    f: &mut fmt::Formatter,
+    parameters: &[KotlinTypeParameter],
+) -> fmt::Result {
+    if parameters.is_empty() {
+        return Ok(());
+    }
+    f.write_str("<")?;
+    write_list(f, parameters)?;
+    f.write_str("> ")
+}
+
+impl fmt::Display for KotlinVariance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Invariant => "",
+            Self::In => "in ",
+            Self::Out => "out ",
+        })
+    }
+}
+
+impl fmt::Display for KotlinType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_suspend {
+            f.write_str("suspend ")?;
+        }
+        match &self.classifier {
+            KotlinClassifier::Class(name) | KotlinClassifier::TypeAlias(name) => {
+                f.write_str(simple_name(name))?
+            }
+            KotlinClassifier::TypeParameter(name) => f.write_str(name)?,
+        }
+        if !self.arguments.is_empty() {
+            f.write_str("<")?;
+            write_list(f, &self.arguments)?;
+            f.write_str(">")?;
+        }
+        if self.is_nullable {
+            f.write_str("?")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for KotlinTypeArgument {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Star => f.write_str("*"),
+            Self::Projection(variance, data_type) => write!(f, "{}{}", variance, data_type),
+        }
+    }
+}
+
+impl fmt::Display for KotlinTypeParameter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_reified {
+            f.write_str("reified ")?;
+        }
+        write!(f, "{}{}", self.variance, self.name)?;
+        if let [bound] = &self.upper_bounds[..] {
+            write!(f, " : {}", bound)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for KotlinParameter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.vararg_element_type {
+            Some(element) => write!(f, "vararg {}: {}", self.name, element)?,
+            None => write!(f, "{}: {}", self.name, self.data_type)?,
+        }
+        if self.has_default {
+            f.write_str(" = …")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for KotlinConstructor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}constructor(", visibility_prefix(self.visibility))?;
+        write_list(f, &self.parameters)?;
+        f.write_str(")")
+    }
+}
+
+impl fmt::Display for KotlinFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(visibility_prefix(self.visibility))?;
+        for (is_set, modifier) in [
+            (self.modality == KotlinModality::Abstract, "abstract "),
+            (self.modality == KotlinModality::Open, "open "),
+            (self.is_inline, "inline "),
+            (self.is_operator, "operator "),
+            (self.is_infix, "infix "),
+            (self.is_suspend, "suspend "),
+        ] {
+            if is_set {
+                f.write_str(modifier)?;
+            }
+        }
+        f.write_str("fun ")?;
+        write_type_parameters(f, &self.type_parameters)?;
+        if let Some(receiver) = &self.receiver {
+            write!(f, "{}.", receiver)?;
+        }
+        write!(f, "{}(", self.name)?;
+        write_list(f, &self.parameters)?;
+        write!(f, "): {}", self.return_type)
+    }
+}
+
+impl fmt::Display for KotlinProperty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(visibility_prefix(self.visibility))?;
+        for (is_set, modifier) in [
+            (self.modality == KotlinModality::Abstract, "abstract "),
+            (self.modality == KotlinModality::Open, "open "),
+            (self.is_const, "const "),
+            (self.is_lateinit, "lateinit "),
+        ] {
+            if is_set {
+                f.write_str(modifier)?;
+            }
+        }
+        f.write_str(if self.is_var { "var " } else { "val " })?;
+        write_type_parameters(f, &self.type_parameters)?;
+        if let Some(receiver) = &self.receiver {
+            write!(f, "{}.", receiver)?;
+        }
+        write!(f, "{}: {}", self.name, self.data_type)?;
+        if self.is_delegated {
+            f.write_str(" by …")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn class_type(name: &str, is_nullable: bool) -> KotlinType {
+        KotlinType {
+            classifier: KotlinClassifier::Class(name.to_string()),
+            arguments: vec![],
+            is_nullable,
+            is_suspend: false,
+        }
+    }
+
+    #[test]
+    fn class_paths() {
+        assert_eq!(
+            class_id_to_path("bttv/Outer.Inner").unwrap(),
+            "bttv.Outer$Inner"
+        );
+        assert_eq!(class_id_to_path("Top").unwrap(), "Top");
+    }
+
+    #[test]
+    fn render() {
+        let mut list = class_type("kotlin/collections/List", true);
+        list.arguments.push(KotlinTypeArgument::Projection(
+            KotlinVariance::Out,
+            class_type("kotlin/collections/Map.Entry", false),
+        ));
+        let function = KotlinFunction {
+            name: "load".to_string(),
+            visibility: KotlinVisibility::Internal,
+            modality: KotlinModality::Final,
+            is_suspend: true,
+            is_inline: false,
+            is_operator: false,
+            is_infix: false,
+            type_parameters: vec![],
+            receiver: None,
+            parameters: vec![KotlinParameter {
+                name: "id".to_string(),
+                data_type: class_type("kotlin/String", false),
+                vararg_element_type: None,
+                has_default: true,
+            }],
+            return_type: list,
+            signature: None,
+        };
+        assert_eq!(
+            function.to_string(),
+            "internal suspend fun load(id: String = …): List<out Map.Entry>?"
+        );
+    }
+}
@@ -0,0 +1,261 @@
+//! The string encoding of `d1` and the JVM string table that resolves names against `d2`
+
+use super::proto::{self, Reader};
+use crate::err::*;
+use std::convert::TryFrom;
+
+const UTF8_MODE_MARKER: char = '\u{0}';
+const BIT7_MODE_MARKER: char = '\u{ffff}';
+
+/// Strings that records can refer to by index instead of repeating them in `d2`
+const PREDEFINED_STRINGS: [&str; 44] = [
+    "kotlin/Any",
+    "kotlin/Nothing",
+    "kotlin/Unit",
+    "kotlin/Throwable",
+    "kotlin/Number",
+    "kotlin/Byte",
+    "kotlin/Double",
+    "kotlin/Float",
+    "kotlin/Int",
+    "kotlin/Long",
+    "kotlin/Short",
+    "kotlin/Boolean",
+    "kotlin/Char",
+    "kotlin/CharSequence",
+    "kotlin/String",
+    "kotlin/Comparable",
+    "kotlin/Enum",
+    "kotlin/Array",
+    "kotlin/ByteArray",
+    "kotlin/DoubleArray",
+    "kotlin/FloatArray",
+    "kotlin/IntArray",
+    "kotlin/LongArray",
+    "kotlin/ShortArray",
+    "kotlin/BooleanArray",
+    "kotlin/CharArray",
+    "kotlin/Cloneable",
+    "kotlin/Annotation",
+    "kotlin/collections/Iterable",
+    "kotlin/collections/MutableIterable",
+    "kotlin/collections/Collection",
+    "kotlin/collections/MutableCollection",
+    "kotlin/collections/List",
+    "kotlin/collections/MutableList",
+    "kotlin/collections/Set",
+    "kotlin/collections/MutableSet",
+    "kotlin/collections/Map",
+    "kotlin/collections/MutableMap",
+    "kotlin/collections/Map.Entry",
+    "kotlin/collections/MutableMap.MutableEntry",
+    "kotlin/collections/Iterator",
+    "kotlin/collections/MutableIterator",
+    "kotlin/collections/ListIterator",
+    "kotlin/collections/MutableListIterator",
+];
+
+/// Decodes the `d1` strings into the protobuf bytes
+///
+/// Current compilers mark the first string with `\0` and store one byte per char,
+/// older ones pack 7 bits per char
+pub fn decode_bytes(d1: &[String]) -> KotlinResult<Vec<u8>> {
+    let mut chars = d1.iter().flat_map(|string| string.chars()).peekable();
+
+    if chars.peek() == Some(&UTF8_MODE_MARKER) {
+        chars.next();
+        return chars
+            .map(|ch| u8::try_from(u32::from(ch)).map_err(|_| KotlinError::InvalidEncoding()))
+            .collect();
+    }
+    if chars.peek() == Some(&BIT7_MODE_MARKER) {
+        chars.next();
+    }
+
+    let bytes = chars
+        .map(|ch| {
+            // adding 0x7f modulo 128 undoes the shift that keeps the chars out of the 0 byte
+            let byte = u8::try_from(u32::from(ch)).map_err(|_| KotlinError::InvalidEncoding())?;
+            Ok(byte.wrapping_add(0x7f) & 0x7f)
+        })
+        .collect::<KotlinResult<Vec<u8>>>()?;
+    Ok(decode_7to8(&bytes))
+}
+
+fn decode_7to8(data: &[u8]) -> Vec<u8> {
+    let len = 7 * data.len() / 8;
+    let mut result = Vec::with_capacity(len);
+    let mut index = 0;
+    let mut bit = 0;
+    for _ in 0..len {
+        let first = u32::from(data[index]) >> bit;
+        index += 1;
+        let second = (u32::from(data[index]) & ((1 << (bit + 1)) - 1)) << (7 - bit);
+        // only the low byte is kept, like the java byte cast
+        result.push((first + second) as u8);
+        if bit == 6 {
+            index += 1;
+            bit = 0;
+        } else {
+            bit += 1;
+        }
+    }
+    result
+}
+
+#[derive(Default, Clone)]
+struct Record {
+    predefined_index: Option<i32>,
+    string: Option<String>,
+    operation: i32,
+    substring_index: Vec<i32>,
+    replace_char: Vec<i32>,
+}
+
+/// Resolves the string indices of the metadata, the `JvmNameResolver` of the kotlin compiler
+pub struct NameResolver<'a> {
+    strings: &'a [String],
+    records: Vec<Record>,
+}
+
+impl<'a> NameResolver<'a> {
+    /// Reads the `StringTableTypes` message that precedes the declarations in `d1`
+    pub fn read(reader: &mut Reader, strings: &'a [String]) -> KotlinResult<Self> {
+        let mut records = vec![];
+
+        // field 5 lists the local class names, they are not needed
+        for (number, value) in proto::fields(reader.delimited()?)? {
+            if number != 1 {
+                continue;
+            }
+            let mut range = 1;
+            let mut record = Record::default();
+            for (number, value) in proto::fields(value.bytes()?)? {
+                match number {
+                    1 => range = value.int()?,
+                    2 => record.predefined_index = Some(value.int()?),
+                    3 => record.operation = value.int()?,
+                    4 => record.substring_index.extend(value.ints()?),
+                    5 => record.replace_char.extend(value.ints()?),
+                    6 => {
+                        let bytes = value.bytes()?;
+                        let string = String::from_utf8(bytes.to_vec())
+                            .map_err(|_| KotlinError::InvalidProtobuf("invalid string"))?;
+                        record.string = Some(string);
+                    }
+                    _ => {}
+                }
+            }
+            // a record applies to `range` consecutive strings
+            for _ in 0..range {
+                records.push(record.clone());
+            }
+        }
+
+        Ok(Self { strings, records })
+    }
+
+    pub fn string(&self, index: i32) -> KotlinResult<String> {
+        let position = usize::try_from(index).map_err(|_| KotlinError::InvalidString(index))?;
+        let record = self.records.get(position).cloned().unwrap_or_default();
+
+        let mut string = if let Some(string) = record.string {
+            string
+        } else if let Some(predefined) = record
+            .predefined_index
+            .and_then(|index| usize::try_from(index).ok())
+            .and_then(|index| PREDEFINED_STRINGS.get(index))
+        {
+            predefined.to_string()
+        } else {
+            self.strings
+                .get(position)
+                .ok_or(KotlinError::InvalidString(index))?
+                .clone()
+        };
+
+        if let [begin, end, ..] = record.substring_index[..] {
+            let begin = usize::try_from(begin).unwrap_or_default();
+            let end = usize::try_from(end).unwrap_or_default();
+            string = string
+                .chars()
+                .skip(begin)
+                .take(end.saturating_sub(begin))
+                .collect();
+        }
+        if let [from, to, ..] = record.replace_char[..] {
+            let from = u32::try_from(from).ok().and_then(char::from_u32);
+            let to = u32::try_from(to).ok().and_then(char::from_u32);
+            if let (Some(from), Some(to)) = (from, to) {
+                string = string.replace(from, &to.to_string());
+            }
+        }
+
+        match record.operation {
+            // INTERNAL_TO_CLASS_ID
+            1 => string = string.replace('$', "."),
+            // DESC_TO_CLASS_ID
+            2 => {
+                if let Some(inner) = string.get(1..string.len().saturating_sub(1)) {
+                    string = inner.to_string();
+                }
+                string = string.replace('$', ".");
+            }
+            _ => {}
+        }
+
+        Ok(string)
+    }
+
+    /// A class id like `kotlin/collections/Map.Entry`, nested classes are separated by `.`
+    pub fn class_name(&self, index: i32) -> KotlinResult<String> {
+        self.string(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utf8_mode() {
+        let d1 = vec!["\u{0}\u{6}\u{ff}".to_string(), "\u{1}".to_string()];
+        assert_eq!(decode_bytes(&d1).unwrap(), vec![6, 0xff, 1]);
+        assert!(decode_bytes(&["\u{0}\u{100}".to_string()]).is_err());
+    }
+
+    #[test]
+    fn bit7_mode() {
+        // 8 chars of 7 bits hold 7 bytes, every char is stored shifted by one
+        let bytes = [0x12u8, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde];
+        let mut bits = 0u64;
+        for (i, byte) in bytes.iter().enumerate() {
+            bits |= u64::from(*byte) << (8 * i);
+        }
+        let encoded: String = (0..8)
+            .map(|i| char::from((((bits >> (7 * i)) & 0x7f) as u8 + 1) & 0x7f))
+            .collect();
+        assert_eq!(decode_bytes(&[encoded]).unwrap(), bytes.to_vec());
+    }
+
+    #[test]
+    fn records() {
+        // records: 2x plain, predefined kotlin/String, "Lbttv/A$B;" as DESC_TO_CLASS_ID
+        let table = [
+            0x0f, 0x0a, 0x02, 0x08, 0x02, 0x0a, 0x02, 0x10, 0x0e, 0x0a, 0x02, 0x18, 0x02, 0x2a,
+            0x01, 0x06,
+        ];
+        let strings = vec![
+            "Lbttv/Test;".to_string(),
+            "run".to_string(),
+            "".to_string(),
+            "Lbttv/A$B;".to_string(),
+        ];
+        let mut reader = Reader::new(&table);
+        let names = NameResolver::read(&mut reader, &strings).unwrap();
+        assert_eq!(names.string(1).unwrap(), "run");
+        assert_eq!(names.class_name(2).unwrap(), "kotlin/String");
+        assert_eq!(names.class_name(3).unwrap(), "bttv/A.B");
+        assert!(names.string(4).is_err());
+    }
+}
@@ -0,0 +1,165 @@
+//! A minimal protobuf wire format reader, messages are read into their raw fields and
+//! interpreted by the callers that know the schema
+
+use crate::err::*;
+use std::convert::TryFrom;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    // fixed width values are not used by the kotlin schema, they are skipped
+    Fixed,
+}
+
+impl<'a> Value<'a> {
+    /// int32 fields, negative values are sign extended to ten bytes and truncate back
+    pub fn int(self) -> KotlinResult<i32> {
+        match self {
+            Self::Varint(value) => Ok(value as i32),
+            _ => Err(KotlinError::InvalidProtobuf("expected a varint")),
+        }
+    }
+
+    pub fn bool(self) -> KotlinResult<bool> {
+        Ok(self.int()? != 0)
+    }
+
+    pub fn bytes(self) -> KotlinResult<&'a [u8]> {
+        match self {
+            Self::Bytes(bytes) => Ok(bytes),
+            _ => Err(KotlinError::InvalidProtobuf(
+                "expected a length delimited field",
+            )),
+        }
+    }
+
+    /// repeated int32 fields, either packed into one field or one varint per field
+    pub fn ints(self) -> KotlinResult<Vec<i32>> {
+        match self {
+            Self::Varint(_) => Ok(vec![self.int()?]),
+            Self::Bytes(bytes) => {
+                let mut reader = Reader::new(bytes);
+                let mut ints = vec![];
+                while !reader.is_empty() {
+                    ints.push(reader.varint()? as i32);
+                }
+                Ok(ints)
+            }
+            Self::Fixed => Err(KotlinError::InvalidProtobuf("expected packed varints")),
+        }
+    }
+}
+
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    pub fn varint(&mut self) -> KotlinResult<u64> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = *self
+                .bytes
+                .get(self.pos)
+                .ok_or(KotlinError::InvalidProtobuf("unexpected end of message"))?;
+            self.pos += 1;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(KotlinError::InvalidProtobuf("varint is too long"))
+    }
+
+    fn take(&mut self, len: usize) -> KotlinResult<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(KotlinError::InvalidProtobuf("unexpected end of message"))?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    /// A length prefixed message like java's `parseDelimitedFrom` reads it
+    pub fn delimited(&mut self) -> KotlinResult<&'a [u8]> {
+        let len = self.varint()?;
+        self.take(usize::try_from(len).map_err(|_| KotlinError::InvalidProtobuf("too long"))?)
+    }
+
+    /// The remaining bytes
+    pub fn rest(&mut self) -> &'a [u8] {
+        let rest = &self.bytes[self.pos.min(self.bytes.len())..];
+        self.pos = self.bytes.len();
+        rest
+    }
+
+    fn field(&mut self) -> KotlinResult<(u32, Value<'a>)> {
+        let key = self.varint()?;
+        let number = u32::try_from(key >> 3)
+            .map_err(|_| KotlinError::InvalidProtobuf("invalid field number"))?;
+        let value = match key & 0x7 {
+            0 => Value::Varint(self.varint()?),
+            1 => {
+                self.take(8)?;
+                Value::Fixed
+            }
+            2 => Value::Bytes(self.delimited()?),
+            5 => {
+                self.take(4)?;
+                Value::Fixed
+            }
+            _ => return Err(KotlinError::InvalidProtobuf("unsupported wire type")),
+        };
+        Ok((number, value))
+    }
+}
+
+/// Splits a message into its fields in wire order
+pub fn fields(bytes: &[u8]) -> KotlinResult<Vec<(u32, Value<'_>)>> {
+    let mut reader = Reader::new(bytes);
+    let mut fields = vec![];
+    while !reader.is_empty() {
+        fields.push(reader.field()?);
+    }
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wire_types() {
+        // 1: 150, 2: "ab", 3: fixed32, 4: packed [1, 300], 5: -1
+        let bytes = [
+            0x08, 0x96, 0x01, 0x12, 0x02, b'a', b'b', 0x1d, 0, 0, 0, 0, 0x22, 0x03, 0x01, 0xac,
+            0x02, 0x28, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01,
+        ];
+        let fields = fields(&bytes).unwrap();
+        assert_eq!(fields.len(), 5);
+        assert_eq!(fields[0].0, 1);
+        assert_eq!(fields[0].1.int().unwrap(), 150);
+        assert_eq!(fields[1].1.bytes().unwrap(), b"ab");
+        assert!(matches!(fields[2].1, Value::Fixed));
+        assert_eq!(fields[3].1.ints().unwrap(), vec![1, 300]);
+        assert_eq!(fields[4].1.int().unwrap(), -1);
+    }
+
+    #[test]
+    fn truncated() {
+        assert!(fields(&[0x12, 0x05, b'a']).is_err());
+        assert!(fields(&[0x08, 0x96]).is_err());
+    }
+}
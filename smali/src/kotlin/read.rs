@@ -0,0 +1,662 @@
+//! Reads the protobuf messages of `d1` into the declaration model
+//!
+//! Field numbers follow `metadata.proto` and `jvm_metadata.proto` of the kotlin compiler,
+//! unknown fields are skipped so newer compilers stay readable
+
+use super::model::*;
+use super::names::NameResolver;
+use super::proto::{self, Value};
+use crate::err::*;
+use std::convert::TryFrom;
+
+const DEFAULT_FLAGS: i32 = 6;
+const DEFAULT_PROPERTY_FLAGS: i32 = 518;
+
+/// the extension field holding the JVM signature of functions, constructors and properties
+const JVM_SIGNATURE: u32 = 100;
+const TYPE_TABLE: u32 = 30;
+
+pub fn read_class<'a>(bytes: &'a [u8], names: &'a NameResolver<'a>) -> KotlinResult<KotlinClass> {
+    let fields = proto::fields(bytes)?;
+    let (context, type_parameters) = Context::new(names).scope(&fields, 5)?;
+
+    let mut flags = DEFAULT_FLAGS;
+    let mut name = None;
+    let mut class = KotlinClass {
+        name: String::new(),
+        kind: KotlinClassKind::Class,
+        visibility: KotlinVisibility::Public,
+        modality: KotlinModality::Final,
+        is_inner: false,
+        is_data: false,
+        is_value: false,
+        is_fun: false,
+        type_parameters,
+        supertypes: vec![],
+        companion_object: None,
+        nested_classes: vec![],
+        enum_entries: vec![],
+        sealed_subclasses: vec![],
+        constructors: vec![],
+        functions: vec![],
+        properties: vec![],
+    };
+
+    for (number, value) in fields {
+        match number {
+            1 => flags = value.int()?,
+            3 => name = Some(names.class_name(value.int()?)?),
+            4 => class.companion_object = Some(names.string(value.int()?)?),
+            6 => class.supertypes.push(context.read_type(value.bytes()?)?),
+            2 => {
+                for id in value.ints()? {
+                    class.supertypes.push(context.type_by_id(id)?);
+                }
+            }
+            7 => {
+                for index in value.ints()? {
+                    class.nested_classes.push(names.string(index)?);
+                }
+            }
+            8 => class
+                .constructors
+                .push(context.read_constructor(value.bytes()?)?),
+            9 => class.functions.push(context.read_function(value.bytes()?)?),
+            10 => class
+                .properties
+                .push(context.read_property(value.bytes()?)?),
+            13 => {
+                for (number, value) in proto::fields(value.bytes()?)? {
+                    if number == 1 {
+                        class.enum_entries.push(names.string(value.int()?)?);
+                    }
+                }
+            }
+            16 => {
+                for index in value.ints()? {
+                    class.sealed_subclasses.push(names.class_name(index)?);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    class.name = name.ok_or(KotlinError::MissingField("Class.fq_name"))?;
+    class.visibility = visibility(flags);
+    class.modality = modality(flags);
+    class.kind = match (flags >> 6) & 0x7 {
+        0 => KotlinClassKind::Class,
+        1 => KotlinClassKind::Interface,
+        2 => KotlinClassKind::EnumClass,
+        3 => KotlinClassKind::EnumEntry,
+        4 => KotlinClassKind::AnnotationClass,
+        5 => KotlinClassKind::Object,
+        _ => KotlinClassKind::CompanionObject,
+    };
+    class.is_inner = flag(flags, 9);
+    class.is_data = flag(flags, 10);
+    class.is_value = flag(flags, 13);
+    class.is_fun = flag(flags, 14);
+    Ok(class)
+}
+
+pub fn read_package<'a>(
+    bytes: &'a [u8],
+    names: &'a NameResolver<'a>,
+) -> KotlinResult<KotlinPackage> {
+    let fields = proto::fields(bytes)?;
+    let (context, _) = Context::new(names).scope(&fields, 0)?;
+
+    let mut package = KotlinPackage::default();
+    for (number, value) in fields {
+        match number {
+            3 => package
+                .functions
+                .push(context.read_function(value.bytes()?)?),
+            4 => package
+                .properties
+                .push(context.read_property(value.bytes()?)?),
+            _ => {}
+        }
+    }
+    Ok(package)
+}
+
+/// The types of a declaration can be stored inline or as index into the type table
+#[derive(Clone, Default)]
+struct TypeTable<'a> {
+    types: Vec<&'a [u8]>,
+    // types from this index on are nullable, used by old compilers
+    first_nullable: Option<usize>,
+}
+
+/// The names, types and type parameters visible to a declaration
+#[derive(Clone)]
+struct Context<'a> {
+    names: &'a NameResolver<'a>,
+    type_table: TypeTable<'a>,
+    type_parameters: Vec<(i32, String)>,
+}
+
+impl<'a> Context<'a> {
+    fn new(names: &'a NameResolver<'a>) -> Self {
+        Self {
+            names,
+            type_table: TypeTable::default(),
+            type_parameters: vec![],
+        }
+    }
+
+    /// Enters a declaration with the type parameters in field `type_parameter_field` (0 if it
+    /// has none) and its own type table
+    fn scope(
+        &self,
+        fields: &[(u32, Value<'a>)],
+        type_parameter_field: u32,
+    ) -> KotlinResult<(Self, Vec<KotlinTypeParameter>)> {
+        let mut context = self.clone();
+
+        if let Some((_, table)) = fields.iter().find(|(number, _)| *number == TYPE_TABLE) {
+            let mut type_table = TypeTable::default();
+            for (number, value) in proto::fields(table.bytes()?)? {
+                match number {
+                    1 => type_table.types.push(value.bytes()?),
+                    2 => type_table.first_nullable = usize::try_from(value.int()?).ok(),
+                    _ => {}
+                }
+            }
+            context.type_table = type_table;
+        }
+
+        // parameters can be bounded by each other, so all names are known before bounds are read
+        let raw_parameters: Vec<&[u8]> = fields
+            .iter()
+            .filter(|(number, _)| type_parameter_field != 0 && *number == type_parameter_field)
+            .map(|(_, value)| value.bytes())
+            .collect::<KotlinResult<_>>()?;
+        for raw in &raw_parameters {
+            let mut id = 0;
+            let mut name = None;
+            for (number, value) in proto::fields(raw)? {
+                match number {
+                    1 => id = value.int()?,
+                    2 => name = Some(self.names.string(value.int()?)?),
+                    _ => {}
+                }
+            }
+            let name = name.ok_or(KotlinError::MissingField("TypeParameter.name"))?;
+            context.type_parameters.push((id, name));
+        }
+
+        let type_parameters = raw_parameters
+            .into_iter()
+            .map(|raw| context.read_type_parameter(raw))
+            .collect::<KotlinResult<_>>()?;
+        Ok((context, type_parameters))
+    }
+
+    fn read_type_parameter(&self, bytes: &'a [u8]) -> KotlinResult<KotlinTypeParameter> {
+        let mut parameter = KotlinTypeParameter {
+            name: String::new(),
+            variance: KotlinVariance::Invariant,
+            is_reified: false,
+            upper_bounds: vec![],
+        };
+        for (number, value) in proto::fields(bytes)? {
+            match number {
+                2 => parameter.name = self.names.string(value.int()?)?,
+                3 => parameter.is_reified = value.bool()?,
+                4 => parameter.variance = variance(value.int()?),
+                5 => parameter.upper_bounds.push(self.read_type(value.bytes()?)?),
+                6 => {
+                    for id in value.ints()? {
+                        parameter.upper_bounds.push(self.type_by_id(id)?);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(parameter)
+    }
+
+    fn type_by_id(&self, id: i32) -> KotlinResult<KotlinType> {
+        let index = usize::try_from(id).map_err(|_| KotlinError::MissingField("TypeTable.type"))?;
+        let bytes = self
+            .type_table
+            .types
+            .get(index)
+            .ok_or(KotlinError::MissingField("TypeTable.type"))?;
+        let mut data_type = self.read_type(bytes)?;
+        if self
+            .type_table
+            .first_nullable
+            .is_some_and(|first| index >= first)
+        {
+            data_type.is_nullable = true;
+        }
+        Ok(data_type)
+    }
+
+    fn read_type(&self, bytes: &'a [u8]) -> KotlinResult<KotlinType> {
+        let mut flags = 0;
+        let mut is_nullable = false;
+        let mut classifier = None;
+        let mut arguments = vec![];
+
+        for (number, value) in proto::fields(bytes)? {
+            match number {
+                1 => flags = value.int()?,
+                2 => arguments.push(self.read_type_argument(value.bytes()?)?),
+                3 => is_nullable = value.bool()?,
+                6 => {
+                    let name = self.names.class_name(value.int()?)?;
+                    classifier = Some(KotlinClassifier::Class(name));
+                }
+                7 => {
+                    let id = value.int()?;
+                    let name = self
+                        .type_parameters
+                        .iter()
+                        .rev()
+                        .find(|(parameter_id, _)| *parameter_id == id)
+                        .map_or_else(|| format!("T#{}", id), |(_, name)| name.clone());
+                    classifier = Some(KotlinClassifier::TypeParameter(name));
+                }
+                9 => {
+                    let name = self.names.string(value.int()?)?;
+                    classifier = Some(KotlinClassifier::TypeParameter(name));
+                }
+                12 => {
+                    let name = self.names.class_name(value.int()?)?;
+                    classifier = Some(KotlinClassifier::TypeAlias(name));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(KotlinType {
+            classifier: classifier.ok_or(KotlinError::MissingField("Type.classifier"))?,
+            arguments,
+            is_nullable,
+            is_suspend: flag(flags, 0),
+        })
+    }
+
+    fn read_type_argument(&self, bytes: &'a [u8]) -> KotlinResult<KotlinTypeArgument> {
+        let mut projection = 2;
+        let mut data_type = None;
+        for (number, value) in proto::fields(bytes)? {
+            match number {
+                1 => projection = value.int()?,
+                2 => data_type = Some(self.read_type(value.bytes()?)?),
+                3 => data_type = Some(self.type_by_id(value.int()?)?),
+                _ => {}
+            }
+        }
+        if projection == 3 {
+            return Ok(KotlinTypeArgument::Star);
+        }
+        let data_type = data_type.ok_or(KotlinError::MissingField("Type.Argument.type"))?;
+        Ok(KotlinTypeArgument::Projection(
+            variance(projection),
+            data_type,
+        ))
+    }
+
+    fn read_parameter(&self, bytes: &'a [u8]) -> KotlinResult<KotlinParameter> {
+        let mut flags = 0;
+        let mut name = None;
+        let mut data_type = None;
+        let mut vararg_element_type = None;
+        for (number, value) in proto::fields(bytes)? {
+            match number {
+                1 => flags = value.int()?,
+                2 => name = Some(self.names.string(value.int()?)?),
+                3 => data_type = Some(self.read_type(value.bytes()?)?),
+                5 => data_type = Some(self.type_by_id(value.int()?)?),
+                4 => vararg_element_type = Some(self.read_type(value.bytes()?)?),
+                6 => vararg_element_type = Some(self.type_by_id(value.int()?)?),
+                _ => {}
+            }
+        }
+        Ok(KotlinParameter {
+            name: name.ok_or(KotlinError::MissingField("ValueParameter.name"))?,
+            data_type: data_type.ok_or(KotlinError::MissingField("ValueParameter.type"))?,
+            vararg_element_type,
+            has_default: flag(flags, 1),
+        })
+    }
+
+    fn read_constructor(&self, bytes: &'a [u8]) -> KotlinResult<KotlinConstructor> {
+        let mut flags = DEFAULT_FLAGS;
+        let mut parameters = vec![];
+        let mut signature = (None, None);
+        for (number, value) in proto::fields(bytes)? {
+            match number {
+                1 => flags = value.int()?,
+                2 => parameters.push(self.read_parameter(value.bytes()?)?),
+                JVM_SIGNATURE => signature = self.read_signature(value.bytes()?)?,
+                _ => {}
+            }
+        }
+
+        let descriptor = match signature.1 {
+            Some(descriptor) => Some(descriptor),
+            None => default_descriptor(None, &parameters, None),
+        };
+        Ok(KotlinConstructor {
+            visibility: visibility(flags),
+            is_secondary: flag(flags, 4),
+            parameters,
+            signature: descriptor.map(|descriptor| JvmSignature {
+                name: "<init>".to_string(),
+                descriptor,
+            }),
+        })
+    }
+
+    fn read_function(&self, bytes: &'a [u8]) -> KotlinResult<KotlinFunction> {
+        let fields = proto::fields(bytes)?;
+        let (context, type_parameters) = self.scope(&fields, 4)?;
+
+        let mut flags = None;
+        let mut old_flags = None;
+        let mut name = None;
+        let mut receiver = None;
+        let mut parameters = vec![];
+        let mut return_type = None;
+        let mut signature = (None, None);
+        for (number, value) in fields {
+            match number {
+                9 => flags = Some(value.int()?),
+                1 => old_flags = Some(value.int()?),
+                2 => name = Some(context.names.string(value.int()?)?),
+                3 => return_type = Some(context.read_type(value.bytes()?)?),
+                7 => return_type = Some(context.type_by_id(value.int()?)?),
+                5 => receiver = Some(context.read_type(value.bytes()?)?),
+                8 => receiver = Some(context.type_by_id(value.int()?)?),
+                6 => parameters.push(context.read_parameter(value.bytes()?)?),
+                JVM_SIGNATURE => signature = context.read_signature(value.bytes()?)?,
+                _ => {}
+            }
+        }
+
+        let flags = flags.or(old_flags).unwrap_or(DEFAULT_FLAGS);
+        let name = name.ok_or(KotlinError::MissingField("Function.name"))?;
+        let return_type = return_type.ok_or(KotlinError::MissingField("Function.return_type"))?;
+        let (jvm_name, descriptor) = signature;
+        let descriptor = match descriptor {
+            Some(descriptor) => Some(descriptor),
+            None => default_descriptor(receiver.as_ref(), &parameters, Some(&return_type)),
+        };
+
+        Ok(KotlinFunction {
+            signature: descriptor.map(|descriptor| JvmSignature {
+                name: jvm_name.unwrap_or_else(|| name.clone()),
+                descriptor,
+            }),
+            name,
+            visibility: visibility(flags),
+            modality: modality(flags),
+            is_suspend: flag(flags, 13),
+            is_inline: flag(flags, 10),
+            is_operator: flag(flags, 8),
+            is_infix: flag(flags, 9),
+            type_parameters,
+            receiver,
+            parameters,
+            return_type,
+        })
+    }
+
+    fn read_property(&self, bytes: &'a [u8]) -> KotlinResult<KotlinProperty> {
+        let fields = proto::fields(bytes)?;
+        let (context, type_parameters) = self.scope(&fields, 4)?;
+
+        let mut flags = None;
+        let mut old_flags = None;
+        let mut name = None;
+        let mut receiver = None;
+        let mut data_type = None;
+        let mut signature = None;
+        for (number, value) in fields {
+            match number {
+                11 => flags = Some(value.int()?),
+                1 => old_flags = Some(value.int()?),
+                2 => name = Some(context.names.string(value.int()?)?),
+                3 => data_type = Some(context.read_type(value.bytes()?)?),
+                9 => data_type = Some(context.type_by_id(value.int()?)?),
+                5 => receiver = Some(context.read_type(value.bytes()?)?),
+                10 => receiver = Some(context.type_by_id(value.int()?)?),
+                JVM_SIGNATURE => signature = Some(value.bytes()?),
+                _ => {}
+            }
+        }
+
+        let flags = flags.or(old_flags).unwrap_or(DEFAULT_PROPERTY_FLAGS);
+        let name = name.ok_or(KotlinError::MissingField("Property.name"))?;
+        let data_type = data_type.ok_or(KotlinError::MissingField("Property.return_type"))?;
+
+        let mut property = KotlinProperty {
+            name,
+            visibility: visibility(flags),
+            modality: modality(flags),
+            is_var: flag(flags, 8),
+            is_const: flag(flags, 11),
+            is_lateinit: flag(flags, 12),
+            is_delegated: flag(flags, 15),
+            type_parameters,
+            receiver,
+            data_type,
+            field: None,
+            getter: None,
+            setter: None,
+        };
+
+        for (number, value) in proto::fields(signature.unwrap_or_default())? {
+            let (name, descriptor) = context.read_signature(value.bytes()?)?;
+            match number {
+                1 => {
+                    let descriptor = descriptor.or_else(|| type_descriptor(&property.data_type));
+                    property.field = descriptor.map(|descriptor| JvmSignature {
+                        name: name.unwrap_or_else(|| property.name.clone()),
+                        descriptor,
+                    });
+                }
+                3 | 4 => {
+                    // accessors always store their full signature
+                    let accessor = name
+                        .zip(descriptor)
+                        .map(|(name, descriptor)| JvmSignature { name, descriptor });
+                    if number == 3 {
+                        property.getter = accessor;
+                    } else {
+                        property.setter = accessor;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(property)
+    }
+
+    /// Reads a `JvmMethodSignature` or `JvmFieldSignature`, both store a name and a descriptor
+    fn read_signature(&self, bytes: &'a [u8]) -> KotlinResult<(Option<String>, Option<String>)> {
+        let mut name = None;
+        let mut descriptor = None;
+        for (number, value) in proto::fields(bytes)? {
+            match number {
+                1 => name = Some(self.names.string(value.int()?)?),
+                2 => descriptor = Some(self.names.string(value.int()?)?),
+                _ => {}
+            }
+        }
+        Ok((name, descriptor))
+    }
+}
+
+fn flag(flags: i32, bit: u32) -> bool {
+    flags & (1 << bit) != 0
+}
+
+fn visibility(flags: i32) -> KotlinVisibility {
+    match (flags >> 1) & 0x7 {
+        0 => KotlinVisibility::Internal,
+        1 => KotlinVisibility::Private,
+        2 => KotlinVisibility::Protected,
+        3 => KotlinVisibility::Public,
+        4 => KotlinVisibility::PrivateToThis,
+        _ => KotlinVisibility::Local,
+    }
+}
+
+fn modality(flags: i32) -> KotlinModality {
+    match (flags >> 4) & 0x3 {
+        0 => KotlinModality::Final,
+        1 => KotlinModality::Open,
+        2 => KotlinModality::Abstract,
+        _ => KotlinModality::Sealed,
+    }
+}
+
+fn variance(value: i32) -> KotlinVariance {
+    match value {
+        0 => KotlinVariance::In,
+        1 => KotlinVariance::Out,
+        _ => KotlinVariance::Invariant,
+    }
+}
+
+/// The descriptor the compiler derives when it omits the signature, None if a type is not
+/// a plain class. The return type is void for constructors.
+fn default_descriptor(
+    receiver: Option<&KotlinType>,
+    parameters: &[KotlinParameter],
+    return_type: Option<&KotlinType>,
+) -> Option<String> {
+    let mut descriptor = "(".to_string();
+    for data_type in receiver
+        .into_iter()
+        .chain(parameters.iter().map(|parameter| &parameter.data_type))
+    {
+        descriptor.push_str(&type_descriptor(data_type)?);
+    }
+    descriptor.push(')');
+    match return_type {
+        Some(return_type) => descriptor.push_str(&type_descriptor(return_type)?),
+        None => descriptor.push('V'),
+    }
+    Some(descriptor)
+}
+
+/// Maps a type to its JVM descriptor the way the compiler's `ClassMapperLite` does,
+/// nullability and type arguments are ignored
+fn type_descriptor(data_type: &KotlinType) -> Option<String> {
+    match &data_type.classifier {
+        KotlinClassifier::Class(class_id) => Some(class_descriptor(class_id)),
+        _ => None,
+    }
+}
+
+fn class_descriptor(class_id: &str) -> String {
+    const PRIMITIVES: [(&str, &str); 8] = [
+        ("Boolean", "Z"),
+        ("Char", "C"),
+        ("Byte", "B"),
+        ("Short", "S"),
+        ("Int", "I"),
+        ("Float", "F"),
+        ("Long", "J"),
+        ("Double", "D"),
+    ];
+
+    let Some(name) = class_id.strip_prefix("kotlin/") else {
+        return format!("L{};", class_id.replace('.', "$"));
+    };
+    for (primitive, descriptor) in PRIMITIVES {
+        if name == primitive {
+            return descriptor.to_string();
+        }
+        if name.strip_prefix(primitive) == Some("Array") {
+            return format!("[{}", descriptor);
+        }
+    }
+
+    let java = match name {
+        "Unit" => return "V".to_string(),
+        "Any" => "java/lang/Object",
+        "Nothing" => "java/lang/Void",
+        "Annotation" => "java/lang/annotation/Annotation",
+        "String" | "CharSequence" | "Throwable" | "Cloneable" | "Number" | "Comparable"
+        | "Enum" => return format!("Ljava/lang/{};", name),
+        "collections/Iterable" | "collections/MutableIterable" => "java/lang/Iterable",
+        "collections/Map.Entry" | "collections/MutableMap.MutableEntry" => "java/util/Map$Entry",
+        _ => {
+            if let Some(collection) = name.strip_prefix("collections/") {
+                let collection = collection.strip_prefix("Mutable").unwrap_or(collection);
+                if matches!(
+                    collection,
+                    "Iterator" | "Collection" | "List" | "Set" | "Map" | "ListIterator"
+                ) {
+                    return format!("Ljava/util/{};", collection);
+                }
+            }
+            if let Some(arity) = name.strip_prefix("Function") {
+                if arity.parse::<u8>().is_ok_and(|arity| arity <= 22) {
+                    return format!("Lkotlin/jvm/functions/Function{};", arity);
+                }
+            }
+            if let Some(arity) = name.strip_prefix("reflect/KFunction") {
+                if arity.parse::<u8>().is_ok_and(|arity| arity <= 22) {
+                    return "Lkotlin/reflect/KFunction;".to_string();
+                }
+            }
+            if let Some(companion) = name.strip_suffix(".Companion") {
+                if matches!(
+                    companion,
+                    "Char"
+                        | "Byte"
+                        | "Short"
+                        | "Int"
+                        | "Float"
+                        | "Long"
+                        | "Double"
+                        | "String"
+                        | "Enum"
+                ) {
+                    return format!("Lkotlin/jvm/internal/{}CompanionObject;", companion);
+                }
+            }
+            return format!("L{};", class_id.replace('.', "$"));
+        }
+    };
+    format!("L{};", java)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn class_descriptors() {
+        assert_eq!(class_descriptor("kotlin/Int"), "I");
+        assert_eq!(class_descriptor("kotlin/IntArray"), "[I");
+        assert_eq!(class_descriptor("kotlin/Unit"), "V");
+        assert_eq!(class_descriptor("kotlin/Any"), "Ljava/lang/Object;");
+        assert_eq!(class_descriptor("kotlin/String"), "Ljava/lang/String;");
+        assert_eq!(
+            class_descriptor("kotlin/collections/MutableList"),
+            "Ljava/util/List;"
+        );
+        assert_eq!(
+            class_descriptor("kotlin/collections/Map.Entry"),
+            "Ljava/util/Map$Entry;"
+        );
+        assert_eq!(
+            class_descriptor("kotlin/Function1"),
+            "Lkotlin/jvm/functions/Function1;"
+        );
+        assert_eq!(class_descriptor("bttv/Outer.Inner"), "Lbttv/Outer$Inner;");
+    }
+}
@@ -3,28 +3,161 @@
 
 extern crate common;
 
+mod annotation;
+mod apktool;
+#[cfg(feature = "archive")]
+mod archive;
+mod bodies;
+#[cfg(feature = "cache")]
+mod cache;
+mod class_index;
+mod class_path;
+mod enums;
 mod err;
+mod format;
+mod hierarchy;
+pub mod kotlin;
+mod mapping;
 mod parser;
+#[cfg(feature = "serde")]
+pub mod serialization;
 mod smali_class;
+mod strings;
+mod symbol;
+mod synthetic;
+mod tree;
+pub mod visit;
+mod writer;
+mod xref;
 
+pub use annotation::*;
+pub use apktool::{ApktoolInfo, APKTOOL_YML};
+#[cfg(feature = "archive")]
+pub use archive::is_archive;
+pub use bodies::{read_bodies, ClassBodies};
+#[cfg(feature = "cache")]
+pub use cache::{default_cache_file, CacheStats, PARSER_VERSION};
+pub use class_index::{ClassEntry, ClassIndex};
+pub use class_path::*;
+pub use enums::{EnumOrder, SmaliEnum};
+pub use err::*;
+pub use format::{Formatted, Notation};
+pub use hierarchy::{Hierarchy, MemberOwner};
+pub use mapping::{ClassMapping, Direction, FieldMapping, Mapping, MethodMapping};
 pub use smali_class::*;
+pub use strings::*;
+pub use symbol::Symbol;
+pub use synthetic::SyntheticKind;
+pub use tree::{find_smali_files, smali_roots, ParsedFile};
+pub use writer::write_class;
+pub use xref::*;
 
-use err::ParserResult;
-use rayon::prelude::ParallelBridge;
 use std::fs::File;
 use std::io::BufRead;
 use std::io::BufReader;
 
-/// Given a file_path `parse_file` reads the file and parses it's content into a SmaliClass
+/// Given a file_path `parse_file` reads the file and parses it's content into a SmaliClass,
+/// lines that are not valid UTF-8 result in a `ParserError::InvalidUtf8`
 pub fn parse_file(file_path: impl AsRef<std::path::Path>) -> ParserResult<SmaliClass> {
-    let file = File::open(file_path.as_ref())?;
-    let reader = BufReader::new(file);
-    let lines = reader.lines().filter(|l| l.is_ok()).map(Result::unwrap);
+    parser::parse_smali(read_lines(file_path.as_ref())?)
+}
+
+/// Reads the references in all method bodies of a smali file, see `XrefIndex` for whole trees
+pub fn xrefs_file(file_path: impl AsRef<std::path::Path>) -> ParserResult<ClassXrefs> {
+    xref::read_xrefs(read_lines(file_path.as_ref())?)
+}
+
+/// Reads the string literals in all method bodies of a smali file, see `StringIndex` for whole trees
+pub fn strings_file(file_path: impl AsRef<std::path::Path>) -> ParserResult<ClassStrings> {
+    strings::read_strings(read_lines(file_path.as_ref())?)
+}
+
+/// Reads the references and string literals in all method bodies of a smali file at once
+pub fn bodies_file(file_path: impl AsRef<std::path::Path>) -> ParserResult<ClassBodies> {
+    bodies::read_bodies(read_lines(file_path.as_ref())?)
+}
 
-    parser::parse_smali(lines.par_bridge())
+fn read_lines(
+    file_path: &std::path::Path,
+) -> ParserResult<impl Iterator<Item = ParserResult<String>>> {
+    let file = File::open(file_path)?;
+    Ok(split_lines(BufReader::new(file)))
+}
+
+/// Splits into lines like `str::lines`, but reports lines that are not valid UTF-8
+/// as `ParserError::InvalidUtf8`
+fn split_lines(reader: impl BufRead) -> impl Iterator<Item = ParserResult<String>> {
+    reader.split(b'\n').enumerate().map(|(i, line)| {
+        let mut line = line?;
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        String::from_utf8(line).map_err(|_| ParserError::InvalidUtf8(i + 1))
+    })
+}
+
+/// Parses the raw bytes of a smali file like `parse_file` and reads its method bodies
+/// like `bodies_file`
+#[cfg(any(feature = "cache", feature = "archive"))]
+fn read_bytes(bytes: &[u8]) -> class_index::ReadFile {
+    (
+        parser::parse_smali(split_lines(bytes)),
+        bodies::read_bodies(split_lines(bytes)),
+    )
 }
 
 /// Parses a smali class (in form of a String or alike) into a SmaliClass
 pub fn parse_class(class_string: &str) -> ParserResult<SmaliClass> {
-    parser::parse_smali(class_string.lines().par_bridge())
+    parser::parse_smali(class_string.lines().map(Ok))
+}
+
+/// Parses an enum class and orders its constants by the ordinals in its `<clinit>`,
+/// returns None if the file does not contain an enum class
+pub fn parse_enum_file(file_path: impl AsRef<std::path::Path>) -> ParserResult<Option<SmaliEnum>> {
+    let smali = std::fs::read_to_string(file_path)?;
+    let class = parse_class(&smali)?;
+    Ok(SmaliEnum::from_smali(&class, &smali))
+}
+
+/// Parses a method descriptor like `(I[J)V` into its parameter and return types
+pub fn parse_method_descriptor(descriptor: &str) -> ParserResult<(Vec<SmaliType>, SmaliType)> {
+    parser::method::parse_descriptor(descriptor)
+}
+
+/// Writes a SmaliClass as smali file to file_path, see `write_class`
+pub fn write_file(class: &SmaliClass, file_path: impl AsRef<std::path::Path>) -> ParserResult<()> {
+    std::fs::write(file_path, write_class(class))?;
+    Ok(())
+}
+
+/// Like `parse_file`, but only reads the class, field and method headers and skips all method bodies.
+/// Results are equal to `parse_file` for valid smali, use this when instructions are not needed.
+pub fn scan_file(file_path: impl AsRef<std::path::Path>) -> ParserResult<SmaliClass> {
+    let bytes = std::fs::read(file_path.as_ref())?;
+    parser::scan::scan_smali(&bytes)
+}
+
+/// Like `parse_class`, but only reads the class, field and method headers from raw bytes
+pub fn scan_class(class_bytes: &[u8]) -> ParserResult<SmaliClass> {
+    parser::scan::scan_smali(class_bytes)
+}
+
+/// Parses all given files with `parse_file`, in parallel on the rayon thread pool
+pub fn parse_files<P: AsRef<std::path::Path> + Sync>(file_paths: &[P]) -> Vec<ParsedFile> {
+    tree::parse_files_with(file_paths, |path| parse_file(path))
+}
+
+/// Parses all given files with `scan_file`, in parallel on the rayon thread pool
+pub fn scan_files<P: AsRef<std::path::Path> + Sync>(file_paths: &[P]) -> Vec<ParsedFile> {
+    tree::parse_files_with(file_paths, |path| scan_file(path))
+}
+
+/// Parses every `.smali` file below dir (e.g. an apktool output directory) in parallel
+pub fn parse_tree(dir: impl AsRef<std::path::Path>) -> ParserResult<Vec<ParsedFile>> {
+    Ok(parse_files(&find_smali_files(dir)?))
+}
+
+/// Scans the headers of every `.smali` file below dir in parallel, see `scan_file`
+pub fn scan_tree(dir: impl AsRef<std::path::Path>) -> ParserResult<Vec<ParsedFile>> {
+    Ok(scan_files(&find_smali_files(dir)?))
 }
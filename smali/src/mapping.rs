@@ -0,0 +1,480 @@
+//! ProGuard/R8 `mapping.txt` files, translating obfuscated names back to the original ones
+//! and the other way around
+//!
+//! ```text
+//! tv.twitch.android.Player -> a.b:
+//!     java.lang.String name -> a
+//!     1:4:void load(int,java.lang.String):12:15 -> b
+//! ```
+
+use crate::class_path::ClassPath;
+use crate::err::*;
+use crate::smali_class::{SmaliClass, SmaliMethod, SmaliType, SmaliValue};
+use crate::symbol::Symbol;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Which names a translation starts from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// obfuscated names to original names
+    Deobfuscate,
+    /// original names to obfuscated names
+    Obfuscate,
+}
+
+/// The mapping of one class and its members, member types use the original class names
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassMapping {
+    pub original: ClassPath,
+    pub obfuscated: ClassPath,
+    pub fields: Vec<FieldMapping>,
+    pub methods: Vec<MethodMapping>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldMapping {
+    pub original: Symbol,
+    pub obfuscated: Symbol,
+    pub data_type: SmaliType,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodMapping {
+    pub original: Symbol,
+    pub obfuscated: Symbol,
+    pub parameter_types: Vec<SmaliType>,
+    pub return_type: SmaliType,
+}
+
+/// A parsed mapping file, classes that are not in the mapping keep their names
+#[derive(Debug, Clone, Default)]
+pub struct Mapping {
+    classes: Vec<ClassMapping>,
+    by_original: HashMap<ClassPath, usize>,
+    by_obfuscated: HashMap<ClassPath, usize>,
+}
+
+impl Mapping {
+    /// Reads and parses a mapping file, see `parse`
+    pub fn from_file(file_path: impl AsRef<Path>) -> MappingResult<Self> {
+        Self::parse(&std::fs::read_to_string(file_path)?)
+    }
+
+    /// Parses the content of a mapping file. Comments and the original line numbers are ignored,
+    /// methods inlined by R8 from other classes are not members of the class they appear in
+    pub fn parse(text: &str) -> MappingResult<Self> {
+        let mut mapping = Self::default();
+        // the line range and obfuscated name of the previous method and whether it was added,
+        // R8 writes the frames of inlined methods before the method they were inlined into
+        let mut previous_method: Option<(&str, &str, bool)> = None;
+
+        for (i, line) in text.lines().enumerate() {
+            let invalid = || MappingError::InvalidLine(i + 1, line.to_string());
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            if !line.starts_with(char::is_whitespace) {
+                let (original, obfuscated) = trimmed
+                    .strip_suffix(':')
+                    .and_then(|line| line.split_once(" -> "))
+                    .ok_or_else(invalid)?;
+                let original = ClassPath::from_java(original.trim()).map_err(|_| invalid())?;
+                let obfuscated = ClassPath::from_java(obfuscated.trim()).map_err(|_| invalid())?;
+                mapping.push(ClassMapping {
+                    original,
+                    obfuscated,
+                    fields: vec![],
+                    methods: vec![],
+                });
+                previous_method = None;
+                continue;
+            }
+
+            let class = mapping
+                .classes
+                .last_mut()
+                .ok_or(MappingError::MemberOutsideClass(i + 1))?;
+            // R8 appends comments with metadata to members as well
+            let member = trimmed
+                .split_once('#')
+                .map_or(trimmed, |(member, _)| member);
+            let (original, obfuscated) = member.split_once(" -> ").ok_or_else(invalid)?;
+            let obfuscated = obfuscated.trim();
+
+            let Some(open) = original.find('(') else {
+                let (data_type, name) = original.trim().split_once(' ').ok_or_else(invalid)?;
+                class.fields.push(FieldMapping {
+                    original: Symbol::intern(name.trim()),
+                    obfuscated: Symbol::intern(obfuscated),
+                    data_type: java_type(data_type).ok_or_else(invalid)?,
+                });
+                previous_method = None;
+                continue;
+            };
+
+            let close = original
+                .rfind(')')
+                .filter(|close| *close > open)
+                .ok_or_else(invalid)?;
+            let head = &original[..open];
+            // `1:4:void load` with the line range of the obfuscated method
+            let (range, head) = match head.rsplit_once(':') {
+                Some((range, head)) => (range, head),
+                None => ("", head),
+            };
+            let (return_type, name) = head.trim().split_once(' ').ok_or_else(invalid)?;
+            let name = name.trim();
+            let parameter_types = original[open + 1..close]
+                .split(',')
+                .map(str::trim)
+                .filter(|parameter| !parameter.is_empty())
+                .map(java_type)
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(invalid)?;
+
+            if let Some((previous_range, previous_name, added)) = previous_method {
+                if added
+                    && !range.is_empty()
+                    && (previous_range, previous_name) == (range, obfuscated)
+                {
+                    // the outer method comes last, it replaces the frame inlined into it
+                    class.methods.pop();
+                }
+            }
+            let method = MethodMapping {
+                original: Symbol::intern(name),
+                obfuscated: Symbol::intern(obfuscated),
+                parameter_types,
+                return_type: java_type(return_type).ok_or_else(invalid)?,
+            };
+            // methods inlined from other classes are qualified, methods split into several
+            // line ranges are listed once per range
+            let add = !name.contains('.') && !class.methods.contains(&method);
+            if add {
+                class.methods.push(method);
+            }
+            previous_method = Some((range, obfuscated, add));
+        }
+
+        Ok(mapping)
+    }
+
+    fn push(&mut self, class: ClassMapping) {
+        let index = self.classes.len();
+        self.by_original.insert(class.original.clone(), index);
+        self.by_obfuscated.insert(class.obfuscated.clone(), index);
+        self.classes.push(class);
+    }
+
+    /// All class mappings in the order of the file
+    pub fn classes(&self) -> &[ClassMapping] {
+        &self.classes
+    }
+
+    /// Finds the mapping of a class by its obfuscated name when deobfuscating
+    /// and by its original name when obfuscating
+    pub fn class(&self, class_path: &ClassPath, direction: Direction) -> Option<&ClassMapping> {
+        let index = match direction {
+            Direction::Deobfuscate => self.by_obfuscated.get(class_path),
+            Direction::Obfuscate => self.by_original.get(class_path),
+        };
+        index.map(|index| &self.classes[*index])
+    }
+
+    /// Translates a class path, classes without mapping keep their name
+    pub fn map_class(&self, class_path: &ClassPath, direction: Direction) -> ClassPath {
+        match self.class(class_path, direction) {
+            Some(class) => class.target(direction).clone(),
+            None => class_path.clone(),
+        }
+    }
+
+    /// Translates the class paths in a type
+    pub fn map_type(&self, data_type: &SmaliType, direction: Direction) -> SmaliType {
+        match data_type {
+            SmaliType::Arr(inner) => SmaliType::Arr(Box::new(self.map_type(inner, direction))),
+            SmaliType::Class(class_path) => SmaliType::Class(self.map_class(class_path, direction)),
+            _ => data_type.clone(),
+        }
+    }
+
+    /// Translates the name of a field declared in the class at class_path,
+    /// all paths are in the names the translation starts from
+    pub fn map_field(
+        &self,
+        class_path: &ClassPath,
+        value: &SmaliValue,
+        direction: Direction,
+    ) -> Symbol {
+        let Some(class) = self.class(class_path, direction) else {
+            return value.name.clone();
+        };
+        let data_type = self.original_type(&value.data_type, direction);
+        class
+            .fields
+            .iter()
+            .find(|field| *field.source(direction) == value.name && field.data_type == data_type)
+            .map_or_else(
+                || value.name.clone(),
+                |field| field.target(direction).clone(),
+            )
+    }
+
+    /// Translates the name of a method declared in the class at class_path,
+    /// overloads are told apart by their parameter types
+    pub fn map_method(
+        &self,
+        class_path: &ClassPath,
+        method: &SmaliMethod,
+        direction: Direction,
+    ) -> Symbol {
+        let Some(class) = self.class(class_path, direction) else {
+            return method.name.clone();
+        };
+        let parameter_types: Vec<_> = method
+            .parameter_types
+            .iter()
+            .map(|parameter| self.original_type(parameter, direction))
+            .collect();
+        let return_type = self.original_type(&method.return_type, direction);
+        class
+            .methods
+            .iter()
+            .find(|mapping| {
+                *mapping.source(direction) == method.name
+                    && mapping.parameter_types == parameter_types
+                    && mapping.return_type == return_type
+            })
+            .map_or_else(
+                || method.name.clone(),
+                |mapping| mapping.target(direction).clone(),
+            )
+    }
+
+    /// Translates a whole class, its class paths, member names and member types.
+    /// Annotation elements are kept as they are.
+    pub fn map_smali_class(&self, class: &SmaliClass, direction: Direction) -> SmaliClass {
+        let mut mapped = class.clone();
+        mapped.class_path = self.map_class(&class.class_path, direction);
+        mapped.super_path = class
+            .super_path
+            .as_ref()
+            .map(|super_path| self.map_class(super_path, direction));
+        mapped.interfaces = class
+            .interfaces
+            .iter()
+            .map(|interface| self.map_class(interface, direction))
+            .collect();
+        for (mapped, value) in mapped.values.iter_mut().zip(&class.values) {
+            mapped.name = self.map_field(&class.class_path, value, direction);
+            mapped.data_type = self.map_type(&value.data_type, direction);
+        }
+        for (mapped, method) in mapped.methods.iter_mut().zip(&class.methods) {
+            mapped.name = self.map_method(&class.class_path, method, direction);
+            mapped.parameter_types = method
+                .parameter_types
+                .iter()
+                .map(|parameter| self.map_type(parameter, direction))
+                .collect();
+            mapped.return_type = self.map_type(&method.return_type, direction);
+        }
+        for annotation in &mut mapped.annotations {
+            annotation.type_path = self.map_class(&annotation.type_path, direction);
+        }
+        mapped
+    }
+
+    /// Member mappings store their types with original names
+    fn original_type(&self, data_type: &SmaliType, direction: Direction) -> SmaliType {
+        match direction {
+            Direction::Deobfuscate => self.map_type(data_type, direction),
+            Direction::Obfuscate => data_type.clone(),
+        }
+    }
+}
+
+impl ClassMapping {
+    fn target(&self, direction: Direction) -> &ClassPath {
+        match direction {
+            Direction::Deobfuscate => &self.original,
+            Direction::Obfuscate => &self.obfuscated,
+        }
+    }
+}
+
+impl FieldMapping {
+    fn source(&self, direction: Direction) -> &Symbol {
+        match direction {
+            Direction::Deobfuscate => &self.obfuscated,
+            Direction::Obfuscate => &self.original,
+        }
+    }
+
+    fn target(&self, direction: Direction) -> &Symbol {
+        match direction {
+            Direction::Deobfuscate => &self.original,
+            Direction::Obfuscate => &self.obfuscated,
+        }
+    }
+}
+
+impl MethodMapping {
+    fn source(&self, direction: Direction) -> &Symbol {
+        match direction {
+            Direction::Deobfuscate => &self.obfuscated,
+            Direction::Obfuscate => &self.original,
+        }
+    }
+
+    fn target(&self, direction: Direction) -> &Symbol {
+        match direction {
+            Direction::Deobfuscate => &self.original,
+            Direction::Obfuscate => &self.obfuscated,
+        }
+    }
+}
+
+/// Parses a type in java source notation (`int`, `java.lang.String[]`)
+fn java_type(name: &str) -> Option<SmaliType> {
+    let name = name.trim();
+    if let Some(inner) = name.strip_suffix("[]") {
+        return Some(SmaliType::Arr(Box::new(java_type(inner)?)));
+    }
+    Some(match name {
+        "void" => SmaliType::Void,
+        "boolean" => SmaliType::Boolean,
+        "byte" => SmaliType::Byte,
+        "char" => SmaliType::Char,
+        "short" => SmaliType::Short,
+        "float" => SmaliType::Float,
+        "double" => SmaliType::Double,
+        "int" => SmaliType::Int,
+        "long" => SmaliType::Long,
+        _ => SmaliType::Class(ClassPath::from_java(name).ok()?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAPPING: &str = "\
+# compiler: R8
+# {\"id\":\"com.android.tools.r8.mapping\",\"version\":\"2.0\"}
+tv.twitch.Player -> a.b:
+# {\"id\":\"sourceFile\",\"fileName\":\"Player.java\"}
+    java.lang.String name -> a
+    tv.twitch.Player$State state -> b
+    1:1:void <init>():10:10 -> <init>
+    1:3:void load(int):20:22 -> a
+    1:3:void load(java.lang.String):30:32 -> b
+    4:4:int tv.twitch.Util.clamp(int):5:5 -> b
+    4:4:void load(java.lang.String):33 -> b
+    5:5:boolean isPlaying() -> c
+    tv.twitch.Player$State[] states(tv.twitch.Player$State) -> d
+tv.twitch.Player$State -> a.c:
+";
+
+    fn class_path(java: &str) -> ClassPath {
+        ClassPath::from_java(java).unwrap()
+    }
+
+    #[test]
+    fn classes() {
+        let mapping = Mapping::parse(MAPPING).unwrap();
+        assert_eq!(mapping.classes().len(), 2);
+
+        let player = &mapping.classes()[0];
+        assert_eq!(player.original, "tv.twitch.Player");
+        assert_eq!(player.obfuscated, "a.b");
+        assert_eq!(player.fields.len(), 2);
+        assert_eq!(
+            player.fields[1].data_type,
+            SmaliType::Class(class_path("tv.twitch.Player$State"))
+        );
+
+        let names: Vec<_> = player
+            .methods
+            .iter()
+            .map(|method| format!("{} -> {}", method.original, method.obfuscated))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "<init> -> <init>",
+                "load -> a",
+                "load -> b",
+                "isPlaying -> c",
+                "states -> d"
+            ]
+        );
+
+        assert_eq!(
+            mapping.map_class(&class_path("a.c"), Direction::Deobfuscate),
+            "tv.twitch.Player$State"
+        );
+        assert_eq!(
+            mapping.map_class(&class_path("tv.twitch.Player"), Direction::Obfuscate),
+            "a.b"
+        );
+        assert_eq!(
+            mapping.map_class(&class_path("java.lang.String"), Direction::Obfuscate),
+            "java.lang.String"
+        );
+    }
+
+    #[test]
+    fn both_directions() {
+        let mapping = Mapping::parse(MAPPING).unwrap();
+        let obfuscated = crate::parse_class(
+            ".class public La/b;
+.super Ljava/lang/Object;
+.field private b:La/c;
+.field private c:I
+.method public a(I)V
+.end method
+.method public b(Ljava/lang/String;)V
+.end method
+.method public d(La/c;)[La/c;
+.end method",
+        )
+        .unwrap();
+
+        let original = mapping.map_smali_class(&obfuscated, Direction::Deobfuscate);
+        assert_eq!(original.class_path, "tv.twitch.Player");
+        assert_eq!(original.values[0].name, "state");
+        assert_eq!(
+            original.values[0].data_type,
+            SmaliType::Class(class_path("tv.twitch.Player$State"))
+        );
+        // not in the mapping
+        assert_eq!(original.values[1].name, "c");
+        assert_eq!(original.methods[0].name, "load");
+        assert_eq!(original.methods[1].name, "load");
+        assert_eq!(original.methods[2].name, "states");
+
+        assert_eq!(
+            mapping.map_smali_class(&original, Direction::Obfuscate),
+            obfuscated
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(matches!(
+            Mapping::parse("    int a -> b"),
+            Err(MappingError::MemberOutsideClass(1))
+        ));
+        assert!(matches!(
+            Mapping::parse("a.B -> c\n"),
+            Err(MappingError::InvalidLine(1, _))
+        ));
+        assert!(matches!(
+            Mapping::parse("a.B -> c:\n    void x(int -> y"),
+            Err(MappingError::InvalidLine(2, _))
+        ));
+    }
+}
@@ -0,0 +1,414 @@
+//! Parsing of class level `.annotation` blocks
+//!
+//! Values are read in the notation baksmali writes, member references are kept as smali strings
+
+use crate::annotation::*;
+use crate::class_path::ClassPath;
+use crate::err::*;
+use crate::smali_class::SmaliType;
+use crate::symbol::Symbol;
+use std::str::FromStr;
+
+const END_SUBANNOTATION: &str = ".end subannotation";
+
+/// Parses the lines of an annotation block, starting with the `.annotation` line and
+/// without the closing `.end annotation`
+pub fn parse_block(lines: &[String]) -> ParserResult<SmaliAnnotation> {
+    let header = lines.first().map_or("", String::as_str);
+    let error = || ParserError::InvalidAnnotation(header.to_string());
+
+    let mut tokens = header
+        .split_ascii_whitespace()
+        .take_while(|token| !token.starts_with('#'));
+    if tokens.next() != Some(".annotation") {
+        return Err(error());
+    }
+    let visibility = tokens
+        .next()
+        .and_then(|token| AnnotationVisibility::from_str(token).ok())
+        .ok_or_else(error)?;
+    let type_path = ClassPath::from_smali(tokens.next().ok_or_else(error)?)?;
+
+    let body = lines[1..].join("\n");
+    let mut cursor = Cursor::new(&body);
+    let elements = cursor.elements(visibility, None)?;
+
+    Ok(SmaliAnnotation {
+        visibility,
+        type_path,
+        elements,
+    })
+}
+
+/// Decodes a quoted string literal with java escapes as written by baksmali (`"a\nä"`),
+/// unpaired surrogates are replaced with U+FFFD
+pub fn parse_string_literal(literal: &str) -> Option<String> {
+    let mut cursor = Cursor::new(literal.trim());
+    if !cursor.rest().starts_with('"') {
+        return None;
+    }
+    let units = cursor.quoted('"').ok()?;
+    cursor.skip_blank();
+    cursor
+        .rest()
+        .is_empty()
+        .then(|| String::from_utf16_lossy(&units))
+}
+
+struct Cursor<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input, pos: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn error(&self) -> ParserError {
+        let rest = self.rest().trim_start();
+        let line = rest.lines().next().unwrap_or(rest);
+        ParserError::InvalidAnnotation(line.to_string())
+    }
+
+    /// skips whitespace and comments
+    fn skip_blank(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if !trimmed.starts_with('#') {
+                return;
+            }
+            self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+        }
+    }
+
+    fn eat(&mut self, prefix: &str) -> bool {
+        if self.rest().starts_with(prefix) {
+            self.pos += prefix.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// a plain token ends at whitespace, a separating `,` or the end of an array
+    fn token(&mut self) -> &'a str {
+        let rest = self.rest();
+        let len = rest
+            .find(|ch: char| ch.is_whitespace() || ch == ',' || ch == '}')
+            .unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    /// reads `name = value` pairs until `end` or the end of the input if `end` is None
+    fn elements(
+        &mut self,
+        visibility: AnnotationVisibility,
+        end: Option<&str>,
+    ) -> ParserResult<Vec<AnnotationElement>> {
+        let mut elements = vec![];
+        loop {
+            self.skip_blank();
+            match end {
+                Some(end) if self.eat(end) => return Ok(elements),
+                None if self.rest().is_empty() => return Ok(elements),
+                _ => {}
+            }
+
+            let rest = self.rest();
+            let len = rest
+                .find(|ch: char| ch.is_whitespace() || ch == '=')
+                .ok_or_else(|| self.error())?;
+            if len == 0 {
+                return Err(self.error());
+            }
+            self.pos += len;
+            self.skip_blank();
+            if !self.eat("=") {
+                return Err(self.error());
+            }
+
+            let value = self.value(visibility)?;
+            elements.push(AnnotationElement {
+                name: Symbol::intern(&rest[..len]),
+                value,
+            });
+        }
+    }
+
+    fn value(&mut self, visibility: AnnotationVisibility) -> ParserResult<AnnotationValue> {
+        self.skip_blank();
+        if self.eat("{") {
+            let mut values = vec![];
+            loop {
+                self.skip_blank();
+                if self.eat("}") {
+                    return Ok(AnnotationValue::Array(values));
+                }
+                values.push(self.value(visibility)?);
+                self.skip_blank();
+                self.eat(",");
+            }
+        }
+        if self.eat(".subannotation") {
+            self.skip_blank();
+            let type_path = ClassPath::from_smali(self.token())?;
+            let elements = self.elements(visibility, Some(END_SUBANNOTATION))?;
+            return Ok(AnnotationValue::Annotation(SmaliAnnotation {
+                visibility,
+                type_path,
+                elements,
+            }));
+        }
+        if self.eat(".enum") {
+            self.skip_blank();
+            return Ok(AnnotationValue::Enum(self.token().to_string()));
+        }
+        if self.rest().starts_with('"') {
+            let units = self.quoted('"')?;
+            let string = String::from_utf16(&units).map_err(|_| self.error())?;
+            return Ok(AnnotationValue::String(string));
+        }
+        if self.rest().starts_with('\'') {
+            let units = self.quoted('\'')?;
+            return match units[..] {
+                [unit] => Ok(AnnotationValue::Char(unit)),
+                _ => Err(self.error()),
+            };
+        }
+
+        let start = self.pos;
+        let token = self.token();
+        parse_plain_value(token).ok_or_else(|| {
+            self.pos = start;
+            self.error()
+        })
+    }
+
+    /// reads a quoted literal with java escapes into UTF-16 code units
+    fn quoted(&mut self, quote: char) -> ParserResult<Vec<u16>> {
+        let error = self.error();
+        let mut chars = self.rest().char_indices().skip(1);
+        let mut units = vec![];
+        let mut buf = [0; 2];
+
+        while let Some((i, ch)) = chars.next() {
+            if ch == quote {
+                self.pos += i + ch.len_utf8();
+                return Ok(units);
+            }
+            if ch != '\\' {
+                units.extend_from_slice(ch.encode_utf16(&mut buf));
+                continue;
+            }
+            let unit = match chars.next().map(|(_, ch)| ch) {
+                Some('n') => u16::from(b'\n'),
+                Some('t') => u16::from(b'\t'),
+                Some('r') => u16::from(b'\r'),
+                Some('b') => 0x8,
+                Some('f') => 0xc,
+                Some('u') => {
+                    let mut code = 0;
+                    for _ in 0..4 {
+                        let digit = chars.next().and_then(|(_, ch)| ch.to_digit(16));
+                        code = code * 16 + digit.ok_or_else(|| self.error())?;
+                    }
+                    // four hex digits always fit
+                    code as u16
+                }
+                Some(ch @ ('"' | '\'' | '\\')) => ch as u16,
+                _ => return Err(error),
+            };
+            units.push(unit);
+        }
+        Err(error)
+    }
+}
+
+/// Parses literals, types and member references that are written as a single token
+fn parse_plain_value(token: &str) -> Option<AnnotationValue> {
+    match token {
+        "" => return None,
+        "null" => return Some(AnnotationValue::Null),
+        "true" => return Some(AnnotationValue::Boolean(true)),
+        "false" => return Some(AnnotationValue::Boolean(false)),
+        _ => {}
+    }
+    if token.contains('@') {
+        return Some(AnnotationValue::MethodHandle(token.to_string()));
+    }
+    if token.contains("->") {
+        return Some(if token.contains('(') {
+            AnnotationValue::Method(token.to_string())
+        } else {
+            AnnotationValue::Field(token.to_string())
+        });
+    }
+    if token.starts_with('(') {
+        return Some(AnnotationValue::MethodType(token.to_string()));
+    }
+    if token.starts_with(|ch: char| ch.is_ascii_digit() || ch == '-' || ch == '+')
+        || matches!(token, "NaN" | "NaNf" | "Infinity" | "Infinityf")
+    {
+        return parse_number(token);
+    }
+    SmaliType::from_str(token).ok().map(AnnotationValue::Type)
+}
+
+/// Integers are written as hex with a `t` (byte), `s` (short) or `L` (long) suffix,
+/// floats with an `f` suffix and doubles without one
+fn parse_number(token: &str) -> Option<AnnotationValue> {
+    let (negative, unsigned) = match token.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, token.strip_prefix('+').unwrap_or(token)),
+    };
+
+    let hex = unsigned
+        .strip_prefix("0x")
+        .or_else(|| unsigned.strip_prefix("0X"));
+    let is_float =
+        hex.is_none() && (unsigned.contains(['.', 'e', 'E']) || unsigned.starts_with(['N', 'I']));
+    if is_float || (hex.is_none() && unsigned.ends_with(['f', 'F', 'd', 'D'])) {
+        return if let Some(float) = token.strip_suffix(['f', 'F']) {
+            float.parse().ok().map(AnnotationValue::Float)
+        } else {
+            let double = token.strip_suffix(['d', 'D']).unwrap_or(token);
+            double.parse().ok().map(AnnotationValue::Double)
+        };
+    }
+
+    let digits = hex.unwrap_or(unsigned);
+    let (digits, suffix) = match digits.char_indices().last()? {
+        (i, suffix @ ('t' | 'T' | 's' | 'S' | 'l' | 'L')) => (&digits[..i], Some(suffix)),
+        _ => (digits, None),
+    };
+    let magnitude = u64::from_str_radix(digits, if hex.is_some() { 16 } else { 10 }).ok()?;
+    // two's complement wrapping keeps values like -0x80000000 and 0xffffffff intact
+    let value = if negative {
+        0i64.wrapping_sub_unsigned(magnitude)
+    } else {
+        magnitude as i64
+    };
+
+    Some(match suffix {
+        Some('t' | 'T') => AnnotationValue::Byte(value as i8),
+        Some('s' | 'S') => AnnotationValue::Short(value as i16),
+        Some(_) => AnnotationValue::Long(value),
+        None => AnnotationValue::Int(value as i32),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(input: &str) -> SmaliAnnotation {
+        let lines: Vec<_> = input.lines().map(str::to_string).collect();
+        parse_block(&lines).unwrap()
+    }
+
+    #[test]
+    fn literals() {
+        let annotation = block(
+            ".annotation runtime Lbttv/Ann;
+    b = 0x7ft
+    s = -0x2s
+    c = '\\u0000'
+    i = -0x80000000
+    l = 0x10L
+    f = 1.5f
+    d = -Infinity
+    z = true
+    n = null
+    str = \"a\\\"b\\n\\u00e4\"",
+        );
+        assert_eq!(annotation.visibility, AnnotationVisibility::Runtime);
+        assert_eq!(annotation.type_path, "bttv.Ann");
+        let values: Vec<_> = annotation
+            .elements
+            .into_iter()
+            .map(|element| element.value)
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                AnnotationValue::Byte(127),
+                AnnotationValue::Short(-2),
+                AnnotationValue::Char(0),
+                AnnotationValue::Int(i32::MIN),
+                AnnotationValue::Long(16),
+                AnnotationValue::Float(1.5),
+                AnnotationValue::Double(f64::NEG_INFINITY),
+                AnnotationValue::Boolean(true),
+                AnnotationValue::Null,
+                AnnotationValue::String("a\"b\n\u{e4}".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn nested() {
+        let annotation = block(
+            ".annotation system Ldalvik/annotation/Test;
+    types = {
+        Lbttv/Util;,
+        [I
+    }
+    empty = {}
+    refs = {
+        .enum Lbttv/Kind;->A:Lbttv/Kind;,
+        Lbttv/Util;->run(I)V,
+        Lbttv/Util;->count:I
+    }
+    inner = .subannotation Lbttv/Inner;
+        value = 0x1
+    .end subannotation",
+        );
+        assert_eq!(
+            annotation.element("types"),
+            Some(&AnnotationValue::Array(vec![
+                AnnotationValue::Type(SmaliType::from_str("Lbttv/Util;").unwrap()),
+                AnnotationValue::Type(SmaliType::Arr(Box::new(SmaliType::Int))),
+            ]))
+        );
+        assert_eq!(
+            annotation.element("empty"),
+            Some(&AnnotationValue::Array(vec![]))
+        );
+        assert_eq!(
+            annotation.element("refs"),
+            Some(&AnnotationValue::Array(vec![
+                AnnotationValue::Enum("Lbttv/Kind;->A:Lbttv/Kind;".to_string()),
+                AnnotationValue::Method("Lbttv/Util;->run(I)V".to_string()),
+                AnnotationValue::Field("Lbttv/Util;->count:I".to_string()),
+            ]))
+        );
+        match annotation.element("inner") {
+            Some(AnnotationValue::Annotation(inner)) => {
+                assert_eq!(inner.visibility, AnnotationVisibility::System);
+                assert_eq!(inner.element("value"), Some(&AnnotationValue::Int(1)));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        let lines = vec![
+            ".annotation runtime Lbttv/Ann;".to_string(),
+            "    value = \"unterminated".to_string(),
+        ];
+        assert!(matches!(
+            parse_block(&lines),
+            Err(ParserError::InvalidAnnotation(_))
+        ));
+        assert!(parse_block(&[".annotation public Lbttv/Ann;".to_string()]).is_err());
+    }
+}
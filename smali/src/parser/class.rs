@@ -1,13 +1,16 @@
+use crate::class_path::ClassPath;
 use crate::err::*;
 use crate::parser::util::*;
 use crate::smali_class::*;
 use std::str::FromStr;
 
 pub fn parse_line(line: &str) -> ParserResult<SmaliClass> {
-    let tokens = line.split_whitespace();
+    let tokens = line.split_ascii_whitespace();
 
     let mut class_path = None;
     let mut is_abstract = false;
+    let mut is_synthetic = false;
+    let mut kind = ClassKind::Class;
     let mut access = SmaliAccessModifier::Package;
 
     for token in tokens {
@@ -20,6 +23,19 @@ pub fn parse_line(line: &str) -> ParserResult<SmaliClass> {
             continue;
         }
 
+        if token == "synthetic" {
+            is_synthetic = true;
+            continue;
+        }
+
+        match token {
+            // annotation types are flagged as `interface` too
+            "interface" if kind == ClassKind::Class => kind = ClassKind::Interface,
+            "annotation" => kind = ClassKind::Annotation,
+            "enum" => kind = ClassKind::Enum,
+            _ => {}
+        }
+
         if let Ok(access_modifier) = SmaliAccessModifier::from_str(token) {
             access = access_modifier;
             continue;
@@ -29,7 +45,7 @@ pub fn parse_line(line: &str) -> ParserResult<SmaliClass> {
             continue;
         }
 
-        class_path = Some(smali_to_java_path(token)?);
+        class_path = Some(ClassPath::from_smali(token)?);
         break;
     }
 
@@ -37,7 +53,9 @@ pub fn parse_line(line: &str) -> ParserResult<SmaliClass> {
         return Err(ParserError::MissingClassPath(line.to_string()));
     }
 
-    let class = SmaliClass::new(class_path.unwrap(), access, is_abstract);
+    let mut class = SmaliClass::new(class_path.unwrap(), access, is_abstract);
+    class.is_synthetic = is_synthetic;
+    class.kind = kind;
     Ok(class)
 }
 
@@ -49,7 +67,7 @@ mod test {
     fn test_simple() {
         let line = ".class Lbttv/test/Util;";
         let expected = SmaliClass::new(
-            "bttv.test.Util".to_string(),
+            ClassPath::from_java("bttv.test.Util").unwrap(),
             SmaliAccessModifier::Package,
             false,
         );
@@ -60,10 +78,25 @@ mod test {
     fn test_abstract() {
         let line = ".class abstract Lbttv/test/Util;";
         let expected = SmaliClass::new(
-            "bttv.test.Util".to_string(),
+            ClassPath::from_java("bttv.test.Util").unwrap(),
             SmaliAccessModifier::Package,
             true,
         );
         assert_eq!(parse_line(line).unwrap(), expected);
     }
+
+    #[test]
+    fn test_kinds() {
+        let kind = |line| parse_line(line).unwrap().kind;
+        assert_eq!(kind(".class public La/B;"), ClassKind::Class);
+        assert_eq!(
+            kind(".class public interface abstract La/B;"),
+            ClassKind::Interface
+        );
+        assert_eq!(
+            kind(".class public interface abstract annotation La/B;"),
+            ClassKind::Annotation
+        );
+        assert_eq!(kind(".class public final enum La/B;"), ClassKind::Enum);
+    }
 }
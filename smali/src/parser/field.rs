@@ -1,6 +1,7 @@
 use crate::err::*;
-use crate::parser::util::is_modifier;
+use crate::parser::util::{is_modifier, unescape};
 use crate::smali_class::*;
+use crate::symbol::Symbol;
 use std::str::FromStr;
 
 pub fn parse_line(line: &str) -> ParserResult<SmaliValue> {
@@ -8,6 +9,8 @@ pub fn parse_line(line: &str) -> ParserResult<SmaliValue> {
 
     let mut is_static = false;
     let mut is_final = false;
+    let mut is_synthetic = false;
+    let mut is_enum = false;
     let mut access = SmaliAccessModifier::Package;
 
     for token in tokens {
@@ -25,6 +28,16 @@ pub fn parse_line(line: &str) -> ParserResult<SmaliValue> {
             continue;
         }
 
+        if token == "synthetic" {
+            is_synthetic = true;
+            continue;
+        }
+
+        if token == "enum" {
+            is_enum = true;
+            continue;
+        }
+
         if let Ok(parsed_access) = SmaliAccessModifier::from_str(token) {
             access = parsed_access;
         }
@@ -35,7 +48,8 @@ pub fn parse_line(line: &str) -> ParserResult<SmaliValue> {
 
         let mut parts = token.splitn(2, ':');
 
-        let name = get_next(&mut parts)?.to_string();
+        let name = unescape(get_next(&mut parts)?).ok_or(ParserError::InvalidField())?;
+        let name = Symbol::intern(&name);
         let typ = SmaliType::from_str(get_next(&mut parts)?)?;
 
         return Ok(SmaliValue {
@@ -44,6 +58,8 @@ pub fn parse_line(line: &str) -> ParserResult<SmaliValue> {
             access,
             is_final,
             is_static,
+            is_synthetic,
+            is_enum,
         });
     }
 
@@ -60,19 +76,23 @@ fn get_next<'a>(split: &'a mut std::str::SplitN<char>) -> ParserResult<&'a str>
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::class_path::ClassPath;
 
     #[test]
     fn simple() {
         let input =
             ".field public mUrlDrawable:Ltv/twitch/android/shared/ui/elements/span/UrlDrawable;";
         let expected = SmaliValue {
-            name: "mUrlDrawable".to_string(),
+            name: Symbol::intern("mUrlDrawable"),
             data_type: SmaliType::Class(
-                "tv.twitch.android.shared.ui.elements.span.UrlDrawable".to_string(),
+                ClassPath::from_java("tv.twitch.android.shared.ui.elements.span.UrlDrawable")
+                    .unwrap(),
             ),
             access: SmaliAccessModifier::Public,
             is_static: false,
             is_final: false,
+            is_synthetic: false,
+            is_enum: false,
         };
         let res = parse_line(input);
         assert!(res.is_ok());
@@ -83,14 +103,23 @@ mod tests {
     fn final_static() {
         let input = ".field private final static mUrlDrawable:I";
         let expected = SmaliValue {
-            name: "mUrlDrawable".to_string(),
+            name: Symbol::intern("mUrlDrawable"),
             data_type: SmaliType::Int,
             access: SmaliAccessModifier::Private,
             is_static: true,
             is_final: true,
+            is_synthetic: false,
+            is_enum: false,
         };
         let res = parse_line(input);
         assert!(res.is_ok());
         assert_eq!(res.unwrap(), expected)
     }
+
+    #[test]
+    fn unicode_name() {
+        let input = ".field private static \\u00e4\u{a0}b:I";
+        let res = parse_line(input).unwrap();
+        assert_eq!(res.name, "\u{e4}\u{a0}b");
+    }
 }
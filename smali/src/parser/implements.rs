@@ -1,8 +1,8 @@
-use super::util::smali_to_java_path;
+use crate::class_path::ClassPath;
 use crate::err::*;
 
-pub fn parse_line(line: &str) -> ParserResult<String> {
-    let tokens = line.split_whitespace();
+pub fn parse_line(line: &str) -> ParserResult<ClassPath> {
+    let tokens = line.split_ascii_whitespace();
     for token in tokens {
         if token.starts_with('#') {
             break; // ignore comments
@@ -12,7 +12,7 @@ pub fn parse_line(line: &str) -> ParserResult<String> {
             continue;
         }
 
-        return smali_to_java_path(token);
+        return ClassPath::from_smali(token);
     }
     Err(ParserError::MissingInterfacePath(line.to_string()))
 }
@@ -1,6 +1,7 @@
-use super::util::is_modifier;
+use super::util::{is_modifier, unescape};
 use crate::err::*;
 use crate::smali_class::*;
+use crate::symbol::Symbol;
 use std::str::FromStr;
 
 pub fn parse_line(line: &str) -> ParserResult<SmaliMethod> {
@@ -11,6 +12,7 @@ pub fn parse_line(line: &str) -> ParserResult<SmaliMethod> {
     let mut return_type = None;
     let mut is_final = false;
     let mut is_static = false;
+    let mut is_synthetic = false;
     let mut access = SmaliAccessModifier::Package;
 
     for token in tokens {
@@ -26,6 +28,10 @@ pub fn parse_line(line: &str) -> ParserResult<SmaliMethod> {
             is_final = true;
         }
 
+        if token == "synthetic" {
+            is_synthetic = true;
+        }
+
         if let Ok(access_modifier) = SmaliAccessModifier::from_str(token) {
             access = access_modifier;
         }
@@ -51,6 +57,7 @@ pub fn parse_line(line: &str) -> ParserResult<SmaliMethod> {
         return_type: return_type.unwrap(),
         is_static,
         is_final,
+        is_synthetic,
         access,
     };
 
@@ -58,14 +65,25 @@ pub fn parse_line(line: &str) -> ParserResult<SmaliMethod> {
 }
 
 /// returns name, params and return type in that order
-fn parse_method(token: &str) -> ParserResult<(String, Vec<SmaliType>, SmaliType)> {
+fn parse_method(token: &str) -> ParserResult<(Symbol, Vec<SmaliType>, SmaliType)> {
     let (name, token) = token.split_once('(').ok_or(ParserError::InvalidMethod())?;
 
     let (params, token) = parse_type_stream(token)?;
 
     let return_t = SmaliType::from_str(token)?;
 
-    Ok((name.to_string(), params, return_t))
+    let name = unescape(name).ok_or(ParserError::InvalidMethod())?;
+
+    Ok((Symbol::intern(&name), params, return_t))
+}
+
+/// parses a method descriptor like `(I[J)V` into its parameter and return types
+pub fn parse_descriptor(descriptor: &str) -> ParserResult<(Vec<SmaliType>, SmaliType)> {
+    let stream = descriptor
+        .strip_prefix('(')
+        .ok_or(ParserError::InvalidMethod())?;
+    let (params, token) = parse_type_stream(stream)?;
+    Ok((params, SmaliType::from_str(token)?))
 }
 
 /// expects a stream of smali types in a &str and parses them, returns once it sees an invalid char
@@ -75,7 +93,7 @@ fn parse_type_stream(stream: &str) -> ParserResult<(Vec<SmaliType>, &str)> {
     let mut vec = vec![];
 
     let mut started_collecting_class_at = None;
-    let mut is_array = false;
+    let mut array_depth = 0;
 
     let mut last_i = 0;
 
@@ -84,8 +102,8 @@ fn parse_type_stream(stream: &str) -> ParserResult<(Vec<SmaliType>, &str)> {
             if char == ';' {
                 let str_to_parse = &stream[started..=i];
                 let parsed = SmaliType::from_str(str_to_parse)?;
-                vec.push(maybe_wrap_in_arr(parsed, is_array));
-                is_array = false;
+                vec.push(wrap_in_arr(parsed, array_depth));
+                array_depth = 0;
                 started_collecting_class_at = None;
             }
             continue;
@@ -97,7 +115,7 @@ fn parse_type_stream(stream: &str) -> ParserResult<(Vec<SmaliType>, &str)> {
         }
 
         if char == '[' {
-            is_array = true;
+            array_depth += 1;
             continue;
         }
 
@@ -107,23 +125,22 @@ fn parse_type_stream(stream: &str) -> ParserResult<(Vec<SmaliType>, &str)> {
             break;
         }
         let parsed = parsed.unwrap();
-        vec.push(maybe_wrap_in_arr(parsed, is_array));
-        is_array = false;
+        vec.push(wrap_in_arr(parsed, array_depth));
+        array_depth = 0;
     }
 
     Ok((vec, &stream[last_i + 1..]))
 }
 
-fn maybe_wrap_in_arr(type_p: SmaliType, should_wrap: bool) -> SmaliType {
-    if !should_wrap {
-        return type_p;
-    }
-    SmaliType::Arr(Box::new(type_p))
+/// wraps type_p in depth array dimensions
+fn wrap_in_arr(type_p: SmaliType, depth: usize) -> SmaliType {
+    (0..depth).fold(type_p, |inner, _| SmaliType::Arr(Box::new(inner)))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::class_path::ClassPath;
 
     #[cfg(test)]
     mod parse_method_tests {
@@ -135,15 +152,30 @@ mod tests {
                 "$values()[Ltv/twitch/android/api/resumewatching/ResumeWatchingApi$VideoType;";
             let res = parse_method(input);
             let (name, params, return_t) = res.unwrap();
-            assert_eq!(name, "$values".to_string());
+            assert_eq!(name, "$values");
             assert!(params.is_empty());
             assert_eq!(
                 return_t,
                 SmaliType::Arr(Box::new(SmaliType::Class(
-                    "tv.twitch.android.api.resumewatching.ResumeWatchingApi$VideoType".to_string()
+                    ClassPath::from_java(
+                        "tv.twitch.android.api.resumewatching.ResumeWatchingApi$VideoType"
+                    )
+                    .unwrap()
                 )))
             );
         }
+
+        #[test]
+        fn escaped_name() {
+            let input = "\\u0430\\u0431(L\\u0430;)V";
+            let (name, params, return_t) = parse_method(input).unwrap();
+            assert_eq!(name, "\u{430}\u{431}");
+            assert_eq!(
+                params,
+                vec![SmaliType::Class(ClassPath::from_java("\u{430}").unwrap())]
+            );
+            assert_eq!(return_t, SmaliType::Void);
+        }
     }
 
     #[cfg(test)]
@@ -183,18 +215,38 @@ mod tests {
             assert_eq!(res.1, "test");
         }
 
+        #[test]
+        fn nested_arr() {
+            let input = "[[I[[[Ltest/Test;BCS)test";
+            let expected = vec![
+                SmaliType::Arr(Box::new(SmaliType::Arr(Box::new(SmaliType::Int)))),
+                SmaliType::Arr(Box::new(SmaliType::Arr(Box::new(SmaliType::Arr(
+                    Box::new(SmaliType::Class(ClassPath::from_java("test.Test").unwrap())),
+                ))))),
+                SmaliType::Byte,
+                SmaliType::Char,
+                SmaliType::Short,
+            ];
+
+            let res = parse_type_stream(input).unwrap();
+            assert_eq!(res.0, expected);
+            assert_eq!(res.1, "test");
+        }
+
         #[test]
         fn complex() {
             let input = "[Ltest/test/Test;VZF[DIJLtest/test/Test;)test";
             let expected = vec![
-                SmaliType::Arr(Box::new(SmaliType::Class("test.test.Test".to_string()))),
+                SmaliType::Arr(Box::new(SmaliType::Class(
+                    ClassPath::from_java("test.test.Test").unwrap(),
+                ))),
                 SmaliType::Void,
                 SmaliType::Boolean,
                 SmaliType::Float,
                 SmaliType::Arr(Box::new(SmaliType::Double)),
                 SmaliType::Int,
                 SmaliType::Long,
-                SmaliType::Class("test.test.Test".to_string()),
+                SmaliType::Class(ClassPath::from_java("test.test.Test").unwrap()),
             ];
 
             let res = parse_type_stream(input).unwrap();
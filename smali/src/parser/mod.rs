@@ -1,82 +1,111 @@
-mod class;
+pub mod annotation;
+pub mod class;
 mod field;
 mod implements;
-mod method;
+pub mod method;
+pub mod scan;
 mod super_p;
 pub mod util;
 
+use crate::annotation::SmaliAnnotation;
+use crate::class_path::ClassPath;
 use crate::err::*;
 use crate::smali_class::*;
-use crossbeam_queue::SegQueue;
-use parking_lot::Mutex;
-use rayon::iter::ParallelIterator;
-use util::set_mutex_once_or_err;
 
 const ERR_TOO_MANY_CLASSES: ParserError = ParserError::TooManyClasses();
 const ERR_TOO_MANY_SUPERS: ParserError = ParserError::TooManySupers();
 
 pub fn parse_smali(
-    lines: impl ParallelIterator<Item = impl AsRef<str> + Send> + Send,
+    lines: impl Iterator<Item = ParserResult<impl AsRef<str>>>,
 ) -> ParserResult<SmaliClass> {
-    let current_class = Mutex::new(None);
-    let super_path = Mutex::new(None);
-    let interfaces = SegQueue::new();
-    let values = SegQueue::new();
-    let methods = SegQueue::new();
-
-    let res: ParserResult<()> = lines
-        .map(|line| parse_line(line.as_ref()))
-        .try_for_each(|line| {
-            match line? {
-                Line::Class(class) => {
-                    set_mutex_once_or_err(&current_class, class, ERR_TOO_MANY_CLASSES)?;
-                }
-                Line::Super(super_p) => {
-                    set_mutex_once_or_err(&super_path, super_p, ERR_TOO_MANY_SUPERS)?;
-                }
-                Line::Implements(interface_path) => {
-                    interfaces.push(interface_path);
-                }
-                Line::Value(value) => {
-                    values.push(value);
-                }
-                Line::Method(method) => {
-                    methods.push(method);
-                }
-                Line::Other => {}
-            }
-            Ok(())
-        });
-
-    if let Err(err) = res {
-        return Err(err);
+    let mut builder = ClassBuilder::default();
+    for line in lines {
+        builder.push_line(line?.as_ref())?;
     }
+    builder.finish()
+}
 
-    let current_class = current_class.into_inner();
+/// Collects the parsed lines of a single class sequentially
+#[derive(Default)]
+struct ClassBuilder {
+    class: Option<SmaliClass>,
+    super_path: Option<ClassPath>,
+    interfaces: Vec<ClassPath>,
+    values: Vec<SmaliValue>,
+    methods: Vec<SmaliMethod>,
+    annotations: Vec<SmaliAnnotation>,
+    // the lines of the class level annotation that is currently read
+    annotation: Option<Vec<String>>,
+}
 
-    if current_class.is_none() {
-        return Err(ParserError::MissingClass());
+impl ClassBuilder {
+    fn push_line(&mut self, line: &str) -> ParserResult<()> {
+        match &mut self.annotation {
+            Some(lines) if line.trim_end() == ".end annotation" => {
+                let annotation = annotation::parse_block(lines)?;
+                self.annotations.push(annotation);
+                self.annotation = None;
+                Ok(())
+            }
+            Some(lines) => {
+                lines.push(line.to_string());
+                Ok(())
+            }
+            None => self.push(parse_line(line)?),
+        }
     }
 
-    // unwrap: guarded by if above
-    let mut current_class = current_class.unwrap();
-
-    current_class.super_path = super_path.into_inner();
+    /// whether the following lines belong to a class level annotation
+    fn in_annotation(&self) -> bool {
+        self.annotation.is_some()
+    }
 
-    current_class.interfaces = interfaces.into_iter().collect();
-    current_class.values = values.into_iter().collect();
-    current_class.methods = methods.into_iter().collect();
+    fn push(&mut self, line: Line) -> ParserResult<()> {
+        match line {
+            Line::Class(class) => {
+                if self.class.is_some() {
+                    return Err(ERR_TOO_MANY_CLASSES);
+                }
+                self.class = Some(class);
+            }
+            Line::Super(super_p) => {
+                if self.super_path.is_some() {
+                    return Err(ERR_TOO_MANY_SUPERS);
+                }
+                self.super_path = Some(super_p);
+            }
+            Line::Implements(interface_path) => self.interfaces.push(interface_path),
+            Line::Value(value) => self.values.push(value),
+            Line::Method(method) => self.methods.push(method),
+            Line::Annotation(header) => self.annotation = Some(vec![header]),
+            Line::Other => {}
+        }
+        Ok(())
+    }
 
-    Ok(current_class)
+    fn finish(mut self) -> ParserResult<SmaliClass> {
+        // a class level annotation without `.end annotation`
+        if let Some(mut lines) = self.annotation.take() {
+            return Err(ParserError::InvalidAnnotation(lines.swap_remove(0)));
+        }
+        let mut class = self.class.ok_or(ParserError::MissingClass())?;
+        class.super_path = self.super_path;
+        class.interfaces = self.interfaces;
+        class.values = self.values;
+        class.methods = self.methods;
+        class.annotations = self.annotations;
+        Ok(class)
+    }
 }
 
 #[derive(Debug)]
 enum Line {
-    Class(SmaliClass),   // class declaration
-    Super(String),       // super class path
-    Implements(String),  // impl. interface path
-    Value(SmaliValue),   // value declaration
-    Method(SmaliMethod), // method head
+    Class(SmaliClass),     // class declaration
+    Super(ClassPath),      // super class path
+    Implements(ClassPath), // impl. interface path
+    Value(SmaliValue),     // value declaration
+    Method(SmaliMethod),   // method head
+    Annotation(String),    // start of a class level annotation
     Other,
 }
 
@@ -93,6 +122,9 @@ fn parse_line(line: &str) -> ParserResult<Line> {
     } else if line.starts_with(".field") {
         let field = field::parse_line(line)?;
         Ok(Line::Value(field))
+    } else if line.starts_with(".annotation") {
+        // member annotations are indented, only class annotations start at the line start
+        Ok(Line::Annotation(line.to_string()))
     } else if line.starts_with(".method") {
         let method = method::parse_line(line)?;
         Ok(Line::Method(method))
@@ -100,3 +132,54 @@ fn parse_line(line: &str) -> ParserResult<Line> {
         Ok(Line::Other)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_class;
+
+    /// a field and a method annotation, indented like baksmali writes them
+    pub(super) const MEMBER_ANNOTATIONS: &str = ".class public Lbttv/test/Util;
+.super Ljava/lang/Object;
+
+.annotation runtime Lbttv/test/Ann;
+.end annotation
+
+.field public count:I
+    .annotation runtime Lbttv/test/FieldAnn;
+        value = 0x1
+    .end annotation
+.end field
+
+.method public run()V
+    .registers 1
+    .annotation runtime Lbttv/test/MethodAnn;
+    .end annotation
+
+    return-void
+.end method
+";
+
+    #[test]
+    fn member_annotations() {
+        let class = parse_class(MEMBER_ANNOTATIONS).unwrap();
+        assert_eq!(class.annotations.len(), 1);
+        assert_eq!(class.annotations[0].type_path, "bttv.test.Ann");
+        assert_eq!(class.values.len(), 1);
+        assert_eq!(class.methods.len(), 1);
+    }
+
+    #[test]
+    fn unclosed_annotation() {
+        let err = parse_class(
+            ".class public Lbttv/test/Util;
+.annotation runtime Lbttv/test/Ann;
+    value = 0x1",
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ParserError::InvalidAnnotation(header) if header == ".annotation runtime Lbttv/test/Ann;"
+        ));
+    }
+}
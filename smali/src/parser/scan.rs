@@ -0,0 +1,134 @@
+//! Header-only scanning of smali files
+//!
+//! Only `.class`, `.super`, `.implements`, `.field` and `.method` lines and class level
+//! `.annotation` blocks are parsed.
+//! Method bodies are skipped from `.method` to `.end method` by looking at raw bytes,
+//! they are neither tokenized nor checked for valid UTF-8.
+
+use super::ClassBuilder;
+use crate::err::*;
+use crate::smali_class::SmaliClass;
+use memchr::memmem;
+
+const METHOD: &[u8] = b".method";
+const END_METHOD: &[u8] = b".end method";
+
+/// Scans the headers of a smali class given as raw bytes into a SmaliClass
+pub fn scan_smali(bytes: &[u8]) -> ParserResult<SmaliClass> {
+    let end_method = memmem::Finder::new(END_METHOD);
+
+    let mut builder = ClassBuilder::default();
+    let mut line_number = 0;
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let rest = &bytes[pos..];
+        let line_len = memchr::memchr(b'\n', rest).unwrap_or(rest.len());
+        let line = &rest[..line_len];
+        line_number += 1;
+        pos += line_len + 1;
+
+        // every line we are interested in is a directive or part of a class annotation
+        if line.first() != Some(&b'.') && !builder.in_annotation() {
+            continue;
+        }
+
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let line = std::str::from_utf8(line).map_err(|_| ParserError::InvalidUtf8(line_number))?;
+
+        builder.push_line(line)?;
+
+        if line.as_bytes().starts_with(METHOD) {
+            let skipped = skip_method_body(bytes, pos, &end_method);
+            line_number += memchr::memchr_iter(b'\n', &bytes[pos..skipped]).count();
+            pos = skipped;
+        }
+    }
+
+    builder.finish()
+}
+
+/// returns the position of the line after the next `.end method` line starting from pos
+fn skip_method_body(bytes: &[u8], mut pos: usize, end_method: &memmem::Finder) -> usize {
+    while let Some(found) = end_method.find(&bytes[pos..]) {
+        let found = pos + found;
+        let line_start = memchr::memrchr(b'\n', &bytes[..found]).map_or(0, |i| i + 1);
+        let line_end = memchr::memchr(b'\n', &bytes[found..]).map_or(bytes.len(), |i| found + i);
+
+        pos = line_end + 1;
+
+        // `.end method` has to be the first token on its line, it could be part of a string otherwise
+        if bytes[line_start..found]
+            .iter()
+            .all(|byte| byte.is_ascii_whitespace())
+        {
+            return pos;
+        }
+    }
+    bytes.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_method_bodies() {
+        let input = b".class Lbttv/test/Util;
+.method public a()V
+    .registers 1
+    const-string v0, \"\xff not utf-8 \"
+.field public fake:I
+.end method
+.method public b()V
+.end method
+.field public real:I
+";
+        let class = scan_smali(input).unwrap();
+        assert_eq!(class.methods.len(), 2);
+        assert_eq!(class.values.len(), 1);
+        assert_eq!(class.values[0].name, "real");
+    }
+
+    #[test]
+    fn end_method_in_string() {
+        let input = b".class Lbttv/test/Util;
+.method public a()V
+    const-string v0, \".end method\"
+.field public fake:I
+  .end method
+.field public real:I
+";
+        let class = scan_smali(input).unwrap();
+        assert_eq!(class.values.len(), 1);
+        assert_eq!(class.values[0].name, "real");
+    }
+
+    #[test]
+    fn member_annotations() {
+        let input = crate::parser::tests::MEMBER_ANNOTATIONS;
+        let class = scan_smali(input.as_bytes()).unwrap();
+        assert_eq!(class, crate::parse_class(input).unwrap());
+        assert_eq!(class.annotations.len(), 1);
+        assert_eq!(class.annotations[0].type_path, "bttv.test.Ann");
+    }
+
+    #[test]
+    fn unclosed_annotation() {
+        let input = b".class Lbttv/test/Util;\n.annotation runtime Lbttv/test/Ann;\n";
+        assert!(matches!(
+            scan_smali(input).unwrap_err(),
+            ParserError::InvalidAnnotation(_)
+        ));
+    }
+
+    #[test]
+    fn invalid_utf8_header() {
+        let input =
+            b".class Lbttv/test/Util;\n.method a()V\n\xff\n.end method\n.field public \xff:I\n";
+        assert!(matches!(
+            scan_smali(input).unwrap_err(),
+            ParserError::InvalidUtf8(5)
+        ));
+    }
+}
@@ -1,8 +1,8 @@
+use crate::class_path::ClassPath;
 use crate::err::*;
-use crate::parser::util::smali_to_java_path;
 
-pub fn parse_line(line: &str) -> ParserResult<String> {
-    let tokens = line.split_whitespace();
+pub fn parse_line(line: &str) -> ParserResult<ClassPath> {
+    let tokens = line.split_ascii_whitespace();
 
     let mut super_path = None;
 
@@ -15,7 +15,7 @@ pub fn parse_line(line: &str) -> ParserResult<String> {
             continue;
         }
 
-        super_path = Some(smali_to_java_path(token)?);
+        super_path = Some(ClassPath::from_smali(token)?);
         break;
     }
 
@@ -1,6 +1,6 @@
 use crate::parser::ParserError;
 use crate::ParserResult;
-use parking_lot::Mutex;
+use std::borrow::Cow;
 
 pub fn is_access_modifier(token: &str) -> bool {
     matches!(token, "private" | "public" | "protected")
@@ -10,56 +10,117 @@ pub fn is_modifier(token: &str) -> bool {
     is_access_modifier(token)
         || matches!(
             token,
-            "static" | "final" | "synthetic" | "constructor" | "enum" | "varargs" | "abstract"
+            "static"
+                | "final"
+                | "synthetic"
+                | "constructor"
+                | "enum"
+                | "varargs"
+                | "abstract"
+                | "interface"
+                | "annotation"
+                | "bridge"
+                | "native"
+                | "strictfp"
+                | "synchronized"
+                | "declared-synchronized"
+                | "transient"
+                | "volatile"
         )
 }
 
+/// Converts a smali class descriptor (`Lbttv/test/Util;`) into a java class path (`bttv.test.Util`).
+/// Non-ASCII identifiers are kept as they are and `\uXXXX` escapes are decoded.
 pub fn smali_to_java_path(input: &str) -> ParserResult<String> {
-    let error = Err(ParserError::InvalidClassPath(input.to_string()));
-    if input.len() < 2 {
-        return error;
+    let error = || ParserError::InvalidClassPath(input.to_string());
+
+    let body = input
+        .strip_prefix('L')
+        .and_then(|rest| rest.strip_suffix(';'))
+        .ok_or_else(error)?;
+
+    if body.is_empty() {
+        return Err(error());
     }
 
-    let mut string = String::with_capacity(input.len() - 2);
+    let body = unescape(body).ok_or_else(error)?;
 
-    for (i, ch) in input.chars().enumerate() {
-        if i == 0 {
-            if ch != 'L' {
-                return error;
-            }
+    Ok(body.replace('/', "."))
+}
+
+/// Decodes smali `\uXXXX` escapes (including surrogate pairs) in an identifier,
+/// returns None if an escape sequence is malformed
+pub fn unescape(input: &str) -> Option<Cow<'_, str>> {
+    if !input.contains('\\') {
+        return Some(Cow::Borrowed(input));
+    }
+
+    let mut string = String::with_capacity(input.len());
+    let mut chars = input.chars();
+
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            string.push(ch);
             continue;
         }
 
-        if i == input.len() - 1 {
-            if ch != ';' {
-                return error;
-            }
-            break;
+        let high = read_unicode_escape(&mut chars)?;
+        if !(0xD800..0xDC00).contains(&high) {
+            string.push(char::from_u32(high)?);
+            continue;
         }
 
-        if ch == '/' {
-            string.push('.');
-        } else {
-            string.push(ch);
+        // high surrogate, the low surrogate has to follow as another escape
+        if chars.next()? != '\\' {
+            return None;
+        }
+        let low = read_unicode_escape(&mut chars)?;
+        if !(0xDC00..0xE000).contains(&low) {
+            return None;
         }
+        string.push(char::from_u32(
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00),
+        )?);
     }
 
-    Ok(string)
+    Some(Cow::Owned(string))
 }
 
-/// Sets the value of a Mutex<Option<I>> and errors when I was not None
-pub fn set_mutex_once_or_err<I>(
-    mutex: &Mutex<Option<I>>,
-    value: I,
-    error: ParserError,
-) -> ParserResult<()> {
-    let mut mutex = mutex.lock();
+/// Escapes all characters that would end a smali token (whitespace, control characters and `\`)
+/// as `\uXXXX`, the inverse of `unescape`
+pub fn escape(input: &str) -> Cow<'_, str> {
+    if !input.chars().any(needs_escape) {
+        return Cow::Borrowed(input);
+    }
 
-    if mutex.is_some() {
-        return Err(error);
+    let mut string = String::with_capacity(input.len() + 8);
+    let mut units = [0; 2];
+    for ch in input.chars() {
+        if !needs_escape(ch) {
+            string.push(ch);
+            continue;
+        }
+        for unit in ch.encode_utf16(&mut units) {
+            string.push_str(&format!("\\u{:04x}", unit));
+        }
     }
-    *mutex = Some(value);
-    Ok(())
+    Cow::Owned(string)
+}
+
+fn needs_escape(ch: char) -> bool {
+    ch.is_whitespace() || ch.is_control() || ch == '\\'
+}
+
+/// reads the `uXXXX` part of an escape sequence, the backslash has to be consumed already
+fn read_unicode_escape(chars: &mut std::str::Chars) -> Option<u32> {
+    if chars.next()? != 'u' {
+        return None;
+    }
+    let mut code = 0;
+    for _ in 0..4 {
+        code = code * 16 + chars.next()?.to_digit(16)?;
+    }
+    Some(code)
 }
 
 #[cfg(test)]
@@ -94,31 +155,45 @@ mod test {
             }
             _ => panic!(),
         }
+        let s = "L;";
+        match smali_to_java_path(s).unwrap_err() {
+            ParserError::InvalidClassPath(token) => {
+                assert_eq!(token, s)
+            }
+            _ => panic!(),
+        }
     }
 
-    #[cfg(test)]
-    mod test_set_mutex_once_or_err {
-        use super::super::*;
-
-        #[test]
-        fn green() {
-            let mutex = Mutex::new(None);
-            let value = 5;
-            let result = set_mutex_once_or_err(&mutex, value, ParserError::TooManyClasses());
-            assert!(result.is_ok());
-            assert_eq!(mutex.into_inner(), Some(value));
-        }
+    #[test]
+    fn test_smali_to_java_path_unicode() {
+        assert_eq!(
+            smali_to_java_path("Lbttv/\\u00e4\\u00f6/Util;").unwrap(),
+            "bttv.\u{e4}\u{f6}.Util".to_string()
+        );
+        assert_eq!(
+            smali_to_java_path("Lbttv/\u{e4}\u{f6}/\u{5b57};").unwrap(),
+            "bttv.\u{e4}\u{f6}.\u{5b57}".to_string()
+        );
+        assert!(smali_to_java_path("La/\\u00;").is_err());
+    }
 
-        #[test]
-        fn set_before() {
-            let prev = Some(10);
-            let mutex = Mutex::new(prev);
-            let value = 5;
-            let err = ParserError::TooManyClasses();
-            let result = set_mutex_once_or_err(&mutex, value, err);
-            assert!(result.is_err());
-            assert!(matches!(result.unwrap_err(), ParserError::TooManyClasses()));
-            assert_eq!(mutex.into_inner(), prev);
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape("plain").unwrap(), "plain");
+        assert_eq!(unescape("a\\u0062c").unwrap(), "abc");
+        assert_eq!(unescape("\\uD83D\\uDE00").unwrap(), "\u{1F600}");
+        assert!(unescape("\\uD83D").is_none());
+        assert!(unescape("\\uDE00").is_none());
+        assert!(unescape("\\x0062").is_none());
+        assert!(unescape("\\u00g2").is_none());
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("plain\u{e4}"), "plain\u{e4}");
+        assert_eq!(escape("a b\\"), "a\\u0020b\\u005c");
+        for input in ["a b\\", "\u{a0}\u{2028}\t", "\u{e4}\u{1F600}"] {
+            assert_eq!(unescape(&escape(input)).unwrap(), input);
         }
     }
 }
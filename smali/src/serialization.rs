@@ -0,0 +1,195 @@
+//! `serde` support for the smali model, enabled by the `serde` cargo feature
+//!
+//! # Representation (format version 8)
+//!
+//! - structs are maps keyed by their rust field names (`class_path`, `super_path`, `values`, ...)
+//! - `ClassPath` and `Symbol` are plain strings, class paths in java notation (`tv.twitch.Foo$Bar`)
+//! - `SmaliAccessModifier` and `ClassKind` are the variant names as strings (`"Public"`,
+//!   `"Interface"`, ...)
+//! - `SmaliType` is externally tagged: `"Int"`, `{"Class": "java.lang.String"}`,
+//!   `{"Arr": {"Class": "java.lang.String"}}`
+//!
+//! Data that is stored or shared between processes should be wrapped in `Versioned`,
+//! which records `FORMAT_VERSION` and refuses to deserialize data written by another version.
+//! Any change to the representation above increments `FORMAT_VERSION`, and so does every new,
+//! removed or renamed field or variant of the model and of the diff results built on it.
+//! The `representation` tests pin the serialized form of each version. The version is bumped
+//! in the same change as the model, and the history below is append-only: published entries are
+//! never renumbered or reworded, corrections are added as notes after them.
+//!
+//! # Version history
+//!
+//! 1. the initial representation
+//! 2. `SmaliClass::annotations`, the `Byte`, `Char` and `Short` variants of `SmaliType` and the
+//!    `is_synthetic` flags of classes and members
+//! 3. `ClassDiff::only_in_mock` and `ClassDiff::only_in_app` of the diff results
+//! 4. `MethodDiff::descriptor` of the diff results
+//! 5. `SmaliValue::is_enum`
+//! 6. the SDK versions of `ApktoolInfo` as strings, so codenames of preview SDKs are kept
+//! 7. `SyntheticDiff::only_in_mock` and `SyntheticDiff::only_in_app` of the diff results
+//! 8. `SmaliClass::kind`
+//!
+//! Note on 1 and 2: the dex reader added `SmaliClass::annotations` and the `Byte`, `Char` and
+//! `Short` variants without a bump, so data written as version 1 by the builds between it and
+//! the synthetic flags may already contain them. Version 2 is the first to guarantee them.
+
+use crate::class_path::ClassPath;
+use crate::symbol::Symbol;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The version of the serialized representation of the smali model and the diff results,
+/// see the version history in the module docs
+pub const FORMAT_VERSION: u32 = 8;
+
+/// Wraps serialized data together with the `FORMAT_VERSION` it was written with
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Versioned<T> {
+    pub format_version: u32,
+    pub data: T,
+}
+
+impl<T> Versioned<T> {
+    pub fn new(data: T) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            data,
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Versioned<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Unchecked<T> {
+            format_version: u32,
+            data: T,
+        }
+
+        let unchecked = Unchecked::deserialize(deserializer)?;
+        if unchecked.format_version != FORMAT_VERSION {
+            return Err(D::Error::custom(format!(
+                "unsupported format version {}, expected {}",
+                unchecked.format_version, FORMAT_VERSION
+            )));
+        }
+        Ok(Self {
+            format_version: unchecked.format_version,
+            data: unchecked.data,
+        })
+    }
+}
+
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Symbol {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = <std::borrow::Cow<str>>::deserialize(deserializer)?;
+        Ok(Symbol::intern(&name))
+    }
+}
+
+impl Serialize for ClassPath {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_java())
+    }
+}
+
+impl<'de> Deserialize<'de> for ClassPath {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let java_path = <std::borrow::Cow<str>>::deserialize(deserializer)?;
+        ClassPath::from_java(&java_path).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn representation() {
+        let value = SmaliValue {
+            name: Symbol::intern("names"),
+            data_type: SmaliType::Arr(Box::new(SmaliType::Class(
+                ClassPath::from_java("java.lang.String").unwrap(),
+            ))),
+            access: SmaliAccessModifier::Public,
+            is_static: false,
+            is_final: true,
+            is_synthetic: false,
+            is_enum: false,
+        };
+        let json = serde_json::to_string(&Versioned::new(&value)).unwrap();
+        assert_eq!(
+            json,
+            r#"{"format_version":8,"data":{"name":"names","data_type":{"Arr":{"Class":"java.lang.String"}},"access":"Public","is_static":false,"is_final":true,"is_synthetic":false,"is_enum":false}}"#
+        );
+
+        let parsed: Versioned<SmaliValue> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.data, value);
+    }
+
+    #[test]
+    fn class_representation() {
+        let class = parse_class(
+            ".class public final Lbttv/test/Chars;
+.super Ljava/lang/Object;
+.annotation runtime Lbttv/test/Ann;
+    value = 0x1t
+.end annotation
+.field public b:B
+.field public c:C
+.field public s:S",
+        )
+        .unwrap();
+        let json = serde_json::to_string(&Versioned::new(&class)).unwrap();
+        assert_eq!(
+            json,
+            concat!(
+                r#"{"format_version":8,"data":{"class_path":"bttv.test.Chars","access":"Public","#,
+                r#""is_abstract":false,"is_synthetic":false,"kind":"Class","super_path":"java.lang.Object","#,
+                r#""interfaces":[],"values":["#,
+                r#"{"name":"b","data_type":"Byte","access":"Public","is_static":false,"#,
+                r#""is_final":false,"is_synthetic":false,"is_enum":false},"#,
+                r#"{"name":"c","data_type":"Char","access":"Public","is_static":false,"#,
+                r#""is_final":false,"is_synthetic":false,"is_enum":false},"#,
+                r#"{"name":"s","data_type":"Short","access":"Public","is_static":false,"#,
+                r#""is_final":false,"is_synthetic":false,"is_enum":false}],"methods":[],"annotations":["#,
+                r#"{"visibility":"Runtime","type_path":"bttv.test.Ann","#,
+                r#""elements":[{"name":"value","value":{"Byte":1}}]}]}}"#
+            )
+        );
+        let parsed: Versioned<SmaliClass> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.data, class);
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let json = r#"{"format_version":0,"data":"a.B"}"#;
+        assert!(serde_json::from_str::<Versioned<ClassPath>>(json).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_class_paths() {
+        assert!(serde_json::from_str::<ClassPath>(r#""a..B""#).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let class = parse_class(
+            ".class public abstract Lbttv/test/Util;
+.super Lbttv/test/Super;
+.implements Lbttv/test/Interface;
+.field private static final names:[Ljava/lang/String;
+.method public abstract run(I[J)Ljava/lang/Object;",
+        )
+        .unwrap();
+        let json = serde_json::to_string(&class).unwrap();
+        assert_eq!(serde_json::from_str::<SmaliClass>(&json).unwrap(), class);
+    }
+}
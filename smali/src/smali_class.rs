@@ -1,8 +1,12 @@
+use crate::annotation::SmaliAnnotation;
+use crate::class_path::ClassPath;
 use crate::err::*;
-use crate::parser::util::smali_to_java_path;
+use crate::kotlin::{KotlinMetadata, METADATA_ANNOTATION};
+use crate::symbol::Symbol;
 use std::str::FromStr;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SmaliAccessModifier {
     Public,
     Private,
@@ -21,64 +25,130 @@ impl std::str::FromStr for SmaliAccessModifier {
     }
 }
 
-#[derive(Debug, PartialEq)]
+/// The kind of type a class file declares, from the `interface`, `annotation` and `enum` flags
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ClassKind {
+    Class,
+    Interface,
+    Enum,
+    /// an annotation type, which is an interface as well
+    Annotation,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SmaliClass {
     // parsed from .class line
-    pub class_path: String,
+    pub class_path: ClassPath,
     pub access: SmaliAccessModifier,
     pub is_abstract: bool,
+    pub is_synthetic: bool,
+    pub kind: ClassKind,
 
     // parsed from .super line
-    pub super_path: Option<String>,
+    pub super_path: Option<ClassPath>,
 
     // parsed from .implements lines
-    pub interfaces: Vec<String>,
+    pub interfaces: Vec<ClassPath>,
     pub values: Vec<SmaliValue>,
     pub methods: Vec<SmaliMethod>,
+
+    // class level annotations, parsed from .annotation blocks
+    pub annotations: Vec<SmaliAnnotation>,
 }
 impl SmaliClass {
-    pub fn new(class_path: String, access: SmaliAccessModifier, is_abstract: bool) -> Self {
+    pub fn new(class_path: ClassPath, access: SmaliAccessModifier, is_abstract: bool) -> Self {
         Self {
             class_path,
             super_path: None,
             access,
             interfaces: vec![],
+            annotations: vec![],
             values: vec![],
             methods: vec![],
             is_abstract,
+            is_synthetic: false,
+            kind: ClassKind::Class,
         }
     }
+
+    /// Decodes the `kotlin.Metadata` annotation, None for classes not compiled by kotlinc
+    pub fn kotlin_metadata(&self) -> Option<KotlinResult<KotlinMetadata>> {
+        self.annotations
+            .iter()
+            .find(|annotation| annotation.type_path == METADATA_ANNOTATION)
+            .map(KotlinMetadata::from_annotation)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SmaliMethod {
-    pub name: String,
+    pub name: Symbol,
     pub access: SmaliAccessModifier,
     pub parameter_types: Vec<SmaliType>,
     pub return_type: SmaliType,
     pub is_static: bool,
     pub is_final: bool,
+    pub is_synthetic: bool,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SmaliValue {
-    pub name: String,
+    pub name: Symbol,
     pub data_type: SmaliType,
     pub access: SmaliAccessModifier,
     pub is_static: bool,
     pub is_final: bool,
+    pub is_synthetic: bool,
+    /// whether the field holds a constant of its enum class
+    pub is_enum: bool,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SmaliType {
     Void,
     Boolean,
+    Byte,
+    Char,
+    Short,
     Float,
     Double,
     Int,
     Long,
     Arr(Box<SmaliType>),
-    Class(String),
+    Class(ClassPath),
+}
+
+impl SmaliType {
+    /// The type as smali descriptor (`I`, `[Ljava/lang/String;`)
+    pub fn to_smali(&self) -> String {
+        match self {
+            Self::Void => "V".to_string(),
+            Self::Boolean => "Z".to_string(),
+            Self::Byte => "B".to_string(),
+            Self::Char => "C".to_string(),
+            Self::Short => "S".to_string(),
+            Self::Float => "F".to_string(),
+            Self::Double => "D".to_string(),
+            Self::Int => "I".to_string(),
+            Self::Long => "J".to_string(),
+            Self::Arr(inner) => format!("[{}", inner.to_smali()),
+            Self::Class(class_path) => class_path.to_smali(),
+        }
+    }
+
+    /// The number of registers a value of this type occupies
+    pub fn register_count(&self) -> usize {
+        match self {
+            Self::Void => 0,
+            Self::Double | Self::Long => 2,
+            _ => 1,
+        }
+    }
 }
 
 impl FromStr for SmaliType {
@@ -87,6 +157,9 @@ impl FromStr for SmaliType {
         match token {
             "V" => Ok(Self::Void),
             "Z" => Ok(Self::Boolean),
+            "B" => Ok(Self::Byte),
+            "C" => Ok(Self::Char),
+            "S" => Ok(Self::Short),
             "F" => Ok(Self::Float),
             "D" => Ok(Self::Double),
             "I" => Ok(Self::Int),
@@ -95,7 +168,7 @@ impl FromStr for SmaliType {
                 if let Some(rest) = token.strip_prefix('[') {
                     return Ok(Self::Arr(Box::new(Self::from_str(rest)?)));
                 }
-                Ok(Self::Class(smali_to_java_path(token)?))
+                Ok(Self::Class(ClassPath::from_smali(token)?))
             }
         }
     }
@@ -117,7 +190,9 @@ mod smali_type_tests {
     #[test]
     fn arr() {
         let input = "[Lbttv/test/Util;";
-        let expected = SmaliType::Arr(Box::new(SmaliType::Class("bttv.test.Util".to_string())));
+        let expected = SmaliType::Arr(Box::new(SmaliType::Class(
+            ClassPath::from_java("bttv.test.Util").unwrap(),
+        )));
         let res = SmaliType::from_str(input);
         assert!(res.is_ok());
         assert_eq!(res.unwrap(), expected)
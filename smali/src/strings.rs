@@ -0,0 +1,247 @@
+//! String literals of method bodies
+//!
+//! The literals of `const-string` and `const-string/jumbo` instructions are read together with
+//! their line in the smali file. A `StringIndex` over a whole tree finds the classes and methods
+//! that use matching literals.
+
+use crate::bodies::walk_bodies;
+use crate::class_index::ClassIndex;
+use crate::class_path::ClassPath;
+use crate::err::*;
+use crate::parser::annotation::parse_string_literal;
+use crate::smali_class::SmaliMethod;
+use crate::tree::read_tree_with;
+use std::path::{Path, PathBuf};
+
+/// A string literal in a method body
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConstString {
+    pub value: String,
+    /// the line of the instruction in the smali file, starting at 1
+    pub line: usize,
+}
+
+/// A method with all string literals in its body
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MethodStrings {
+    pub method: SmaliMethod,
+    /// the line of the `.method` directive
+    pub line: usize,
+    pub strings: Vec<ConstString>,
+}
+
+/// The string literals of all methods of a class
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClassStrings {
+    pub class_path: ClassPath,
+    pub methods: Vec<MethodStrings>,
+}
+
+/// Reads the string literals of all method bodies of a smali class
+pub fn read_strings(
+    lines: impl Iterator<Item = ParserResult<impl AsRef<str>>>,
+) -> ParserResult<ClassStrings> {
+    let (class_path, methods) = walk_bodies(lines, parse_instruction)?;
+    Ok(ClassStrings {
+        class_path,
+        methods: methods
+            .into_iter()
+            .map(|body| MethodStrings {
+                method: body.method,
+                line: body.line,
+                strings: body.items,
+            })
+            .collect(),
+    })
+}
+
+pub(crate) fn parse_instruction(
+    line: &str,
+    line_number: usize,
+) -> ParserResult<Option<ConstString>> {
+    let Some((opcode, operands)) = line.split_once(char::is_whitespace) else {
+        return Ok(None);
+    };
+    if opcode != "const-string" && opcode != "const-string/jumbo" {
+        return Ok(None);
+    }
+    let value = operands
+        .split_once(',')
+        .and_then(|(_, literal)| parse_string_literal(literal))
+        .ok_or_else(|| ParserError::InvalidString(line_number, line.to_string()))?;
+    Ok(Some(ConstString {
+        value,
+        line: line_number,
+    }))
+}
+
+/// A string literal found by a `StringIndex` together with the method it is in
+#[derive(Debug, Clone, Copy)]
+pub struct StringHit<'a> {
+    pub method: &'a MethodStrings,
+    pub string: &'a ConstString,
+}
+
+/// The string literals of a class that matched a search
+#[derive(Debug, Clone)]
+pub struct ClassStringHits<'a> {
+    pub path: &'a Path,
+    pub class_path: &'a ClassPath,
+    pub hits: Vec<StringHit<'a>>,
+}
+
+/// The string literals of every class in a tree
+#[derive(Debug, Default)]
+pub struct StringIndex {
+    files: Vec<(PathBuf, ClassStrings)>,
+    failed: Vec<(PathBuf, ParserError)>,
+}
+
+impl StringIndex {
+    /// Reads the string literals of every `.smali` file below dir in parallel. Unlike
+    /// `from_index` this reads any directory, but every definition of a duplicate class is included.
+    pub fn from_tree(dir: impl AsRef<Path>) -> ParserResult<Self> {
+        let tree = read_tree_with(dir.as_ref(), |path| crate::strings_file(path))?;
+        Ok(Self {
+            files: tree.files,
+            failed: tree.failed,
+        })
+    }
+
+    /// The string literals of the classes used by a `ClassIndex`, see `XrefIndex::from_index`
+    pub fn from_index(classes: &ClassIndex) -> Self {
+        classes.read_all_bodies();
+        let mut index = Self::default();
+        for entry in classes.entries() {
+            if let Ok(bodies) = entry.bodies() {
+                index.push(entry.file.clone(), bodies.strings.clone());
+            }
+        }
+        index
+    }
+
+    /// Adds the string literals of a class read from path
+    pub fn push(&mut self, path: PathBuf, class: ClassStrings) {
+        self.files.push((path, class));
+    }
+
+    /// The files that could not be read with their errors
+    pub fn failed(&self) -> &[(PathBuf, ParserError)] {
+        &self.failed
+    }
+
+    /// All string literals accepted by matches, grouped by class in file order.
+    /// Classes without a match are left out.
+    pub fn search(&self, matches: impl Fn(&str) -> bool) -> Vec<ClassStringHits<'_>> {
+        self.files
+            .iter()
+            .filter_map(|(path, class)| {
+                let hits: Vec<_> = class
+                    .methods
+                    .iter()
+                    .flat_map(|method| {
+                        method
+                            .strings
+                            .iter()
+                            .map(move |string| StringHit { method, string })
+                    })
+                    .filter(|hit| matches(&hit.string.value))
+                    .collect();
+                (!hits.is_empty()).then(|| ClassStringHits {
+                    path,
+                    class_path: &class.class_path,
+                    hits,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAYER: &str = r#".class public Ltv/twitch/Player;
+.super Ljava/lang/Object;
+
+.field private static final TAG:Ljava/lang/String; = "not in a method"
+
+.method public load(I)V
+    .registers 4
+
+    const-string v0, "https://usher.ttvnw.net/api/channel/hls/%s.m3u8"
+    const-string/jumbo v1, "a, \"b\"\n\u00e4"  # comment
+    const-string v2, ""
+    const-class v3, Ltv/twitch/Player;
+    return-void
+.end method
+
+.method public static log()V
+    .registers 1
+
+    const-string v0, "Player"
+    return-void
+.end method
+"#;
+
+    fn read(smali: &str) -> ClassStrings {
+        read_strings(smali.lines().map(Ok)).unwrap()
+    }
+
+    #[test]
+    fn instructions() {
+        let class = read(PLAYER);
+        assert_eq!(class.class_path, "tv.twitch.Player");
+        assert_eq!(class.methods.len(), 2);
+
+        let strings = &class.methods[0].strings;
+        assert_eq!(
+            strings,
+            &[
+                ConstString {
+                    value: "https://usher.ttvnw.net/api/channel/hls/%s.m3u8".to_string(),
+                    line: 9,
+                },
+                ConstString {
+                    value: "a, \"b\"\n\u{e4}".to_string(),
+                    line: 10,
+                },
+                ConstString {
+                    value: String::new(),
+                    line: 11,
+                },
+            ]
+        );
+        assert_eq!(class.methods[1].strings[0].value, "Player");
+    }
+
+    #[test]
+    fn invalid_string() {
+        let smali = ".class La/B;\n.method a()V\n    const-string v0, \"\\x\"\n.end method";
+        assert!(matches!(
+            read_strings(smali.lines().map(Ok)),
+            Err(ParserError::InvalidString(3, _))
+        ));
+    }
+
+    #[test]
+    fn search() {
+        let mut index = StringIndex::default();
+        index.push(PathBuf::from("Player.smali"), read(PLAYER));
+
+        let classes = index.search(|value| value.contains("Player") || value.contains("usher"));
+        assert_eq!(classes.len(), 1);
+        assert_eq!(classes[0].class_path, "tv.twitch.Player");
+        let hits: Vec<_> = classes[0]
+            .hits
+            .iter()
+            .map(|hit| (hit.method.method.name.as_str(), hit.string.line))
+            .collect();
+        assert_eq!(hits, [("load", 9), ("log", 19)]);
+
+        assert!(index.search(|value| value == "missing").is_empty());
+    }
+}
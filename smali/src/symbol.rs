@@ -0,0 +1,134 @@
+use parking_lot::Mutex;
+use std::borrow::Borrow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::{Arc, OnceLock};
+
+/// number of independently locked parts of the interner, keeps contention low when parsing in parallel
+const SHARDS: usize = 32;
+
+type Shard = Mutex<HashSet<Arc<str>>>;
+
+static INTERNER: OnceLock<Vec<Shard>> = OnceLock::new();
+
+/// An interned, immutable string
+///
+/// Every distinct name is allocated once per process, cloning a Symbol only bumps a
+/// reference count and comparing two Symbols is a pointer comparison in the common case
+///
+/// The interner is global and interned names are never freed, not even when every Symbol of a
+/// name is dropped. It holds each distinct name once, so it grows with the vocabulary of the
+/// parsed apps and not with the number of parsed or cached classes, but a long running process
+/// that parses many unrelated apps keeps the names of all of them.
+#[derive(Clone, PartialOrd, Ord)]
+pub struct Symbol(Arc<str>);
+
+impl Symbol {
+    /// Returns the Symbol for `name`, allocating it only if it was not interned before
+    pub fn intern(name: &str) -> Self {
+        let shards = INTERNER.get_or_init(|| (0..SHARDS).map(|_| Mutex::default()).collect());
+
+        let mut hasher = DefaultHasher::new();
+        name.hash(&mut hasher);
+        let shard = &shards[hasher.finish() as usize % SHARDS];
+
+        let mut set = shard.lock();
+        if let Some(existing) = set.get(name) {
+            return Self(Arc::clone(existing));
+        }
+        let symbol: Arc<str> = Arc::from(name);
+        set.insert(Arc::clone(&symbol));
+        Self(symbol)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0) || self.0 == other.0
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Symbol {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Self::intern(name)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(name: String) -> Self {
+        Self::intern(&name)
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interned_once() {
+        let a = Symbol::intern("java.lang.String");
+        let b = Symbol::from("java.lang.String".to_string());
+        assert!(Arc::ptr_eq(&a.0, &b.0));
+        assert_eq!(a, b);
+        assert_eq!(a, "java.lang.String");
+        assert_ne!(a, Symbol::intern("java.lang.Object"));
+    }
+}
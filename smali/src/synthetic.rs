@@ -0,0 +1,175 @@
+//! Classification of classes and members generated by javac, kotlinc, d8 and r8
+//!
+//! Members are recognized by the `synthetic` flag and by the names the compilers give them,
+//! classes read from sources carry no flags, so the names are all there is for them.
+
+use crate::smali_class::{SmaliClass, SmaliMethod, SmaliType, SmaliValue};
+
+const DEFAULT_CONSTRUCTOR_MARKER: &str = "kotlin.jvm.internal.DefaultConstructorMarker";
+
+/// What a compiler generated a class or member for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SyntheticKind {
+    /// lambda classes and the methods holding lambda bodies
+    /// (`-$$Lambda$…`, `$$ExternalSyntheticLambda0`, `lambda$run$0`, `$r8$lambda$…`)
+    Lambda,
+    /// helper classes d8/r8 place next to their callers (`$$ExternalSyntheticBackport0`)
+    Outline,
+    /// accessors of private members for nested classes (`access$100`, `-$$Nest$mrun`)
+    Accessor,
+    /// the constant array of enums (`$VALUES`, `$values()`)
+    EnumValues,
+    /// kotlin methods that fill in default arguments (`load$default`, constructors taking a
+    /// `DefaultConstructorMarker`)
+    DefaultArguments,
+    /// ordinal tables for switches over enums (`$SwitchMap$…`, `$WhenMappings`)
+    SwitchMap,
+    /// references to the outer instance and captured variables (`this$0`, `val$minutes`)
+    Captured,
+    /// bridge methods and everything else only recognized by the synthetic flag
+    Other,
+}
+
+impl SmaliClass {
+    /// Why this class was generated, None for classes written in source
+    pub fn synthetic_kind(&self) -> Option<SyntheticKind> {
+        let name = self.class_path.binary_name();
+        if self.class_path.is_lambda() {
+            Some(SyntheticKind::Lambda)
+        } else if name.contains("$$ExternalSynthetic") {
+            Some(SyntheticKind::Outline)
+        } else if self.class_path.simple_name() == "WhenMappings" {
+            Some(SyntheticKind::SwitchMap)
+        } else if self.is_synthetic {
+            Some(SyntheticKind::Other)
+        } else {
+            None
+        }
+    }
+}
+
+impl SmaliMethod {
+    /// Why this method was generated, None for methods written in source
+    pub fn synthetic_kind(&self) -> Option<SyntheticKind> {
+        let name = self.name.as_str();
+        if name.starts_with("lambda$") || name.starts_with("$r8$lambda$") {
+            Some(SyntheticKind::Lambda)
+        } else if name.starts_with("access$") || name.starts_with("-$$Nest$") {
+            Some(SyntheticKind::Accessor)
+        } else if name == "$values" {
+            Some(SyntheticKind::EnumValues)
+        } else if name.ends_with("$default") || self.has_default_constructor_marker() {
+            Some(SyntheticKind::DefaultArguments)
+        } else if self.is_synthetic {
+            Some(SyntheticKind::Other)
+        } else {
+            None
+        }
+    }
+
+    fn has_default_constructor_marker(&self) -> bool {
+        self.name == "<init>"
+            && matches!(
+                self.parameter_types.last(),
+                Some(SmaliType::Class(path)) if *path == DEFAULT_CONSTRUCTOR_MARKER
+            )
+    }
+}
+
+impl SmaliValue {
+    /// Why this field was generated, None for fields written in source
+    pub fn synthetic_kind(&self) -> Option<SyntheticKind> {
+        let name = self.name.as_str();
+        if name == "$VALUES" {
+            Some(SyntheticKind::EnumValues)
+        } else if name.starts_with("$SwitchMap$") {
+            Some(SyntheticKind::SwitchMap)
+        } else if name.starts_with("this$") || name.starts_with("val$") {
+            Some(SyntheticKind::Captured)
+        } else if self.is_synthetic || name == "$assertionsDisabled" {
+            Some(SyntheticKind::Other)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(smali: &str) -> (Option<SyntheticKind>, Vec<Option<SyntheticKind>>) {
+        let class = crate::parse_class(smali).unwrap();
+        let members = class
+            .values
+            .iter()
+            .map(SmaliValue::synthetic_kind)
+            .chain(class.methods.iter().map(SmaliMethod::synthetic_kind))
+            .collect();
+        (class.synthetic_kind(), members)
+    }
+
+    #[test]
+    fn members() {
+        let (class, members) = kinds(
+            ".class public Ltv/twitch/Player;
+.field static final synthetic $SwitchMap$tv$twitch$State:[I
+.field final synthetic this$0:Ltv/twitch/Outer;
+.field private name:Ljava/lang/String;
+.method static synthetic access$100(Ltv/twitch/Player;)V
+.end method
+.method private static synthetic lambda$load$0(I)V
+.end method
+.method public static synthetic load$default(Ltv/twitch/Player;IILjava/lang/Object;)V
+.end method
+.method public synthetic constructor <init>(IILkotlin/jvm/internal/DefaultConstructorMarker;)V
+.end method
+.method public bridge synthetic compareTo(Ljava/lang/Object;)I
+.end method
+.method public load(I)V
+.end method",
+        );
+        assert_eq!(class, None);
+        assert_eq!(
+            members,
+            [
+                Some(SyntheticKind::SwitchMap),
+                Some(SyntheticKind::Captured),
+                None,
+                Some(SyntheticKind::Accessor),
+                Some(SyntheticKind::Lambda),
+                Some(SyntheticKind::DefaultArguments),
+                Some(SyntheticKind::DefaultArguments),
+                Some(SyntheticKind::Other),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn classes() {
+        let kind = |header: &str| kinds(header).0;
+        assert_eq!(
+            kind(".class final synthetic Ltv/twitch/-$$Lambda$Player$abc;"),
+            Some(SyntheticKind::Lambda)
+        );
+        assert_eq!(
+            kind(".class public final synthetic Ltv/twitch/Player$$ExternalSyntheticLambda0;"),
+            Some(SyntheticKind::Lambda)
+        );
+        assert_eq!(
+            kind(".class public final synthetic Ltv/twitch/Player$$ExternalSyntheticBackport0;"),
+            Some(SyntheticKind::Outline)
+        );
+        assert_eq!(
+            kind(".class public final Ltv/twitch/Player$WhenMappings;"),
+            Some(SyntheticKind::SwitchMap)
+        );
+        assert_eq!(
+            kind(".class synthetic Ltv/twitch/Player$1;"),
+            Some(SyntheticKind::Other)
+        );
+        assert_eq!(kind(".class Ltv/twitch/Player$1;"), None);
+    }
+}
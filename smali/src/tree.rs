@@ -0,0 +1,110 @@
+use crate::err::*;
+use crate::smali_class::SmaliClass;
+use rayon::prelude::*;
+use std::path::{Path, PathBuf};
+
+/// A smali file of a tree together with the result of parsing it
+#[derive(Debug)]
+pub struct ParsedFile {
+    pub path: PathBuf,
+    pub class: ParserResult<SmaliClass>,
+}
+
+/// Recursively collects all `.smali` files below dir, sorted by path
+pub fn find_smali_files(dir: impl AsRef<Path>) -> ParserResult<Vec<PathBuf>> {
+    let mut files = vec![];
+    let mut dirs = vec![dir.as_ref().to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "smali") {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// The smali roots of an apktool output directory in the order of their dex files
+/// (`smali`, `smali_classes2`, ..., `smali_classes10`), in which classes are looked up at runtime
+pub fn smali_roots(apktool_dir: impl AsRef<Path>) -> ParserResult<Vec<PathBuf>> {
+    let mut roots = vec![];
+    for entry in std::fs::read_dir(apktool_dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if let Some(number) = name.to_str().and_then(dex_number) {
+            if entry.file_type()?.is_dir() {
+                roots.push((number, entry.path()));
+            }
+        }
+    }
+    roots.sort();
+    Ok(roots.into_iter().map(|(_, root)| root).collect())
+}
+
+/// The number of the dex file a smali root was disassembled from, `smali` is `classes.dex`
+pub(crate) fn dex_number(dir_name: &str) -> Option<u32> {
+    if dir_name == "smali" {
+        return Some(1);
+    }
+    dir_name.strip_prefix("smali_classes")?.parse().ok()
+}
+
+/// Reads every `.smali` file below dir with read in parallel, the results and the files that
+/// could not be read are in path order
+pub(crate) fn read_tree_with<T, F>(dir: &Path, read: F) -> ParserResult<ReadTree<T>>
+where
+    T: Send,
+    F: Fn(&Path) -> ParserResult<T> + Sync,
+{
+    let results: Vec<_> = find_smali_files(dir)?
+        .into_par_iter()
+        .map(|path| {
+            let result = read(&path);
+            (path, result)
+        })
+        .collect();
+
+    let mut tree = ReadTree {
+        files: vec![],
+        failed: vec![],
+    };
+    for (path, result) in results {
+        match result {
+            Ok(read) => tree.files.push((path, read)),
+            Err(err) => tree.failed.push((path, err)),
+        }
+    }
+    Ok(tree)
+}
+
+/// What `read_tree_with` read from the files of a tree
+pub(crate) struct ReadTree<T> {
+    pub files: Vec<(PathBuf, T)>,
+    pub failed: Vec<(PathBuf, ParserError)>,
+}
+
+/// Parses all files with `parse` in parallel, one file per task,
+/// the results are in the same order as file_paths
+pub fn parse_files_with<P, F>(file_paths: &[P], parse: F) -> Vec<ParsedFile>
+where
+    P: AsRef<Path> + Sync,
+    F: Fn(&Path) -> ParserResult<SmaliClass> + Sync,
+{
+    file_paths
+        .par_iter()
+        .map(|path| {
+            let path = path.as_ref();
+            ParsedFile {
+                path: path.to_path_buf(),
+                class: parse(path),
+            }
+        })
+        .collect()
+}
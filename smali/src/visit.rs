@@ -0,0 +1,344 @@
+//! Visitors that walk a `SmaliClass`, its members, annotations and every type it references
+//!
+//! Implement `Visit` (or `VisitMut` to modify the class) and override only the callbacks that
+//! are needed, every other callback keeps walking into its children. An overriding callback
+//! continues the walk by calling the matching `walk_*` function, or skips the children by
+//! not calling it.
+//!
+//! Type references inside the strings of `AnnotationValue::Field`, `Method`, `MethodType`,
+//! `MethodHandle` and `Enum` are not visited, they are kept in their smali notation.
+
+use crate::annotation::*;
+use crate::class_path::ClassPath;
+use crate::smali_class::*;
+
+/// Where a type is referenced, passed to `visit_type` and `visit_class_path`.
+/// The position of a type is also the position of all array element types it contains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TypePosition {
+    /// the class path of the visited class itself
+    Class,
+    Super,
+    Interface,
+    Field,
+    /// a method parameter with its index
+    Parameter(usize),
+    Return,
+    /// the type of an annotation or a type in its elements
+    Annotation,
+}
+
+/// Walks a class immutably, see the module docs
+pub trait Visit {
+    fn visit_class(&mut self, class: &SmaliClass) {
+        walk_class(self, class);
+    }
+
+    fn visit_field(&mut self, field: &SmaliValue) {
+        walk_field(self, field);
+    }
+
+    fn visit_method(&mut self, method: &SmaliMethod) {
+        walk_method(self, method);
+    }
+
+    fn visit_annotation(&mut self, annotation: &SmaliAnnotation) {
+        walk_annotation(self, annotation);
+    }
+
+    fn visit_annotation_value(&mut self, value: &AnnotationValue) {
+        walk_annotation_value(self, value);
+    }
+
+    fn visit_type(&mut self, smali_type: &SmaliType, position: TypePosition) {
+        walk_type(self, smali_type, position);
+    }
+
+    fn visit_class_path(&mut self, _class_path: &ClassPath, _position: TypePosition) {}
+}
+
+/// Visits the class path, super class, interfaces, annotations, fields and methods in this order
+pub fn walk_class<V: Visit + ?Sized>(visitor: &mut V, class: &SmaliClass) {
+    visitor.visit_class_path(&class.class_path, TypePosition::Class);
+    if let Some(super_path) = &class.super_path {
+        visitor.visit_class_path(super_path, TypePosition::Super);
+    }
+    for interface in &class.interfaces {
+        visitor.visit_class_path(interface, TypePosition::Interface);
+    }
+    for annotation in &class.annotations {
+        visitor.visit_annotation(annotation);
+    }
+    for field in &class.values {
+        visitor.visit_field(field);
+    }
+    for method in &class.methods {
+        visitor.visit_method(method);
+    }
+}
+
+pub fn walk_field<V: Visit + ?Sized>(visitor: &mut V, field: &SmaliValue) {
+    visitor.visit_type(&field.data_type, TypePosition::Field);
+}
+
+pub fn walk_method<V: Visit + ?Sized>(visitor: &mut V, method: &SmaliMethod) {
+    for (i, parameter) in method.parameter_types.iter().enumerate() {
+        visitor.visit_type(parameter, TypePosition::Parameter(i));
+    }
+    visitor.visit_type(&method.return_type, TypePosition::Return);
+}
+
+pub fn walk_annotation<V: Visit + ?Sized>(visitor: &mut V, annotation: &SmaliAnnotation) {
+    visitor.visit_class_path(&annotation.type_path, TypePosition::Annotation);
+    for element in &annotation.elements {
+        visitor.visit_annotation_value(&element.value);
+    }
+}
+
+pub fn walk_annotation_value<V: Visit + ?Sized>(visitor: &mut V, value: &AnnotationValue) {
+    match value {
+        AnnotationValue::Type(smali_type) => {
+            visitor.visit_type(smali_type, TypePosition::Annotation)
+        }
+        AnnotationValue::Array(values) => {
+            for value in values {
+                visitor.visit_annotation_value(value);
+            }
+        }
+        AnnotationValue::Annotation(annotation) => visitor.visit_annotation(annotation),
+        _ => {}
+    }
+}
+
+/// Visits the element type of arrays and the class path of class types
+pub fn walk_type<V: Visit + ?Sized>(
+    visitor: &mut V,
+    smali_type: &SmaliType,
+    position: TypePosition,
+) {
+    match smali_type {
+        SmaliType::Arr(element) => visitor.visit_type(element, position),
+        SmaliType::Class(class_path) => visitor.visit_class_path(class_path, position),
+        _ => {}
+    }
+}
+
+/// Walks a class mutably, see the module docs
+pub trait VisitMut {
+    fn visit_class_mut(&mut self, class: &mut SmaliClass) {
+        walk_class_mut(self, class);
+    }
+
+    fn visit_field_mut(&mut self, field: &mut SmaliValue) {
+        walk_field_mut(self, field);
+    }
+
+    fn visit_method_mut(&mut self, method: &mut SmaliMethod) {
+        walk_method_mut(self, method);
+    }
+
+    fn visit_annotation_mut(&mut self, annotation: &mut SmaliAnnotation) {
+        walk_annotation_mut(self, annotation);
+    }
+
+    fn visit_annotation_value_mut(&mut self, value: &mut AnnotationValue) {
+        walk_annotation_value_mut(self, value);
+    }
+
+    fn visit_type_mut(&mut self, smali_type: &mut SmaliType, position: TypePosition) {
+        walk_type_mut(self, smali_type, position);
+    }
+
+    fn visit_class_path_mut(&mut self, _class_path: &mut ClassPath, _position: TypePosition) {}
+}
+
+/// Like `walk_class`, in the same order
+pub fn walk_class_mut<V: VisitMut + ?Sized>(visitor: &mut V, class: &mut SmaliClass) {
+    visitor.visit_class_path_mut(&mut class.class_path, TypePosition::Class);
+    if let Some(super_path) = &mut class.super_path {
+        visitor.visit_class_path_mut(super_path, TypePosition::Super);
+    }
+    for interface in &mut class.interfaces {
+        visitor.visit_class_path_mut(interface, TypePosition::Interface);
+    }
+    for annotation in &mut class.annotations {
+        visitor.visit_annotation_mut(annotation);
+    }
+    for field in &mut class.values {
+        visitor.visit_field_mut(field);
+    }
+    for method in &mut class.methods {
+        visitor.visit_method_mut(method);
+    }
+}
+
+pub fn walk_field_mut<V: VisitMut + ?Sized>(visitor: &mut V, field: &mut SmaliValue) {
+    visitor.visit_type_mut(&mut field.data_type, TypePosition::Field);
+}
+
+pub fn walk_method_mut<V: VisitMut + ?Sized>(visitor: &mut V, method: &mut SmaliMethod) {
+    for (i, parameter) in method.parameter_types.iter_mut().enumerate() {
+        visitor.visit_type_mut(parameter, TypePosition::Parameter(i));
+    }
+    visitor.visit_type_mut(&mut method.return_type, TypePosition::Return);
+}
+
+pub fn walk_annotation_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    annotation: &mut SmaliAnnotation,
+) {
+    visitor.visit_class_path_mut(&mut annotation.type_path, TypePosition::Annotation);
+    for element in &mut annotation.elements {
+        visitor.visit_annotation_value_mut(&mut element.value);
+    }
+}
+
+pub fn walk_annotation_value_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    value: &mut AnnotationValue,
+) {
+    match value {
+        AnnotationValue::Type(smali_type) => {
+            visitor.visit_type_mut(smali_type, TypePosition::Annotation)
+        }
+        AnnotationValue::Array(values) => {
+            for value in values {
+                visitor.visit_annotation_value_mut(value);
+            }
+        }
+        AnnotationValue::Annotation(annotation) => visitor.visit_annotation_mut(annotation),
+        _ => {}
+    }
+}
+
+pub fn walk_type_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    smali_type: &mut SmaliType,
+    position: TypePosition,
+) {
+    match smali_type {
+        SmaliType::Arr(element) => visitor.visit_type_mut(element, position),
+        SmaliType::Class(class_path) => visitor.visit_class_path_mut(class_path, position),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    const PLAYER: &str = ".class public Ltv/twitch/Player;
+.super Ltv/twitch/Base;
+.implements Ltv/twitch/Playable;
+.annotation system Ldalvik/annotation/MemberClasses;
+    value = {
+        Ltv/twitch/Player$Controls;
+    }
+.end annotation
+.field private listeners:[[Ltv/twitch/Listener;
+.field private state:I
+.method public play(Ltv/twitch/Stream;J)Ljava/lang/String;
+.end method";
+
+    fn player() -> SmaliClass {
+        crate::parse_class(PLAYER).unwrap()
+    }
+
+    #[derive(Default)]
+    struct TypeCollector(Vec<(String, TypePosition)>);
+
+    impl Visit for TypeCollector {
+        fn visit_class_path(&mut self, class_path: &ClassPath, position: TypePosition) {
+            self.0.push((class_path.to_string(), position));
+        }
+    }
+
+    #[test]
+    fn collects_types() {
+        let mut collector = TypeCollector::default();
+        collector.visit_class(&player());
+        let types: Vec<_> = collector
+            .0
+            .iter()
+            .map(|(class_path, position)| (class_path.as_str(), *position))
+            .collect();
+        assert_eq!(
+            types,
+            [
+                ("tv.twitch.Player", TypePosition::Class),
+                ("tv.twitch.Base", TypePosition::Super),
+                ("tv.twitch.Playable", TypePosition::Interface),
+                ("dalvik.annotation.MemberClasses", TypePosition::Annotation),
+                ("tv.twitch.Player$Controls", TypePosition::Annotation),
+                ("tv.twitch.Listener", TypePosition::Field),
+                ("tv.twitch.Stream", TypePosition::Parameter(0)),
+                ("java.lang.String", TypePosition::Return),
+            ]
+        );
+    }
+
+    /// Counts the methods and fields but skips the types of private fields
+    #[derive(Default)]
+    struct Stats {
+        fields: usize,
+        methods: usize,
+        types: BTreeSet<String>,
+    }
+
+    impl Visit for Stats {
+        fn visit_field(&mut self, field: &SmaliValue) {
+            self.fields += 1;
+            if field.access != SmaliAccessModifier::Private {
+                walk_field(self, field);
+            }
+        }
+
+        fn visit_method(&mut self, method: &SmaliMethod) {
+            self.methods += 1;
+            walk_method(self, method);
+        }
+
+        fn visit_class_path(&mut self, class_path: &ClassPath, position: TypePosition) {
+            if position != TypePosition::Class {
+                self.types.insert(class_path.to_string());
+            }
+        }
+    }
+
+    #[test]
+    fn skips_children() {
+        let mut stats = Stats::default();
+        stats.visit_class(&player());
+        assert_eq!((stats.fields, stats.methods), (2, 1));
+        assert!(!stats.types.contains("tv.twitch.Listener"));
+        assert!(stats.types.contains("tv.twitch.Stream"));
+    }
+
+    struct Rename<'a>(&'a str, ClassPath);
+
+    impl VisitMut for Rename<'_> {
+        fn visit_class_path_mut(&mut self, class_path: &mut ClassPath, _position: TypePosition) {
+            if class_path.as_java() == self.0 {
+                *class_path = self.1.clone();
+            }
+        }
+    }
+
+    #[test]
+    fn renames_types() {
+        let mut class = player();
+        let target = ClassPath::from_java("tv.twitch.PlayerListener").unwrap();
+        Rename("tv.twitch.Listener", target.clone()).visit_class_mut(&mut class);
+        assert_eq!(
+            class.values[0].data_type,
+            SmaliType::Arr(Box::new(SmaliType::Arr(Box::new(SmaliType::Class(target)))))
+        );
+
+        let target = ClassPath::from_java("tv.twitch.chat.Controls").unwrap();
+        Rename("tv.twitch.Player$Controls", target.clone()).visit_class_mut(&mut class);
+        let mut collector = TypeCollector::default();
+        collector.visit_class(&class);
+        assert_eq!(collector.0[4].0, "tv.twitch.chat.Controls");
+    }
+}
@@ -0,0 +1,375 @@
+use crate::annotation::*;
+use crate::parser::util::escape;
+use crate::smali_class::*;
+use std::fmt::Write;
+
+/// Serializes a SmaliClass into the content of a valid smali file
+///
+/// Methods are written as stubs that throw a `RuntimeException`, parsing the output yields a
+/// SmaliClass equal to the input
+pub fn write_class(class: &SmaliClass) -> String {
+    let mut out = String::new();
+
+    let mut header = vec![".class"];
+    push_access(&mut header, &class.access);
+    if matches!(class.kind, ClassKind::Interface | ClassKind::Annotation) {
+        header.push("interface");
+    }
+    if class.is_abstract {
+        header.push("abstract");
+    }
+    if class.is_synthetic {
+        header.push("synthetic");
+    }
+    match class.kind {
+        ClassKind::Annotation => header.push("annotation"),
+        ClassKind::Enum => header.push("enum"),
+        ClassKind::Class | ClassKind::Interface => {}
+    }
+    let _ = writeln!(out, "{} {}", header.join(" "), class.class_path.to_smali());
+
+    if let Some(super_path) = &class.super_path {
+        let _ = writeln!(out, ".super {}", super_path.to_smali());
+    }
+
+    if !class.interfaces.is_empty() {
+        out.push_str("\n# interfaces\n");
+        for interface in &class.interfaces {
+            let _ = writeln!(out, ".implements {}", interface.to_smali());
+        }
+    }
+
+    if !class.annotations.is_empty() {
+        out.push_str("\n# annotations\n");
+        for annotation in &class.annotations {
+            write_annotation(&mut out, annotation);
+        }
+    }
+
+    if !class.values.is_empty() {
+        out.push_str("\n# fields\n");
+        for value in &class.values {
+            write_value(&mut out, value);
+        }
+    }
+
+    if !class.methods.is_empty() {
+        out.push_str("\n# methods\n");
+        for method in &class.methods {
+            write_method(&mut out, method);
+        }
+    }
+
+    out
+}
+
+fn write_value(out: &mut String, value: &SmaliValue) {
+    let mut header = vec![".field"];
+    push_access(&mut header, &value.access);
+    if value.is_static {
+        header.push("static");
+    }
+    if value.is_final {
+        header.push("final");
+    }
+    if value.is_synthetic {
+        header.push("synthetic");
+    }
+    if value.is_enum {
+        header.push("enum");
+    }
+    let _ = writeln!(
+        out,
+        "{} {}:{}",
+        header.join(" "),
+        escape(&value.name),
+        value.data_type.to_smali()
+    );
+}
+
+fn write_method(out: &mut String, method: &SmaliMethod) {
+    let mut header = vec![".method"];
+    push_access(&mut header, &method.access);
+    if method.is_static {
+        header.push("static");
+    }
+    if method.is_final {
+        header.push("final");
+    }
+    if method.is_synthetic {
+        header.push("synthetic");
+    }
+    if method.name.as_str() == "<init>" || method.name.as_str() == "<clinit>" {
+        header.push("constructor");
+    }
+
+    let params: String = method
+        .parameter_types
+        .iter()
+        .map(SmaliType::to_smali)
+        .collect();
+
+    // v0 holds the exception, followed by the parameter registers (p0 is `this` for instance methods)
+    let this_register = usize::from(!method.is_static);
+    let param_registers: usize = method
+        .parameter_types
+        .iter()
+        .map(SmaliType::register_count)
+        .sum();
+
+    let _ = writeln!(
+        out,
+        "{} {}({}){}",
+        header.join(" "),
+        escape(&method.name),
+        params,
+        method.return_type.to_smali()
+    );
+    let _ = writeln!(
+        out,
+        "    .registers {}",
+        1 + this_register + param_registers
+    );
+    out.push_str("\n    new-instance v0, Ljava/lang/RuntimeException;\n");
+    out.push_str("    invoke-direct {v0}, Ljava/lang/RuntimeException;-><init>()V\n");
+    out.push_str("    throw v0\n");
+    out.push_str(".end method\n\n");
+}
+
+fn write_annotation(out: &mut String, annotation: &SmaliAnnotation) {
+    let visibility = match annotation.visibility {
+        AnnotationVisibility::Build => "build",
+        AnnotationVisibility::Runtime => "runtime",
+        AnnotationVisibility::System => "system",
+    };
+    let _ = writeln!(
+        out,
+        ".annotation {} {}",
+        visibility,
+        annotation.type_path.to_smali()
+    );
+    write_elements(out, &annotation.elements, 1);
+    out.push_str(".end annotation\n\n");
+}
+
+fn write_elements(out: &mut String, elements: &[AnnotationElement], depth: usize) {
+    for element in elements {
+        let _ = write!(out, "{}{} = ", "    ".repeat(depth), escape(&element.name));
+        write_annotation_value(out, &element.value, depth);
+        out.push('\n');
+    }
+}
+
+/// Writes a value the way baksmali does, arrays and subannotations span multiple lines
+fn write_annotation_value(out: &mut String, value: &AnnotationValue, depth: usize) {
+    let indent = "    ".repeat(depth);
+    let _ = match value {
+        AnnotationValue::Byte(value) => write!(out, "{}t", hex(i64::from(*value))),
+        AnnotationValue::Short(value) => write!(out, "{}s", hex(i64::from(*value))),
+        AnnotationValue::Char(value) => write!(out, "'{}'", escape_literal(&[*value])),
+        AnnotationValue::Int(value) => write!(out, "{}", hex(i64::from(*value))),
+        AnnotationValue::Long(value) => write!(out, "{}L", hex(*value)),
+        AnnotationValue::Float(value) => write!(out, "{}f", java_float(*value)),
+        AnnotationValue::Double(value) => write!(out, "{}", java_float(*value)),
+        AnnotationValue::Boolean(value) => write!(out, "{}", value),
+        AnnotationValue::Null => write!(out, "null"),
+        AnnotationValue::String(string) => {
+            let units: Vec<u16> = string.encode_utf16().collect();
+            write!(out, "\"{}\"", escape_literal(&units))
+        }
+        AnnotationValue::Type(data_type) => write!(out, "{}", data_type.to_smali()),
+        AnnotationValue::Field(member)
+        | AnnotationValue::Method(member)
+        | AnnotationValue::MethodType(member)
+        | AnnotationValue::MethodHandle(member) => write!(out, "{}", member),
+        AnnotationValue::Enum(member) => write!(out, ".enum {}", member),
+        AnnotationValue::Array(values) if values.is_empty() => write!(out, "{{}}"),
+        AnnotationValue::Array(values) => {
+            out.push_str("{\n");
+            for (i, value) in values.iter().enumerate() {
+                let _ = write!(out, "{}    ", indent);
+                write_annotation_value(out, value, depth + 1);
+                out.push_str(if i + 1 < values.len() { ",\n" } else { "\n" });
+            }
+            write!(out, "{}}}", indent)
+        }
+        AnnotationValue::Annotation(annotation) => {
+            let _ = writeln!(out, ".subannotation {}", annotation.type_path.to_smali());
+            write_elements(out, &annotation.elements, depth + 1);
+            write!(out, "{}.end subannotation", indent)
+        }
+    };
+}
+
+/// `0x1f` or `-0x1f` like baksmali writes integers
+fn hex(value: i64) -> String {
+    if value < 0 {
+        format!("-0x{:x}", value.unsigned_abs())
+    } else {
+        format!("0x{:x}", value)
+    }
+}
+
+/// `NaN` and `Infinity` are spelled like in java, finite values keep a fraction (`1.0`)
+fn java_float<T: Into<f64> + std::fmt::Debug + Copy>(value: T) -> String {
+    let wide: f64 = value.into();
+    if wide.is_nan() {
+        "NaN".to_string()
+    } else if wide.is_infinite() {
+        if wide > 0.0 { "Infinity" } else { "-Infinity" }.to_string()
+    } else {
+        format!("{:?}", value)
+    }
+}
+
+/// Escapes UTF-16 code units for a quoted literal, everything but printable ASCII
+/// becomes an escape sequence
+fn escape_literal(units: &[u16]) -> String {
+    let mut string = String::with_capacity(units.len());
+    for &unit in units {
+        match unit {
+            0x22 | 0x27 | 0x5c => {
+                string.push('\\');
+                string.push(char::from(unit as u8));
+            }
+            0x20..=0x7e => string.push(char::from(unit as u8)),
+            0x0a => string.push_str("\\n"),
+            0x0d => string.push_str("\\r"),
+            0x09 => string.push_str("\\t"),
+            _ => {
+                let _ = write!(string, "\\u{:04x}", unit);
+            }
+        }
+    }
+    string
+}
+
+fn push_access(tokens: &mut Vec<&str>, access: &SmaliAccessModifier) {
+    if *access != SmaliAccessModifier::Package {
+        tokens.push(access.keyword());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_class, ClassPath, Symbol};
+
+    #[test]
+    fn simple() {
+        let mut class = SmaliClass::new(
+            ClassPath::from_java("bttv.test.Util").unwrap(),
+            SmaliAccessModifier::Public,
+            false,
+        );
+        class.super_path = Some(ClassPath::from_java("java.lang.Object").unwrap());
+        class.methods.push(SmaliMethod {
+            name: Symbol::intern("<init>"),
+            access: SmaliAccessModifier::Public,
+            parameter_types: vec![SmaliType::Long, SmaliType::Int],
+            return_type: SmaliType::Void,
+            is_static: false,
+            is_final: false,
+            is_synthetic: false,
+        });
+
+        let expected = ".class public Lbttv/test/Util;
+.super Ljava/lang/Object;
+
+# methods
+.method public constructor <init>(JI)V
+    .registers 5
+
+    new-instance v0, Ljava/lang/RuntimeException;
+    invoke-direct {v0}, Ljava/lang/RuntimeException;-><init>()V
+    throw v0
+.end method
+
+";
+        assert_eq!(write_class(&class), expected);
+    }
+
+    #[test]
+    fn escaped_names() {
+        let mut class = SmaliClass::new(
+            ClassPath::from_java("bttv.a b").unwrap(),
+            SmaliAccessModifier::Package,
+            true,
+        );
+        class.values.push(SmaliValue {
+            name: Symbol::intern("x\u{a0}y"),
+            data_type: SmaliType::Class(ClassPath::from_java("bttv.a b").unwrap()),
+            access: SmaliAccessModifier::Private,
+            is_static: true,
+            is_final: true,
+            is_synthetic: false,
+            is_enum: false,
+        });
+
+        let written = write_class(&class);
+        assert!(written.starts_with(".class abstract Lbttv/a\\u0020b;\n"));
+        assert_eq!(parse_class(&written).unwrap(), class);
+    }
+
+    #[test]
+    fn kinds() {
+        let mut class = SmaliClass::new(
+            ClassPath::from_java("bttv.Keep").unwrap(),
+            SmaliAccessModifier::Public,
+            true,
+        );
+        class.kind = ClassKind::Annotation;
+
+        let written = write_class(&class);
+        assert!(written.starts_with(".class public interface abstract annotation Lbttv/Keep;\n"));
+        assert_eq!(parse_class(&written).unwrap(), class);
+    }
+
+    #[test]
+    fn annotations() {
+        let mut class = SmaliClass::new(
+            ClassPath::from_java("bttv.test.Util").unwrap(),
+            SmaliAccessModifier::Public,
+            false,
+        );
+        let element = |name: &str, value| AnnotationElement {
+            name: Symbol::intern(name),
+            value,
+        };
+        class.annotations.push(SmaliAnnotation {
+            visibility: AnnotationVisibility::Runtime,
+            type_path: ClassPath::from_java("kotlin.Metadata").unwrap(),
+            elements: vec![
+                element("k", AnnotationValue::Int(1)),
+                element(
+                    "d1",
+                    AnnotationValue::Array(vec![AnnotationValue::String(
+                        "\u{0}\u{6}\n\"\u{e4}\u{1F600}".to_string(),
+                    )]),
+                ),
+                element("f", AnnotationValue::Float(1.1)),
+                element("d", AnnotationValue::Double(f64::INFINITY)),
+                element("c", AnnotationValue::Char(0x27)),
+                element("e", AnnotationValue::Array(vec![])),
+                element(
+                    "a",
+                    AnnotationValue::Annotation(SmaliAnnotation {
+                        visibility: AnnotationVisibility::Runtime,
+                        type_path: ClassPath::from_java("bttv.Inner").unwrap(),
+                        elements: vec![element("l", AnnotationValue::Long(-1))],
+                    }),
+                ),
+            ],
+        });
+
+        let written = write_class(&class);
+        assert!(written.contains(
+            "    d1 = {\n        \"\\u0000\\u0006\\n\\\"\\u00e4\\ud83d\\ude00\"\n    }\n"
+        ));
+        assert!(written.contains("    f = 1.1f\n    d = Infinity\n    c = '\\''\n    e = {}\n"));
+        assert!(written.contains(
+            "    a = .subannotation Lbttv/Inner;\n        l = -0x1L\n    .end subannotation\n"
+        ));
+        assert_eq!(parse_class(&written).unwrap(), class);
+    }
+}
@@ -0,0 +1,570 @@
+//! Cross references from method bodies to classes, fields and methods
+//!
+//! The references of `invoke-*`, `iget*`/`iput*`, `sget*`/`sput*`, `const-class`, `check-cast`
+//! and `new-instance` instructions are read together with their line in the smali file.
+//! An `XrefIndex` over a whole tree answers who references a class or member (callers)
+//! and what the methods of a class reference (callees).
+
+use crate::bodies::walk_bodies;
+use crate::class_index::ClassIndex;
+use crate::class_path::ClassPath;
+use crate::err::*;
+use crate::parser::method;
+use crate::parser::util::{escape, unescape};
+use crate::smali_class::{SmaliMethod, SmaliType};
+use crate::symbol::Symbol;
+use crate::tree::read_tree_with;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// The instruction a reference was found in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum XrefKind {
+    /// `invoke-*` of a method
+    Invoke,
+    /// `iget*` and `sget*` of a field
+    Read,
+    /// `iput*` and `sput*` of a field
+    Write,
+    ConstClass,
+    CheckCast,
+    NewInstance,
+}
+
+/// A referenced class, field or method
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MemberRef {
+    Class(ClassPath),
+    Field {
+        owner: ClassPath,
+        name: Symbol,
+        data_type: SmaliType,
+    },
+    Method {
+        owner: ClassPath,
+        name: Symbol,
+        parameter_types: Vec<SmaliType>,
+        return_type: SmaliType,
+    },
+}
+
+/// A reference in a method body
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Xref {
+    pub kind: XrefKind,
+    pub target: MemberRef,
+    /// the line of the instruction in the smali file, starting at 1
+    pub line: usize,
+}
+
+/// A method with all references in its body
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MethodXrefs {
+    pub method: SmaliMethod,
+    /// the line of the `.method` directive
+    pub line: usize,
+    pub xrefs: Vec<Xref>,
+}
+
+/// The references of all methods of a class
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClassXrefs {
+    pub class_path: ClassPath,
+    pub methods: Vec<MethodXrefs>,
+}
+
+impl MemberRef {
+    /// The class that is referenced or declares the referenced member
+    pub fn owner(&self) -> &ClassPath {
+        match self {
+            Self::Class(owner) | Self::Field { owner, .. } | Self::Method { owner, .. } => owner,
+        }
+    }
+
+    /// The referenced member in smali notation without its owner (`run(I)V`, `name:I`)
+    fn member(&self) -> Option<String> {
+        match self {
+            Self::Class(_) => None,
+            Self::Field {
+                name, data_type, ..
+            } => Some(format!("{}:{}", escape(name), data_type.to_smali())),
+            Self::Method {
+                name,
+                parameter_types,
+                return_type,
+                ..
+            } => {
+                let parameters: String = parameter_types.iter().map(SmaliType::to_smali).collect();
+                Some(format!(
+                    "{}({}){}",
+                    escape(name),
+                    parameters,
+                    return_type.to_smali()
+                ))
+            }
+        }
+    }
+
+    /// A method of a class as reference, e.g. to look up its callers
+    pub fn method(owner: &ClassPath, method: &SmaliMethod) -> Self {
+        Self::Method {
+            owner: owner.clone(),
+            name: method.name.clone(),
+            parameter_types: method.parameter_types.clone(),
+            return_type: method.return_type.clone(),
+        }
+    }
+}
+
+impl fmt::Display for MemberRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.owner().to_smali())?;
+        match self.member() {
+            Some(member) => write!(f, "->{}", member),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for XrefKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Invoke => "invoke",
+            Self::Read => "read",
+            Self::Write => "write",
+            Self::ConstClass => "const-class",
+            Self::CheckCast => "check-cast",
+            Self::NewInstance => "new-instance",
+        })
+    }
+}
+
+/// Reads the references of all method bodies of a smali class
+pub fn read_xrefs(
+    lines: impl Iterator<Item = ParserResult<impl AsRef<str>>>,
+) -> ParserResult<ClassXrefs> {
+    let (class_path, methods) = walk_bodies(lines, parse_instruction)?;
+    Ok(ClassXrefs {
+        class_path,
+        methods: methods
+            .into_iter()
+            .map(|body| MethodXrefs {
+                method: body.method,
+                line: body.line,
+                xrefs: body.items,
+            })
+            .collect(),
+    })
+}
+
+pub(crate) fn parse_instruction(line: &str, line_number: usize) -> ParserResult<Option<Xref>> {
+    let (opcode, operands) = match line.split_once(char::is_whitespace) {
+        Some(split) => split,
+        None => return Ok(None),
+    };
+    let invalid = || ParserError::InvalidReference(line_number, line.to_string());
+    // the reference is the last operand, invoke-polymorphic adds a prototype after it
+    let last_operand = || operands.rsplit(", ").next().unwrap_or(operands).trim();
+
+    let (kind, target) = if opcode.starts_with("invoke-") && opcode != "invoke-custom" {
+        let reference = operands
+            .split_once("}, ")
+            .and_then(|(_, reference)| reference.split(", ").next())
+            .ok_or_else(invalid)?;
+        if reference.starts_with('[') {
+            // methods of array types like `[I->clone()` refer to no class
+            return Ok(None);
+        }
+        (XrefKind::Invoke, method_ref(reference).ok_or_else(invalid)?)
+    } else if opcode.starts_with("iget") || opcode.starts_with("sget") {
+        (
+            XrefKind::Read,
+            field_ref(last_operand()).ok_or_else(invalid)?,
+        )
+    } else if opcode.starts_with("iput") || opcode.starts_with("sput") {
+        (
+            XrefKind::Write,
+            field_ref(last_operand()).ok_or_else(invalid)?,
+        )
+    } else {
+        let kind = match opcode {
+            "const-class" => XrefKind::ConstClass,
+            "check-cast" => XrefKind::CheckCast,
+            "new-instance" => XrefKind::NewInstance,
+            _ => return Ok(None),
+        };
+        let data_type = SmaliType::from_str(last_operand()).map_err(|_| invalid())?;
+        match class_of(&data_type) {
+            Some(class_path) => (kind, MemberRef::Class(class_path.clone())),
+            // arrays of primitives
+            None => return Ok(None),
+        }
+    };
+
+    Ok(Some(Xref {
+        kind,
+        target,
+        line: line_number,
+    }))
+}
+
+/// The class of a class type or of the elements of an array type
+fn class_of(data_type: &SmaliType) -> Option<&ClassPath> {
+    match data_type {
+        SmaliType::Class(class_path) => Some(class_path),
+        SmaliType::Arr(inner) => class_of(inner),
+        _ => None,
+    }
+}
+
+/// `Lowner;->name(I)V`
+fn method_ref(reference: &str) -> Option<MemberRef> {
+    let (owner, member) = reference.split_once("->")?;
+    let owner = ClassPath::from_smali(owner).ok()?;
+    let (name, descriptor) = member.split_at(member.find('(')?);
+    let (parameter_types, return_type) = method::parse_descriptor(descriptor).ok()?;
+    Some(MemberRef::Method {
+        owner,
+        name: Symbol::intern(&unescape(name)?),
+        parameter_types,
+        return_type,
+    })
+}
+
+/// `Lowner;->name:I`
+fn field_ref(reference: &str) -> Option<MemberRef> {
+    let (owner, member) = reference.split_once("->")?;
+    let (name, data_type) = member.split_once(':')?;
+    Some(MemberRef::Field {
+        owner: ClassPath::from_smali(owner).ok()?,
+        name: Symbol::intern(&unescape(name)?),
+        data_type: SmaliType::from_str(data_type).ok()?,
+    })
+}
+
+/// A class or member to look up in an `XrefIndex`
+///
+/// Parsed from a class in java (`tv.twitch.Foo`) or smali (`Ltv/twitch/Foo;`) notation,
+/// optionally followed by `->` and a member: a name matching all overloads (`run`),
+/// a method (`run(I)V`) or a field (`name:I`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct XrefQuery {
+    pub owner: ClassPath,
+    pub name: Option<Symbol>,
+    /// the smali descriptor of the member without name, `(I)V` for methods and `:I` for fields
+    pub descriptor: Option<String>,
+}
+
+impl FromStr for XrefQuery {
+    type Err = ParserError;
+    fn from_str(query: &str) -> ParserResult<Self> {
+        let (owner, member) = match query.split_once("->") {
+            Some((owner, member)) => (owner, Some(member)),
+            None => (query, None),
+        };
+        let owner = if owner.starts_with('L') && owner.ends_with(';') {
+            ClassPath::from_smali(owner)
+        } else {
+            ClassPath::from_java(owner)
+        }
+        .map_err(|_| ParserError::InvalidXrefQuery(query.to_string()))?;
+
+        let (name, descriptor) = match member {
+            None => (None, None),
+            Some(member) => {
+                let split = member.find(['(', ':']).unwrap_or(member.len());
+                let (name, descriptor) = member.split_at(split);
+                let name = unescape(name)
+                    .filter(|name| !name.is_empty())
+                    .ok_or_else(|| ParserError::InvalidXrefQuery(query.to_string()))?;
+                let descriptor = Some(descriptor)
+                    .filter(|descriptor| !descriptor.is_empty())
+                    .map(str::to_string);
+                (Some(Symbol::intern(&name)), descriptor)
+            }
+        };
+        Ok(Self {
+            owner,
+            name,
+            descriptor,
+        })
+    }
+}
+
+impl XrefQuery {
+    /// Whether a reference points to the queried class (or one of its members)
+    /// or the queried member
+    pub fn matches(&self, target: &MemberRef) -> bool {
+        if *target.owner() != self.owner {
+            return false;
+        }
+        let Some(name) = &self.name else {
+            return true;
+        };
+        let (target_name, descriptor) = match target {
+            MemberRef::Class(_) => return false,
+            MemberRef::Field {
+                name, data_type, ..
+            } => (name, format!(":{}", data_type.to_smali())),
+            MemberRef::Method {
+                name,
+                parameter_types,
+                return_type,
+                ..
+            } => {
+                let parameters: String = parameter_types.iter().map(SmaliType::to_smali).collect();
+                (name, format!("({}){}", parameters, return_type.to_smali()))
+            }
+        };
+        target_name == name
+            && self
+                .descriptor
+                .as_ref()
+                .is_none_or(|expected| *expected == descriptor)
+    }
+
+    /// Whether a method is the queried member or a method of the queried class
+    fn matches_method(&self, owner: &ClassPath, method: &SmaliMethod) -> bool {
+        self.matches(&MemberRef::method(owner, method))
+    }
+}
+
+/// A reference found by an `XrefIndex` together with the method and file it is in
+#[derive(Debug, Clone, Copy)]
+pub struct XrefHit<'a> {
+    pub path: &'a Path,
+    pub class_path: &'a ClassPath,
+    pub method: &'a MethodXrefs,
+    pub xref: &'a Xref,
+}
+
+impl XrefHit<'_> {
+    /// The method containing the reference
+    pub fn source(&self) -> MemberRef {
+        MemberRef::method(self.class_path, &self.method.method)
+    }
+}
+
+/// The references of every class in a tree, indexed by the referenced class
+#[derive(Debug, Default)]
+pub struct XrefIndex {
+    files: Vec<(PathBuf, ClassXrefs)>,
+    failed: Vec<(PathBuf, ParserError)>,
+    by_class: HashMap<ClassPath, usize>,
+    // (file, method, xref) indices of all references by the class they point to
+    by_target: HashMap<ClassPath, Vec<(usize, usize, usize)>>,
+}
+
+impl XrefIndex {
+    /// Reads the references of every `.smali` file below dir in parallel. Unlike `from_index`
+    /// this reads any directory, but every definition of a duplicate class is included.
+    pub fn from_tree(dir: impl AsRef<Path>) -> ParserResult<Self> {
+        let tree = read_tree_with(dir.as_ref(), |path| crate::xrefs_file(path))?;
+        let mut index = Self::default();
+        for (path, xrefs) in tree.files {
+            index.push(path, xrefs);
+        }
+        index.failed = tree.failed;
+        Ok(index)
+    }
+
+    /// The references of the classes used by a `ClassIndex`, method bodies that were not read
+    /// or taken from its cache yet are read in parallel. Files that could not be read are
+    /// reported by `ClassIndex::failed`.
+    pub fn from_index(classes: &ClassIndex) -> Self {
+        classes.read_all_bodies();
+        let mut index = Self::default();
+        for entry in classes.entries() {
+            if let Ok(bodies) = entry.bodies() {
+                index.push(entry.file.clone(), bodies.xrefs.clone());
+            }
+        }
+        index
+    }
+
+    /// Adds the references of a class read from path
+    pub fn push(&mut self, path: PathBuf, class: ClassXrefs) {
+        let file = self.files.len();
+        for (m, method) in class.methods.iter().enumerate() {
+            for (x, xref) in method.xrefs.iter().enumerate() {
+                self.by_target
+                    .entry(xref.target.owner().clone())
+                    .or_default()
+                    .push((file, m, x));
+            }
+        }
+        self.by_class.insert(class.class_path.clone(), file);
+        self.files.push((path, class));
+    }
+
+    /// The files that could not be read with their errors
+    pub fn failed(&self) -> &[(PathBuf, ParserError)] {
+        &self.failed
+    }
+
+    /// All references to the queried class or member, in file and line order
+    pub fn callers(&self, query: &XrefQuery) -> Vec<XrefHit<'_>> {
+        let Some(refs) = self.by_target.get(&query.owner) else {
+            return vec![];
+        };
+        refs.iter()
+            .map(|(file, method, xref)| {
+                let (path, class) = &self.files[*file];
+                let method = &class.methods[*method];
+                XrefHit {
+                    path,
+                    class_path: &class.class_path,
+                    method,
+                    xref: &method.xrefs[*xref],
+                }
+            })
+            .filter(|hit| query.matches(&hit.xref.target))
+            .collect()
+    }
+
+    /// All references in the methods of the queried class or in the queried method
+    pub fn callees(&self, query: &XrefQuery) -> Vec<XrefHit<'_>> {
+        let Some(file) = self.by_class.get(&query.owner) else {
+            return vec![];
+        };
+        let (path, class) = &self.files[*file];
+        class
+            .methods
+            .iter()
+            .filter(|method| query.matches_method(&class.class_path, &method.method))
+            .flat_map(|method| {
+                method.xrefs.iter().map(move |xref| XrefHit {
+                    path,
+                    class_path: &class.class_path,
+                    method,
+                    xref,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAYER: &str = r#".class public Ltv/twitch/Player;
+.super Ljava/lang/Object;
+
+.field private name:Ljava/lang/String;
+
+.method public load(I)V
+    .registers 4
+
+    iget-object v0, p0, Ltv/twitch/Player;->name:Ljava/lang/String;
+    const-string v1, "iget-object v0, La/B;->c:I"
+    invoke-static {v0, p1}, Ltv/twitch/Util;->log(Ljava/lang/String;I)V
+    new-instance v1, Ltv/twitch/Request;
+    invoke-direct/range {v1 .. v1}, Ltv/twitch/Request;-><init>()V
+    check-cast v1, [Ltv/twitch/Request;
+    const-class v2, [I
+    invoke-virtual {v2}, [I->clone()Ljava/lang/Object;
+    return-void
+.end method
+
+.method public static log()V
+    .registers 1
+
+    sput-object v0, Ltv/twitch/Util;->ä:I
+    return-void
+.end method
+"#;
+
+    fn read(smali: &str) -> ClassXrefs {
+        read_xrefs(smali.lines().map(Ok)).unwrap()
+    }
+
+    #[test]
+    fn instructions() {
+        let class = read(PLAYER);
+        assert_eq!(class.class_path, "tv.twitch.Player");
+        assert_eq!(class.methods.len(), 2);
+        assert_eq!(class.methods[0].line, 6);
+
+        let refs: Vec<_> = class.methods[0]
+            .xrefs
+            .iter()
+            .map(|xref| format!("{} {} {}", xref.line, xref.kind, xref.target))
+            .collect();
+        assert_eq!(
+            refs,
+            [
+                "9 read Ltv/twitch/Player;->name:Ljava/lang/String;",
+                "11 invoke Ltv/twitch/Util;->log(Ljava/lang/String;I)V",
+                "12 new-instance Ltv/twitch/Request;",
+                "13 invoke Ltv/twitch/Request;-><init>()V",
+                "14 check-cast Ltv/twitch/Request;",
+            ]
+        );
+
+        let xref = &class.methods[1].xrefs[0];
+        assert_eq!(xref.kind, XrefKind::Write);
+        assert!(matches!(&xref.target, MemberRef::Field { name, .. } if *name == "ä"));
+    }
+
+    #[test]
+    fn invalid_reference() {
+        let smali = ".class La/B;\n.method a()V\n    invoke-static {}, La/B;\n.end method";
+        assert!(matches!(
+            read_xrefs(smali.lines().map(Ok)),
+            Err(ParserError::InvalidReference(3, _))
+        ));
+    }
+
+    #[test]
+    fn queries() {
+        let query: XrefQuery = "tv.twitch.Util".parse().unwrap();
+        assert_eq!(query.owner, "tv.twitch.Util");
+        assert_eq!(query.name, None);
+
+        let query: XrefQuery = "Ltv/twitch/Util;->log(Ljava/lang/String;I)V"
+            .parse()
+            .unwrap();
+        assert_eq!(query.name.as_deref(), Some("log"));
+        assert_eq!(query.descriptor.as_deref(), Some("(Ljava/lang/String;I)V"));
+
+        let query: XrefQuery = "tv.twitch.Player->name:Ljava/lang/String;".parse().unwrap();
+        assert_eq!(query.descriptor.as_deref(), Some(":Ljava/lang/String;"));
+
+        assert!("tv.twitch.Player->".parse::<XrefQuery>().is_err());
+        assert!("tv/twitch/Player".parse::<XrefQuery>().is_err());
+    }
+
+    #[test]
+    fn index() {
+        let mut index = XrefIndex::default();
+        index.push(PathBuf::from("Player.smali"), read(PLAYER));
+
+        let callers = |query: &str| {
+            index
+                .callers(&query.parse().unwrap())
+                .iter()
+                .map(|hit| format!("{}:{} {}", hit.path.display(), hit.xref.line, hit.source()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            callers("tv.twitch.Util->log"),
+            ["Player.smali:11 Ltv/twitch/Player;->load(I)V"]
+        );
+        assert_eq!(callers("tv.twitch.Util").len(), 2);
+        assert_eq!(callers("tv.twitch.Util->log()V").len(), 0);
+        assert_eq!(callers("tv.twitch.Request").len(), 3);
+
+        let callees = index.callees(&"tv.twitch.Player->log".parse().unwrap());
+        assert_eq!(callees.len(), 1);
+        assert_eq!(callees[0].xref.line, 23);
+        assert_eq!(index.callees(&"tv.twitch.Player".parse().unwrap()).len(), 6);
+    }
+}
@@ -5,7 +5,7 @@ use smali::*;
 fn test_simple_class() {
     let input = ".class Lbttv/test/Util;";
     let expected = SmaliClass::new(
-        "bttv.test.Util".to_string(),
+        ClassPath::from_java("bttv.test.Util").unwrap(),
         SmaliAccessModifier::Package,
         false,
     );
@@ -17,11 +17,11 @@ fn test_super_class() {
     let input = ".class public abstract Lbttv/test/Util;
 .super Lbttv/test/SuperClass$1;";
     let mut expected = SmaliClass::new(
-        "bttv.test.Util".to_string(),
+        ClassPath::from_java("bttv.test.Util").unwrap(),
         SmaliAccessModifier::Public,
         true,
     );
-    expected.super_path = Some("bttv.test.SuperClass$1".to_string());
+    expected.super_path = Some(ClassPath::from_java("bttv.test.SuperClass$1").unwrap());
     assert_eq!(parse_class(input).unwrap(), expected);
 }
 
@@ -34,13 +34,13 @@ fn test_interfaces_class() {
     let class = parse_class(input).unwrap();
     assert!(class
         .interfaces
-        .contains(&"bttv.test.Interface$1".to_string()));
+        .contains(&ClassPath::from_java("bttv.test.Interface$1").unwrap()));
     assert!(class
         .interfaces
-        .contains(&"bttv.test.Interface$2".to_string()));
+        .contains(&ClassPath::from_java("bttv.test.Interface$2").unwrap()));
     assert!(class
         .interfaces
-        .contains(&"bttv.test.Interface$3".to_string()));
+        .contains(&ClassPath::from_java("bttv.test.Interface$3").unwrap()));
 
     assert_eq!(class.interfaces.len(), 3);
 }
@@ -56,44 +56,58 @@ fn test_class_with_fields() {
 
     let res = parse_class(input).unwrap();
 
-    assert_eq!(res.class_path, "bttv.test.Util$1".to_string());
+    assert_eq!(res.class_path, "bttv.test.Util$1");
 
     assert!(res.values.contains(&SmaliValue {
-        name: "$VALUES".to_string(),
+        name: Symbol::intern("$VALUES"),
         access: SmaliAccessModifier::Private,
         is_final: true,
+        is_synthetic: true,
+        is_enum: false,
         is_static: true,
-        data_type: SmaliType::Arr(Box::new(SmaliType::Class("bttv.test.Util$1".to_string())))
+        data_type: SmaliType::Arr(Box::new(SmaliType::Class(
+            ClassPath::from_java("bttv.test.Util$1").unwrap()
+        )))
     }));
 
     assert!(res.values.contains(&SmaliValue {
-        name: "LIVE".to_string(),
+        name: Symbol::intern("LIVE"),
         access: SmaliAccessModifier::Public,
         is_final: true,
+        is_synthetic: false,
+        is_enum: true,
         is_static: true,
-        data_type: SmaliType::Class("bttv.test.Util$1".to_string())
+        data_type: SmaliType::Class(ClassPath::from_java("bttv.test.Util$1").unwrap())
     }));
 
     assert!(res.values.contains(&SmaliValue {
-        name: "VOD".to_string(),
+        name: Symbol::intern("VOD"),
         access: SmaliAccessModifier::Public,
         is_final: true,
+        is_synthetic: false,
+        is_enum: false,
         is_static: true,
         data_type: SmaliType::Int
     }));
 
     assert!(res.values.contains(&SmaliValue {
-        name: "gqlVideoType".to_string(),
+        name: Symbol::intern("gqlVideoType"),
         access: SmaliAccessModifier::Private,
         is_final: true,
+        is_synthetic: false,
+        is_enum: false,
         is_static: false,
-        data_type: SmaliType::Arr(Box::new(SmaliType::Class("bttv.test.Util$1".to_string())))
+        data_type: SmaliType::Arr(Box::new(SmaliType::Class(
+            ClassPath::from_java("bttv.test.Util$1").unwrap()
+        )))
     }));
 
     assert!(res.values.contains(&SmaliValue {
-        name: "notSure".to_string(),
+        name: Symbol::intern("notSure"),
         access: SmaliAccessModifier::Private,
         is_final: false,
+        is_synthetic: false,
+        is_enum: false,
         is_static: false,
         data_type: SmaliType::Int
     }));
@@ -107,23 +121,34 @@ fn test_class_methods() {
 .method private constructor <init>(Ljava/lang/String;ILautogenerated/type/VideoType;)V
 ";
     let mut expected = SmaliClass::new(
-        "bttv.test.Util".to_string(),
+        ClassPath::from_java("bttv.test.Util").unwrap(),
         SmaliAccessModifier::Package,
         false,
     );
 
     expected.methods.push(SmaliMethod {
-        name: "<init>".to_string(),
+        name: Symbol::intern("<init>"),
         access: SmaliAccessModifier::Private,
         is_static: false,
         is_final: false,
+        is_synthetic: false,
         return_type: SmaliType::Void,
         parameter_types: vec![
-            SmaliType::Class("java.lang.String".to_string()),
+            SmaliType::Class(ClassPath::from_java("java.lang.String").unwrap()),
             SmaliType::Int,
-            SmaliType::Class("autogenerated.type.VideoType".to_string()),
+            SmaliType::Class(ClassPath::from_java("autogenerated.type.VideoType").unwrap()),
         ],
     });
 
     assert_eq!(parse_class(input).unwrap(), expected);
 }
+
+#[test]
+fn test_too_many_classes() {
+    let input = ".class Lbttv/test/Util;
+.class Lbttv/test/Util2;";
+    assert!(matches!(
+        parse_class(input).unwrap_err(),
+        ParserError::TooManyClasses()
+    ));
+}
@@ -11,35 +11,49 @@ fn one() {
     let res = parse_file(path);
     let class = res.unwrap();
 
-    assert_eq!(class.class_path, "bttv.SleepTimer$2".to_string());
+    assert_eq!(class.class_path, "bttv.SleepTimer$2");
     assert_eq!(class.access, SmaliAccessModifier::Package);
-    assert_eq!(class.is_abstract, false);
-    assert_eq!(class.super_path.unwrap(), "java.lang.Object".to_string());
+    assert!(!class.is_abstract);
+    assert_eq!(class.super_path.unwrap(), "java.lang.Object");
 
     assert_eq!(
         class.interfaces,
-        vec!["android.content.DialogInterface$OnClickListener".to_string()]
+        vec![ClassPath::from_java("android.content.DialogInterface$OnClickListener").unwrap()]
     );
 
     assert_eq!(class.values.len(), 2);
     assert!(class.values.contains(&SmaliValue {
-        name: "val$minutes".to_string(),
+        name: Symbol::intern("val$minutes"),
         is_final: true,
+        is_synthetic: true,
+        is_enum: false,
         is_static: false,
         access: SmaliAccessModifier::Package,
         data_type: SmaliType::Arr(Box::new(SmaliType::Int))
     }));
     assert!(class.values.contains(&SmaliValue {
-        name: "val$selected".to_string(),
+        name: Symbol::intern("val$selected"),
         is_final: true,
+        is_synthetic: true,
+        is_enum: false,
         is_static: false,
         access: SmaliAccessModifier::Package,
         data_type: SmaliType::Arr(Box::new(SmaliType::Int))
     }));
 
+    assert_eq!(class.annotations.len(), 2);
+    assert_eq!(
+        class.annotations[0].type_path,
+        "dalvik.annotation.EnclosingMethod"
+    );
+    assert_eq!(
+        class.annotations[1].element("name"),
+        Some(&AnnotationValue::Null)
+    );
+
     assert_eq!(class.methods.len(), 2);
     assert!(class.methods.contains(&SmaliMethod {
-        name: "<init>".to_string(),
+        name: Symbol::intern("<init>"),
         access: SmaliAccessModifier::Package,
         parameter_types: vec![
             SmaliType::Arr(Box::new(SmaliType::Int)),
@@ -47,17 +61,262 @@ fn one() {
         ],
         return_type: SmaliType::Void,
         is_static: false,
-        is_final: false
+        is_final: false,
+        is_synthetic: false
     }));
     assert!(class.methods.contains(&SmaliMethod {
-        name: "onClick".to_string(),
+        name: Symbol::intern("onClick"),
         access: SmaliAccessModifier::Public,
         parameter_types: vec![
-            SmaliType::Class("android.content.DialogInterface".to_string()),
+            SmaliType::Class(ClassPath::from_java("android.content.DialogInterface").unwrap()),
             SmaliType::Int
         ],
         return_type: SmaliType::Void,
         is_static: false,
-        is_final: false
+        is_final: false,
+        is_synthetic: false
     }));
 }
+
+#[test]
+fn unicode() {
+    let path = files_path().join("unicode.smali");
+    let class = parse_file(path).unwrap();
+
+    assert_eq!(class.class_path, "bttv.\u{fc}ml\u{e4}ut.\u{424}");
+    assert_eq!(class.super_path.unwrap(), "bttv.\u{e4}");
+    assert_eq!(class.values.len(), 1);
+    assert_eq!(class.values[0].name, "\u{5b57}");
+}
+
+#[test]
+fn invalid_utf8() {
+    let path = files_path().join("invalid_utf8.smali");
+    let res = parse_file(path);
+    assert!(matches!(res.unwrap_err(), ParserError::InvalidUtf8(3)));
+}
+
+#[test]
+fn find_file() {
+    let apktool_dir = files_path().join("apktool");
+
+    let class_path = ClassPath::from_java("tv.twitch.Foo$Bar").unwrap();
+    let file = class_path.find_file(&apktool_dir).unwrap();
+    assert_eq!(
+        file,
+        apktool_dir.join("smali_classes3/tv/twitch/Foo$Bar.smali")
+    );
+    assert_eq!(parse_file(file).unwrap().class_path, class_path);
+
+    let class_path = ClassPath::from_java("tv.twitch.Foo").unwrap();
+    assert_eq!(
+        class_path.find_file(&apktool_dir).unwrap(),
+        apktool_dir.join("smali/tv/twitch/Foo.smali")
+    );
+
+    let class_path = ClassPath::from_java("tv.twitch.Missing").unwrap();
+    assert!(class_path.find_file(&apktool_dir).is_none());
+
+    // by dex number, not by name
+    let dex_order = files_path().join("dex_order");
+    let class_path = ClassPath::from_java("a.Dup").unwrap();
+    assert_eq!(
+        class_path.find_file(&dex_order).unwrap(),
+        dex_order.join("smali_classes2/a/Dup.smali")
+    );
+}
+
+#[test]
+fn scan_equals_parse() {
+    for file in ["one.smali", "unicode.smali", "kotlin.smali"] {
+        let path = files_path().join(file);
+        assert_eq!(scan_file(&path).unwrap(), parse_file(&path).unwrap());
+    }
+}
+
+#[test]
+fn tree() {
+    let apktool_dir = files_path().join("apktool");
+    let files = parse_tree(&apktool_dir).unwrap();
+
+    let class_paths: Vec<_> = files
+        .into_iter()
+        .map(|file| file.class.unwrap().class_path)
+        .collect();
+    assert_eq!(class_paths, vec!["tv.twitch.Foo", "tv.twitch.Foo$Bar"]);
+
+    let scanned = scan_tree(&apktool_dir).unwrap();
+    assert_eq!(
+        scanned[1].path,
+        apktool_dir.join("smali_classes3/tv/twitch/Foo$Bar.smali")
+    );
+}
+
+#[test]
+fn write_round_trip() {
+    for file in ["one.smali", "unicode.smali", "kotlin.smali"] {
+        let class = parse_file(files_path().join(file)).unwrap();
+        let written = write_class(&class);
+        assert_eq!(parse_class(&written).unwrap(), class);
+    }
+}
+
+#[test]
+fn xrefs() {
+    let class = xrefs_file(files_path().join("one.smali")).unwrap();
+    assert_eq!(class.class_path, "bttv.SleepTimer$2");
+
+    let on_click = &class.methods[1];
+    assert_eq!(on_click.method.name, "onClick");
+    let xref = on_click.xrefs.last().unwrap();
+    assert_eq!(xref.kind, XrefKind::Invoke);
+    assert_eq!(xref.line, 67);
+    assert_eq!(xref.target.to_string(), "Lbttv/SleepTimer;->access$100(I)V");
+
+    let index = XrefIndex::from_tree(files_path()).unwrap();
+    // invalid_utf8.smali can not be read
+    assert_eq!(index.failed().len(), 1);
+    let callers = index.callers(&"bttv.SleepTimer->access$100".parse().unwrap());
+    assert_eq!(callers.len(), 1);
+    assert_eq!(callers[0].method.method.name, "onClick");
+}
+
+#[test]
+fn strings() {
+    let apktool_dir = files_path().join("apktool");
+    let class = strings_file(apktool_dir.join("smali/tv/twitch/Foo.smali")).unwrap();
+    assert_eq!(class.methods[0].method.name, "url");
+    let string = &class.methods[0].strings[0];
+    assert_eq!(string.value, "https://api.twitch.tv/helix/users?login=%s");
+    assert_eq!(string.line, 17);
+
+    let index = StringIndex::from_tree(&apktool_dir).unwrap();
+    assert!(index.failed().is_empty());
+    let classes = index.search(|value| value.contains("api.twitch.tv"));
+    let class_paths: Vec<_> = classes
+        .iter()
+        .map(|class| class.class_path.clone())
+        .collect();
+    assert_eq!(class_paths, vec!["tv.twitch.Foo", "tv.twitch.Foo$Bar"]);
+    assert_eq!(classes[1].hits[0].string.value, "Bar(api.twitch.tv)");
+}
+
+#[test]
+fn hierarchy() {
+    let hierarchy = Hierarchy::from_tree(files_path().join("apktool")).unwrap();
+    assert!(hierarchy.failed().is_empty());
+
+    let object = ClassPath::from_java("java.lang.Object").unwrap();
+    assert!(hierarchy.is_stub(&object));
+    assert_eq!(
+        hierarchy.direct_subtypes(&object),
+        ["tv.twitch.Foo", "tv.twitch.Foo$Bar"]
+    );
+
+    let bar = ClassPath::from_java("tv.twitch.Foo$Bar").unwrap();
+    assert_eq!(hierarchy.superclasses(&bar), ["java.lang.Object"]);
+    assert!(matches!(
+        hierarchy.resolve_method(&bar, "toString", &[]),
+        Some(MemberOwner::Declared { class_path, .. }) if *class_path == bar
+    ));
+    assert!(matches!(
+        hierarchy.resolve_method(&bar, "hashCode", &[]),
+        Some(MemberOwner::Outside(class_path)) if *class_path == object
+    ));
+}
+
+#[test]
+fn class_index() {
+    let multidex = files_path().join("multidex");
+    let index = ClassIndex::open(&multidex).unwrap();
+    assert_eq!(
+        index.roots(),
+        ["smali", "smali_classes2", "smali_classes10"].map(|root| multidex.join(root))
+    );
+    assert_eq!(index.len(), 4);
+
+    // the first dex file wins
+    let player = ClassPath::from_java("tv.twitch.Player").unwrap();
+    assert_eq!(index.duplicates(), [&player]);
+    let definitions = index.definitions(&player);
+    assert_eq!(definitions.len(), 2);
+    assert_eq!(definitions[1].root, 2);
+    let entry = index.get(&player).unwrap();
+    assert_eq!(entry.file, multidex.join("smali/tv/twitch/Player.smali"));
+    assert!(!entry.is_parsed());
+    assert_eq!(entry.class().unwrap().methods.len(), 1);
+    assert!(entry.is_parsed());
+
+    let class_paths = |entries: Vec<&ClassEntry>| {
+        entries
+            .into_iter()
+            .map(|entry| entry.class_path.to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        class_paths(index.in_package("tv.twitch")),
+        [
+            "tv.twitch.Player",
+            "tv.twitch.Player$Controls",
+            "tv.twitch.chat.Controls"
+        ]
+    );
+    assert_eq!(
+        class_paths(index.by_simple_name("Controls")),
+        ["tv.twitch.Player$Controls", "tv.twitch.chat.Controls"]
+    );
+    assert_eq!(index.in_package("").len(), 4);
+
+    index.parse_all();
+    assert!(index.failed().is_empty());
+    assert!(ClassIndex::open(files_path()).is_err());
+
+    let app = index.app().unwrap();
+    assert_eq!(app.version_code, Some(1502000));
+    assert_eq!(app.version_name.as_deref(), Some("15.2.0"));
+    assert_eq!(
+        app.dex_files,
+        ["classes.dex", "classes2.dex", "classes10.dex"]
+    );
+    assert_eq!(
+        app.to_string(),
+        "twitch.apk 15.2.0 (1502000), minSdk 21, targetSdk 33, 3 dex files"
+    );
+    let apktool = ClassIndex::open(files_path().join("apktool")).unwrap();
+    assert!(apktool.app().is_none());
+}
+
+#[test]
+fn indexes_of_class_index() {
+    let multidex = files_path().join("multidex");
+    let index = ClassIndex::open(&multidex).unwrap();
+    let strings = |index: &StringIndex| {
+        index
+            .search(|_| true)
+            .iter()
+            .flat_map(|class| class.hits.iter().map(|hit| hit.string.value.clone()))
+            .collect::<Vec<_>>()
+    };
+
+    // only the definition of the first dex file is used
+    assert_eq!(strings(&StringIndex::from_index(&index)), ["played"]);
+    assert_eq!(
+        strings(&StringIndex::from_tree(&multidex).unwrap()),
+        ["played", "shadowed"]
+    );
+    assert!(index.failed().is_empty());
+
+    let xrefs = XrefIndex::from_index(&index);
+    let callers = xrefs.callers(&"tv.twitch.chat.Controls".parse().unwrap());
+    assert_eq!(callers.len(), 1);
+    assert_eq!(
+        callers[0].path,
+        multidex.join("smali/tv/twitch/Player.smali")
+    );
+    assert_eq!(callers[0].xref.line, 10);
+
+    let hierarchy = Hierarchy::from_index(&index);
+    let player = ClassPath::from_java("tv.twitch.Player").unwrap();
+    assert_eq!(hierarchy.class(&player).unwrap().methods[0].name, "play");
+    assert!(hierarchy.failed().is_empty());
+}
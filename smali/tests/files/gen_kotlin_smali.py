@@ -0,0 +1,280 @@
+#!/usr/bin/env python3
+"""Generates kotlin.smali, the fixture for the kotlin metadata tests.
+
+There is no kotlin compiler in the test environment, so the metadata of this class is
+assembled by hand, the way kotlinc 1.6 writes it:
+
+    package bttv.kt
+
+    class Player(val name: String, quality: Int = 0) {
+        var volume: Float? = null
+        suspend fun load(id: String, force: Boolean = false): List<String>? = null
+        fun <T : Any> find(key: T): T? = null
+        companion object
+    }
+
+Usage: python3 gen_kotlin_smali.py  (writes kotlin.smali next to this script)
+"""
+
+import os
+
+
+def varint(value):
+    value &= (1 << 64) - 1
+    out = bytearray()
+    while True:
+        byte = value & 0x7F
+        value >>= 7
+        if value:
+            out.append(byte | 0x80)
+        else:
+            out.append(byte)
+            return bytes(out)
+
+
+def field(number, value):
+    """an int field for int values, a length delimited field for bytes"""
+    if isinstance(value, int):
+        return varint(number << 3) + varint(value)
+    return varint(number << 3 | 2) + varint(len(value)) + value
+
+
+def message(*fields):
+    return b"".join(field(number, value) for number, value in fields)
+
+
+# d2 and the records of the string table, None marks predefined strings
+D2 = [
+    ("Lbttv/kt/Player;", dict(operation=2)),  # 0
+    (None, dict(predefined=0)),  # 1 kotlin/Any
+    ("name", {}),  # 2
+    (None, dict(predefined=14)),  # 3 kotlin/String
+    ("quality", {}),  # 4
+    (None, dict(predefined=8)),  # 5 kotlin/Int
+    ("volume", {}),  # 6
+    (None, dict(predefined=7)),  # 7 kotlin/Float
+    ("load", {}),  # 8
+    ("id", {}),  # 9
+    ("force", {}),  # 10
+    (None, dict(predefined=11)),  # 11 kotlin/Boolean
+    (None, dict(predefined=32)),  # 12 kotlin/collections/List
+    ("find", {}),  # 13
+    ("T", {}),  # 14
+    ("key", {}),  # 15
+    ("Companion", {}),  # 16
+    ("getName", {}),  # 17
+    ("()Ljava/lang/String;", {}),  # 18
+    ("getVolume", {}),  # 19
+    ("()Ljava/lang/Float;", {}),  # 20
+    ("setVolume", {}),  # 21
+    ("(Ljava/lang/Float;)V", {}),  # 22
+    ("(Ljava/lang/String;ZLkotlin/coroutines/Continuation;)Ljava/lang/Object;", {}),  # 23
+    ("(Ljava/lang/Object;)Ljava/lang/Object;", {}),  # 24
+    ("Ljava/lang/Float;", {}),  # 25
+]
+
+
+def string_table():
+    records = []
+    for _, record in D2:
+        fields = []
+        if "predefined" in record:
+            fields.append((2, record["predefined"]))
+        if "operation" in record:
+            fields.append((3, record["operation"]))
+        # consecutive plain strings share one record with a range
+        if not fields and records and records[-1][1] == []:
+            records[-1][0] += 1
+        else:
+            records.append([1, fields])
+    table = b"".join(
+        field(1, message(*(([(1, count)] if count > 1 else []) + fields)))
+        for count, fields in records
+    )
+    return varint(len(table)) + table
+
+
+def class_type(name, nullable=False, *arguments):
+    fields = [(6, name)]
+    fields += [(2, message((2, argument))) for argument in arguments]
+    if nullable:
+        fields.append((3, 1))
+    return message(*fields)
+
+
+def parameter(name, data_type, has_default=False):
+    fields = [(1, 2)] if has_default else []
+    return message(*(fields + [(2, name), (3, data_type)]))
+
+
+def signature(name=None, desc=None):
+    fields = []
+    if name is not None:
+        fields.append((1, name))
+    if desc is not None:
+        fields.append((2, desc))
+    return message(*fields)
+
+
+STRING = class_type(3)
+
+CLASS = message(
+    (1, 6),
+    (3, 0),
+    (4, 16),
+    (6, class_type(1)),
+    (8, message((1, 6), (2, parameter(2, STRING)), (2, parameter(4, class_type(5), True)))),
+    # suspend fun load, the return type is stored in the type table
+    (
+        9,
+        message(
+            (9, 6 | 1 << 13),
+            (2, 8),
+            (7, 0),
+            (6, parameter(9, STRING)),
+            (6, parameter(10, class_type(11), True)),
+            (100, signature(desc=23)),
+        ),
+    ),
+    (
+        9,
+        message(
+            (9, 6),
+            (2, 13),
+            (4, message((1, 0), (2, 14), (5, class_type(1)))),
+            (3, message((7, 0), (3, 1))),
+            (6, parameter(15, message((7, 0)))),
+            (100, signature(desc=24)),
+        ),
+    ),
+    (
+        10,
+        message(
+            (11, 518),
+            (2, 2),
+            (3, STRING),
+            (100, message((1, b""), (3, signature(17, 18)))),
+        ),
+    ),
+    (
+        10,
+        message(
+            (11, 518 | 1 << 8 | 1 << 10),
+            (2, 6),
+            (3, class_type(7, True)),
+            (
+                100,
+                message((1, signature(desc=25)), (3, signature(19, 20)), (4, signature(21, 22))),
+            ),
+        ),
+    ),
+    (30, message((1, class_type(12, True, STRING)))),
+)
+
+
+def smali_string(data):
+    out = []
+    for ch in data:
+        code = ord(ch)
+        if ch in "\"'\\":
+            out.append("\\" + ch)
+        elif 0x20 <= code < 0x7F:
+            out.append(ch)
+        elif ch == "\n":
+            out.append("\\n")
+        elif ch == "\r":
+            out.append("\\r")
+        elif ch == "\t":
+            out.append("\\t")
+        else:
+            out.append("\\u%04x" % code)
+    return '"%s"' % "".join(out)
+
+
+def stub(header):
+    return """%s
+    .registers 8
+
+    new-instance v0, Ljava/lang/RuntimeException;
+    invoke-direct {v0}, Ljava/lang/RuntimeException;-><init>()V
+    throw v0
+.end method
+""" % header
+
+
+def main():
+    d1 = "\u0000" + "".join(chr(byte) for byte in string_table() + CLASS)
+    d2 = ",\n        ".join(smali_string(string or "") for string, _ in D2)
+
+    methods = [
+        ".method static constructor <clinit>()V",
+        ".method public constructor <init>(Ljava/lang/String;I)V",
+        ".method public synthetic constructor <init>(Ljava/lang/String;IILkotlin/jvm/internal/DefaultConstructorMarker;)V",
+        ".method public static synthetic load$default(Lbttv/kt/Player;Ljava/lang/String;ZLkotlin/coroutines/Continuation;ILjava/lang/Object;)Ljava/lang/Object;",
+        ".method public final find(Ljava/lang/Object;)Ljava/lang/Object;",
+        ".method public final getName()Ljava/lang/String;",
+        ".method public final getVolume()Ljava/lang/Float;",
+        ".method public final load(Ljava/lang/String;ZLkotlin/coroutines/Continuation;)Ljava/lang/Object;",
+        ".method public final setVolume(Ljava/lang/Float;)V",
+    ]
+
+    smali = """.class public final Lbttv/kt/Player;
+.super Ljava/lang/Object;
+.source "Player.kt"
+
+
+# annotations
+.annotation system Ldalvik/annotation/MemberClasses;
+    value = {
+        Lbttv/kt/Player$Companion;
+    }
+.end annotation
+
+.annotation runtime Lkotlin/Metadata;
+    d1 = {
+        %s
+    }
+    d2 = {
+        %s
+    }
+    k = 0x1
+    mv = {
+        0x1,
+        0x6,
+        0x0
+    }
+    xi = 0x30
+.end annotation
+
+
+# static fields
+.field public static final Companion:Lbttv/kt/Player$Companion;
+
+
+# instance fields
+.field private final name:Ljava/lang/String;
+    .annotation build Lorg/jetbrains/annotations/NotNull;
+    .end annotation
+.end field
+
+.field private volume:Ljava/lang/Float;
+
+
+# direct methods
+%s
+
+# virtual methods
+%s""" % (
+        smali_string(d1),
+        d2,
+        "\n".join(stub(header) for header in methods[:4]),
+        "\n".join(stub(header) for header in methods[4:]),
+    )
+
+    path = os.path.join(os.path.dirname(os.path.abspath(__file__)), "kotlin.smali")
+    with open(path, "w") as file:
+        file.write(smali)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,167 @@
+use smali::kotlin::*;
+use smali::*;
+use std::path::PathBuf;
+
+fn player() -> SmaliClass {
+    let path = std::env::current_dir()
+        .unwrap()
+        .join("tests/files")
+        .join("kotlin.smali");
+    parse_file(path).unwrap()
+}
+
+fn metadata() -> KotlinMetadata {
+    player().kotlin_metadata().unwrap().unwrap()
+}
+
+fn kotlin_class(metadata: &KotlinMetadata) -> &KotlinClass {
+    match &metadata.declaration {
+        KotlinDeclaration::Class(class) => class,
+        other => panic!("unexpected declaration {:?}", other),
+    }
+}
+
+#[test]
+fn annotations() {
+    let class = player();
+    // member annotations are not class annotations
+    assert_eq!(class.annotations.len(), 2);
+    let metadata = &class.annotations[1];
+    assert_eq!(metadata.type_path, "kotlin.Metadata");
+    assert_eq!(metadata.visibility, AnnotationVisibility::Runtime);
+    assert_eq!(metadata.element("xi"), Some(&AnnotationValue::Int(0x30)));
+}
+
+#[test]
+fn declarations() {
+    let metadata = metadata();
+    assert_eq!(metadata.version, vec![1, 6, 0]);
+
+    let class = kotlin_class(&metadata);
+    assert_eq!(class.name, "bttv/kt/Player");
+    assert_eq!(class.class_path().unwrap(), "bttv.kt.Player");
+    assert_eq!(class.kind, KotlinClassKind::Class);
+    assert_eq!(class.visibility, KotlinVisibility::Public);
+    assert_eq!(class.modality, KotlinModality::Final);
+    assert_eq!(class.companion_object.as_deref(), Some("Companion"));
+    assert_eq!(class.supertypes[0].to_string(), "Any");
+
+    let rendered: Vec<_> = class
+        .constructors
+        .iter()
+        .map(ToString::to_string)
+        .chain(class.functions.iter().map(ToString::to_string))
+        .chain(class.properties.iter().map(ToString::to_string))
+        .collect();
+    assert_eq!(
+        rendered,
+        vec![
+            "constructor(name: String, quality: Int = …)",
+            "suspend fun load(id: String, force: Boolean = …): List<String>?",
+            "fun <T : Any> find(key: T): T?",
+            "val name: String",
+            "var volume: Float?",
+        ]
+    );
+
+    assert_eq!(
+        class.constructors[0].signature,
+        Some(JvmSignature {
+            name: "<init>".to_string(),
+            descriptor: "(Ljava/lang/String;I)V".to_string(),
+        })
+    );
+    let name = &class.properties[0];
+    assert_eq!(
+        name.field,
+        Some(JvmSignature {
+            name: "name".to_string(),
+            descriptor: "Ljava/lang/String;".to_string(),
+        })
+    );
+}
+
+#[test]
+fn explain_members() {
+    let class = player();
+    let metadata = class.kotlin_metadata().unwrap().unwrap();
+
+    let methods: Vec<_> = class
+        .methods
+        .iter()
+        .map(|method| {
+            metadata
+                .explain_method(method)
+                .map(|member| member.to_string())
+        })
+        .collect();
+    assert_eq!(
+        methods,
+        vec![
+            None,
+            Some("constructor(name: String, quality: Int = …)".to_string()),
+            Some("default arguments of constructor(name: String, quality: Int = …)".to_string()),
+            Some(
+                "default arguments of suspend fun load(id: String, force: Boolean = …): List<String>?"
+                    .to_string()
+            ),
+            Some("fun <T : Any> find(key: T): T?".to_string()),
+            Some("getter of val name: String".to_string()),
+            Some("getter of var volume: Float?".to_string()),
+            Some("suspend fun load(id: String, force: Boolean = …): List<String>?".to_string()),
+            Some("setter of var volume: Float?".to_string()),
+        ]
+    );
+
+    let values: Vec<_> = class
+        .values
+        .iter()
+        .map(|value| {
+            metadata
+                .explain_value(value)
+                .map(|member| member.to_string())
+        })
+        .collect();
+    assert_eq!(
+        values,
+        vec![
+            Some("companion object Companion".to_string()),
+            Some("backing field of val name: String".to_string()),
+            Some("backing field of var volume: Float?".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn not_kotlin() {
+    let path: PathBuf = std::env::current_dir()
+        .unwrap()
+        .join("tests/files/one.smali");
+    assert!(parse_file(path).unwrap().kotlin_metadata().is_none());
+}
+
+#[test]
+fn invalid_metadata() {
+    let mut class = player();
+    let metadata = &mut class.annotations[1];
+    metadata.elements.retain(|element| element.name != "d2");
+    assert!(matches!(
+        class.kotlin_metadata(),
+        Some(Err(KotlinError::InvalidString(_)))
+    ));
+
+    let annotation = SmaliAnnotation {
+        visibility: AnnotationVisibility::Runtime,
+        type_path: ClassPath::from_java("kotlin.Metadata").unwrap(),
+        elements: vec![AnnotationElement {
+            name: Symbol::intern("k"),
+            value: AnnotationValue::Int(3),
+        }],
+    };
+    assert_eq!(
+        KotlinMetadata::from_annotation(&annotation)
+            .unwrap()
+            .declaration,
+        KotlinDeclaration::Synthetic
+    );
+}